@@ -0,0 +1,414 @@
+use crate::cache;
+use crate::session;
+use crate::setup::seedelf_home_path;
+use anyhow::{Context, Result, anyhow, bail};
+use parking_lot::Mutex;
+use seedelf_crypto::convert;
+use seedelf_crypto::register::Register;
+use seedelf_crypto::schnorr::{create_proof, random_scalar};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream as StdUnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader as AsyncBufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::Notify;
+
+/// Environment variable pointing clients and the agent at a socket other than the default.
+pub const AGENT_SOCK_ENV: &str = "SEEDELF_AGENT_SOCK";
+
+/// The name of the agent socket inside the seedelf home.
+pub const AGENT_SOCKET: &str = "agent.sock";
+
+/// How long the agent keeps the key without a request before it wipes it and exits.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// How long either side waits for the rest of a request or response once it has started.
+pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The most a client may send on one connection, enough for a few thousand registers.
+const MAX_REQUEST_BYTES: u64 = 1024 * 1024;
+
+/// A request to the agent, sent as one line of JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum AgentRequest {
+    /// The wallet the agent holds.
+    Status,
+    /// The base register of the held key.
+    Register,
+    /// A Schnorr proof of the key behind `register`, bound to `bound`.
+    CreateProof { register: Register, bound: String },
+    /// Whether the held key owns each register.
+    IsOwned { registers: Vec<Register> },
+    /// A value derived from the held key, only for the wallet cache tags.
+    Derive { tag: String },
+    /// The payment key hash of the held key's external address.
+    KeyHash,
+    /// Wipe the key and stop the agent.
+    Lock,
+}
+
+/// The answer to an [`AgentRequest`], sent as one line of JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum AgentResponse {
+    Status { wallet: String, idle_timeout: u64 },
+    Register { register: Register },
+    Proof { z: String, g_r: String },
+    Owned { owned: Vec<bool> },
+    Derived { value: String },
+    KeyHash { key_hash: String },
+    Locked,
+    Error { message: String },
+}
+
+/// Where the agent listens, [`AGENT_SOCK_ENV`] if it is set, else `agent.sock` in the seedelf home.
+pub fn socket_path() -> PathBuf {
+    std::env::var_os(AGENT_SOCK_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| seedelf_home_path().join(AGENT_SOCKET))
+}
+
+/// Holds an unlocked wallet key in the locked session slot and answers requests that
+/// need it, so the key itself never leaves the process.
+pub struct Agent {
+    wallet: String,
+    idle_timeout: Duration,
+    /// How long a started request may take to arrive in full.
+    request_timeout: Duration,
+    /// When a request was last answered, on any connection.
+    last_request: Mutex<Instant>,
+    /// Woken once a client locks the agent.
+    locked: Notify,
+}
+
+impl Agent {
+    /// An agent for a key already placed in the session with [`session::unlock`].
+    ///
+    /// # Arguments
+    ///
+    /// * `wallet` - The name of the wallet the key came from.
+    /// * `idle_timeout` - How long to wait for a request before locking.
+    pub fn new(wallet: String, idle_timeout: Duration) -> Self {
+        Agent {
+            wallet,
+            idle_timeout,
+            request_timeout: CONNECTION_TIMEOUT,
+            last_request: Mutex::new(Instant::now()),
+            locked: Notify::new(),
+        }
+    }
+
+    /// Sets how long a started request may take to arrive, [`CONNECTION_TIMEOUT`] by default.
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Answers one request from the session key.
+    pub fn answer(&self, request: AgentRequest) -> AgentResponse {
+        let answer: Result<AgentResponse> = match request {
+            AgentRequest::Status => Ok(AgentResponse::Status {
+                wallet: self.wallet.clone(),
+                idle_timeout: self.idle_timeout.as_secs(),
+            }),
+            AgentRequest::Register => with_key(|sk| Register::create(*sk))
+                .map(|register| AgentResponse::Register { register }),
+            AgentRequest::CreateProof { register, bound } => {
+                with_key(|sk| create_proof(register, *sk, bound, random_scalar()))
+                    .map(|(z, g_r)| AgentResponse::Proof { z, g_r })
+            }
+            AgentRequest::IsOwned { registers } => with_key(|sk| {
+                registers
+                    .iter()
+                    .map(|register| register.is_owned(*sk))
+                    .collect::<Result<Vec<bool>>>()
+            })
+            .map(|owned| AgentResponse::Owned { owned }),
+            AgentRequest::Derive { tag } => {
+                if cache::DERIVE_TAGS.contains(&tag.as_str()) {
+                    with_key(|sk| Ok(cache::derive(&tag, *sk))).map(|value| {
+                        AgentResponse::Derived {
+                            value: hex::encode(value),
+                        }
+                    })
+                } else {
+                    Err(anyhow!("Unknown Derivation: {tag}"))
+                }
+            }
+            AgentRequest::KeyHash => with_key(|sk| Ok(convert::secret_key_to_public_key(*sk)))
+                .map(|key_hash| AgentResponse::KeyHash { key_hash }),
+            AgentRequest::Lock => {
+                session::lock();
+                self.locked.notify_one();
+                Ok(AgentResponse::Locked)
+            }
+        };
+        answer.unwrap_or_else(|e| AgentResponse::Error {
+            message: format!("{e:#}"),
+        })
+    }
+
+    /// Answers connections until the agent is locked, idles out or is signalled, then
+    /// wipes the key and removes the socket. Each connection is answered on its own task
+    /// so a quiet client holds up no one else. Connections from any other user are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - The socket from [`bind`].
+    /// * `path` - Where the socket lives, removed on the way out.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Once the agent has stopped.
+    /// * `Err(anyhow::Error)` - If the signal handlers can not be installed.
+    pub async fn serve(self, listener: UnixListener, path: &Path) -> Result<()> {
+        let mut terminate = signal(SignalKind::terminate()).context("Failed To Watch SIGTERM")?;
+        let agent: Arc<Agent> = Arc::new(self);
+        loop {
+            let remaining: Duration = agent
+                .idle_timeout
+                .saturating_sub(agent.last_request.lock().elapsed());
+            if remaining.is_zero() {
+                break;
+            }
+            let stream: UnixStream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        eprintln!("Error: Failed To Accept A Connection: {e}");
+                        continue;
+                    }
+                },
+                _ = tokio::time::sleep(remaining) => continue,
+                _ = agent.locked.notified() => break,
+                _ = tokio::signal::ctrl_c() => break,
+                _ = terminate.recv() => break,
+            };
+            if !same_user(&stream) {
+                continue;
+            }
+            let agent: Arc<Agent> = Arc::clone(&agent);
+            tokio::spawn(async move {
+                if let Err(e) = agent.handle(stream).await {
+                    eprintln!("Error: {e:#}");
+                }
+            });
+        }
+        session::lock();
+        let _ = fs::remove_file(path);
+        Ok(())
+    }
+
+    /// Answers every request on one connection until it closes or locks the agent.
+    ///
+    /// A client may stay quiet between requests for as long as it likes, a command
+    /// keeps its connection while it scans the chain, but a request that has started
+    /// must arrive within the request timeout.
+    async fn handle(&self, stream: UnixStream) -> Result<()> {
+        let (read_half, mut write_half) = stream.into_split();
+        let mut reader = AsyncBufReader::new(read_half.take(MAX_REQUEST_BYTES));
+        let mut line: String = String::new();
+        loop {
+            line.clear();
+            if reader
+                .fill_buf()
+                .await
+                .context("Failed To Read Request")?
+                .is_empty()
+            {
+                return Ok(());
+            }
+            tokio::time::timeout(self.request_timeout, reader.read_line(&mut line))
+                .await
+                .context("Client Went Quiet")?
+                .context("Failed To Read Request")?;
+            let response: AgentResponse = match serde_json::from_str::<AgentRequest>(&line) {
+                Ok(request) => self.answer(request),
+                Err(e) => AgentResponse::Error {
+                    message: format!("Invalid Request: {e}"),
+                },
+            };
+            let mut reply: String =
+                serde_json::to_string(&response).context("Failed To Serialize Response")?;
+            reply.push('\n');
+            write_half
+                .write_all(reply.as_bytes())
+                .await
+                .context("Failed To Write Response")?;
+            *self.last_request.lock() = Instant::now();
+            if response == AgentResponse::Locked {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn with_key<F, R>(f: F) -> Result<R>
+where
+    F: FnOnce(&blstrs::Scalar) -> Result<R>,
+{
+    session::with_key_sync(f).map_err(anyhow::Error::msg)?
+}
+
+/// Creates the agent socket readable by its owner only, clearing a stale socket first.
+///
+/// # Arguments
+///
+/// * `path` - The socket to create.
+///
+/// # Returns
+///
+/// * `Ok(UnixListener)` - The listening socket.
+/// * `Err(anyhow::Error)` - If the socket can not be created or another agent holds it.
+pub fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        if StdUnixStream::connect(path).is_ok() {
+            bail!("An Agent Is Already Listening On {}", path.display());
+        }
+        fs::remove_file(path)
+            .with_context(|| format!("Failed To Remove Stale Socket {}", path.display()))?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed To Create {}", parent.display()))?;
+    }
+    // the umask keeps the socket private from the moment it exists
+    let listener = unsafe {
+        let previous: libc::mode_t = libc::umask(0o177);
+        let listener = UnixListener::bind(path);
+        libc::umask(previous);
+        listener
+    }
+    .with_context(|| format!("Failed To Listen On {}", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed To Restrict {}", path.display()))?;
+    Ok(listener)
+}
+
+fn same_user(stream: &UnixStream) -> bool {
+    stream
+        .peer_cred()
+        .map(|cred| cred.uid() == unsafe { libc::geteuid() })
+        .unwrap_or(false)
+}
+
+/// A connection to a running agent.
+pub struct AgentClient {
+    reader: BufReader<StdUnixStream>,
+    writer: StdUnixStream,
+}
+
+impl AgentClient {
+    /// Connects to the agent listening on a socket.
+    pub fn connect(path: &Path) -> Result<Self> {
+        let writer: StdUnixStream = StdUnixStream::connect(path)
+            .with_context(|| format!("No Agent Is Listening On {}", path.display()))?;
+        writer.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+        let reader: BufReader<StdUnixStream> = BufReader::new(writer.try_clone()?);
+        Ok(AgentClient { reader, writer })
+    }
+
+    /// Connects to the agent at [`socket_path`] if it holds the given wallet.
+    pub fn for_wallet(wallet: &str) -> Option<Self> {
+        let mut client: AgentClient = Self::connect(&socket_path()).ok()?;
+        match client.status() {
+            Ok(held) if held == wallet => Some(client),
+            _ => None,
+        }
+    }
+
+    /// Sends one request and waits for its answer, an error answer becomes an `Err`.
+    pub fn request(&mut self, request: &AgentRequest) -> Result<AgentResponse> {
+        let mut line: String = serde_json::to_string(request).context("Failed To Serialize")?;
+        line.push('\n');
+        self.writer
+            .write_all(line.as_bytes())
+            .context("Failed To Reach The Agent")?;
+        line.clear();
+        if self
+            .reader
+            .read_line(&mut line)
+            .context("Failed To Read From The Agent")?
+            == 0
+        {
+            bail!("The Agent Closed The Connection");
+        }
+        match serde_json::from_str(&line).context("Invalid Agent Response")? {
+            AgentResponse::Error { message } => bail!("Agent: {message}"),
+            response => Ok(response),
+        }
+    }
+
+    /// The name of the wallet the agent holds.
+    pub fn status(&mut self) -> Result<String> {
+        match self.request(&AgentRequest::Status)? {
+            AgentResponse::Status { wallet, .. } => Ok(wallet),
+            _ => bail!("Unexpected Agent Response"),
+        }
+    }
+
+    /// The base register of the held key.
+    pub fn register(&mut self) -> Result<Register> {
+        match self.request(&AgentRequest::Register)? {
+            AgentResponse::Register { register } => Ok(register),
+            _ => bail!("Unexpected Agent Response"),
+        }
+    }
+
+    /// Asks the agent for a proof, the same `(z, g_r)` as [`create_proof`].
+    ///
+    /// # Arguments
+    ///
+    /// * `register` - The register being spent.
+    /// * `bound` - The value the proof is bound to, the signing key hash.
+    pub fn create_proof(&mut self, register: Register, bound: String) -> Result<(String, String)> {
+        match self.request(&AgentRequest::CreateProof { register, bound })? {
+            AgentResponse::Proof { z, g_r } => Ok((z, g_r)),
+            _ => bail!("Unexpected Agent Response"),
+        }
+    }
+
+    /// Whether the held key owns each register, in order.
+    pub fn is_owned(&mut self, registers: Vec<Register>) -> Result<Vec<bool>> {
+        match self.request(&AgentRequest::IsOwned { registers })? {
+            AgentResponse::Owned { owned } => Ok(owned),
+            _ => bail!("Unexpected Agent Response"),
+        }
+    }
+
+    /// A value derived from the held key under one of the [`cache::DERIVE_TAGS`].
+    pub fn derive(&mut self, tag: &str) -> Result<[u8; 32]> {
+        match self.request(&AgentRequest::Derive {
+            tag: tag.to_string(),
+        })? {
+            AgentResponse::Derived { value } => hex::decode(value)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| anyhow!("Invalid Derived Value")),
+            _ => bail!("Unexpected Agent Response"),
+        }
+    }
+
+    /// The payment key hash of the held key's external address.
+    pub fn key_hash(&mut self) -> Result<String> {
+        match self.request(&AgentRequest::KeyHash)? {
+            AgentResponse::KeyHash { key_hash } => Ok(key_hash),
+            _ => bail!("Unexpected Agent Response"),
+        }
+    }
+
+    /// Wipes the key and stops the agent.
+    pub fn lock(&mut self) -> Result<()> {
+        match self.request(&AgentRequest::Lock)? {
+            AgentResponse::Locked => Ok(()),
+            _ => bail!("Unexpected Agent Response"),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use clap::{Parser, Subcommand};
+
+#[cfg(unix)]
+use {
+    colored::Colorize,
+    seedelf_cli::agent::{
+        AGENT_SOCK_ENV, Agent, AgentClient, DEFAULT_IDLE_TIMEOUT, bind, socket_path,
+    },
+    seedelf_cli::session,
+    seedelf_cli::setup::{self, PasswordSource},
+    std::path::PathBuf,
+    std::time::Duration,
+};
+
+#[derive(Parser)]
+#[command(name = "seedelf-agent")]
+#[command(version = env!("CARGO_PKG_VERSION"))]
+#[command(
+    about = "Holds an unlocked seedelf wallet key and answers proof and ownership requests",
+    long_about = None
+)]
+struct Cli {
+    /// Directory holding the wallets, defaults to ~/.seedelf
+    #[cfg(unix)]
+    #[arg(long, global = true, env = setup::HOME_ENV)]
+    data_dir: Option<PathBuf>,
+
+    /// Socket to listen on or talk to, defaults to agent.sock in the seedelf home
+    #[cfg(unix)]
+    #[arg(long, global = true, env = AGENT_SOCK_ENV)]
+    socket: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: AgentCommands,
+}
+
+#[derive(Subcommand)]
+enum AgentCommands {
+    /// Unlock a wallet and serve it until locked or idle
+    #[cfg(unix)]
+    Start {
+        /// Wallet to hold, defaults to the recorded default or the only wallet
+        #[arg(long, env = setup::WALLET_ENV)]
+        wallet: Option<String>,
+
+        /// Seconds without a request before the key is wiped and the agent exits
+        #[arg(long, env = "SEEDELF_AGENT_IDLE_TIMEOUT", default_value_t = DEFAULT_IDLE_TIMEOUT.as_secs())]
+        idle_timeout: u64,
+
+        /// Read the wallet password from a file instead of prompting
+        #[arg(long, conflicts_with_all = ["password_fd", "password_env"])]
+        password_file: Option<PathBuf>,
+
+        /// Read the wallet password from an inherited file descriptor instead of prompting
+        #[arg(long, conflicts_with = "password_env")]
+        password_fd: Option<i32>,
+
+        /// Read the wallet password from SEEDELF_PASSWORD
+        #[arg(long)]
+        password_env: bool,
+    },
+    /// Show the wallet a running agent holds
+    Status,
+    /// Wipe the key and stop a running agent
+    Lock,
+}
+
+#[cfg(unix)]
+#[tokio::main]
+async fn main() {
+    let cli: Cli = Cli::parse();
+    setup::set_data_dir(cli.data_dir);
+    let socket: PathBuf = cli.socket.unwrap_or_else(socket_path);
+
+    let result: anyhow::Result<()> = match cli.command {
+        AgentCommands::Start {
+            wallet,
+            idle_timeout,
+            password_file,
+            password_fd,
+            password_env,
+        } => {
+            setup::select_wallet(wallet);
+            setup::set_password_source(if let Some(path) = password_file {
+                PasswordSource::File(path)
+            } else if let Some(fd) = password_fd {
+                PasswordSource::Fd(fd)
+            } else if password_env {
+                PasswordSource::Env
+            } else {
+                PasswordSource::Prompt
+            });
+            // take the socket before asking for the password so a second agent fails fast
+            match setup::selected_wallet()
+                .map_err(anyhow::Error::msg)
+                .and_then(|wallet| bind(&socket).map(|listener| (wallet, listener)))
+            {
+                Ok((wallet, listener)) => {
+                    session::harden_process_best_effort();
+                    session::unlock(setup::unlock_wallet_interactive());
+                    println!(
+                        "{} {} {}",
+                        "Holding".bright_blue(),
+                        wallet.bright_white(),
+                        format!("On {}", socket.display()).bright_blue()
+                    );
+                    println!(
+                        "{AGENT_SOCK_ENV}={}; export {AGENT_SOCK_ENV};",
+                        socket.display()
+                    );
+                    Agent::new(wallet, Duration::from_secs(idle_timeout))
+                        .serve(listener, &socket)
+                        .await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        AgentCommands::Status => AgentClient::connect(&socket)
+            .and_then(|mut agent| agent.status())
+            .map(|wallet| println!("{wallet}")),
+        AgentCommands::Lock => AgentClient::connect(&socket).and_then(|mut agent| agent.lock()),
+    };
+    if let Err(err) = result {
+        eprintln!("Error: {err:#}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    let _ = Cli::parse();
+    eprintln!("Error: seedelf-agent needs unix sockets and only runs on Linux and macOS");
+    std::process::exit(1);
+}
@@ -0,0 +1,759 @@
+use crate::key::WalletKey;
+use crate::setup::seedelf_home_path;
+use crate::wallet_file;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Context, Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use blstrs::Scalar;
+use ff::PrimeField;
+use pallas_addresses::Address;
+use pallas_crypto::hash::Hasher;
+use pallas_traverse::MultiEraTx;
+use rand_core::OsRng;
+use seedelf_core::address;
+use seedelf_core::constants::{Config, MAXIMUM_WALLET_UTXOS};
+use seedelf_core::utxos;
+use seedelf_crypto::register::Register;
+use seedelf_koios::datum::resolve_datum_hashes;
+use seedelf_koios::koios::{
+    Asset, BlockchainTip, CredentialTx, TxResponse, UtxoResponse, contains_policy_id,
+    extract_bytes_with_logging,
+};
+use seedelf_koios::node::output_response;
+use seedelf_koios::provider::ChainProvider;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Blocks this close to the tip may still roll back, so they are never written to the cache.
+pub const ROLLBACK_DEPTH: u64 = 10;
+
+/// Seconds a submitted transaction may stay unconfirmed before it is assumed evicted.
+pub const PENDING_EXPIRY: u64 = 30 * 60;
+
+/// Bumped whenever the cache layout changes, older caches are rebuilt.
+pub const CACHE_VERSION: u32 = 4;
+
+/// The tag the cache file name is derived under.
+const CACHE_ID_TAG: &str = "seedelf-wallet-cache-id";
+
+/// The tag the cache encryption key is derived under.
+const CACHE_KEY_TAG: &str = "seedelf-wallet-cache";
+
+/// The tags a value may be derived from the wallet key under, an agent refuses any other.
+pub const DERIVE_TAGS: [&str; 2] = [CACHE_ID_TAG, CACHE_KEY_TAG];
+
+/// An amount of a native token.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenAmount {
+    pub policy_id: String,
+    pub asset_name: String,
+    pub amount: u64,
+}
+
+/// One owned input or output of a transaction.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Movement {
+    /// `true` for a spent input, `false` for a received output.
+    pub spend: bool,
+    /// The UTxO that was spent or created.
+    #[serde(default)]
+    pub tx_hash: String,
+    #[serde(default)]
+    pub tx_index: u64,
+    pub lovelace: u64,
+    pub tokens: Vec<TokenAmount>,
+}
+
+/// A transaction that spent from or paid into the wallet.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub tx_hash: String,
+    pub block_height: u64,
+    pub block_time: u64,
+    pub spend: bool,
+    pub receive: bool,
+    /// Lovelace taken out of owned registers.
+    pub spent_lovelace: u64,
+    /// Lovelace paid into owned registers.
+    pub received_lovelace: u64,
+    pub spent_tokens: Vec<TokenAmount>,
+    pub received_tokens: Vec<TokenAmount>,
+    /// The transaction fee, `None` when the backend does not report it.
+    pub fee: Option<u64>,
+    /// Every owned input and output, in the order they were seen.
+    #[serde(default)]
+    pub movements: Vec<Movement>,
+}
+
+impl HistoryEntry {
+    fn new(tx_hash: &str, block_height: u64, block_time: u64, fee: Option<u64>) -> Self {
+        HistoryEntry {
+            tx_hash: tx_hash.to_string(),
+            block_height,
+            block_time,
+            fee,
+            ..Default::default()
+        }
+    }
+
+    fn add_spent(&mut self, utxo: (&str, u64), lovelace: u64, assets: &[Asset]) {
+        self.spend = true;
+        self.spent_lovelace += lovelace;
+        add_tokens(&mut self.spent_tokens, assets);
+        self.add_movement(true, utxo, lovelace, assets);
+    }
+
+    fn add_received(&mut self, utxo: (&str, u64), lovelace: u64, assets: &[Asset]) {
+        self.receive = true;
+        self.received_lovelace += lovelace;
+        add_tokens(&mut self.received_tokens, assets);
+        self.add_movement(false, utxo, lovelace, assets);
+    }
+
+    fn add_movement(&mut self, spend: bool, utxo: (&str, u64), lovelace: u64, assets: &[Asset]) {
+        let mut tokens: Vec<TokenAmount> = Vec::new();
+        add_tokens(&mut tokens, assets);
+        self.movements.push(Movement {
+            spend,
+            tx_hash: utxo.0.to_string(),
+            tx_index: utxo.1,
+            lovelace,
+            tokens,
+        });
+    }
+
+    /// Builds the entry of a transaction, `None` if no owned register took part.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction at the wallet contract.
+    /// * `key` - The wallet key.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Option<HistoryEntry>)` - The entry, if the wallet was involved.
+    /// * `Err(anyhow::Error)` - If a register can not be checked.
+    pub fn from_tx(tx: &TxResponse, key: &WalletKey) -> Result<Option<Self>> {
+        let mut entry: HistoryEntry =
+            HistoryEntry::new(&tx.tx_hash, tx.block_height, tx.block_time, tx.fee);
+        let owned: Vec<bool> = key.owns(
+            tx.inputs
+                .iter()
+                .map(|input| input.register.clone())
+                .chain(tx.outputs.iter().map(|output| output.register.clone()))
+                .collect(),
+        )?;
+        let (spent, received) = owned.split_at(tx.inputs.len());
+        for (input, _) in tx.inputs.iter().zip(spent).filter(|(_, owned)| **owned) {
+            entry.add_spent(
+                (&input.tx_hash, input.tx_index),
+                input.lovelace,
+                &input.assets,
+            );
+        }
+        for (output, _) in tx.outputs.iter().zip(received).filter(|(_, owned)| **owned) {
+            entry.add_received(
+                (&output.tx_hash, output.tx_index),
+                output.lovelace,
+                &output.assets,
+            );
+        }
+        Ok((entry.spend || entry.receive).then_some(entry))
+    }
+
+    /// The change of the wallet's lovelace, the fee included for a spend.
+    pub fn net_lovelace(&self) -> i128 {
+        self.received_lovelace as i128 - self.spent_lovelace as i128
+    }
+
+    /// The change of each token held by the wallet, zero changes left out.
+    pub fn net_tokens(&self) -> Vec<(String, String, i128)> {
+        let mut net: Vec<(String, String, i128)> = Vec::new();
+        let changes = self
+            .received_tokens
+            .iter()
+            .map(|t| (t, t.amount as i128))
+            .chain(self.spent_tokens.iter().map(|t| (t, -(t.amount as i128))));
+        for (token, change) in changes {
+            match net
+                .iter_mut()
+                .find(|(p, n, _)| *p == token.policy_id && *n == token.asset_name)
+            {
+                Some((_, _, amount)) => *amount += change,
+                None => net.push((token.policy_id.clone(), token.asset_name.clone(), change)),
+            }
+        }
+        net.retain(|(_, _, amount)| *amount != 0);
+        net
+    }
+}
+
+/// Replays the history to find the UTxOs the wallet held at some earlier point.
+///
+/// # Arguments
+///
+/// * `history` - The wallet history, ordered by block.
+/// * `upto` - Whether an entry happened at or before the point of interest.
+///
+/// # Returns
+///
+/// * `Vec<Movement>` - The owned outputs that were created and not yet spent.
+pub fn utxos_at(history: &[HistoryEntry], upto: impl Fn(&HistoryEntry) -> bool) -> Vec<Movement> {
+    let mut held: Vec<Movement> = Vec::new();
+    for entry in history.iter().take_while(|entry| upto(entry)) {
+        for movement in &entry.movements {
+            if movement.spend {
+                held.retain(|utxo| {
+                    utxo.tx_hash != movement.tx_hash || utxo.tx_index != movement.tx_index
+                });
+            } else {
+                held.push(movement.clone());
+            }
+        }
+    }
+    held
+}
+
+fn add_tokens(tokens: &mut Vec<TokenAmount>, assets: &[Asset]) {
+    for asset in assets {
+        let amount: u64 = asset.quantity.parse().unwrap_or_default();
+        match tokens
+            .iter_mut()
+            .find(|t| t.policy_id == asset.policy_id && t.asset_name == asset.asset_name)
+        {
+            Some(token) => token.amount += amount,
+            None => tokens.push(TokenAmount {
+                policy_id: asset.policy_id.clone(),
+                asset_name: asset.asset_name.clone(),
+                amount,
+            }),
+        }
+    }
+}
+
+/// A submitted transaction that is not on chain yet.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PendingTx {
+    pub tx_hash: String,
+    /// Unix time of the submission.
+    pub submitted_at: u64,
+    /// The wallet UTxOs it spends as `(tx_hash, tx_index)`.
+    pub inputs: Vec<(String, u64)>,
+    /// Its outputs owned by the wallet, spendable before it confirms.
+    pub outputs: Vec<UtxoResponse>,
+}
+
+/// Everything the wallet key owns at the wallet contract, as of `synced_block`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WalletCache {
+    /// The layout of the cache, see `CACHE_VERSION`.
+    #[serde(default)]
+    pub version: u32,
+    /// The wallet contract the cache was built from.
+    pub wallet_contract_hash: String,
+    /// Every transaction up to this block is reflected in the cache.
+    pub synced_block: u64,
+    /// Every owned UTxO, the ones holding a seedelf included.
+    pub utxos: Vec<UtxoResponse>,
+    /// The names of the owned seedelfs.
+    pub seedelfs: Vec<String>,
+    /// The spend/receive history, `None` when the backend can not provide it.
+    pub history: Option<Vec<HistoryEntry>>,
+    /// Submitted transactions that are not on chain yet, oldest first.
+    #[serde(default)]
+    pub pending: Vec<PendingTx>,
+}
+
+/// The cache file, the JSON of a `WalletCache` sealed with AES-256-GCM.
+#[derive(Serialize, Deserialize)]
+struct EncryptedCache {
+    nonce: String,
+    data: String,
+}
+
+/// Derives a 32 byte value from the wallet key, separated by a domain tag.
+pub fn derive(tag: &str, sk: Scalar) -> [u8; 32] {
+    let mut hasher: Hasher<256> = Hasher::<256>::new();
+    hasher.input(tag.as_bytes());
+    hasher.input(&sk.to_repr());
+    *hasher.finalize()
+}
+
+/// The cache file of a wallet key on a network and contract.
+///
+/// The file name is derived from the key, so wallets never share a cache, but
+/// it reveals nothing about the key itself.
+///
+/// # Arguments
+///
+/// * `key` - The wallet key.
+/// * `config` - The contract variant being cached.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod.
+///     - `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - The path of the cache file inside `.seedelf/cache`.
+/// * `Err(anyhow::Error)` - If the key can not be reached.
+pub fn cache_path(key: &WalletKey, config: &Config, network_flag: bool) -> Result<PathBuf> {
+    let network: &str = if network_flag { "preprod" } else { "mainnet" };
+    let contract: String = hex::encode(&config.contract.wallet_contract_hash[..4]);
+    let id: String = hex::encode(&key.derive(CACHE_ID_TAG)?[..8]);
+    Ok(seedelf_home_path()
+        .join("cache")
+        .join(format!("{network}-{contract}-{id}.db")))
+}
+
+impl WalletCache {
+    /// Reads and decrypts a cache file, `None` if it is missing or unreadable.
+    pub fn load(path: &Path, key: &WalletKey) -> Option<Self> {
+        let file: EncryptedCache = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        let nonce: Vec<u8> = STANDARD.decode(file.nonce).ok()?;
+        let data: Vec<u8> = STANDARD.decode(file.data).ok()?;
+        if nonce.len() != 12 {
+            return None;
+        }
+        let secret: [u8; 32] = key.derive(CACHE_KEY_TAG).ok()?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&secret));
+        let plain: Vec<u8> = cipher
+            .decrypt(Nonce::from_slice(&nonce), data.as_ref())
+            .ok()?;
+        serde_json::from_slice(&plain).ok()
+    }
+
+    /// Encrypts the cache with the wallet key and writes it, replacing the old file atomically.
+    pub fn save(&self, path: &Path, key: &WalletKey) -> Result<()> {
+        let secret: [u8; 32] = key.derive(CACHE_KEY_TAG)?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&secret));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let plain: Vec<u8> = serde_json::to_vec(self).context("Failed To Serialize Cache")?;
+        let data: Vec<u8> = cipher
+            .encrypt(&nonce, plain.as_ref())
+            .map_err(|e| anyhow!("Failed To Encrypt Cache: {e}"))?;
+        let file: String = serde_json::to_string(&EncryptedCache {
+            nonce: STANDARD.encode(nonce),
+            data: STANDARD.encode(data),
+        })?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed To Create Cache Directory")?;
+        }
+        wallet_file::write_atomically(path, &file).context("Failed To Write Cache")
+    }
+
+    /// The owned UTxOs without a seedelf, these make up the balance.
+    pub fn wallet_utxos(&self, seedelf_policy_id: &str) -> Vec<UtxoResponse> {
+        self.utxos
+            .iter()
+            .filter(|utxo| !contains_policy_id(&utxo.asset_list, seedelf_policy_id))
+            .cloned()
+            .collect()
+    }
+
+    /// The wallet UTxOs a single transaction may spend.
+    pub fn spendable_utxos(&self, seedelf_policy_id: &str) -> Vec<UtxoResponse> {
+        let mut utxos: Vec<UtxoResponse> = self.wallet_utxos(seedelf_policy_id);
+        utxos.truncate(MAXIMUM_WALLET_UTXOS as usize);
+        utxos
+    }
+
+    /// Drops the pending transactions that are confirmed, evicted or expired.
+    ///
+    /// A pending transaction is still waiting while one of its inputs is unspent, or
+    /// is the output of another transaction that is still waiting. This has to run
+    /// on a synced cache before [`WalletCache::apply_pending`].
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current unix time.
+    pub fn settle_pending(&mut self, now: u64) {
+        let mut waiting: Vec<PendingTx> = Vec::new();
+        for tx in std::mem::take(&mut self.pending) {
+            let expired: bool = now.saturating_sub(tx.submitted_at) > PENDING_EXPIRY;
+            let unspent: bool = tx.inputs.iter().any(|(tx_hash, tx_index)| {
+                self.utxos
+                    .iter()
+                    .any(|u| &u.tx_hash == tx_hash && u.tx_index == *tx_index)
+                    || waiting.iter().any(|p| &p.tx_hash == tx_hash)
+            });
+            if !expired && unspent {
+                waiting.push(tx);
+            }
+        }
+        self.pending = waiting;
+    }
+
+    /// Spends the inputs and adds the outputs of the pending transactions, so coin
+    /// selection skips what is already spent and may chain off the change.
+    pub fn apply_pending(&mut self, seedelf_policy_id: &str) {
+        for tx in self.pending.clone() {
+            for (tx_hash, tx_index) in &tx.inputs {
+                self.remove(tx_hash, *tx_index, seedelf_policy_id);
+            }
+            for output in tx.outputs {
+                self.insert(output, seedelf_policy_id);
+            }
+        }
+    }
+
+    /// Adds an owned UTxO unless it is already known.
+    fn insert(&mut self, utxo: UtxoResponse, seedelf_policy_id: &str) {
+        if self
+            .utxos
+            .iter()
+            .any(|u| u.tx_hash == utxo.tx_hash && u.tx_index == utxo.tx_index)
+        {
+            return;
+        }
+        if let Some(seedelf) = seedelf_name(&utxo, seedelf_policy_id)
+            && !self.seedelfs.contains(&seedelf)
+        {
+            self.seedelfs.push(seedelf);
+        }
+        self.utxos.push(utxo);
+    }
+
+    /// Drops a spent UTxO, returning whether it was owned.
+    fn remove(&mut self, tx_hash: &str, tx_index: u64, seedelf_policy_id: &str) -> bool {
+        let Some(position) = self
+            .utxos
+            .iter()
+            .position(|u| u.tx_hash == tx_hash && u.tx_index == tx_index)
+        else {
+            return false;
+        };
+        let utxo: UtxoResponse = self.utxos.remove(position);
+        if let Some(seedelf) = seedelf_name(&utxo, seedelf_policy_id) {
+            self.seedelfs.retain(|s| s != &seedelf);
+        }
+        true
+    }
+
+    /// Applies new transactions at the wallet contract to the cache.
+    ///
+    /// Applying a transaction twice changes nothing, so overlapping syncs are safe.
+    ///
+    /// # Arguments
+    ///
+    /// * `txs` - The transactions, ordered by block.
+    /// * `key` - The wallet key.
+    /// * `seedelf_policy_id` - The seedelf policy id.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Once every transaction is applied.
+    /// * `Err(anyhow::Error)` - If a register can not be checked.
+    pub fn apply(
+        &mut self,
+        txs: &[CredentialTx],
+        key: &WalletKey,
+        seedelf_policy_id: &str,
+    ) -> Result<()> {
+        // outputs first, a batch may create and spend the same utxo
+        let mut entries: Vec<HistoryEntry> = Vec::with_capacity(txs.len());
+        for tx in txs {
+            let mut entry: HistoryEntry =
+                HistoryEntry::new(&tx.tx_hash, tx.block_height, tx.block_time, Some(tx.fee));
+            let outputs: Vec<&UtxoResponse> = self.at_contract(&tx.outputs);
+            for (output, owned) in outputs.iter().zip(owned(&outputs, key)?) {
+                if owned {
+                    self.insert((*output).clone(), seedelf_policy_id);
+                    entry.add_received(
+                        (&output.tx_hash, output.tx_index),
+                        utxo_lovelace(output),
+                        assets_of(output),
+                    );
+                }
+            }
+            entries.push(entry);
+        }
+        for (tx, mut entry) in txs.iter().zip(entries) {
+            let inputs: Vec<&UtxoResponse> = self.at_contract(&tx.inputs);
+            for (input, owned) in inputs.iter().zip(owned(&inputs, key)?) {
+                if self.remove(&input.tx_hash, input.tx_index, seedelf_policy_id) || owned {
+                    entry.add_spent(
+                        (&input.tx_hash, input.tx_index),
+                        utxo_lovelace(input),
+                        assets_of(input),
+                    );
+                }
+            }
+            if let Some(history) = self.history.as_mut()
+                && (entry.spend || entry.receive)
+                && !history.iter().any(|known| known.tx_hash == tx.tx_hash)
+            {
+                history.push(entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// The UTxOs sitting at the wallet contract.
+    fn at_contract<'a>(&self, utxos: &'a [UtxoResponse]) -> Vec<&'a UtxoResponse> {
+        utxos
+            .iter()
+            .filter(|utxo| utxo.payment_cred == self.wallet_contract_hash)
+            .collect()
+    }
+}
+
+fn utxo_lovelace(utxo: &UtxoResponse) -> u64 {
+    utxo.value.parse().unwrap_or_default()
+}
+
+fn assets_of(utxo: &UtxoResponse) -> &[Asset] {
+    utxo.asset_list.as_deref().unwrap_or_default()
+}
+
+/// The name of the seedelf a UTxO holds, if any.
+fn seedelf_name(utxo: &UtxoResponse, seedelf_policy_id: &str) -> Option<String> {
+    utxo.asset_list.as_ref().and_then(|assets| {
+        assets
+            .iter()
+            .find(|asset| asset.policy_id == seedelf_policy_id)
+            .map(|asset| asset.asset_name.clone())
+    })
+}
+
+/// Whether each UTxO holds a register the key owns, in order.
+fn owned(utxos: &[&UtxoResponse], key: &WalletKey) -> Result<Vec<bool>> {
+    let registers: Vec<Option<Register>> = utxos
+        .iter()
+        .map(|utxo| extract_bytes_with_logging(&utxo.inline_datum))
+        .collect();
+    let mut owned = key
+        .owns(registers.iter().flatten().cloned().collect())?
+        .into_iter();
+    Ok(registers
+        .iter()
+        .map(|register| register.is_some() && owned.next().unwrap_or(false))
+        .collect())
+}
+
+/// Builds the cache from scratch, checking every UTxO at the wallet contract.
+///
+/// Returns the cache to save, holding only what is older than its synced block, and
+/// the view of the wallet at the tip.
+async fn rescan(
+    provider: &impl ChainProvider,
+    config: &Config,
+    key: &WalletKey,
+    network_flag: bool,
+    tip: u64,
+) -> Result<(WalletCache, WalletCache)> {
+    let mut view: WalletCache = WalletCache {
+        version: CACHE_VERSION,
+        wallet_contract_hash: hex::encode(config.contract.wallet_contract_hash),
+        synced_block: tip.saturating_sub(ROLLBACK_DEPTH),
+        ..Default::default()
+    };
+    let seedelf_policy_id: &str = &config.contract.seedelf_policy_id;
+    let mut found: Vec<UtxoResponse> = Vec::new();
+    utxos::for_each_credential_page(provider, config.contract.wallet_contract_hash, |page| {
+        let mask: Vec<bool> = owned(&page.iter().collect::<Vec<&UtxoResponse>>(), key)?;
+        found.extend(
+            page.into_iter()
+                .zip(mask)
+                .filter_map(|(utxo, owned)| owned.then_some(utxo)),
+        );
+        Ok(true)
+    })
+    .await?;
+    for utxo in found {
+        view.insert(utxo, seedelf_policy_id);
+    }
+
+    // not every backend can list the transactions of an address
+    let wallet_addr: Address =
+        address::wallet_contract(network_flag, config.contract.wallet_contract_hash);
+    if let Ok(txs) = provider.address_transactions(wallet_addr.to_string()).await {
+        view.history = Some(history_of(&txs, key)?);
+    }
+
+    // the last blocks may still roll back, so they stay out of the saved cache
+    let stable: u64 = view.synced_block;
+    let mut cache: WalletCache = WalletCache {
+        version: view.version,
+        wallet_contract_hash: view.wallet_contract_hash.clone(),
+        synced_block: stable,
+        history: view.history.as_ref().map(|history| {
+            history
+                .iter()
+                .filter(|entry| entry.block_height <= stable)
+                .cloned()
+                .collect()
+        }),
+        ..Default::default()
+    };
+    for utxo in view.utxos.iter().filter(|utxo| utxo.block_height <= stable) {
+        cache.insert(utxo.clone(), seedelf_policy_id);
+    }
+    Ok((cache, view))
+}
+
+/// Classifies the transactions of the wallet contract by the registers the key owns.
+fn history_of(txs: &[TxResponse], key: &WalletKey) -> Result<Vec<HistoryEntry>> {
+    let mut history: Vec<HistoryEntry> = Vec::new();
+    for tx in txs {
+        if let Some(entry) = HistoryEntry::from_tx(tx, key)? {
+            history.push(entry);
+        }
+    }
+    history.sort_by_key(|entry| entry.block_height);
+    Ok(history)
+}
+
+/// Brings the wallet cache up to the tip and returns the current view of the wallet.
+///
+/// Only the transactions since the last synced block are fetched and checked. The
+/// ones in the last `ROLLBACK_DEPTH` blocks are part of the returned view but are
+/// not saved, so a rollback can not corrupt the cache, and a rescan leaves out the
+/// UTxOs of those blocks the same way. Backends that can not list new transactions
+/// always rescan.
+///
+/// # Arguments
+///
+/// * `provider` - The chain data backend.
+/// * `config` - The contract variant.
+/// * `key` - The wallet key, it owns the UTxOs and encrypts the cache.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod.
+///     - `false` for Mainnet.
+/// * `rescan` - Ignore the cache and rebuild it from the whole contract.
+///
+/// # Returns
+///
+/// * `Ok(WalletCache)` - The wallet as of the tip.
+/// * `Err(anyhow::Error)` - If the chain could not be read or the cache not written.
+pub async fn sync(
+    provider: &impl ChainProvider,
+    config: &Config,
+    key: &WalletKey,
+    network_flag: bool,
+    rescan_flag: bool,
+) -> Result<WalletCache> {
+    let path: PathBuf = cache_path(key, config, network_flag)?;
+    let tips: Vec<BlockchainTip> = provider.tip().await.context("Failed To Get The Tip")?;
+    let tip: u64 = tips
+        .first()
+        .map(|tip| tip.block_no)
+        .ok_or_else(|| anyhow!("No Tip Returned"))?;
+    let seedelf_policy_id: &str = &config.contract.seedelf_policy_id;
+    let credential: String = hex::encode(config.contract.wallet_contract_hash);
+
+    let cached: Option<WalletCache> = WalletCache::load(&path, key)
+        .filter(|cache| cache.version == CACHE_VERSION && cache.wallet_contract_hash == credential);
+    let new_txs: Option<Vec<CredentialTx>> = match &cached {
+        Some(cache) if !rescan_flag => provider
+            .credential_transactions(&credential, cache.synced_block)
+            .await
+            .context("Failed To Get New Transactions")?,
+        _ => None,
+    };
+    let (mut cache, mut view): (WalletCache, WalletCache) = match (cached, new_txs) {
+        (Some(mut cache), Some(mut new_txs)) => {
+            for tx in new_txs.iter_mut() {
+                resolve_datum_hashes(provider, &mut tx.outputs)
+                    .await
+                    .context("Failed To Resolve Datum Hashes")?;
+                resolve_datum_hashes(provider, &mut tx.inputs)
+                    .await
+                    .context("Failed To Resolve Datum Hashes")?;
+            }
+            let stable: u64 = tip.saturating_sub(ROLLBACK_DEPTH);
+            let (settled, recent): (Vec<CredentialTx>, Vec<CredentialTx>) = new_txs
+                .into_iter()
+                .partition(|tx| tx.block_height <= stable);
+            cache.apply(&settled, key, seedelf_policy_id)?;
+            cache.synced_block = cache.synced_block.max(stable);
+            let mut view: WalletCache = cache.clone();
+            view.apply(&recent, key, seedelf_policy_id)?;
+            (cache, view)
+        }
+        (cached, _) => {
+            let (cache, mut view) = rescan(provider, config, key, network_flag, tip).await?;
+            // the chain knows nothing about what was submitted from here
+            view.pending = cached.map(|cached| cached.pending).unwrap_or_default();
+            (cache, view)
+        }
+    };
+
+    view.settle_pending(unix_now());
+    cache.pending = view.pending.clone();
+    cache.save(&path, key)?;
+
+    view.apply_pending(seedelf_policy_id);
+    Ok(view)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Records a submitted transaction so its inputs are not selected again before it
+/// confirms and its change can be spent right away.
+///
+/// Nothing is recorded without a cache, the next sync reads the chain anyway.
+///
+/// # Arguments
+///
+/// * `config` - The contract variant.
+/// * `key` - The wallet key.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod.
+///     - `false` for Mainnet.
+/// * `tx_bytes` - The submitted transaction.
+/// * `spent` - The wallet UTxOs it spends.
+///
+/// # Returns
+///
+/// * `Ok(())` - Once the transaction is recorded.
+/// * `Err(anyhow::Error)` - If the transaction can not be decoded or the cache not written.
+pub fn track_pending(
+    config: &Config,
+    key: &WalletKey,
+    network_flag: bool,
+    tx_bytes: &[u8],
+    spent: &[UtxoResponse],
+) -> Result<()> {
+    let path: PathBuf = cache_path(key, config, network_flag)?;
+    let Some(mut cache) = WalletCache::load(&path, key) else {
+        return Ok(());
+    };
+    let tx: MultiEraTx = MultiEraTx::decode(tx_bytes).context("Invalid Transaction")?;
+    let tx_hash: String = tx.hash().to_string();
+
+    let created: Vec<UtxoResponse> = tx
+        .outputs()
+        .iter()
+        .enumerate()
+        .map(|(tx_index, output)| output_response(output, tx_hash.clone(), tx_index as u64))
+        .collect();
+    let at_contract: Vec<&UtxoResponse> = cache.at_contract(&created);
+    let outputs: Vec<UtxoResponse> = at_contract
+        .iter()
+        .zip(owned(&at_contract, key)?)
+        .filter(|(_, owned)| *owned)
+        .map(|(utxo, _)| (*utxo).clone())
+        .collect();
+
+    cache.pending.retain(|pending| pending.tx_hash != tx_hash);
+    cache.pending.push(PendingTx {
+        tx_hash,
+        submitted_at: unix_now(),
+        inputs: spent
+            .iter()
+            .map(|utxo| (utxo.tx_hash.clone(), utxo.tx_index))
+            .collect(),
+        outputs,
+    });
+    cache.save(&path, key)
+}
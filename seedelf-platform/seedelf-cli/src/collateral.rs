@@ -0,0 +1,62 @@
+use crate::key::WalletKey;
+use crate::web_server;
+use anyhow::{Context, Result, anyhow, bail};
+use pallas_crypto::key::ed25519::PublicKey;
+use pallas_txbuilder::BuiltTransaction;
+use seedelf_core::collateral::{Collateral, CollateralKind, add_vkey_witnesses};
+use seedelf_core::constants::COLLATERAL_PUBLIC_KEY;
+use seedelf_display::display;
+use seedelf_koios::koios::witness_collateral;
+use serde_json::Value;
+
+/// Witnesses the collateral input of a transaction.
+///
+/// Any failure is returned, a transaction without its collateral witness would
+/// only be rejected by the node.
+///
+/// # Arguments
+///
+/// * `tx` - The transaction, already signed by every other key.
+/// * `collateral` - The collateral used by the transaction.
+/// * `key` - The wallet key, it signs `External` collateral.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod.
+///     - `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `Ok(BuiltTransaction)` - The transaction with the collateral witness.
+/// * `Err(anyhow::Error)` - If the witness could not be obtained.
+pub async fn witness(
+    tx: BuiltTransaction,
+    collateral: &Collateral,
+    key: &WalletKey,
+    network_flag: bool,
+) -> Result<BuiltTransaction> {
+    match collateral.kind {
+        CollateralKind::Service => {
+            let tx_cbor: String = hex::encode(tx.tx_bytes.as_ref());
+            let response: Value = witness_collateral(tx_cbor, network_flag)
+                .await
+                .context("Collateral Witness Request Failed")?;
+            let witness_cbor: &str = match response.get("witness").and_then(Value::as_str) {
+                Some(witness_cbor) if witness_cbor.len() >= 128 => witness_cbor,
+                _ => bail!("Collateral Witness Refused: {response}"),
+            };
+            let signature: [u8; 64] = hex::decode(&witness_cbor[witness_cbor.len() - 128..])
+                .context("Invalid Collateral Witness")?
+                .try_into()
+                .map_err(|_| anyhow!("Invalid Collateral Witness"))?;
+            tx.add_signature(PublicKey::from(COLLATERAL_PUBLIC_KEY), signature)
+                .map_err(|e| anyhow!("Failed To Add Collateral Witness: {e}"))
+        }
+        CollateralKind::External => key.sign(tx).context("Failed To Sign Collateral"),
+        CollateralKind::Cip30 => {
+            display::webserver_address();
+            let witness_set: Vec<u8> =
+                web_server::request_witness(hex::encode(tx.tx_bytes.as_ref()), network_flag)
+                    .await?;
+            add_vkey_witnesses(tx, &witness_set)
+        }
+    }
+}
@@ -1,44 +1,148 @@
+use crate::cache::{self, HistoryEntry, Movement, TokenAmount, WalletCache};
+use crate::key::WalletKey;
 use crate::setup;
-use anyhow::Result;
-use blstrs::Scalar;
+use anyhow::{Result, anyhow, bail};
+use chrono::NaiveDate;
+use clap::Args;
 use colored::Colorize;
 use hex;
-use seedelf_core::constants::{Config, get_config};
+use seedelf_core::constants::{Config, VARIANT, scan_variants};
 use seedelf_core::utxos;
 use seedelf_display::display;
 use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::provider::ChainProvider;
+use std::str::FromStr;
 
-pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
+/// A point in the past, a block height or the end of a UTC day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum At {
+    Block(u64),
+    Date(NaiveDate),
+}
+
+impl FromStr for At {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: &str = s.trim();
+        if let Ok(block) = s.parse::<u64>() {
+            return Ok(At::Block(block));
+        }
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(At::Date)
+            .map_err(|_| format!("Invalid Point: {s}, expected a block height or YYYY-MM-DD"))
+    }
+}
+
+impl At {
+    /// Whether a transaction happened at or before this point.
+    fn includes(&self, tx: &HistoryEntry) -> bool {
+        match self {
+            At::Block(block) => tx.block_height <= *block,
+            At::Date(date) => {
+                let end_of_day: i64 = date
+                    .and_hms_opt(23, 59, 59)
+                    .map(|time| time.and_utc().timestamp())
+                    .unwrap_or_default();
+                (tx.block_time as i64) <= end_of_day
+            }
+        }
+    }
+}
+
+/// Struct to hold command-specific arguments
+#[derive(Args)]
+pub struct BalanceArgs {
+    /// Show the balance as it was at a block height or date
+    #[arg(
+        long,
+        help = "Balance at a block height or YYYY-MM-DD in UTC, replayed from the history",
+        display_order = 1
+    )]
+    at: Option<At>,
+}
+
+pub async fn run(
+    args: BalanceArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: Option<u64>,
+    rescan: bool,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
-    display::block_number_and_time(network_flag).await;
+    display::block_number_and_time(provider).await;
+
+    let variants: Vec<(u64, Config)> = scan_variants(variant, network_flag);
+    if variants.is_empty() {
+        bail!("Invalid Variant");
+    }
 
     println!("{}", "\nSeedelf Wallet Information:".bright_white());
 
-    let scalar: Scalar = setup::unlock_wallet_interactive();
+    let key: WalletKey = setup::wallet_key();
 
-    let config: Config = get_config(variant, network_flag).unwrap_or_else(|| {
-        eprintln!("Error: Invalid Variant");
-        std::process::exit(1);
-    });
+    let mut total_lovelace: u64 = 0;
+    let mut stranded: Vec<u64> = Vec::new();
+    for (variant, config) in &variants {
+        let current: &str = if *variant == VARIANT {
+            " (current)"
+        } else {
+            ""
+        };
+        println!("{}", format!("\nVariant {variant}{current}").bright_cyan());
 
-    let seedelfs = display::all_seedelfs(
-        scalar,
-        network_flag,
-        hex::encode(config.contract.wallet_contract_hash).as_str(),
-        &config.contract.seedelf_policy_id,
-    )
-    .await;
+        let wallet: WalletCache = cache::sync(provider, config, &key, network_flag, rescan).await?;
 
-    if !seedelfs.is_empty() {
+        let (held, lovelace): (usize, u64) = match args.at {
+            Some(at) => {
+                let history: Vec<HistoryEntry> = wallet
+                    .history
+                    .ok_or_else(|| anyhow!("History Is Not Available From This Backend"))?;
+                print_balance_at(at, &history, &config.contract.seedelf_policy_id)
+            }
+            None => print_balance(&wallet, &config.contract.seedelf_policy_id)?,
+        };
+        total_lovelace += lovelace;
+        if *variant != VARIANT && held > 0 {
+            stranded.push(*variant);
+        }
+    }
+
+    if variants.len() > 1 {
+        println!(
+            "\nTotal Balance Across {} Variants: {} ₳",
+            variants.len(),
+            format!("{:.6}", total_lovelace as f64 / 1_000_000.0).bright_yellow()
+        );
+    }
+    for variant in stranded {
+        println!(
+            "{}",
+            format!(
+                "\nFunds Are Left In Variant {variant}, Move Them With `--variant {variant} sweep --all --address <address>` Then `fund` A Seedelf Of Variant {VARIANT}"
+            )
+            .bright_red()
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints the seedelfs, UTxOs and balance held under one variant.
+///
+/// # Returns
+///
+/// * `Ok((usize, u64))` - The seedelfs and UTxOs held, and the lovelace balance.
+/// * `Err(anyhow::Error)` - If a UTxO value can not be read.
+fn print_balance(wallet: &WalletCache, seedelf_policy_id: &str) -> Result<(usize, u64)> {
+    if !wallet.seedelfs.is_empty() {
         println!("{}", "\nCurrent Seedelf:\n".bright_green());
-        display::print_seedelfs(seedelfs);
+        display::print_seedelfs(wallet.seedelfs.clone());
     }
 
-    let every_utxo: Vec<UtxoResponse> =
-        utxos::get_credential_utxos(config.contract.wallet_contract_hash, network_flag).await?;
-    let all_utxos: Vec<UtxoResponse> =
-        utxos::collect_all_wallet_utxos(scalar, &config.contract.seedelf_policy_id, every_utxo)?;
+    let all_utxos: Vec<UtxoResponse> = wallet.wallet_utxos(seedelf_policy_id);
+    let held: usize = wallet.seedelfs.len() + all_utxos.len();
 
     let (total_lovelace, tokens) = utxos::assets_of(all_utxos.clone())?;
 
@@ -67,5 +171,74 @@ pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
         }
     }
 
-    Ok(())
+    Ok((held, total_lovelace))
+}
+
+/// Prints what the wallet held at a past point, seedelfs apart from the balance.
+///
+/// # Returns
+///
+/// * `(usize, u64)` - The seedelfs and UTxOs held, and the lovelace balance.
+fn print_balance_at(at: At, history: &[HistoryEntry], seedelf_policy_id: &str) -> (usize, u64) {
+    let held: Vec<Movement> = cache::utxos_at(history, |tx| at.includes(tx));
+    let (seedelfs, held): (Vec<Movement>, Vec<Movement>) = held.into_iter().partition(|utxo| {
+        utxo.tokens
+            .iter()
+            .any(|token| token.policy_id == seedelf_policy_id)
+    });
+    let point: String = match at {
+        At::Block(block) => format!("Block {block}"),
+        At::Date(date) => format!("The End Of {date}"),
+    };
+
+    if !seedelfs.is_empty() {
+        println!("{}", format!("\nSeedelf At {point}:\n").bright_green());
+        display::print_seedelfs(
+            seedelfs
+                .iter()
+                .flat_map(|utxo| &utxo.tokens)
+                .filter(|token| token.policy_id == seedelf_policy_id)
+                .map(|token| token.asset_name.clone())
+                .collect(),
+        );
+    }
+
+    println!(
+        "\nWallet Had {} UTxOs At {}",
+        held.len().to_string().bright_yellow(),
+        point
+    );
+    let mut lovelace: u64 = 0;
+    let mut tokens: Vec<TokenAmount> = Vec::new();
+    for utxo in &held {
+        println!("UTxO: {}#{}", utxo.tx_hash, utxo.tx_index);
+        lovelace += utxo.lovelace;
+        for token in &utxo.tokens {
+            match tokens
+                .iter_mut()
+                .find(|t| t.policy_id == token.policy_id && t.asset_name == token.asset_name)
+            {
+                Some(t) => t.amount += token.amount,
+                None => tokens.push(token.clone()),
+            }
+        }
+    }
+    println!(
+        "\nBalance: {} ₳",
+        format!("{:.6}", lovelace as f64 / 1_000_000.0).bright_yellow()
+    );
+
+    if !tokens.is_empty() {
+        println!("{}", "\nTokens:\n".bright_magenta());
+        for token in tokens {
+            println!(
+                "{} {}.{}",
+                token.amount.to_string().white(),
+                token.policy_id.white(),
+                token.asset_name.white()
+            );
+        }
+    }
+
+    (seedelfs.len() + held.len(), lovelace)
 }
@@ -0,0 +1,58 @@
+use crate::config::Config;
+use anyhow::{Result, bail};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print the value of a key
+    Get {
+        /// profile, wallet, update_check, or a profile key, optionally as <profile>.<key>
+        key: String,
+    },
+    /// Set a key, an empty value removes it
+    Set {
+        /// profile, wallet, update_check, or a profile key, optionally as <profile>.<key>
+        key: String,
+        /// The new value
+        value: String,
+    },
+    /// List every key that is set
+    List,
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+pub fn run(args: ConfigArgs, selected_profile: Option<&str>) -> Result<()> {
+    let mut config: Config = Config::load()?;
+    match args.command {
+        ConfigCommands::Get { key } => match config.get(&key, selected_profile)? {
+            Some(value) => println!("{value}"),
+            None => bail!("{key} Is Not Set"),
+        },
+        ConfigCommands::Set { key, value } => {
+            config.set(&key, &value, selected_profile)?;
+            config.save()?;
+            println!(
+                "{} {}",
+                "Saved To".bright_blue(),
+                Config::path().display().to_string().bright_white()
+            );
+        }
+        ConfigCommands::List => {
+            println!(
+                "{} {}",
+                "Active Profile:".bright_blue(),
+                config.active_profile(selected_profile).bright_white()
+            );
+            for (key, value) in config.list() {
+                println!("{key} = {value}");
+            }
+        }
+    }
+    Ok(())
+}
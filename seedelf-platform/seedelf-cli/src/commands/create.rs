@@ -1,25 +1,28 @@
 use crate::setup;
 use crate::web_server;
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use blstrs::Scalar;
 use clap::Args;
 use colored::Colorize;
 use hex;
 use pallas_addresses::Address;
 use pallas_crypto::key::ed25519::SecretKey;
-use pallas_traverse::fees;
 use pallas_txbuilder::{BuildConway, BuiltTransaction, Input, Output, StagingTransaction};
 use pallas_wallet::PrivateKey;
 use rand_core::OsRng;
 use seedelf_core::address;
 use seedelf_core::assets::Assets;
-use seedelf_core::constants::{Config, get_config, plutus_v3_cost_model};
+use seedelf_core::collateral::set_total_collateral;
+use seedelf_core::constants::{Config, get_config};
 use seedelf_core::data_structures;
+use seedelf_core::evaluate::{self, Evaluation};
 use seedelf_core::transaction;
 use seedelf_core::utxos;
 use seedelf_crypto::register::Register;
 use seedelf_display::{display, text_coloring};
-use seedelf_koios::koios::{UtxoResponse, address_utxos, evaluate_transaction};
+use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -42,10 +45,10 @@ pub struct CreateArgs {
     #[arg(
         short = 'a',
         long,
-        help = "The address paying for the seedelf.",
+        help = "The address paying for the seedelf. Defaults to the address in the config.",
         display_order = 1
     )]
-    address: String,
+    address: Option<String>,
 
     #[arg(
         short = 'l',
@@ -56,7 +59,22 @@ pub struct CreateArgs {
     label: Option<String>,
 }
 
-pub async fn run(args: CreateArgs, network_flag: bool, variant: u64) -> Result<()> {
+impl CreateArgs {
+    /// Uses the profile's address when `--address` was left out.
+    pub fn default_address(&mut self, address: Option<String>) {
+        if self.address.is_none() {
+            self.address = address;
+        }
+    }
+}
+
+pub async fn run(
+    args: CreateArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: u64,
+    evaluation: Evaluation,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
 
@@ -64,9 +82,13 @@ pub async fn run(args: CreateArgs, network_flag: bool, variant: u64) -> Result<(
         eprintln!("Error: Invalid Variant");
         std::process::exit(1);
     });
+    let params: &ProtocolParameters = &setup::protocol_parameters(provider, network_flag).await;
 
     // we need to make sure that the network flag and the address provided makes sense here
-    let addr: Address = Address::from_bech32(args.address.as_str()).unwrap();
+    let user_address: String = args
+        .address
+        .ok_or_else(|| anyhow!("No Address Given, Use --address Or Set address In The Config"))?;
+    let addr: Address = Address::from_bech32(user_address.as_str())?;
     if !(address::is_not_a_script(addr.clone())
         && address::is_on_correct_network(addr.clone(), network_flag))
     {
@@ -88,8 +110,11 @@ pub async fn run(args: CreateArgs, network_flag: bool, variant: u64) -> Result<(
         mem_units,
     } = build_create_seedelf(
         config,
+        provider,
         network_flag,
-        args.address,
+        params,
+        evaluation,
+        user_address,
         args.label.unwrap_or_default(),
         scalar,
     )
@@ -101,7 +126,7 @@ pub async fn run(args: CreateArgs, network_flag: bool, variant: u64) -> Result<(
 
     // // we need about 2 ada for the utxo
     let tmp_fee: u64 = 205_000;
-    let lovelace_goal: u64 = transaction::seedelf_minimum_lovelace()? + tmp_fee;
+    let lovelace_goal: u64 = transaction::seedelf_minimum_lovelace(params)? + tmp_fee;
 
     // if the lovelace isn't enough then error
     if total_lovelace < lovelace_goal {
@@ -155,8 +180,8 @@ pub async fn run(args: CreateArgs, network_flag: bool, variant: u64) -> Result<(
 }
 
 pub async fn assign_collateral_and_get_utxos(
+    provider: &impl ChainProvider,
     address: String,
-    network_flag: bool,
     mut draft_tx: StagingTransaction,
 ) -> (StagingTransaction, Vec<UtxoResponse>) {
     // utxos
@@ -165,7 +190,7 @@ pub async fn assign_collateral_and_get_utxos(
     let mut found_collateral: bool = false;
 
     // This should probably be some generalized function later
-    match address_utxos(&address, network_flag).await {
+    match provider.address_utxos(&address).await {
         Ok(utxos) => {
             // loop all the utxos found from the address
             for utxo in utxos {
@@ -196,9 +221,13 @@ pub async fn assign_collateral_and_get_utxos(
     (draft_tx, all_utxos)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn build_create_seedelf(
     config: Config,
+    provider: &impl ChainProvider,
     network_flag: bool,
+    params: &ProtocolParameters,
+    evaluation: Evaluation,
     user_address: String,
     label: String,
     scalar: Scalar,
@@ -215,15 +244,16 @@ pub async fn build_create_seedelf(
 
     // we need about 2 ada for the utxo
     let tmp_fee: u64 = 205_000;
-    let lovelace_goal: u64 = transaction::seedelf_minimum_lovelace().unwrap_or_default() + tmp_fee;
+    let lovelace_goal: u64 =
+        transaction::seedelf_minimum_lovelace(params).unwrap_or_default() + tmp_fee;
 
     // This should probably be some generalized function later
     let (mut draft_tx, all_utxos) =
-        assign_collateral_and_get_utxos(user_address, network_flag, draft_tx).await;
+        assign_collateral_and_get_utxos(provider, user_address, draft_tx).await;
 
     // lovelace goal here should account for the estimated fee
     let selected_utxos: Vec<UtxoResponse> =
-        utxos::select(all_utxos, lovelace_goal, Assets::new()).unwrap_or_default();
+        utxos::select(all_utxos, lovelace_goal, Assets::new(), params).unwrap_or_default();
     for utxo in selected_utxos.clone() {
         // draft and raw are built the same here
         draft_tx = draft_tx.input(Input::new(
@@ -237,7 +267,7 @@ pub async fn build_create_seedelf(
         ));
     }
 
-    let (total_lovelace, tokens) = utxos::assets_of(selected_utxos).unwrap_or_default();
+    let (total_lovelace, tokens) = utxos::assets_of(selected_utxos.clone()).unwrap_or_default();
 
     let datum_vector: Vec<u8> = Register::create(scalar)
         .unwrap_or_default()
@@ -253,7 +283,7 @@ pub async fn build_create_seedelf(
         transaction::seedelf_token_name(label.clone(), draft_tx.inputs.as_ref())
             .unwrap_or_default();
 
-    let min_utxo: u64 = transaction::seedelf_minimum_lovelace().unwrap_or_default();
+    let min_utxo: u64 = transaction::seedelf_minimum_lovelace(params).unwrap_or_default();
 
     let mut change_output: Output = Output::new(addr.clone(), total_lovelace - min_utxo - tmp_fee);
     for asset in tokens.items.clone() {
@@ -311,7 +341,7 @@ pub async fn build_create_seedelf(
         )
         .language_view(
             pallas_txbuilder::ScriptKind::PlutusV3,
-            plutus_v3_cost_model(),
+            params.plutus_v3_cost_model.clone(),
         );
 
     // clone the tx but remove the tmp fee, collateral, change output, and fake redeemer
@@ -331,27 +361,30 @@ pub async fn build_create_seedelf(
     let intermediate_tx: BuiltTransaction = draft_tx.build_conway_raw().unwrap();
 
     // Lets evaluate the transaction to get the execution units
-    let (cpu_units, mem_units) =
-        match evaluate_transaction(hex::encode(intermediate_tx.tx_bytes.as_ref()), network_flag)
-            .await
-        {
-            Ok(execution_units) => {
-                if let Some(_error) = execution_units.get("error") {
-                    println!("Error: {execution_units:?}");
-                    std::process::exit(1);
-                }
-                let cpu_units: u64 = execution_units
-                    .pointer("/result/0/budget/cpu")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-                let mem_units: u64 = execution_units
-                    .pointer("/result/0/budget/memory")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-                (cpu_units, mem_units)
-            }
-            Err(_) => (0, 0),
-        };
+    let resolved: Vec<(Input, Output)> = evaluate::resolve_inputs(
+        &selected_utxos,
+        &[(
+            config.reference.seedelf_reference_utxo,
+            &config.contract.seedelf_contract_cbor,
+        )],
+    )
+    .unwrap_or_default();
+    let (cpu_units, mem_units) = match evaluate::evaluate_transaction(
+        provider,
+        intermediate_tx.tx_bytes.as_ref(),
+        &resolved,
+        params,
+        network_flag,
+        evaluation,
+    )
+    .await
+    {
+        Ok(budgets) => budgets.first().copied().unwrap_or_default(),
+        Err(err) => {
+            eprintln!("Failed to evaluate transaction: {err}");
+            (0, 0)
+        }
+    };
 
     // we can fake the signature here to get the correct tx size
     let fake_signer_secret_key: SecretKey = SecretKey::new(OsRng);
@@ -367,9 +400,10 @@ pub async fn build_create_seedelf(
         .try_into()
         .unwrap();
 
-    let tx_fee: u64 = fees::compute_linear_fee_policy(tx_size, &(fees::PolicyParams::default()));
-    let compute_fee: u64 = transaction::computation_fee(mem_units, cpu_units);
-    let script_reference_fee: u64 = config.contract.seedelf_contract_size * 15;
+    let tx_fee: u64 = params.min_fee(tx_size);
+    let compute_fee: u64 = transaction::computation_fee(mem_units, cpu_units, params);
+    let script_reference_fee: u64 =
+        params.reference_script_fee(config.contract.seedelf_contract_size);
 
     // total fee is the sum
     let mut total_fee: u64 = tx_fee + compute_fee + script_reference_fee;
@@ -407,7 +441,10 @@ pub async fn build_create_seedelf(
             }),
         );
 
-    let tx: BuiltTransaction = raw_tx.build_conway_raw().unwrap();
+    // the collateral return leaves 3/2 of the fee at stake
+    let tx: BuiltTransaction =
+        set_total_collateral(raw_tx.build_conway_raw().unwrap(), (total_fee) * 3 / 2)
+            .expect("Failed To Set Total Collateral");
 
     let tx_cbor: String = hex::encode(tx.tx_bytes);
 
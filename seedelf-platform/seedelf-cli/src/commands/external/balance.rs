@@ -8,11 +8,12 @@ use seedelf_core::utxos;
 use seedelf_crypto::convert;
 use seedelf_display::display;
 use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::provider::ChainProvider;
 
-pub async fn run(network_flag: bool) -> Result<()> {
+pub async fn run(provider: &impl ChainProvider, network_flag: bool) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
-    display::block_number_and_time(network_flag).await;
+    display::block_number_and_time(provider).await;
 
     println!(
         "{}: {}",
@@ -32,7 +33,7 @@ pub async fn run(network_flag: bool) -> Result<()> {
     let addr_bech32: String = addr.to_bech32().unwrap();
     println!("\nAddress: {}", addr_bech32.bright_blue());
 
-    let all_utxos: Vec<UtxoResponse> = utxos::get_address_utxos(&addr_bech32, network_flag).await?;
+    let all_utxos: Vec<UtxoResponse> = utxos::get_address_utxos(provider, &addr_bech32).await?;
     let (total_lovelace, tokens) = utxos::assets_of(all_utxos.clone())?;
 
     println!(
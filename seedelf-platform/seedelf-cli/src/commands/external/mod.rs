@@ -1,4 +1,6 @@
+use anyhow::Result;
 use clap::{Args, Subcommand};
+use seedelf_koios::provider::ChainProvider;
 
 pub mod balance;
 pub mod sweep;
@@ -17,17 +19,14 @@ pub struct ExternalArgs {
     pub command: ExternalCommands,
 }
 
-pub async fn run(args: ExternalArgs, preprod_flag: bool, variant: u64) {
+pub async fn run(
+    args: ExternalArgs,
+    provider: &impl ChainProvider,
+    preprod_flag: bool,
+    variant: u64,
+) -> Result<()> {
     match args.command {
-        ExternalCommands::Balance => {
-            if let Err(err) = balance::run(preprod_flag).await {
-                eprintln!("Error: {err}");
-            }
-        }
-        ExternalCommands::Sweep => {
-            if let Err(err) = sweep::run(preprod_flag, variant).await {
-                eprintln!("Error: {err}");
-            }
-        }
+        ExternalCommands::Balance => balance::run(provider, preprod_flag).await,
+        ExternalCommands::Sweep => sweep::run(provider, preprod_flag, variant).await,
     }
 }
@@ -4,7 +4,6 @@ use blstrs::Scalar;
 use colored::Colorize;
 use pallas_addresses::Address;
 use pallas_crypto::key::ed25519::SecretKey;
-use pallas_traverse::fees;
 use pallas_txbuilder::{BuildConway, BuiltTransaction, Input, Output, StagingTransaction};
 use pallas_wallet::PrivateKey;
 use rand_core::OsRng;
@@ -16,9 +15,11 @@ use seedelf_core::utxos;
 use seedelf_crypto::convert;
 use seedelf_crypto::register::Register;
 use seedelf_display::display;
-use seedelf_koios::koios::{UtxoResponse, submit_tx};
+use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
 
-pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
+pub async fn run(provider: &impl ChainProvider, network_flag: bool, variant: u64) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
     println!("\n{}", "Sweeping All External UTxOs".bright_blue(),);
@@ -27,6 +28,7 @@ pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
         eprintln!("Error: Invalid Variant");
         std::process::exit(1);
     });
+    let params: &ProtocolParameters = &setup::protocol_parameters(provider, network_flag).await;
 
     let wallet_addr: Address =
         address::wallet_contract(network_flag, config.contract.wallet_contract_hash);
@@ -40,7 +42,7 @@ pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
     let addr: Address = address::dapp_address(vkey.clone(), network_flag)?;
     let addr_bech32: String = addr.to_bech32().unwrap();
 
-    let all_utxos: Vec<UtxoResponse> = utxos::get_address_utxos(&addr_bech32, network_flag).await?;
+    let all_utxos: Vec<UtxoResponse> = utxos::get_address_utxos(provider, &addr_bech32).await?;
     if all_utxos.is_empty() {
         bail!("Not Enough Lovelace/Tokens");
     }
@@ -81,7 +83,7 @@ pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
     // a max tokens per change output here
     for (i, change) in change_token_per_utxo.iter().enumerate() {
         let datum_vector: Vec<u8> = Register::create(scalar)?.rerandomize()?.to_vec()?;
-        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone())?;
+        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone(), params)?;
         let change_lovelace: u64 = if i == number_of_change_utxo - 1 {
             // this is the last one or the only one
             lovelace_amount -= tmp_fee;
@@ -129,7 +131,7 @@ pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
         .len()
         .try_into()
         .unwrap();
-    let tx_fee = fees::compute_linear_fee_policy(tx_size, &(fees::PolicyParams::default()));
+    let tx_fee = params.min_fee(tx_size);
     println!(
         "{} {}",
         "\nTx Size Fee:".bright_blue(),
@@ -147,7 +149,7 @@ pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
     let mut lovelace_amount: u64 = total_lovelace;
     for (i, change) in change_token_per_utxo.iter().enumerate() {
         let datum_vector: Vec<u8> = Register::create(scalar)?.rerandomize()?.to_vec()?;
-        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone())?;
+        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone(), params)?;
         let change_lovelace: u64 = if i == number_of_change_utxo - 1 {
             // this is the last one or the only one
             lovelace_amount -= tx_fee;
@@ -187,7 +189,10 @@ pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
         hex::encode(signed_tx_cbor.tx_bytes.clone()).white()
     );
 
-    match submit_tx(hex::encode(signed_tx_cbor.tx_bytes), network_flag).await {
+    match provider
+        .submit_tx(hex::encode(signed_tx_cbor.tx_bytes))
+        .await
+    {
         Ok(response) => {
             if let Some(_error) = response.get("contents") {
                 println!("\nError: {response}");
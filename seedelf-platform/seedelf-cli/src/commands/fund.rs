@@ -1,11 +1,11 @@
+use crate::setup;
 use crate::web_server;
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use clap::Args;
 use colored::Colorize;
 use hex;
 use pallas_addresses::Address;
 use pallas_crypto::key::ed25519::SecretKey;
-use pallas_traverse::fees;
 use pallas_txbuilder::{BuildConway, BuiltTransaction, Input, Output, StagingTransaction};
 use pallas_wallet::PrivateKey;
 use rand_core::OsRng;
@@ -17,6 +17,8 @@ use seedelf_core::utxos;
 use seedelf_crypto::register::Register;
 use seedelf_display::{display, text_coloring};
 use seedelf_koios::koios::{UtxoResponse, extract_bytes_with_logging};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -33,10 +35,10 @@ pub struct FundArgs {
     #[arg(
         short = 'a',
         long,
-        help = "The address sending funds to the seedelf.",
+        help = "The address sending funds to the seedelf. Defaults to the address in the config.",
         display_order = 1
     )]
-    address: String,
+    address: Option<String>,
 
     /// seedelf to send funds too
     #[arg(
@@ -87,7 +89,21 @@ pub struct FundArgs {
     amount: Option<Vec<u64>>,
 }
 
-pub async fn run(args: FundArgs, network_flag: bool, variant: u64) -> Result<()> {
+impl FundArgs {
+    /// Uses the profile's address when `--address` was left out.
+    pub fn default_address(&mut self, address: Option<String>) {
+        if self.address.is_none() {
+            self.address = address;
+        }
+    }
+}
+
+pub async fn run(
+    args: FundArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: u64,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
 
@@ -102,6 +118,7 @@ pub async fn run(args: FundArgs, network_flag: bool, variant: u64) -> Result<()>
         eprintln!("Error: Invalid Variant");
         std::process::exit(1);
     });
+    let params: &ProtocolParameters = &setup::protocol_parameters(provider, network_flag).await;
 
     // lets collect the tokens if they exist
     let mut selected_tokens: Assets = Assets::new();
@@ -112,11 +129,7 @@ pub async fn run(args: FundArgs, network_flag: bool, variant: u64) -> Result<()>
             bail!("Error: Each --policy-id must have a corresponding --token-name and --amount.",);
         }
 
-        for ((pid, tkn), amt) in policy_id
-            .into_iter()
-            .zip(token_name.into_iter())
-            .zip(amount.into_iter())
-        {
+        for ((pid, tkn), amt) in policy_id.into_iter().zip(token_name).zip(amount) {
             if amt == 0 {
                 bail!("Error: Token Amount must be positive");
             }
@@ -125,13 +138,17 @@ pub async fn run(args: FundArgs, network_flag: bool, variant: u64) -> Result<()>
         }
     }
 
-    let minimum_lovelace: u64 = wallet_minimum_lovelace_with_assets(selected_tokens.clone())?;
+    let minimum_lovelace: u64 =
+        wallet_minimum_lovelace_with_assets(selected_tokens.clone(), params)?;
     if args.lovelace.is_some_and(|l| l < minimum_lovelace) {
         bail!("Not Enough Lovelace On UTxO");
     }
 
     // we need to make sure that the network flag and the address provided makes sense here
-    let addr: Address = Address::from_bech32(args.address.as_str()).unwrap();
+    let user_address: String = args
+        .address
+        .ok_or_else(|| anyhow!("No Address Given, Use --address Or Set address In The Config"))?;
+    let addr: Address = Address::from_bech32(user_address.as_str())?;
     if !(address::is_not_a_script(addr.clone())
         && address::is_on_correct_network(addr.clone(), network_flag))
     {
@@ -144,8 +161,10 @@ pub async fn run(args: FundArgs, network_flag: bool, variant: u64) -> Result<()>
         usable_utxos,
     } = build_fund_seedelf(
         config,
+        provider,
         network_flag,
-        args.address,
+        params,
+        user_address,
         args.seedelf,
         args.lovelace.unwrap_or(minimum_lovelace),
         selected_tokens,
@@ -172,9 +191,12 @@ pub async fn run(args: FundArgs, network_flag: bool, variant: u64) -> Result<()>
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn build_fund_seedelf(
     config: Config,
+    provider: &impl ChainProvider,
     network_flag: bool,
+    params: &ProtocolParameters,
     user_address: String,
     seedelf: String,
     lovelace: u64,
@@ -190,34 +212,35 @@ pub async fn build_fund_seedelf(
     // this is used to calculate the real fee
     let mut draft_tx: StagingTransaction = StagingTransaction::new();
 
-    let every_utxo_at_script: Vec<UtxoResponse> =
-        utxos::get_credential_utxos(config.contract.wallet_contract_hash, network_flag)
-            .await
-            .unwrap_or_default();
-
-    let seedelf_utxo: UtxoResponse = match utxos::find_seedelf_utxo(
-        seedelf.clone(),
+    let seedelf_utxo: UtxoResponse = utxos::scan_seedelf_utxo(
+        provider,
+        config.contract.wallet_contract_hash,
+        &seedelf,
         &config.contract.seedelf_policy_id,
-        every_utxo_at_script,
-    ) {
-        Ok(Some(utxo)) => utxo,
-        _ => UtxoResponse::default(),
-    };
+    )
+    .await
+    .ok()
+    .flatten()
+    .unwrap_or_default();
 
     let seedelf_datum: Register = extract_bytes_with_logging(&seedelf_utxo.inline_datum)
         .ok_or("Not Register Type".to_string())
         .unwrap_or_default();
 
     let every_utxo_at_address: Vec<UtxoResponse> =
-        utxos::get_address_utxos(&user_address, network_flag)
+        utxos::get_address_utxos(provider, &user_address)
             .await
             .unwrap_or_default();
     // all non collateral utxos, assume 5 ada for collateral
     let every_non_collatreal_utxo: Vec<UtxoResponse> =
         utxos::collect_address_utxos(every_utxo_at_address).unwrap_or_default();
-    let usable_utxos: Vec<UtxoResponse> =
-        utxos::select(every_non_collatreal_utxo, lovelace, selected_tokens.clone())
-            .unwrap_or_default();
+    let usable_utxos: Vec<UtxoResponse> = utxos::select(
+        every_non_collatreal_utxo,
+        lovelace,
+        selected_tokens.clone(),
+        params,
+    )
+    .unwrap_or_default();
 
     let (total_lovelace, tokens) = utxos::assets_of(usable_utxos.clone()).unwrap_or_default();
     let change_tokens: Assets = tokens.separate(selected_tokens.clone()).unwrap_or_default();
@@ -262,7 +285,8 @@ pub async fn build_fund_seedelf(
     let mut number_of_change_utxo: usize = change_token_per_utxo.len();
     let mut lovelace_amount: u64 = total_lovelace;
     for (i, change) in change_token_per_utxo.iter().enumerate() {
-        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone()).unwrap_or_default();
+        let minimum: u64 =
+            wallet_minimum_lovelace_with_assets(change.clone(), params).unwrap_or_default();
         let change_lovelace: u64 = if i == number_of_change_utxo - 1 {
             // this is the last one or the only one
             lovelace_amount = lovelace_amount - lovelace - tmp_fee;
@@ -314,8 +338,7 @@ pub async fn build_fund_seedelf(
         .try_into()
         .unwrap();
     // floor division means its safer to just add 1 lovelace
-    let tx_fee: u64 =
-        fees::compute_linear_fee_policy(tx_size, &(fees::PolicyParams::default())) + 1;
+    let tx_fee: u64 = params.min_fee(tx_size) + 1;
 
     // a max tokens per change output here
     let change_token_per_utxo: Vec<Assets> = change_tokens
@@ -324,7 +347,8 @@ pub async fn build_fund_seedelf(
     let number_of_change_utxo: usize = change_token_per_utxo.len();
     let mut lovelace_amount: u64 = total_lovelace;
     for (i, change) in change_token_per_utxo.iter().enumerate() {
-        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone()).unwrap_or_default();
+        let minimum: u64 =
+            wallet_minimum_lovelace_with_assets(change.clone(), params).unwrap_or_default();
         let change_lovelace: u64 = if i == number_of_change_utxo - 1 {
             // this is the last one or the only one
             lovelace_amount = lovelace_amount - lovelace - tx_fee;
@@ -1,4 +1,5 @@
 pub mod balance;
+pub mod config;
 pub mod create;
 pub mod external;
 pub mod fund;
@@ -6,4 +7,5 @@ pub mod remove;
 pub mod sweep;
 pub mod transfer;
 pub mod util;
+pub mod wallet;
 pub mod welcome;
@@ -1,29 +1,30 @@
+use crate::collateral;
+use crate::key::WalletKey;
 use crate::setup;
 use anyhow::{Result, bail};
-use blstrs::Scalar;
 use clap::Args;
 use colored::Colorize;
 use hex;
 use pallas_addresses::Address;
-use pallas_crypto::key::ed25519::{PublicKey, SecretKey};
+use pallas_crypto::key::ed25519::SecretKey;
 use pallas_primitives::Hash;
-use pallas_traverse::fees;
 use pallas_txbuilder::{BuildConway, BuiltTransaction, Input, Output, StagingTransaction};
 use pallas_wallet::PrivateKey;
 use rand_core::OsRng;
 use seedelf_core::address;
-use seedelf_core::constants::{
-    COLLATERAL_HASH, COLLATERAL_PUBLIC_KEY, Config, get_config, plutus_v3_cost_model,
+use seedelf_core::collateral::{
+    Collateral, CollateralSource, select_collateral, set_total_collateral,
 };
+use seedelf_core::constants::{Config, get_config};
 use seedelf_core::data_structures;
+use seedelf_core::evaluate::{self, Evaluation};
 use seedelf_core::transaction;
 use seedelf_core::utxos;
 use seedelf_crypto::register::Register;
-use seedelf_crypto::schnorr::{create_proof, random_scalar};
 use seedelf_display::display;
-use seedelf_koios::koios::{
-    UtxoResponse, evaluate_transaction, extract_bytes_with_logging, submit_tx, witness_collateral,
-};
+use seedelf_koios::koios::{UtxoResponse, extract_bytes_with_logging};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -55,7 +56,14 @@ pub struct RemoveArgs {
     address: String,
 }
 
-pub async fn run(args: RemoveArgs, network_flag: bool, variant: u64) -> Result<()> {
+pub async fn run(
+    args: RemoveArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: u64,
+    evaluation: Evaluation,
+    collateral_source: CollateralSource,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
 
@@ -63,6 +71,7 @@ pub async fn run(args: RemoveArgs, network_flag: bool, variant: u64) -> Result<(
         eprintln!("Error: Invalid Variant");
         std::process::exit(1);
     });
+    let params: &ProtocolParameters = &setup::protocol_parameters(provider, network_flag).await;
 
     // we need to make sure that the network flag and the address provided makes sense here
     let addr: Address = Address::from_bech32(args.address.as_str()).unwrap();
@@ -73,7 +82,9 @@ pub async fn run(args: RemoveArgs, network_flag: bool, variant: u64) -> Result<(
     }
 
     // There is a single register here so we can do this
-    let scalar: Scalar = setup::unlock_wallet_interactive();
+    let key: WalletKey = setup::wallet_key();
+    let collateral: Collateral =
+        select_collateral(provider, &collateral_source, &key.key_hash()?, network_flag).await?;
 
     let RemoveSeedelfOutput {
         tx_cbor,
@@ -86,7 +97,18 @@ pub async fn run(args: RemoveArgs, network_flag: bool, variant: u64) -> Result<(
         mint_mem_units,
         spend_cpu_units,
         spend_mem_units,
-    } = build_remove_seedelf(config, network_flag, args.address, args.seedelf, scalar).await;
+    } = build_remove_seedelf(
+        config,
+        provider,
+        network_flag,
+        params,
+        evaluation,
+        &collateral,
+        args.address,
+        args.seedelf,
+        &key,
+    )
+    .await?;
 
     if mint_cpu_units == 0 || mint_mem_units == 0 || spend_cpu_units == 0 || spend_mem_units == 0 {
         bail!("Invalid Transaction");
@@ -135,36 +157,34 @@ pub async fn run(args: RemoveArgs, network_flag: bool, variant: u64) -> Result<(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn build_remove_seedelf(
     config: Config,
+    provider: &impl ChainProvider,
     network_flag: bool,
+    params: &ProtocolParameters,
+    evaluation: Evaluation,
+    collateral: &Collateral,
     user_address: String,
     seedelf: String,
-    scalar: Scalar,
-) -> RemoveSeedelfOutput {
+    key: &WalletKey,
+) -> Result<RemoveSeedelfOutput> {
     let addr: Address = Address::from_bech32(&user_address).unwrap();
 
-    // we need this as the address type and not the shelley
-    let collat_addr: Address = address::collateral_address(network_flag);
-
     // this is used to calculate the real fee
     let mut draft_tx: StagingTransaction = StagingTransaction::new();
 
     // we do this so I can initialize it to the empty vector
     let mut input_vector: Vec<Input> = Vec::new();
 
-    let every_utxo: Vec<UtxoResponse> =
-        utxos::get_credential_utxos(config.contract.wallet_contract_hash, network_flag)
-            .await
-            .unwrap_or_default();
-    let seedelf_utxo: UtxoResponse = match utxos::find_seedelf_utxo(
-        seedelf.clone(),
+    let seedelf_utxo: UtxoResponse = utxos::scan_seedelf_utxo(
+        provider,
+        config.contract.wallet_contract_hash,
+        &seedelf,
         &config.contract.seedelf_policy_id,
-        every_utxo,
-    ) {
-        Ok(Some(utxo)) => utxo,
-        _ => UtxoResponse::default(),
-    };
+    )
+    .await?
+    .unwrap_or_default();
 
     let seedelf_datum: Register = extract_bytes_with_logging(&seedelf_utxo.inline_datum)
         .ok_or("Not Register Type".to_string())
@@ -181,6 +201,9 @@ pub async fn build_remove_seedelf(
         seedelf_utxo.tx_index,
     );
     draft_tx = draft_tx.input(seedelf_input.clone());
+    if let Some(datum) = transaction::witness_datum(&seedelf_utxo)? {
+        draft_tx = draft_tx.datum(datum);
+    }
     input_vector.push(seedelf_input.clone());
 
     // This is some semi legit fee to be used to estimate it
@@ -195,8 +218,7 @@ pub async fn build_remove_seedelf(
 
     // use the base register to rerandomize for the datum
 
-    let r: Scalar = random_scalar();
-    let (z, g_r) = create_proof(seedelf_datum, scalar, pkh.clone(), r).unwrap_or_default();
+    let (z, g_r) = key.create_proof(seedelf_datum, pkh.clone())?;
     let spend_redeemer_vector: Vec<u8> =
         data_structures::create_spend_redeemer(z, g_r, pkh.clone()).unwrap_or_default();
     let burn_redeemer_vector: Vec<u8> =
@@ -205,11 +227,6 @@ pub async fn build_remove_seedelf(
     // build out the rest of the draft tx with the tmp fee
     draft_tx = draft_tx
         .output(Output::new(addr.clone(), total_lovelace - tmp_fee))
-        .collateral_input(transaction::collateral_input(network_flag))
-        .collateral_output(Output::new(
-            collat_addr.clone(),
-            5_000_000 - (tmp_fee) * 3 / 2,
-        ))
         .fee(tmp_fee)
         .mint_asset(
             pallas_crypto::hash::Hash::new(
@@ -251,15 +268,15 @@ pub async fn build_remove_seedelf(
         )
         .language_view(
             pallas_txbuilder::ScriptKind::PlutusV3,
-            plutus_v3_cost_model(),
+            params.plutus_v3_cost_model.clone(),
         )
         .disclosed_signer(pallas_crypto::hash::Hash::new(
             hex::decode(&pkh)
                 .unwrap()
                 .try_into()
                 .expect("Not Correct Length"),
-        ))
-        .disclosed_signer(pallas_crypto::hash::Hash::new(COLLATERAL_HASH));
+        ));
+    draft_tx = collateral.stage(draft_tx, tmp_fee, params);
 
     // this is what will be signed when the real fee is known
     let mut raw_tx: StagingTransaction = draft_tx
@@ -275,34 +292,42 @@ pub async fn build_remove_seedelf(
                 .expect("Failed to convert to 32-byte array"),
         ));
 
-    let intermediate_tx: BuiltTransaction = draft_tx.build_conway_raw().unwrap();
-
+    // the total collateral is part of the body so it counts towards the size
+    let intermediate_tx: BuiltTransaction = set_total_collateral(
+        draft_tx.build_conway_raw().unwrap(),
+        collateral.total(tmp_fee, params),
+    )?;
+
+    let resolved: Vec<(Input, Output)> = evaluate::resolve_inputs(
+        std::slice::from_ref(&seedelf_utxo),
+        &[
+            (
+                config.reference.seedelf_reference_utxo,
+                &config.contract.seedelf_contract_cbor,
+            ),
+            (
+                config.reference.wallet_reference_utxo,
+                &config.contract.wallet_contract_cbor,
+            ),
+        ],
+    )
+    .unwrap_or_default();
+    // the spend comes before the burn
     let (mint_cpu_units, mint_mem_units, spend_cpu_units, spend_mem_units) =
-        match evaluate_transaction(hex::encode(intermediate_tx.tx_bytes.as_ref()), network_flag)
-            .await
+        match evaluate::evaluate_transaction(
+            provider,
+            intermediate_tx.tx_bytes.as_ref(),
+            &resolved,
+            params,
+            network_flag,
+            evaluation,
+        )
+        .await
         {
-            Ok(execution_units) => {
-                if let Some(_error) = execution_units.get("error") {
-                    println!("Error: {execution_units:?}");
-                    std::process::exit(1);
-                }
-                let spend_cpu_units: u64 = execution_units
-                    .pointer("/result/0/budget/cpu")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-                let spend_mem_units: u64 = execution_units
-                    .pointer("/result/0/budget/memory")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-
-                let mint_cpu_units: u64 = execution_units
-                    .pointer("/result/1/budget/cpu")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-                let mint_mem_units: u64 = execution_units
-                    .pointer("/result/1/budget/memory")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
+            Ok(budgets) => {
+                let (spend_cpu_units, spend_mem_units) =
+                    budgets.first().copied().unwrap_or_default();
+                let (mint_cpu_units, mint_mem_units) = budgets.get(1).copied().unwrap_or_default();
                 (
                     mint_cpu_units,
                     mint_mem_units,
@@ -310,7 +335,10 @@ pub async fn build_remove_seedelf(
                     spend_mem_units,
                 )
             }
-            Err(_) => (0, 0, 0, 0),
+            Err(err) => {
+                eprintln!("Failed to evaluate transaction: {err}");
+                (0, 0, 0, 0)
+            }
         };
 
     // we can fake the signature here to get the correct tx size
@@ -327,11 +355,12 @@ pub async fn build_remove_seedelf(
         .len()
         .try_into()
         .unwrap();
-    let tx_fee: u64 = fees::compute_linear_fee_policy(tx_size, &(fees::PolicyParams::default()));
-    let compute_fee: u64 = transaction::computation_fee(mint_mem_units, mint_cpu_units)
-        + transaction::computation_fee(spend_mem_units, spend_cpu_units);
-    let script_reference_fee: u64 =
-        config.contract.seedelf_contract_size * 15 + config.contract.wallet_contract_size * 15;
+    let tx_fee: u64 = params.min_fee(tx_size);
+    let compute_fee: u64 = transaction::computation_fee(mint_mem_units, mint_cpu_units, params)
+        + transaction::computation_fee(spend_mem_units, spend_cpu_units, params);
+    let script_reference_fee: u64 = params.reference_script_fee(
+        config.contract.seedelf_contract_size + config.contract.wallet_contract_size,
+    );
 
     let mut total_fee: u64 = tx_fee + compute_fee + script_reference_fee;
     // total fee needs to be even for the collateral calculation to work
@@ -343,10 +372,7 @@ pub async fn build_remove_seedelf(
 
     raw_tx = raw_tx
         .output(Output::new(addr.clone(), total_lovelace - total_fee))
-        .collateral_output(Output::new(
-            collat_addr.clone(),
-            5_000_000 - (total_fee) * 3 / 2,
-        ))
+        .collateral_output(collateral.return_output(total_fee, params))
         .fee(total_fee)
         .add_spend_redeemer(
             input_vector.clone().remove(0),
@@ -370,35 +396,29 @@ pub async fn build_remove_seedelf(
             }),
         );
 
-    let tx: BuiltTransaction = raw_tx.build_conway_raw().unwrap();
-    // need to witness it now
-    let tx_cbor: String = hex::encode(tx.tx_bytes.as_ref());
+    let tx: BuiltTransaction = set_total_collateral(
+        raw_tx.build_conway_raw().unwrap(),
+        collateral.total(total_fee, params),
+    )?;
 
-    let witness_public_key: PublicKey = PublicKey::from(COLLATERAL_PUBLIC_KEY);
+    // need to witness it now
+    let signed_tx_cbor: BuiltTransaction = collateral::witness(tx, collateral, key, network_flag)
+        .await?
+        .sign(PrivateKey::from(one_time_secret_key.clone()))
+        .unwrap();
 
-    let signed_tx_cbor: BuiltTransaction = match witness_collateral(tx_cbor.clone(), network_flag)
+    let tx_hash = match provider
+        .submit_tx(hex::encode(signed_tx_cbor.clone().tx_bytes))
         .await
-    {
-        Ok(witness) => {
-            let witness_cbor = witness.get("witness").and_then(|v| v.as_str()).unwrap();
-            let witness_sig = &witness_cbor[witness_cbor.len() - 128..];
-            let witness_vector: [u8; 64] = hex::decode(witness_sig).unwrap().try_into().unwrap();
-
-            tx.sign(PrivateKey::from(one_time_secret_key.clone()))
-                .unwrap()
-                .add_signature(witness_public_key, witness_vector)
-                .unwrap()
-        }
-        _ => tx,
-    };
-
-    let tx_hash = match submit_tx(hex::encode(signed_tx_cbor.clone().tx_bytes), network_flag).await
     {
         Ok(response) => response.as_str().unwrap_or("default").to_string(),
-        Err(_) => String::new(),
+        Err(err) => {
+            eprintln!("\nSubmission Failed: {err}");
+            String::new()
+        }
     };
 
-    RemoveSeedelfOutput {
+    Ok(RemoveSeedelfOutput {
         tx_cbor: hex::encode(signed_tx_cbor.clone().tx_bytes),
         tx_hash,
         tx_fee,
@@ -409,5 +429,5 @@ pub async fn build_remove_seedelf(
         mint_mem_units,
         spend_cpu_units,
         spend_mem_units,
-    }
+    })
 }
@@ -1,37 +1,40 @@
+use crate::cache;
+use crate::collateral;
+use crate::confirm;
+use crate::key::WalletKey;
 use crate::setup;
 use anyhow::{Result, bail};
-use blstrs::Scalar;
 use clap::Args;
 use colored::Colorize;
 use pallas_addresses::Address;
-use pallas_crypto::key::ed25519::{PublicKey, SecretKey};
+use pallas_crypto::key::ed25519::SecretKey;
 use pallas_primitives::Hash;
-use pallas_traverse::fees;
 use pallas_txbuilder::{BuildConway, BuiltTransaction, Input, Output, StagingTransaction};
 use pallas_wallet::PrivateKey;
 use rand_core::OsRng;
 use seedelf_core::address;
 use seedelf_core::assets::{Asset, Assets};
-use seedelf_core::constants::{
-    ADA_HANDLE_POLICY_ID, COLLATERAL_HASH, COLLATERAL_PUBLIC_KEY, Config, MAXIMUM_TOKENS_PER_UTXO,
-    get_config, plutus_v3_cost_model,
+use seedelf_core::collateral::{
+    Collateral, CollateralSource, select_collateral, set_total_collateral,
 };
+use seedelf_core::constants::{ADA_HANDLE_POLICY_ID, Config, MAXIMUM_TOKENS_PER_UTXO, get_config};
 use seedelf_core::data_structures;
+use seedelf_core::evaluate::{self, Evaluation};
 use seedelf_core::transaction::{
-    address_minimum_lovelace_with_assets, collateral_input, extract_budgets, reference_utxo,
-    total_computation_fee, wallet_minimum_lovelace_with_assets,
+    address_minimum_lovelace_with_assets, reference_utxo, total_computation_fee,
+    wallet_minimum_lovelace_with_assets, witness_datum,
 };
 use seedelf_core::utxos;
 use seedelf_crypto::register::Register;
-use seedelf_crypto::schnorr::{create_proof, random_scalar};
 use seedelf_display::display;
-use seedelf_koios::koios::{
-    UtxoResponse, ada_handle_address, evaluate_transaction, extract_bytes_with_logging, submit_tx,
-    witness_collateral,
-};
+use seedelf_koios::koios::{UtxoResponse, ada_handle_address, extract_bytes_with_logging};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::{ChainProvider, submitted_tx_hash};
 
 pub struct SweepSeedelfOutput {
     pub tx_cbor: String,
+    /// The transaction as submitted, witnesses included.
+    pub signed_tx_cbor: String,
     pub tx_hash: String,
     pub tx_fee: u64,
     pub compute_fee: u64,
@@ -110,9 +113,21 @@ pub struct SweepArgs {
     /// Optional repeated 'txId#txIdx'
     #[arg(long = "utxo", help = "The utxos to spend.", display_order = 8)]
     utxos: Option<Vec<String>>,
+
+    /// Wait for this many confirmations after submitting
+    #[arg(long, help = "Wait for this many confirmations.", display_order = 9)]
+    wait: Option<u64>,
 }
 
-pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()> {
+pub async fn run(
+    args: SweepArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: u64,
+    evaluation: Evaluation,
+    collateral_source: CollateralSource,
+    rescan: bool,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
 
@@ -120,6 +135,7 @@ pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()
         eprintln!("Error: Invalid Variant");
         std::process::exit(1);
     });
+    let params: &ProtocolParameters = &setup::protocol_parameters(provider, network_flag).await;
 
     // address or ada handle must be found
     if args.address.is_none() && args.ada_handle.is_none() {
@@ -134,8 +150,8 @@ pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()
         bail!("ADA Handle cannot be empty");
     }
 
-    let outbound_address: String = if args.address.is_some() {
-        args.address.unwrap()
+    let outbound_address: String = if let Some(address) = args.address {
+        address
     } else {
         let wallet_addr: String =
             address::wallet_contract(network_flag, config.contract.wallet_contract_hash)
@@ -183,11 +199,7 @@ pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()
             bail!("Error: Each --policy-id must have a corresponding --token-name and --amount.");
         }
 
-        for ((pid, tkn), amt) in policy_id
-            .into_iter()
-            .zip(token_name.into_iter())
-            .zip(amount.into_iter())
-        {
+        for ((pid, tkn), amt) in policy_id.into_iter().zip(token_name).zip(amount) {
             if amt == 0 {
                 bail!("Error: Token Amount must be positive");
             }
@@ -198,7 +210,7 @@ pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()
     }
 
     let address_minimum_lovelace: u64 =
-        address_minimum_lovelace_with_assets(&outbound_address, selected_tokens.clone())?;
+        address_minimum_lovelace_with_assets(&outbound_address, selected_tokens.clone(), params)?;
     if args.lovelace.is_some_and(|x| x < address_minimum_lovelace) {
         bail!("lovelace Too Small For Min UTxO");
     }
@@ -210,11 +222,15 @@ pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()
         bail!("Supplied Address Is Incorrect");
     }
 
-    let minimum_lovelace: u64 = wallet_minimum_lovelace_with_assets(selected_tokens.clone())?;
-    let scalar: Scalar = setup::unlock_wallet_interactive();
+    let minimum_lovelace: u64 =
+        wallet_minimum_lovelace_with_assets(selected_tokens.clone(), params)?;
+    let key: WalletKey = setup::wallet_key();
+    let collateral: Collateral =
+        select_collateral(provider, &collateral_source, &key.key_hash()?, network_flag).await?;
 
     let SweepSeedelfOutput {
         tx_cbor,
+        signed_tx_cbor,
         tx_hash,
         tx_fee,
         compute_fee,
@@ -223,15 +239,20 @@ pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()
         usable_utxos,
     } = build_sweep_seedelf(
         config,
+        provider,
         network_flag,
+        params,
+        evaluation,
+        &collateral,
         outbound_address,
         args.lovelace.unwrap_or(minimum_lovelace),
         selected_tokens,
         args.utxos,
-        scalar,
+        &key,
         args.all,
+        rescan,
     )
-    .await;
+    .await?;
 
     if usable_utxos.is_empty() {
         bail!("No Usuable UTxOs Found");
@@ -264,21 +285,23 @@ pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()
     println!("\nTx Cbor: {}", hex::encode(tx_cbor.clone()).white());
 
     if tx_hash.is_empty() {
-        println!("\nTransaction Successfully Failed!");
+        bail!("Transaction Was Not Submitted");
+    }
+    println!("\nTransaction Successfully Submitted!");
+    println!("\nTx Hash: {}", tx_hash.bright_cyan());
+    if network_flag {
+        println!(
+            "{}",
+            format!("\nhttps://preprod.cardanoscan.io/transaction/{}", tx_hash).bright_purple()
+        );
     } else {
-        println!("\nTransaction Successfully Submitted!");
-        println!("\nTx Hash: {}", tx_hash.bright_cyan());
-        if network_flag {
-            println!(
-                "{}",
-                format!("\nhttps://preprod.cardanoscan.io/transaction/{}", tx_hash).bright_purple()
-            );
-        } else {
-            println!(
-                "{}",
-                format!("\nhttps://cardanoscan.io/transaction/{}", tx_hash).bright_purple()
-            );
-        }
+        println!(
+            "{}",
+            format!("\nhttps://cardanoscan.io/transaction/{}", tx_hash).bright_purple()
+        );
+    }
+    if let Some(depth) = args.wait {
+        confirm::wait_for_depth(provider, tx_hash, signed_tx_cbor, depth).await?;
     }
 
     Ok(())
@@ -287,17 +310,21 @@ pub async fn run(args: SweepArgs, network_flag: bool, variant: u64) -> Result<()
 #[allow(clippy::too_many_arguments)]
 pub async fn build_sweep_seedelf(
     config: Config,
+    provider: &impl ChainProvider,
     network_flag: bool,
+    params: &ProtocolParameters,
+    evaluation: Evaluation,
+    collateral: &Collateral,
     address: String,
     lovelace: u64,
     selected_tokens: Assets,
     selected_utxos: Option<Vec<String>>,
-    scalar: Scalar,
+    key: &WalletKey,
     send_all: bool,
-) -> SweepSeedelfOutput {
+    rescan: bool,
+) -> Result<SweepSeedelfOutput> {
     let addr: Address = Address::from_bech32(&address).unwrap();
 
-    let collat_addr: Address = address::collateral_address(network_flag);
     let wallet_addr: Address =
         address::wallet_contract(network_flag, config.contract.wallet_contract_hash);
 
@@ -308,20 +335,16 @@ pub async fn build_sweep_seedelf(
     let mut register_vector: Vec<Register> = Vec::new();
 
     let minimum_lovelace: u64 =
-        wallet_minimum_lovelace_with_assets(selected_tokens.clone()).unwrap_or_default();
+        wallet_minimum_lovelace_with_assets(selected_tokens.clone(), params).unwrap_or_default();
     let lovelace_goal: u64 = if lovelace < minimum_lovelace {
         minimum_lovelace
     } else {
         lovelace
     };
 
-    let every_utxo: Vec<UtxoResponse> =
-        utxos::get_credential_utxos(config.contract.wallet_contract_hash, network_flag)
-            .await
-            .unwrap_or_default();
-    let owned_utxos: Vec<UtxoResponse> =
-        utxos::collect_wallet_utxos(scalar, &config.contract.seedelf_policy_id, every_utxo)
-            .unwrap_or_default();
+    let owned_utxos: Vec<UtxoResponse> = cache::sync(provider, &config, key, network_flag, rescan)
+        .await?
+        .spendable_utxos(&config.contract.seedelf_policy_id);
 
     let usable_utxos: Vec<UtxoResponse> = if send_all {
         owned_utxos
@@ -329,7 +352,8 @@ pub async fn build_sweep_seedelf(
         // if not selecting utxos then select from the owned utxos else use the utxos provided
         if selected_utxos.is_none() {
             // we will assume that the change will required ~2 ADA and the fee about ~0.5 ADA
-            utxos::select(owned_utxos, lovelace_goal, selected_tokens.clone()).unwrap_or_default()
+            utxos::select(owned_utxos, lovelace_goal, selected_tokens.clone(), params)
+                .unwrap_or_default()
         } else {
             // assumes the utxos hold the correct tokens else it will error downstream
             match utxos::parse_tx_utxos(selected_utxos.unwrap_or_default()) {
@@ -357,6 +381,9 @@ pub async fn build_sweep_seedelf(
             .unwrap();
         // draft and raw are built the same here
         draft_tx = draft_tx.input(this_input.clone());
+        if let Some(datum) = witness_datum(&utxo)? {
+            draft_tx = draft_tx.datum(datum);
+        }
         input_vector.push(this_input.clone());
         // do the registers
         register_vector.push(inline_datum.clone());
@@ -398,24 +425,19 @@ pub async fn build_sweep_seedelf(
     // build out the rest of the draft tx with the tmp fee
     draft_tx = draft_tx
         .output(sweep_output)
-        .collateral_input(collateral_input(network_flag))
-        .collateral_output(Output::new(
-            collat_addr.clone(),
-            5_000_000 - (tmp_fee) * 3 / 2,
-        ))
         .fee(tmp_fee)
         .reference_input(reference_utxo(config.reference.wallet_reference_utxo))
         .language_view(
             pallas_txbuilder::ScriptKind::PlutusV3,
-            plutus_v3_cost_model(),
+            params.plutus_v3_cost_model.clone(),
         )
         .disclosed_signer(pallas_crypto::hash::Hash::new(
             hex::decode(&pkh)
                 .unwrap()
                 .try_into()
                 .expect("Not Correct Length"),
-        ))
-        .disclosed_signer(pallas_crypto::hash::Hash::new(COLLATERAL_HASH));
+        ));
+    draft_tx = collateral.stage(draft_tx, tmp_fee, params);
 
     // need to check if there is change going back here
     let change_token_per_utxo: Vec<Assets> = change_tokens
@@ -426,14 +448,9 @@ pub async fn build_sweep_seedelf(
     if !send_all {
         // a max tokens per change output here
         for (i, change) in change_token_per_utxo.iter().enumerate() {
-            let datum_vector: Vec<u8> = Register::create(scalar)
-                .unwrap_or_default()
-                .rerandomize()
-                .unwrap_or_default()
-                .to_vec()
-                .unwrap_or_default();
+            let datum_vector: Vec<u8> = key.datum()?;
             let minimum: u64 =
-                wallet_minimum_lovelace_with_assets(change.clone()).unwrap_or_default();
+                wallet_minimum_lovelace_with_assets(change.clone(), params).unwrap_or_default();
             let change_lovelace: u64 = if i == number_of_change_utxo - 1 {
                 // this is the last one or the only one
                 lovelace_amount = lovelace_amount - lovelace_goal - tmp_fee;
@@ -457,12 +474,7 @@ pub async fn build_sweep_seedelf(
 
     if number_of_change_utxo == 0 && !send_all {
         // no tokens so we just need to account for the lovelace going back
-        let datum_vector: Vec<u8> = Register::create(scalar)
-            .unwrap_or_default()
-            .rerandomize()
-            .unwrap_or_default()
-            .to_vec()
-            .unwrap_or_default();
+        let datum_vector: Vec<u8> = key.datum()?;
         let change_lovelace: u64 = lovelace_amount - lovelace_goal - tmp_fee;
         let change_output: Output = Output::new(wallet_addr.clone(), change_lovelace)
             .set_inline_datum(datum_vector.clone());
@@ -474,10 +486,9 @@ pub async fn build_sweep_seedelf(
     for (input, datum) in input_vector
         .clone()
         .into_iter()
-        .zip(register_vector.clone().into_iter())
+        .zip(register_vector.clone())
     {
-        let r: Scalar = random_scalar();
-        let (z, g_r) = create_proof(datum, scalar, pkh.clone(), r).unwrap_or_default();
+        let (z, g_r) = key.create_proof(datum, pkh.clone())?;
         let spend_redeemer_vector =
             data_structures::create_spend_redeemer(z, g_r, pkh.clone()).unwrap_or_default();
         draft_tx = draft_tx.add_spend_redeemer(
@@ -505,22 +516,36 @@ pub async fn build_sweep_seedelf(
         raw_tx = raw_tx.remove_spend_redeemer(input);
     }
 
-    let intermediate_tx: BuiltTransaction = draft_tx.build_conway_raw().unwrap();
-
-    let budgets =
-        match evaluate_transaction(hex::encode(intermediate_tx.tx_bytes.as_ref()), network_flag)
-            .await
-        {
-            Ok(execution_units) => {
-                if let Some(_error) = execution_units.get("error") {
-                    println!("{execution_units:?}");
-                    std::process::exit(1);
-                }
-                let budgets: Vec<(u64, u64)> = extract_budgets(&execution_units);
-                budgets
-            }
-            Err(_) => Vec::new(),
-        };
+    // the total collateral is part of the body so it counts towards the size
+    let intermediate_tx: BuiltTransaction = set_total_collateral(
+        draft_tx.build_conway_raw().unwrap(),
+        collateral.total(tmp_fee, params),
+    )?;
+
+    let resolved: Vec<(Input, Output)> = evaluate::resolve_inputs(
+        &usable_utxos,
+        &[(
+            config.reference.wallet_reference_utxo,
+            &config.contract.wallet_contract_cbor,
+        )],
+    )
+    .unwrap_or_default();
+    let budgets = match evaluate::evaluate_transaction(
+        provider,
+        intermediate_tx.tx_bytes.as_ref(),
+        &resolved,
+        params,
+        network_flag,
+        evaluation,
+    )
+    .await
+    {
+        Ok(budgets) => budgets,
+        Err(err) => {
+            eprintln!("Failed to evaluate transaction: {err}");
+            std::process::exit(1);
+        }
+    };
 
     // we can fake the signature here to get the correct tx size
     let fake_signer_secret_key: SecretKey = SecretKey::new(OsRng);
@@ -536,12 +561,13 @@ pub async fn build_sweep_seedelf(
         .len()
         .try_into()
         .unwrap();
-    let tx_fee = fees::compute_linear_fee_policy(tx_size, &(fees::PolicyParams::default()));
+    let tx_fee = params.min_fee(tx_size);
 
     // This probably should be a function
-    let compute_fee: u64 = total_computation_fee(budgets.clone());
+    let compute_fee: u64 = total_computation_fee(budgets.clone(), params);
 
-    let script_reference_fee: u64 = config.contract.wallet_contract_size * 15;
+    let script_reference_fee: u64 =
+        params.reference_script_fee(config.contract.wallet_contract_size);
 
     // total fee is the sum of everything
     let mut total_fee: u64 = tx_fee + compute_fee + script_reference_fee;
@@ -577,10 +603,7 @@ pub async fn build_sweep_seedelf(
 
     raw_tx = raw_tx
         .output(sweep_output)
-        .collateral_output(Output::new(
-            collat_addr.clone(),
-            5_000_000 - (total_fee) * 3 / 2,
-        ))
+        .collateral_output(collateral.return_output(total_fee, params))
         .fee(total_fee);
 
     // need to check if there is change going back here
@@ -592,14 +615,9 @@ pub async fn build_sweep_seedelf(
         // a max tokens per change output here
         let mut lovelace_amount: u64 = total_lovelace_found;
         for (i, change) in change_token_per_utxo.iter().enumerate() {
-            let datum_vector: Vec<u8> = Register::create(scalar)
-                .unwrap_or_default()
-                .rerandomize()
-                .unwrap_or_default()
-                .to_vec()
-                .unwrap_or_default();
+            let datum_vector: Vec<u8> = key.datum()?;
             let minimum: u64 =
-                wallet_minimum_lovelace_with_assets(change.clone()).unwrap_or_default();
+                wallet_minimum_lovelace_with_assets(change.clone(), params).unwrap_or_default();
             let change_lovelace: u64 = if i == number_of_change_utxo - 1 {
                 // this is the last one or the only one
                 lovelace_amount = lovelace_amount - lovelace_goal - total_fee;
@@ -623,12 +641,7 @@ pub async fn build_sweep_seedelf(
 
     if number_of_change_utxo == 0 && !send_all {
         // no tokens so we just need to account for the lovelace going back
-        let datum_vector: Vec<u8> = Register::create(scalar)
-            .unwrap_or_default()
-            .rerandomize()
-            .unwrap_or_default()
-            .to_vec()
-            .unwrap_or_default();
+        let datum_vector: Vec<u8> = key.datum()?;
         let change_lovelace: u64 = lovelace_amount - lovelace_goal - total_fee;
         let change_output: Output = Output::new(wallet_addr.clone(), change_lovelace)
             .set_inline_datum(datum_vector.clone());
@@ -638,11 +651,10 @@ pub async fn build_sweep_seedelf(
     for ((input, datum), (cpu, mem)) in input_vector
         .clone()
         .into_iter()
-        .zip(register_vector.clone().into_iter())
-        .zip(budgets.clone().into_iter())
+        .zip(register_vector.clone())
+        .zip(budgets.clone())
     {
-        let r: Scalar = random_scalar();
-        let (z, g_r) = create_proof(datum, scalar, pkh.clone(), r).unwrap_or_default();
+        let (z, g_r) = key.create_proof(datum, pkh.clone())?;
         let spend_redeemer_vector = data_structures::create_spend_redeemer(z, g_r, pkh.clone());
         raw_tx = raw_tx.add_spend_redeemer(
             input,
@@ -651,42 +663,56 @@ pub async fn build_sweep_seedelf(
         )
     }
 
-    let tx: BuiltTransaction = raw_tx.build_conway_raw().unwrap();
+    let tx: BuiltTransaction = set_total_collateral(
+        raw_tx.build_conway_raw().unwrap(),
+        collateral.total(total_fee, params),
+    )?;
     // need to witness it now
     let tx_cbor: String = hex::encode(tx.tx_bytes.as_ref());
 
-    let witness_public_key: PublicKey = PublicKey::from(COLLATERAL_PUBLIC_KEY);
+    let signed_tx_cbor: BuiltTransaction = collateral::witness(tx, collateral, key, network_flag)
+        .await?
+        .sign(PrivateKey::from(one_time_secret_key.clone()))
+        .unwrap();
 
-    let signed_tx_cbor: BuiltTransaction = match witness_collateral(tx_cbor.clone(), network_flag)
+    let tx_hash = match provider
+        .submit_tx(hex::encode(signed_tx_cbor.clone().tx_bytes))
         .await
     {
-        Ok(witness) => {
-            let witness_cbor = witness.get("witness").and_then(|v| v.as_str()).unwrap();
-            let witness_sig = &witness_cbor[witness_cbor.len() - 128..];
-            let witness_vector: [u8; 64] = hex::decode(witness_sig).unwrap().try_into().unwrap();
-
-            tx.sign(PrivateKey::from(one_time_secret_key.clone()))
-                .unwrap()
-                .add_signature(witness_public_key, witness_vector)
-                .unwrap()
+        Ok(response) => match submitted_tx_hash(&response) {
+            Some(tx_hash) => {
+                // keep the next selection off these inputs until the tx is on chain
+                if let Err(err) = cache::track_pending(
+                    &config,
+                    key,
+                    network_flag,
+                    signed_tx_cbor.tx_bytes.as_ref(),
+                    &usable_utxos,
+                ) {
+                    eprintln!("\nFailed To Track Pending Transaction: {err:#}");
+                }
+                tx_hash
+            }
+            None => {
+                eprintln!("\nSubmission Rejected: {response}");
+                String::new()
+            }
+        },
+        Err(err) => {
+            eprintln!("\nSubmission Failed: {err}");
+            String::new()
         }
-        _ => tx,
-    };
-
-    let tx_hash = match submit_tx(hex::encode(signed_tx_cbor.clone().tx_bytes), network_flag).await
-    {
-        Ok(response) => response.as_str().unwrap_or("default").to_string(),
-        Err(_) => String::new(),
     };
 
     //
-    SweepSeedelfOutput {
+    Ok(SweepSeedelfOutput {
         tx_cbor,
+        signed_tx_cbor: hex::encode(signed_tx_cbor.tx_bytes.as_ref()),
         tx_hash,
         tx_fee,
         compute_fee,
         script_reference_fee,
         total_fee,
         usable_utxos,
-    }
+    })
 }
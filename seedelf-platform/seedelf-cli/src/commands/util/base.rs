@@ -1,12 +1,11 @@
 use crate::setup;
-use blstrs::Scalar;
+use anyhow::Result;
 use colored::Colorize;
 use seedelf_crypto::register::Register;
 
-pub fn run() {
+pub fn run() -> Result<()> {
     println!("\n{}", "Register Base Element".bright_cyan());
-    let scalar: Scalar = setup::unlock_wallet_interactive();
-    let base: Register = Register::create(scalar).unwrap();
+    let base: Register = setup::wallet_key().register()?;
     println!(
         "\n{} {}",
         "Generator:".bright_blue(),
@@ -17,4 +16,5 @@ pub fn run() {
         "Public Value:".bright_blue(),
         base.public_value.bright_white()
     );
+    Ok(())
 }
@@ -2,20 +2,24 @@ use clap::Args;
 use colored::Colorize;
 use pallas_addresses::Address;
 use pallas_crypto::key::ed25519::SecretKey;
-use pallas_traverse::fees;
 use pallas_txbuilder::{BuildConway, BuiltTransaction, Input, Output, StagingTransaction};
 use pallas_wallet::PrivateKey;
 use rand_core::OsRng;
+use seedelf_core::collateral::set_total_collateral;
 use seedelf_core::data_structures;
-use seedelf_koios::koios::{UtxoResponse, address_utxos, evaluate_transaction, utxo_info};
+use seedelf_core::evaluate::{self, Evaluation};
+use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
 
+use crate::setup;
 use crate::web_server;
 use anyhow::{Result, bail};
 use seedelf_core::address;
 use seedelf_core::assets::Assets;
-use seedelf_core::constants::{Config, get_config, plutus_v3_cost_model};
+use seedelf_core::constants::{Config, get_config};
 use seedelf_core::transaction::{
-    address_minimum_lovelace_with_assets, extract_budgets, reference_utxo, total_computation_fee,
+    address_minimum_lovelace_with_assets, reference_utxo, total_computation_fee,
 };
 use seedelf_core::utxos;
 use seedelf_display::{display, text_coloring};
@@ -36,7 +40,13 @@ pub struct ExtractArgs {
     address: String,
 }
 
-pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<()> {
+pub async fn run(
+    args: ExtractArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: u64,
+    evaluation: Evaluation,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
 
@@ -44,8 +54,8 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
         eprintln!("Error: Invalid Variant");
         std::process::exit(1);
     });
+    let params: &ProtocolParameters = &setup::protocol_parameters(provider, network_flag).await;
 
-    let collat_addr: Address = address::collateral_address(network_flag);
     // we need to make sure that the network flag and the address provided makes sense here
     let addr: Address = Address::from_bech32(args.address.as_str()).unwrap();
     if !(address::is_not_a_script(addr.clone())
@@ -55,7 +65,7 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
     }
 
     let mut empty_datum_utxo = UtxoResponse::default();
-    match utxo_info(&args.utxo, network_flag).await {
+    match provider.utxo_info(&args.utxo).await {
         Ok(utxos) => {
             if !utxos.is_empty() {
                 empty_datum_utxo = utxos.first().unwrap().clone();
@@ -82,7 +92,7 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
     let (empty_utxo_lovelace, empty_utxo_tokens) =
         utxos::assets_of(vec![empty_datum_utxo.clone()])?;
     let minimum_lovelace: u64 =
-        address_minimum_lovelace_with_assets(&args.address, empty_utxo_tokens.clone())?;
+        address_minimum_lovelace_with_assets(&args.address, empty_utxo_tokens.clone(), params)?;
 
     // this is used to calculate the real fee
     let mut draft_tx: StagingTransaction = StagingTransaction::new();
@@ -91,7 +101,7 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
     let mut found_collateral: bool = false;
 
     // This should probably be some generalized function later
-    match address_utxos(&args.address, network_flag).await {
+    match provider.address_utxos(&args.address).await {
         Ok(utxos) => {
             // loop all the utxos found from the address
             for utxo in utxos {
@@ -121,7 +131,7 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
         }
     }
     let usable_utxos: Vec<UtxoResponse> =
-        utxos::select(all_utxos, minimum_lovelace, Assets::new())?;
+        utxos::select(all_utxos, minimum_lovelace, Assets::new(), params)?;
     if usable_utxos.is_empty() {
         bail!("Not Enough Lovelace/Tokens");
     }
@@ -182,7 +192,7 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
         .reference_input(reference_utxo(config.reference.wallet_reference_utxo))
         .language_view(
             pallas_txbuilder::ScriptKind::PlutusV3,
-            plutus_v3_cost_model(),
+            params.plutus_v3_cost_model.clone(),
         );
 
     let intermediate_tx: BuiltTransaction = draft_tx.clone().build_conway_raw().unwrap();
@@ -191,23 +201,31 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
     raw_tx = raw_tx.remove_output(0);
     raw_tx = raw_tx.remove_spend_redeemer(empty_input.clone());
 
-    let budgets: Vec<(u64, u64)> =
-        match evaluate_transaction(hex::encode(intermediate_tx.tx_bytes.as_ref()), network_flag)
-            .await
-        {
-            Ok(execution_units) => {
-                if let Some(_error) = execution_units.get("error") {
-                    println!("{execution_units:?}");
-                    std::process::exit(1);
-                }
-                let budgets: Vec<(u64, u64)> = extract_budgets(&execution_units);
-                budgets
-            }
-            Err(err) => {
-                eprintln!("Failed to evaluate transaction: {err}");
-                std::process::exit(1);
-            }
-        };
+    let mut spent_utxos: Vec<UtxoResponse> = vec![empty_datum_utxo.clone()];
+    spent_utxos.extend(usable_utxos.clone());
+    let resolved: Vec<(Input, Output)> = evaluate::resolve_inputs(
+        &spent_utxos,
+        &[(
+            config.reference.wallet_reference_utxo,
+            &config.contract.wallet_contract_cbor,
+        )],
+    )?;
+    let budgets: Vec<(u64, u64)> = match evaluate::evaluate_transaction(
+        provider,
+        intermediate_tx.tx_bytes.as_ref(),
+        &resolved,
+        params,
+        network_flag,
+        evaluation,
+    )
+    .await
+    {
+        Ok(budgets) => budgets,
+        Err(err) => {
+            eprintln!("Failed to evaluate transaction: {err}");
+            std::process::exit(1);
+        }
+    };
 
     // we can fake the signature here to get the correct tx size
     let fake_signer_secret_key: SecretKey = SecretKey::new(OsRng);
@@ -221,7 +239,7 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
         .len()
         .try_into()
         .unwrap();
-    let tx_fee = fees::compute_linear_fee_policy(tx_size, &(fees::PolicyParams::default()));
+    let tx_fee = params.min_fee(tx_size);
     println!(
         "{} {}",
         "\nTx Size Fee:".bright_blue(),
@@ -229,14 +247,15 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
     );
 
     // This probably should be a function
-    let compute_fee: u64 = total_computation_fee(budgets.clone());
+    let compute_fee: u64 = total_computation_fee(budgets.clone(), params);
     println!(
         "{} {}",
         "Compute Fee:".bright_blue(),
         compute_fee.to_string().bright_white()
     );
 
-    let script_reference_fee: u64 = config.contract.wallet_contract_size * 15;
+    let script_reference_fee: u64 =
+        params.reference_script_fee(config.contract.wallet_contract_size);
     println!(
         "{} {}",
         "Script Reference Fee:".bright_blue(),
@@ -266,10 +285,7 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
 
     raw_tx = raw_tx
         .output(extract_output)
-        .collateral_output(Output::new(
-            collat_addr.clone(),
-            5_000_000 - (total_fee) * 3 / 2,
-        ))
+        .collateral_output(Output::new(addr.clone(), 5_000_000 - (total_fee) * 3 / 2))
         .fee(total_fee);
 
     let (cpu, mem) = budgets.first().unwrap();
@@ -282,7 +298,9 @@ pub async fn run(args: ExtractArgs, network_flag: bool, variant: u64) -> Result<
         }),
     );
 
-    let tx: BuiltTransaction = raw_tx.build_conway_raw().unwrap();
+    // the collateral return leaves 3/2 of the fee at stake
+    let tx: BuiltTransaction =
+        set_total_collateral(raw_tx.build_conway_raw().unwrap(), (total_fee) * 3 / 2)?;
     // need to witness it now
     let tx_cbor: String = hex::encode(tx.tx_bytes.as_ref());
 
@@ -1,10 +1,10 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use clap::Args;
 use colored::Colorize;
-use seedelf_core::constants::{Config, get_config};
+use seedelf_core::constants::{Config, VARIANT, scan_variants};
 use seedelf_core::utxos;
 use seedelf_display::display;
-use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::provider::ChainProvider;
 
 /// Struct to hold command-specific arguments
 #[derive(Args)]
@@ -19,7 +19,12 @@ pub struct FindArgs {
     label: Option<String>,
 }
 
-pub async fn run(args: FindArgs, network_flag: bool, variant: u64) -> Result<()> {
+pub async fn run(
+    args: FindArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: Option<u64>,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
     let label: String = args.label.unwrap_or_default();
@@ -29,15 +34,28 @@ pub async fn run(args: FindArgs, network_flag: bool, variant: u64) -> Result<()>
         label.bright_green()
     );
 
-    let config: Config = get_config(variant, network_flag).unwrap_or_else(|| {
-        eprintln!("Error: Invalid Variant");
-        std::process::exit(1);
-    });
+    let variants: Vec<(u64, Config)> = scan_variants(variant, network_flag);
+    if variants.is_empty() {
+        bail!("Invalid Variant");
+    }
 
-    let every_utxo: Vec<UtxoResponse> =
-        utxos::get_credential_utxos(config.contract.wallet_contract_hash, network_flag).await?;
-    let all_seedelfs =
-        utxos::find_all_seedelfs(label, &config.contract.seedelf_policy_id, every_utxo)?;
-    display::print_seedelfs(all_seedelfs);
+    for (variant, config) in variants {
+        let mut all_seedelfs: Vec<String> = Vec::new();
+        utxos::for_each_credential_page(provider, config.contract.wallet_contract_hash, |page| {
+            all_seedelfs.extend(utxos::find_all_seedelfs(
+                label.clone(),
+                &config.contract.seedelf_policy_id,
+                page,
+            )?);
+            Ok(true)
+        })
+        .await?;
+        let current: &str = if variant == VARIANT { " (current)" } else { "" };
+        println!(
+            "{}",
+            format!("\nVariant {variant}{current}: {} Found", all_seedelfs.len()).bright_cyan()
+        );
+        display::print_seedelfs(all_seedelfs);
+    }
     Ok(())
 }
@@ -1,14 +1,15 @@
+use crate::cache::{self, HistoryEntry};
+use crate::export::{self, Direction, ExportFormat, ExportRow, Preset};
+use crate::key::WalletKey;
 use crate::setup;
-use anyhow::Result;
-use blstrs::Scalar;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, NaiveDate, Utc};
 use clap::Args;
 use colored::Colorize;
-use pallas_addresses::Address;
-use seedelf_core::address;
-use seedelf_core::constants::{Config, get_config};
+use seedelf_core::constants::{Config, VARIANT, scan_variants};
 use seedelf_display::display;
-use seedelf_koios::koios::TxResponse;
-use seedelf_koios::koios::address_transactions;
+use seedelf_koios::provider::ChainProvider;
+use std::path::PathBuf;
 
 /// Struct to hold command-specific arguments
 #[derive(Args)]
@@ -20,52 +21,201 @@ pub struct HistoryArgs {
     /// Show recieve only in history
     #[arg(long, help = "Show receive only", display_order = 2)]
     receive_only: bool,
+
+    /// Skip transactions before this block
+    #[arg(long, help = "Show from this block height", display_order = 3)]
+    from_block: Option<u64>,
+
+    /// Skip transactions after this block
+    #[arg(long, help = "Show up to this block height", display_order = 4)]
+    to_block: Option<u64>,
+
+    /// Skip transactions before this day
+    #[arg(long, help = "Show from this date, YYYY-MM-DD in UTC", value_parser = parse_date, display_order = 5)]
+    from_date: Option<NaiveDate>,
+
+    /// Skip transactions after this day
+    #[arg(long, help = "Show up to this date, YYYY-MM-DD in UTC", value_parser = parse_date, display_order = 6)]
+    to_date: Option<NaiveDate>,
+
+    /// Write the history to a file instead of printing it
+    #[arg(
+        long,
+        help = "Export one row per owned input or output, csv or json, defaults to the output in the config",
+        requires = "output",
+        display_order = 7
+    )]
+    export: Option<ExportFormat>,
+
+    /// Column layout of a CSV export
+    #[arg(long, help = "CSV columns, generic, koinly or cointracking", default_value_t = Preset::Generic, display_order = 8)]
+    preset: Preset,
+
+    /// Where the export is written
+    #[arg(long, help = "The export file", display_order = 9)]
+    output: Option<PathBuf>,
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("Invalid Date {s}: {e}"))
+}
+
+impl HistoryArgs {
+    /// Uses the profile's format when `--output` is given without `--export`.
+    pub fn default_export(&mut self, format: Option<ExportFormat>) {
+        if self.export.is_none() && self.output.is_some() {
+            self.export = format;
+        }
+    }
+
+    /// Whether a transaction falls inside the block and date ranges.
+    fn in_range(&self, block_height: u64, block_time: u64) -> bool {
+        let date: Option<NaiveDate> =
+            DateTime::<Utc>::from_timestamp(block_time as i64, 0).map(|time| time.date_naive());
+        self.from_block.is_none_or(|from| block_height >= from)
+            && self.to_block.is_none_or(|to| block_height <= to)
+            && self
+                .from_date
+                .is_none_or(|from| date.is_some_and(|d| d >= from))
+            && self.to_date.is_none_or(|to| date.is_some_and(|d| d <= to))
+    }
+}
+
+/// Formats lovelace as ADA, with a sign when asked.
+fn ada(lovelace: i128, signed: bool) -> String {
+    let amount: String = format!("{:.6}", lovelace.unsigned_abs() as f64 / 1_000_000.0);
+    match (signed, lovelace < 0) {
+        (_, true) => format!("-{amount}"),
+        (true, false) => format!("+{amount}"),
+        (false, false) => amount,
+    }
+}
+
+fn print_amounts(tx: &HistoryEntry) {
+    let time: String = DateTime::<Utc>::from_timestamp(tx.block_time as i64, 0)
+        .filter(|_| tx.block_time > 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("    Time: {}", time.bright_white());
+    if tx.spend {
+        println!(
+            "    Spent: {} ₳",
+            ada(tx.spent_lovelace as i128, false).bright_white()
+        );
+    }
+    if tx.receive {
+        println!(
+            "    Received: {} ₳",
+            ada(tx.received_lovelace as i128, false).bright_white()
+        );
+    }
+    if tx.spend
+        && let Some(fee) = tx.fee
+    {
+        println!("    Fee: {} ₳", ada(fee as i128, false).bright_white());
+    }
+    let net: String = format!("{} ₳", ada(tx.net_lovelace(), true));
+    if tx.net_lovelace() < 0 {
+        println!("    Net: {}", net.bright_red());
+    } else {
+        println!("    Net: {}", net.bright_green());
+    }
+    for (policy_id, asset_name, amount) in tx.net_tokens() {
+        println!(
+            "    Net Token: {} {}.{}",
+            format!("{amount:+}").white(),
+            policy_id.white(),
+            asset_name.white()
+        );
+    }
 }
 
-pub async fn run(args: HistoryArgs, network_flag: bool, variant: u64) -> Result<()> {
+pub async fn run(
+    args: HistoryArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: Option<u64>,
+    rescan: bool,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
 
-    let scalar: Scalar = setup::unlock_wallet_interactive();
-    let config: Config = get_config(variant, network_flag).unwrap_or_else(|| {
-        eprintln!("Error: Invalid Variant");
-        std::process::exit(1);
-    });
+    let variants: Vec<(u64, Config)> = scan_variants(variant, network_flag);
+    if variants.is_empty() {
+        bail!("Invalid Variant");
+    }
+
+    let key: WalletKey = setup::wallet_key();
 
     println!("\n{}\n", "Getting History..".bright_blue(),);
-    let wallet_addr: Address =
-        address::wallet_contract(network_flag, config.contract.wallet_contract_hash);
-    let txs: Vec<TxResponse> = address_transactions(network_flag, wallet_addr.to_string()).await?;
-    // println!("{:?}", txs);
-    for tx in &txs {
-        let input_match = tx.input_registers.iter().any(|r| {
-            r.is_owned(scalar).unwrap_or_else(|e| {
-                eprintln!("{e}");
-                std::process::exit(1);
-            })
-        });
-        let output_match = tx.output_registers.iter().any(|r| {
-            r.is_owned(scalar).unwrap_or_else(|e| {
-                eprintln!("{e}");
-                std::process::exit(1);
+    let mut histories: Vec<(u64, Vec<HistoryEntry>)> = Vec::with_capacity(variants.len());
+    for (variant, config) in &variants {
+        let history: Vec<HistoryEntry> = cache::sync(provider, config, &key, network_flag, rescan)
+            .await?
+            .history
+            .ok_or_else(|| anyhow!("History Is Not Available From This Backend"))?;
+        histories.push((*variant, history));
+    }
+
+    if let (Some(format), Some(output)) = (args.export, &args.output) {
+        let mut history: Vec<HistoryEntry> = histories
+            .into_iter()
+            .flat_map(|(_, history)| history)
+            .collect();
+        history.sort_by_key(|tx| tx.block_height);
+        // balances run over the whole history before the rows are filtered
+        let rows: Vec<ExportRow> = export::rows(&history)
+            .into_iter()
+            .filter(|row| args.in_range(row.block_height, row.block_time))
+            .filter(|row| match row.direction {
+                Direction::Out => !args.receive_only || args.spend_only,
+                Direction::In => !args.spend_only || args.receive_only,
             })
-        });
+            .collect();
+        let text: String = match format {
+            ExportFormat::Csv => export::to_csv(&rows, args.preset)?,
+            ExportFormat::Json => export::to_json(&rows, network_flag)?,
+        };
+        std::fs::write(output, text)
+            .with_context(|| format!("Failed To Write {}", output.display()))?;
+        println!(
+            "{} {} {}",
+            "Exported".bright_blue(),
+            rows.len().to_string().bright_white(),
+            format!("Rows To {}", output.display()).bright_blue()
+        );
+        return Ok(());
+    }
 
-        if (!args.receive_only || args.spend_only) && input_match {
+    let mut last_variant: u64 = 0;
+    for (variant, tx) in histories.iter().flat_map(|(variant, history)| {
+        history
+            .iter()
+            .filter(|tx| args.in_range(tx.block_height, tx.block_time))
+            .map(move |tx| (*variant, tx))
+    }) {
+        if variant != last_variant {
+            let current: &str = if variant == VARIANT { " (current)" } else { "" };
+            println!("{}", format!("Variant {variant}{current}\n").bright_cyan());
+            last_variant = variant;
+        }
+        if (!args.receive_only || args.spend_only) && tx.spend {
             println!(
                 "Spend: {}, block height: {}",
                 tx.tx_hash.bright_cyan(),
                 tx.block_height.to_string().bright_white()
             );
+            print_amounts(tx);
             continue;
         }
 
-        if (!args.spend_only || args.receive_only) && output_match {
+        if (!args.spend_only || args.receive_only) && tx.receive {
             println!(
                 "Receive: {}, block height: {}",
                 tx.tx_hash.bright_yellow(),
                 tx.block_height.to_string().bright_white()
             );
+            print_amounts(tx);
         }
     }
     Ok(())
@@ -1,33 +1,34 @@
+use crate::cache;
+use crate::collateral;
+use crate::key::WalletKey;
 use crate::setup;
 use anyhow::{Result, bail};
-use blstrs::Scalar;
 use clap::Args;
 use colored::Colorize;
 use pallas_addresses::Address;
-use pallas_crypto::key::ed25519::{PublicKey, SecretKey};
+use pallas_crypto::key::ed25519::SecretKey;
 use pallas_primitives::Hash;
-use pallas_traverse::fees;
 use pallas_txbuilder::{BuildConway, BuiltTransaction, Input, Output, StagingTransaction};
 use pallas_wallet::PrivateKey;
 use rand_core::OsRng;
 use seedelf_core::address;
 use seedelf_core::assets::Assets;
-use seedelf_core::constants::{
-    COLLATERAL_HASH, COLLATERAL_PUBLIC_KEY, Config, MAXIMUM_TOKENS_PER_UTXO, get_config,
-    plutus_v3_cost_model,
+use seedelf_core::collateral::{
+    Collateral, CollateralSource, select_collateral, set_total_collateral,
 };
+use seedelf_core::constants::{Config, MAXIMUM_TOKENS_PER_UTXO, get_config};
 use seedelf_core::data_structures;
+use seedelf_core::evaluate::{self, Evaluation};
 use seedelf_core::transaction::{
-    collateral_input, extract_budgets, reference_utxo, seedelf_minimum_lovelace,
-    seedelf_token_name, total_computation_fee, wallet_minimum_lovelace_with_assets,
+    reference_utxo, seedelf_minimum_lovelace, seedelf_token_name, total_computation_fee,
+    wallet_minimum_lovelace_with_assets, witness_datum,
 };
 use seedelf_core::utxos;
 use seedelf_crypto::register::Register;
-use seedelf_crypto::schnorr::{create_proof, random_scalar};
 use seedelf_display::display;
-use seedelf_koios::koios::{
-    UtxoResponse, evaluate_transaction, extract_bytes_with_logging, submit_tx, witness_collateral,
-};
+use seedelf_koios::koios::{UtxoResponse, extract_bytes_with_logging};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
 /// Struct to hold command-specific arguments
 #[derive(Args)]
 pub struct MintArgs {
@@ -62,7 +63,15 @@ pub struct MintArgs {
     utxos: Option<Vec<String>>,
 }
 
-pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()> {
+pub async fn run(
+    args: MintArgs,
+    provider: &impl ChainProvider,
+    network_flag: bool,
+    variant: u64,
+    evaluation: Evaluation,
+    collateral_source: CollateralSource,
+    rescan: bool,
+) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
 
@@ -70,11 +79,11 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
         eprintln!("Error: Invalid Variant");
         std::process::exit(1);
     });
+    let params: &ProtocolParameters = &setup::protocol_parameters(provider, network_flag).await;
 
     // we need this as the address type and not the shelley
     let wallet_addr: Address =
         address::wallet_contract(network_flag, config.contract.wallet_contract_hash);
-    let collat_addr: Address = address::collateral_address(network_flag);
 
     // this is used to calculate the real fee
     let mut draft_tx: StagingTransaction = StagingTransaction::new();
@@ -84,21 +93,22 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
 
     // we need about 2 ada for the utxo
     let tmp_fee: u64 = 205_000;
-    let lovelace_goal: u64 = seedelf_minimum_lovelace()? + tmp_fee;
+    let lovelace_goal: u64 = seedelf_minimum_lovelace(params)? + tmp_fee;
 
     // if the label is none then just use the empty string
     let label: String = args.label.unwrap_or_default();
 
     // if there is change going back then we need this to rerandomize a datum
-    let scalar: Scalar = setup::unlock_wallet_interactive();
+    let key: WalletKey = setup::wallet_key();
+    let collateral: Collateral =
+        select_collateral(provider, &collateral_source, &key.key_hash()?, network_flag).await?;
 
-    let every_utxo: Vec<UtxoResponse> =
-        utxos::get_credential_utxos(config.contract.wallet_contract_hash, network_flag).await?;
-    let owned_utxos: Vec<UtxoResponse> =
-        utxos::collect_wallet_utxos(scalar, &config.contract.seedelf_policy_id, every_utxo)?;
+    let owned_utxos: Vec<UtxoResponse> = cache::sync(provider, &config, &key, network_flag, rescan)
+        .await?
+        .spendable_utxos(&config.contract.seedelf_policy_id);
 
     let usable_utxos: Vec<UtxoResponse> = if args.utxos.is_none() {
-        utxos::select(owned_utxos, lovelace_goal, Assets::default())?
+        utxos::select(owned_utxos, lovelace_goal, Assets::default(), params)?
     } else {
         // assumes the utxos hold the correct tokens else it will error downstream
         match utxos::parse_tx_utxos(args.utxos.unwrap_or_default()) {
@@ -130,6 +140,9 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
             .ok_or("Not Register Type".to_string())
             .unwrap();
         draft_tx = draft_tx.input(this_input.clone());
+        if let Some(datum) = witness_datum(&utxo)? {
+            draft_tx = draft_tx.datum(datum);
+        }
         input_vector.push(this_input.clone());
         // do the registers
         register_vector.push(inline_datum.clone());
@@ -143,7 +156,7 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
         hex::encode(token_name.clone()).bright_white()
     );
 
-    let min_utxo: u64 = seedelf_minimum_lovelace()?;
+    let min_utxo: u64 = seedelf_minimum_lovelace(params)?;
     println!(
         "{} {}",
         "\nMinimum Required Lovelace:".bright_blue(),
@@ -152,7 +165,7 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
 
     // this is the new seedelf datum
     let datum_vector: Vec<u8> = if args.generator.is_none() && args.public_value.is_none() {
-        Register::create(scalar)?.rerandomize()?.to_vec()?
+        key.datum()?
     } else {
         // both have to be some to get to this point
         // requires should catch the mix cases
@@ -192,11 +205,6 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
     // build out the rest of the draft tx with the tmp fee
     draft_tx = draft_tx
         .output(seedelf_output)
-        .collateral_input(collateral_input(network_flag))
-        .collateral_output(Output::new(
-            collat_addr.clone(),
-            5_000_000 - (tmp_fee) * 3 / 2,
-        ))
         .fee(tmp_fee)
         .mint_asset(
             pallas_crypto::hash::Hash::new(
@@ -226,15 +234,15 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
         .reference_input(reference_utxo(config.reference.wallet_reference_utxo))
         .language_view(
             pallas_txbuilder::ScriptKind::PlutusV3,
-            plutus_v3_cost_model(),
+            params.plutus_v3_cost_model.clone(),
         )
         .disclosed_signer(pallas_crypto::hash::Hash::new(
             hex::decode(&pkh)
                 .unwrap()
                 .try_into()
                 .expect("Not Correct Length"),
-        ))
-        .disclosed_signer(pallas_crypto::hash::Hash::new(COLLATERAL_HASH));
+        ));
+    draft_tx = collateral.stage(draft_tx, tmp_fee, params);
 
     // need to check if there is change going back here
     let change_token_per_utxo: Vec<Assets> = change_tokens
@@ -244,8 +252,8 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
     let mut lovelace_amount: u64 = total_lovelace;
     // a max tokens per change output here
     for (i, change) in change_token_per_utxo.iter().enumerate() {
-        let datum_vector: Vec<u8> = Register::create(scalar)?.rerandomize()?.to_vec()?;
-        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone())?;
+        let datum_vector: Vec<u8> = key.datum()?;
+        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone(), params)?;
         let change_lovelace: u64 = if i == number_of_change_utxo - 1 {
             // this is the last one or the only one
             lovelace_amount = lovelace_amount - min_utxo - tmp_fee;
@@ -268,7 +276,7 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
 
     if number_of_change_utxo == 0 {
         // no tokens so we just need to account for the lovelace going back
-        let datum_vector: Vec<u8> = Register::create(scalar)?.rerandomize()?.to_vec()?;
+        let datum_vector: Vec<u8> = key.datum()?;
         let change_lovelace: u64 = lovelace_amount - min_utxo - tmp_fee;
         let change_output: Output = Output::new(wallet_addr.clone(), change_lovelace)
             .set_inline_datum(datum_vector.clone());
@@ -280,10 +288,9 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
     for (input, datum) in input_vector
         .clone()
         .into_iter()
-        .zip(register_vector.clone().into_iter())
+        .zip(register_vector.clone())
     {
-        let r: Scalar = random_scalar();
-        let (z, g_r) = create_proof(datum, scalar, pkh.clone(), r)?;
+        let (z, g_r) = key.create_proof(datum, pkh.clone())?;
         let spend_redeemer_vector = data_structures::create_spend_redeemer(z, g_r, pkh.clone());
         draft_tx = draft_tx.add_spend_redeemer(
             input,
@@ -314,25 +321,41 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
         raw_tx = raw_tx.remove_spend_redeemer(input);
     }
 
-    let intermediate_tx: BuiltTransaction = draft_tx.build_conway_raw().unwrap();
-
-    let budgets: Vec<(u64, u64)> =
-        match evaluate_transaction(hex::encode(intermediate_tx.tx_bytes.as_ref()), network_flag)
-            .await
-        {
-            Ok(execution_units) => {
-                if let Some(_error) = execution_units.get("error") {
-                    println!("{execution_units:?}");
-                    std::process::exit(1);
-                }
-                let budgets: Vec<(u64, u64)> = extract_budgets(&execution_units);
-                budgets
-            }
-            Err(err) => {
-                eprintln!("Failed to evaluate transaction: {err}");
-                std::process::exit(1);
-            }
-        };
+    // the total collateral is part of the body so it counts towards the size
+    let intermediate_tx: BuiltTransaction = set_total_collateral(
+        draft_tx.build_conway_raw().unwrap(),
+        collateral.total(tmp_fee, params),
+    )?;
+
+    let resolved: Vec<(Input, Output)> = evaluate::resolve_inputs(
+        &usable_utxos,
+        &[
+            (
+                config.reference.seedelf_reference_utxo,
+                &config.contract.seedelf_contract_cbor,
+            ),
+            (
+                config.reference.wallet_reference_utxo,
+                &config.contract.wallet_contract_cbor,
+            ),
+        ],
+    )?;
+    let budgets: Vec<(u64, u64)> = match evaluate::evaluate_transaction(
+        provider,
+        intermediate_tx.tx_bytes.as_ref(),
+        &resolved,
+        params,
+        network_flag,
+        evaluation,
+    )
+    .await
+    {
+        Ok(budgets) => budgets,
+        Err(err) => {
+            eprintln!("Failed to evaluate transaction: {err}");
+            std::process::exit(1);
+        }
+    };
 
     // we can fake the signature here to get the correct tx size
     let fake_signer_secret_key: SecretKey = SecretKey::new(OsRng);
@@ -349,22 +372,23 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
         .try_into()
         .unwrap();
 
-    let tx_fee = fees::compute_linear_fee_policy(tx_size, &(fees::PolicyParams::default()));
+    let tx_fee = params.min_fee(tx_size);
     println!(
         "{} {}",
         "\nTx Size Fee:".bright_blue(),
         tx_fee.to_string().bright_white()
     );
 
-    let compute_fee: u64 = total_computation_fee(budgets.clone());
+    let compute_fee: u64 = total_computation_fee(budgets.clone(), params);
     println!(
         "{} {}",
         "Compute Fee:".bright_blue(),
         compute_fee.to_string().bright_white()
     );
 
-    let script_reference_fee: u64 =
-        config.contract.seedelf_contract_size * 15 + config.contract.wallet_contract_size * 15;
+    let script_reference_fee: u64 = params.reference_script_fee(
+        config.contract.seedelf_contract_size + config.contract.wallet_contract_size,
+    );
     println!(
         "{} {}",
         "Script Reference Fee:".bright_blue(),
@@ -386,10 +410,7 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
     );
 
     raw_tx = raw_tx
-        .collateral_output(Output::new(
-            collat_addr.clone(),
-            5_000_000 - (total_fee) * 3 / 2,
-        ))
+        .collateral_output(collateral.return_output(total_fee, params))
         .fee(total_fee);
 
     // need to check if there is change going back here
@@ -400,8 +421,8 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
     // a max tokens per change output here
     let mut lovelace_amount: u64 = total_lovelace;
     for (i, change) in change_token_per_utxo.iter().enumerate() {
-        let datum_vector: Vec<u8> = Register::create(scalar)?.rerandomize()?.to_vec()?;
-        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone())?;
+        let datum_vector: Vec<u8> = key.datum()?;
+        let minimum: u64 = wallet_minimum_lovelace_with_assets(change.clone(), params)?;
         let change_lovelace: u64 = if i == number_of_change_utxo - 1 {
             // this is the last one or the only one
             lovelace_amount = lovelace_amount - min_utxo - total_fee;
@@ -424,7 +445,7 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
 
     if number_of_change_utxo == 0 {
         // no tokens so we just need to account for the lovelace going back
-        let datum_vector: Vec<u8> = Register::create(scalar)?.rerandomize()?.to_vec()?;
+        let datum_vector: Vec<u8> = key.datum()?;
         let change_lovelace: u64 = lovelace_amount - min_utxo - total_fee;
         let change_output: Output = Output::new(wallet_addr.clone(), change_lovelace)
             .set_inline_datum(datum_vector.clone());
@@ -436,11 +457,10 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
     for ((input, datum), (cpu, mem)) in input_vector
         .clone()
         .into_iter()
-        .zip(register_vector.clone().into_iter())
+        .zip(register_vector.clone())
         .zip(spending.iter())
     {
-        let r: Scalar = random_scalar();
-        let (z, g_r) = create_proof(datum, scalar, pkh.clone(), r)?;
+        let (z, g_r) = key.create_proof(datum, pkh.clone())?;
         let spend_redeemer_vector = data_structures::create_spend_redeemer(z, g_r, pkh.clone());
         raw_tx = raw_tx.add_spend_redeemer(
             input,
@@ -466,68 +486,58 @@ pub async fn run(args: MintArgs, network_flag: bool, variant: u64) -> Result<()>
         }),
     );
 
-    let tx: BuiltTransaction = raw_tx.build_conway_raw().unwrap();
-    let tx_cbor: String = hex::encode(tx.tx_bytes.as_ref());
+    let tx: BuiltTransaction = set_total_collateral(
+        raw_tx.build_conway_raw().unwrap(),
+        collateral.total(total_fee, params),
+    )?;
 
     // need to witness it now
-    let witness_public_key: PublicKey = PublicKey::from(COLLATERAL_PUBLIC_KEY);
-
-    match witness_collateral(tx_cbor.clone(), network_flag).await {
-        Ok(witness) => {
-            let witness_cbor = witness.get("witness").and_then(|v| v.as_str()).unwrap();
-            let witness_sig = &witness_cbor[witness_cbor.len() - 128..];
-            let witness_vector: [u8; 64] = hex::decode(witness_sig).unwrap().try_into().unwrap();
+    let signed_tx_cbor: BuiltTransaction = collateral::witness(tx, &collateral, &key, network_flag)
+        .await?
+        .sign(pallas_wallet::PrivateKey::from(one_time_secret_key.clone()))
+        .unwrap();
 
-            let signed_tx_cbor = tx
-                .sign(pallas_wallet::PrivateKey::from(one_time_secret_key.clone()))
-                .unwrap()
-                .add_signature(witness_public_key, witness_vector)
-                .unwrap();
+    println!(
+        "\nTx Cbor: {}",
+        hex::encode(signed_tx_cbor.tx_bytes.clone()).white()
+    );
 
+    match provider
+        .submit_tx(hex::encode(signed_tx_cbor.tx_bytes))
+        .await
+    {
+        Ok(response) => {
+            if let Some(_error) = response.get("contents") {
+                println!("\nError: {response}");
+                std::process::exit(1);
+            }
+            println!("\nTransaction Successfully Submitted!");
             println!(
-                "\nTx Cbor: {}",
-                hex::encode(signed_tx_cbor.tx_bytes.clone()).white()
+                "\nTx Hash: {}",
+                response.as_str().unwrap_or("default").bright_cyan()
             );
-
-            match submit_tx(hex::encode(signed_tx_cbor.tx_bytes), network_flag).await {
-                Ok(response) => {
-                    if let Some(_error) = response.get("contents") {
-                        println!("\nError: {response}");
-                        std::process::exit(1);
-                    }
-                    println!("\nTransaction Successfully Submitted!");
-                    println!(
-                        "\nTx Hash: {}",
-                        response.as_str().unwrap_or("default").bright_cyan()
-                    );
-                    if network_flag {
-                        println!(
-                            "{}",
-                            format!(
-                                "\nhttps://preprod.cardanoscan.io/transaction/{}",
-                                response.as_str().unwrap_or("default")
-                            )
-                            .bright_purple()
-                        );
-                    } else {
-                        println!(
-                            "{}",
-                            format!(
-                                "\nhttps://cardanoscan.io/transaction/{}",
-                                response.as_str().unwrap_or("default")
-                            )
-                            .bright_purple()
-                        );
-                    }
-                }
-                Err(err) => {
-                    eprintln!("Failed to submit tx: {err}");
-                    std::process::exit(1);
-                }
+            if network_flag {
+                println!(
+                    "{}",
+                    format!(
+                        "\nhttps://preprod.cardanoscan.io/transaction/{}",
+                        response.as_str().unwrap_or("default")
+                    )
+                    .bright_purple()
+                );
+            } else {
+                println!(
+                    "{}",
+                    format!(
+                        "\nhttps://cardanoscan.io/transaction/{}",
+                        response.as_str().unwrap_or("default")
+                    )
+                    .bright_purple()
+                );
             }
         }
         Err(err) => {
-            eprintln!("Failed to fetch UTxOs: {err}");
+            eprintln!("Failed to submit tx: {err}");
             std::process::exit(1);
         }
     }
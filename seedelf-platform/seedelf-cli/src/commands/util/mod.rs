@@ -1,4 +1,9 @@
+use anyhow::Result;
 use clap::{Args, Subcommand};
+use seedelf_core::collateral::CollateralSource;
+use seedelf_core::constants::VARIANT;
+use seedelf_core::evaluate::Evaluation;
+use seedelf_koios::provider::ChainProvider;
 
 pub mod age;
 pub mod base;
@@ -38,48 +43,44 @@ pub struct UtilArgs {
     pub command: UtilCommands,
 }
 
-pub async fn run(args: UtilArgs, preprod_flag: bool, variant: u64) {
+pub async fn run(
+    args: UtilArgs,
+    provider: &impl ChainProvider,
+    preprod_flag: bool,
+    scan_variant: Option<u64>,
+    evaluation: Evaluation,
+    collateral_source: CollateralSource,
+    rescan: bool,
+) -> Result<()> {
+    // find and history scan every variant unless one was picked
+    let variant: u64 = scan_variant.unwrap_or(VARIANT);
     match args.command {
         UtilCommands::ExposeKey => {
             expose_key::run();
+            Ok(())
         }
-        UtilCommands::Find(args) => {
-            if let Err(err) = find::run(args, preprod_flag, variant).await {
-                eprintln!("Error: {err}");
-            }
-        }
-        UtilCommands::Age(args) => {
-            if let Err(err) = age::run(args, preprod_flag, variant).await {
-                eprintln!("Error: {err}");
-            }
-        }
-        UtilCommands::Statistics => {
-            if let Err(err) = statistics::run(preprod_flag, variant).await {
-                eprintln!("Error: {err}");
-            }
-        }
+        UtilCommands::Find(args) => find::run(args, provider, preprod_flag, scan_variant).await,
+        UtilCommands::Age(args) => age::run(args, preprod_flag, variant).await,
+        UtilCommands::Statistics => statistics::run(provider, preprod_flag, variant).await,
         UtilCommands::Extract(args) => {
-            if let Err(err) = extract::run(args, preprod_flag, variant).await {
-                eprintln!("Error: {err}");
-            }
+            extract::run(args, provider, preprod_flag, variant, evaluation).await
         }
         UtilCommands::Mint(args) => {
-            if let Err(err) = mint::run(args, preprod_flag, variant).await {
-                eprintln!("Error: {err}");
-            }
-        }
-        UtilCommands::Migrate(args) => {
-            if let Err(err) = migrate::run(args, preprod_flag).await {
-                eprintln!("Error: {err}");
-            }
+            mint::run(
+                args,
+                provider,
+                preprod_flag,
+                variant,
+                evaluation,
+                collateral_source,
+                rescan,
+            )
+            .await
         }
+        UtilCommands::Migrate(args) => migrate::run(args, preprod_flag).await,
         UtilCommands::History(args) => {
-            if let Err(err) = history::run(args, preprod_flag, variant).await {
-                eprintln!("Error: {err}");
-            }
-        }
-        UtilCommands::Base => {
-            base::run();
+            history::run(args, provider, preprod_flag, scan_variant, rescan).await
         }
+        UtilCommands::Base => base::run(),
     }
 }
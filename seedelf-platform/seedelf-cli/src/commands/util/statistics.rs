@@ -3,9 +3,9 @@ use seedelf_core::constants::{Config, get_config};
 use seedelf_core::utxos;
 use seedelf_display::display;
 use seedelf_display::text_coloring::{display_blue, show_lovelace_and_utxos_counts};
-use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::provider::ChainProvider;
 
-pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
+pub async fn run(provider: &impl ChainProvider, network_flag: bool, variant: u64) -> Result<()> {
     display::is_their_an_update().await;
     display::preprod_text(network_flag);
     display_blue("Seedelf Statistics");
@@ -13,10 +13,16 @@ pub async fn run(network_flag: bool, variant: u64) -> Result<()> {
         eprintln!("Error: Invalid Variant");
         std::process::exit(1);
     });
-    let every_utxo: Vec<UtxoResponse> =
-        utxos::get_credential_utxos(config.contract.wallet_contract_hash, network_flag).await?;
-    let (total_utxos, total_lovelace, total_seedelfs) =
-        utxos::count_lovelace_and_utxos(&config.contract.seedelf_policy_id, every_utxo)?;
+    let (mut total_utxos, mut total_lovelace, mut total_seedelfs) = (0, 0, 0);
+    utxos::for_each_credential_page(provider, config.contract.wallet_contract_hash, |page| {
+        let (utxos, lovelace, seedelfs) =
+            utxos::count_lovelace_and_utxos(&config.contract.seedelf_policy_id, page)?;
+        total_utxos += utxos;
+        total_lovelace += lovelace;
+        total_seedelfs += seedelfs;
+        Ok(true)
+    })
+    .await?;
     show_lovelace_and_utxos_counts(total_utxos, total_lovelace, total_seedelfs);
     // other things can go here
     Ok(())
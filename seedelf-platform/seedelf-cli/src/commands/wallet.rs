@@ -0,0 +1,195 @@
+use crate::config::Config;
+use crate::setup;
+use anyhow::{Context, Result, bail};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use std::fs;
+use std::io::{self, Write};
+
+#[derive(Subcommand)]
+pub enum WalletCommands {
+    /// List the wallets in the seedelf home
+    List,
+    /// Create a new wallet
+    Create {
+        /// The name of the new wallet
+        name: String,
+    },
+    /// Rename a wallet
+    Rename {
+        /// The current name
+        from: String,
+        /// The new name
+        to: String,
+    },
+    /// Delete a wallet file, its key is lost unless it is backed up
+    Delete {
+        /// The wallet to delete
+        name: String,
+    },
+    /// Re-encrypt a wallet under a new password
+    ChangePassword {
+        /// The wallet to change, defaults to the wallet in use
+        name: Option<String>,
+    },
+    /// Show the default wallet, or record a new one
+    Default {
+        /// The wallet opened when --wallet is not given
+        name: Option<String>,
+    },
+}
+
+#[derive(Args)]
+pub struct WalletArgs {
+    #[command(subcommand)]
+    pub command: WalletCommands,
+}
+
+pub fn run(args: WalletArgs, network_flag: bool) -> Result<()> {
+    let mut config: Config = Config::load()?;
+    let wallets: Vec<String> = setup::list_wallets();
+    match args.command {
+        WalletCommands::List => {
+            if wallets.is_empty() {
+                println!("{}", "No Wallets Found".bright_yellow());
+            }
+            let selected: Option<String> = setup::selected_wallet().ok();
+            for name in wallets {
+                let network: String = setup::wallet_header(&name)
+                    .and_then(|header| header.network)
+                    .map(|network| format!(" ({network})"))
+                    .unwrap_or_default();
+                if selected.as_ref() == Some(&name) {
+                    println!(
+                        "{} {}{}",
+                        "*".bright_green(),
+                        name.bright_white(),
+                        network.bright_black()
+                    );
+                } else {
+                    println!("  {name}{}", network.bright_black());
+                }
+            }
+        }
+        WalletCommands::Create { name } => {
+            check_name(&name)?;
+            if wallets.contains(&name) {
+                bail!("Wallet {name} Already Exists");
+            }
+            let password: String = setup::is_valid_password();
+            let network: &str = if network_flag { "preprod" } else { "mainnet" };
+            setup::create_wallet(name.clone(), password, Some(network.to_string()))
+                .map_err(anyhow::Error::msg)?;
+            // record the wallet that was in use so adding one never makes the choice ambiguous
+            if config.wallet.is_none() && wallets.len() <= 1 {
+                config.wallet = Some(wallets.first().unwrap_or(&name).clone());
+                config.save()?;
+            }
+            println!(
+                "{} {}",
+                "Wallet Created:".bright_blue(),
+                name.bright_white()
+            );
+        }
+        WalletCommands::Rename { from, to } => {
+            check_name(&to)?;
+            if !wallets.contains(&from) {
+                bail!("Wallet {from} Does Not Exist");
+            }
+            if wallets.contains(&to) {
+                bail!("Wallet {to} Already Exists");
+            }
+            let password: String = current_password(&from)?;
+            setup::rename_wallet(&from, &to, &password).map_err(anyhow::Error::msg)?;
+            if config.wallet.as_ref() == Some(&from) {
+                config.wallet = Some(to.clone());
+                config.save()?;
+            }
+            println!(
+                "{} {} {} {}",
+                "Renamed".bright_blue(),
+                from.bright_white(),
+                "To".bright_blue(),
+                to.bright_white()
+            );
+        }
+        WalletCommands::Delete { name } => {
+            if !wallets.contains(&name) {
+                bail!("Wallet {name} Does Not Exist");
+            }
+            println!(
+                "{}",
+                format!("\nDeleting {name} Destroys Its Key, Type The Wallet Name To Confirm:")
+                    .bright_red()
+            );
+            io::stdout().flush()?;
+            let mut answer: String = String::new();
+            io::stdin().read_line(&mut answer)?;
+            if answer.trim() != name {
+                bail!("Wallet Not Deleted");
+            }
+            fs::remove_file(setup::wallet_path(&name).map_err(anyhow::Error::msg)?)
+                .with_context(|| format!("Failed To Delete {name}"))?;
+            if config.wallet.as_ref() == Some(&name) {
+                config.wallet = None;
+                config.save()?;
+            }
+            println!("{} {}", "Deleted:".bright_blue(), name.bright_white());
+        }
+        WalletCommands::ChangePassword { name } => {
+            let name: String = match name {
+                Some(name) => name,
+                None => setup::selected_wallet().map_err(anyhow::Error::msg)?,
+            };
+            if !wallets.contains(&name) {
+                bail!("Wallet {name} Does Not Exist");
+            }
+            let password: String = current_password(&name)?;
+            println!("{}", "\nNew Password:".bright_purple());
+            let new_password: String = setup::is_valid_password();
+            setup::change_password(&name, &password, &new_password).map_err(anyhow::Error::msg)?;
+            println!(
+                "{} {}",
+                "Password Changed:".bright_blue(),
+                name.bright_white()
+            );
+        }
+        WalletCommands::Default { name: None } => match config.wallet {
+            Some(name) => println!("{name}"),
+            None => bail!("No Default Wallet Is Recorded"),
+        },
+        WalletCommands::Default { name: Some(name) } => {
+            if !wallets.contains(&name) {
+                bail!("Wallet {name} Does Not Exist");
+            }
+            config.wallet = Some(name.clone());
+            config.save()?;
+            println!(
+                "{} {}",
+                "Default Wallet:".bright_blue(),
+                name.bright_white()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The password of a wallet, from --password-file, -fd or -env when given, else a prompt.
+fn current_password(name: &str) -> Result<String> {
+    match setup::current_password_source() {
+        Some(password) => password.map_err(anyhow::Error::msg),
+        None => {
+            println!(
+                "{}",
+                format!("\nCurrent Password For {name}:").bright_purple()
+            );
+            Ok(setup::enter_password())
+        }
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    setup::wallet_path(name)
+        .map(|_| ())
+        .map_err(anyhow::Error::msg)
+}
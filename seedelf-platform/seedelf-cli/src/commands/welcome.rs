@@ -1,3 +1,4 @@
+use crate::setup;
 use colored::Colorize;
 use seedelf_display::display::is_their_an_update;
 
@@ -21,7 +22,11 @@ pub async fn run() {
         "seedelf-cli help".bright_blue()
     );
     println!(
-        "{}",
-        "\nYou may find the wallet file in your home directory in the .seedelf folder.".white()
+        "{} {}",
+        "\nYou may find the wallet file in".white(),
+        setup::seedelf_home_path()
+            .display()
+            .to_string()
+            .bright_white()
     );
 }
@@ -0,0 +1,355 @@
+use crate::export::ExportFormat;
+use crate::setup::seedelf_home_path;
+use anyhow::{Context, Result, bail};
+use seedelf_koios::blockfrost::Blockfrost;
+use seedelf_koios::koios::Koios;
+use seedelf_koios::node::NodeClient;
+use seedelf_koios::ogmios::OgmiosKupo;
+use seedelf_koios::provider::{Backend, BackendKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the config file inside the seedelf home.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// The profile used when none is selected.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Environment variable selecting the profile, like `--profile`.
+pub const PROFILE_ENV: &str = "SEEDELF_PROFILE";
+
+/// The keys a profile can hold.
+pub const PROFILE_KEYS: [&str; 6] = ["network", "backend", "url", "variant", "address", "output"];
+
+/// Defaults for one network and backend setup.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    /// `mainnet` or `preprod`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// The chain data backend, as accepted by `--backend`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// The backend URL, or the socket path for the node backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The contract variant for commands that build transactions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<u64>,
+    /// The receive address `create` and `fund` use when `--address` is left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// The format `util history --output` writes when `--export` is left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+/// The contents of `config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// The profile used when `--profile` is not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// The wallet opened when `--wallet` is not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    /// `false` turns the check for a new release off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_check: Option<bool>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Profile {
+    /// Whether the profile is on the pre-production network.
+    pub fn preprod(&self) -> bool {
+        self.network.as_deref() == Some("preprod")
+    }
+
+    /// The backend the profile selects, if any.
+    pub fn backend_kind(&self) -> Result<Option<BackendKind>> {
+        self.backend
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(anyhow::Error::msg)
+    }
+
+    /// The export format the profile selects, if any.
+    pub fn output_format(&self) -> Result<Option<ExportFormat>> {
+        self.output
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(anyhow::Error::msg)
+    }
+
+    /// Builds the profile's backend, the rest of its settings coming from the environment.
+    ///
+    /// # Arguments
+    ///
+    /// * `network_flag` - A boolean flag specifying the network:
+    ///     - `true` for Preprod.
+    ///     - `false` for Mainnet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Backend))` - The backend named by the profile, pointed at its URL.
+    /// * `Ok(None)` - If the profile names no backend.
+    /// * `Err(anyhow::Error)` - If the backend can not be built.
+    pub fn backend(&self, network_flag: bool) -> Result<Option<Backend>> {
+        let Some(kind) = self.backend_kind()? else {
+            return Ok(None);
+        };
+        let url: Option<&str> = self.url.as_deref();
+        let backend: Backend = match kind {
+            BackendKind::Koios => {
+                let koios: Koios = Koios::from_env(network_flag).map_err(anyhow::Error::msg)?;
+                Backend::Koios(match url {
+                    Some(url) => koios.with_base_url(url),
+                    None => koios,
+                })
+            }
+            BackendKind::Blockfrost => {
+                let blockfrost: Blockfrost =
+                    Blockfrost::from_env(network_flag).map_err(anyhow::Error::msg)?;
+                Backend::Blockfrost(match url {
+                    Some(url) => blockfrost.with_base_url(url),
+                    None => blockfrost,
+                })
+            }
+            BackendKind::OgmiosKupo => {
+                let ogmios: OgmiosKupo =
+                    OgmiosKupo::from_env(network_flag).map_err(anyhow::Error::msg)?;
+                Backend::OgmiosKupo(match url {
+                    Some(url) => ogmios.with_ogmios_url(url),
+                    None => ogmios,
+                })
+            }
+            BackendKind::Node => Backend::Node(match url {
+                Some(url) => NodeClient::new(network_flag, url),
+                None => NodeClient::from_env(network_flag).map_err(anyhow::Error::msg)?,
+            }),
+        };
+        Ok(Some(backend))
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "network" => self.network.clone(),
+            "backend" => self.backend.clone(),
+            "url" => self.url.clone(),
+            "variant" => self.variant.map(|v| v.to_string()),
+            "address" => self.address.clone(),
+            "output" => self.output.clone(),
+            _ => None,
+        }
+    }
+
+    fn set(&mut self, key: &str, value: Option<String>) -> Result<()> {
+        match key {
+            "network" => {
+                if let Some(network) = &value
+                    && network != "mainnet"
+                    && network != "preprod"
+                {
+                    bail!("Unknown Network: {network}, expected mainnet or preprod");
+                }
+                self.network = value;
+            }
+            "backend" => {
+                if let Some(backend) = &value {
+                    backend.parse::<BackendKind>().map_err(anyhow::Error::msg)?;
+                }
+                self.backend = value;
+            }
+            "url" => self.url = value,
+            "variant" => {
+                self.variant = value
+                    .map(|v| v.parse::<u64>())
+                    .transpose()
+                    .context("The Variant Must Be A Number")?;
+            }
+            "address" => self.address = value,
+            "output" => {
+                if let Some(output) = &value {
+                    output.parse::<ExportFormat>().map_err(anyhow::Error::msg)?;
+                }
+                self.output = value;
+            }
+            other => bail!(
+                "Unknown Key: {other}, expected profile, wallet, update_check or one of {}",
+                PROFILE_KEYS.join(", ")
+            ),
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Where the config lives, `config.toml` in the seedelf home.
+    pub fn path() -> PathBuf {
+        seedelf_home_path().join(CONFIG_FILE)
+    }
+
+    /// Reads the config, an empty one if the file does not exist yet.
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::path())
+    }
+
+    /// Reads the config from a file, an empty one if the file does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The config file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Config)` - The config.
+    /// * `Err(anyhow::Error)` - If the file can not be read or is not valid TOML.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text: String = fs::read_to_string(path)
+            .with_context(|| format!("Failed To Read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid Config In {}", path.display()))
+    }
+
+    /// Writes the config back to the seedelf home.
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::path())
+    }
+
+    /// Writes the config to a file.
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed To Create {}", parent.display()))?;
+        }
+        let text: String = toml::to_string_pretty(self).context("Failed To Serialize Config")?;
+        fs::write(path, text).with_context(|| format!("Failed To Write {}", path.display()))
+    }
+
+    /// The name of the profile in use, the selected one, else the configured one, else `default`.
+    pub fn active_profile(&self, selected: Option<&str>) -> String {
+        selected
+            .or(self.profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_string()
+    }
+
+    /// The settings of the profile in use, empty if it has none.
+    pub fn profile(&self, selected: Option<&str>) -> Profile {
+        self.profiles
+            .get(&self.active_profile(selected))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether the check for a new release should run.
+    pub fn update_check(&self) -> bool {
+        self.update_check.unwrap_or(true)
+    }
+
+    /// Reads a key.
+    ///
+    /// `profile`, `wallet` and `update_check` are global, any other key belongs to a profile,
+    /// either named as `<profile>.<key>` or the profile in use.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to read.
+    /// * `selected` - The profile picked with `--profile`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Option<String>)` - The value, `None` when it is not set.
+    /// * `Err(anyhow::Error)` - If the key is unknown.
+    pub fn get(&self, key: &str, selected: Option<&str>) -> Result<Option<String>> {
+        match key {
+            "profile" => Ok(self.profile.clone()),
+            "wallet" => Ok(self.wallet.clone()),
+            "update_check" => Ok(self.update_check.map(|check| check.to_string())),
+            _ => {
+                let (name, key) = self.split_key(key, selected);
+                if !PROFILE_KEYS.contains(&key) {
+                    bail!(
+                        "Unknown Key: {key}, expected profile, wallet, update_check or one of {}",
+                        PROFILE_KEYS.join(", ")
+                    );
+                }
+                Ok(self.profiles.get(&name).and_then(|p| p.get(key)))
+            }
+        }
+    }
+
+    /// Sets a key, an empty value removes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to set, as for [`Config::get`].
+    /// * `value` - The new value.
+    /// * `selected` - The profile picked with `--profile`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the value is valid for the key.
+    /// * `Err(anyhow::Error)` - If the key is unknown or the value does not parse.
+    pub fn set(&mut self, key: &str, value: &str, selected: Option<&str>) -> Result<()> {
+        let value: Option<String> = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        match key {
+            "profile" => self.profile = value,
+            "wallet" => self.wallet = value,
+            "update_check" => {
+                self.update_check = value
+                    .map(|v| v.parse::<bool>())
+                    .transpose()
+                    .context("update_check Must Be true Or false")?;
+            }
+            _ => {
+                let (name, key) = self.split_key(key, selected);
+                let mut profile: Profile = self.profiles.get(&name).cloned().unwrap_or_default();
+                profile.set(key, value)?;
+                if profile == Profile::default() {
+                    self.profiles.remove(&name);
+                } else {
+                    self.profiles.insert(name, profile);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Every key that is set, as `(key, value)` with profile keys written `<profile>.<key>`.
+    pub fn list(&self) -> Vec<(String, String)> {
+        let mut entries: Vec<(String, String)> = Vec::new();
+        if let Some(profile) = &self.profile {
+            entries.push(("profile".to_string(), profile.clone()));
+        }
+        if let Some(wallet) = &self.wallet {
+            entries.push(("wallet".to_string(), wallet.clone()));
+        }
+        if let Some(check) = self.update_check {
+            entries.push(("update_check".to_string(), check.to_string()));
+        }
+        for (name, profile) in &self.profiles {
+            for key in PROFILE_KEYS {
+                if let Some(value) = profile.get(key) {
+                    entries.push((format!("{name}.{key}"), value));
+                }
+            }
+        }
+        entries
+    }
+
+    fn split_key<'a>(&self, key: &'a str, selected: Option<&str>) -> (String, &'a str) {
+        let key: &str = key.strip_prefix("profiles.").unwrap_or(key);
+        match key.rsplit_once('.') {
+            Some((name, key)) => (name.to_string(), key),
+            None => (self.active_profile(selected), key),
+        }
+    }
+}
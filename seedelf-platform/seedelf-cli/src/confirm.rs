@@ -0,0 +1,66 @@
+use anyhow::Result;
+use colored::Colorize;
+use seedelf_core::lifecycle::{TxState, TxTracker};
+use seedelf_koios::provider::ChainProvider;
+use std::io::{self, Write};
+
+/// Follows a submitted transaction until it reaches a depth, offering to resubmit
+/// the identical signed transaction when a rollback drops it.
+///
+/// # Arguments
+///
+/// * `provider` - The chain data backend.
+/// * `tx_hash` - The hash of the submitted transaction.
+/// * `signed_tx_cbor` - The signed transaction as it was submitted.
+/// * `depth` - The confirmations to wait for.
+///
+/// # Returns
+///
+/// * `Ok(())` - Once the transaction is final or a dropped one is not resubmitted.
+/// * `Err(anyhow::Error)` - If the status can not be read or the resubmission fails.
+pub async fn wait_for_depth(
+    provider: &impl ChainProvider,
+    tx_hash: String,
+    signed_tx_cbor: String,
+    depth: u64,
+) -> Result<()> {
+    let mut tracker: TxTracker = TxTracker::new(tx_hash, signed_tx_cbor).with_depth(depth);
+    println!(
+        "{}",
+        format!("\nWaiting For {} Confirmations..", tracker.depth).bright_blue()
+    );
+    loop {
+        let state: TxState = tracker
+            .follow(provider, |state| match state {
+                TxState::Pending => println!("{}", "Pending".bright_white()),
+                TxState::Confirmed(n) => {
+                    println!("Confirmations: {}", n.to_string().bright_yellow())
+                }
+                TxState::Final(n) => println!(
+                    "{} {}",
+                    "Transaction Is Final With Confirmations:".bright_green(),
+                    n.to_string().bright_yellow()
+                ),
+                TxState::RolledBack => {
+                    println!("{}", "Transaction Was Rolled Back".bright_red())
+                }
+            })
+            .await?;
+        if state != TxState::RolledBack || !confirm_resubmit() {
+            return Ok(());
+        }
+        tracker.resubmit(provider).await?;
+        println!("{}", "Transaction Resubmitted".bright_cyan());
+    }
+}
+
+fn confirm_resubmit() -> bool {
+    print!(
+        "{}",
+        "Resubmit The Identical Signed Transaction? [y/N] ".bright_purple()
+    );
+    io::stdout().flush().ok();
+    let mut answer: String = String::new();
+    io::stdin().read_line(&mut answer).ok();
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
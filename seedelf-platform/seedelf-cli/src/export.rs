@@ -0,0 +1,379 @@
+use crate::cache::{HistoryEntry, Movement, TokenAmount};
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Bumped whenever a field of the JSON export changes meaning or goes away.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// The file format of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!(
+                "Unknown Export Format: {other}, expected csv or json"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Csv => write!(f, "csv"),
+            ExportFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// The CSV columns, either the full seedelf layout or the import layout of a tax tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Preset {
+    /// Every field of a row, tokens in a single column.
+    #[default]
+    Generic,
+    /// The Koinly universal import, one currency per line.
+    Koinly,
+    /// The CoinTracking CSV import, one currency per line.
+    CoinTracking,
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "generic" => Ok(Preset::Generic),
+            "koinly" => Ok(Preset::Koinly),
+            "cointracking" => Ok(Preset::CoinTracking),
+            other => Err(format!(
+                "Unknown Preset: {other}, expected generic, koinly or cointracking"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Preset::Generic => write!(f, "generic"),
+            Preset::Koinly => write!(f, "koinly"),
+            Preset::CoinTracking => write!(f, "cointracking"),
+        }
+    }
+}
+
+/// Whether value left or entered the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    In,
+    Out,
+}
+
+/// One owned input or output, the unit of an export.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportRow {
+    /// The block time in RFC 3339, `None` when the backend does not report it.
+    pub timestamp: Option<String>,
+    pub block_time: u64,
+    pub block_height: u64,
+    pub tx_hash: String,
+    pub direction: Direction,
+    pub lovelace: u64,
+    pub tokens: Vec<TokenAmount>,
+    /// The part of the transaction fee carried by this input, zero for outputs.
+    pub fee_share: u64,
+    /// The wallet's lovelace once this row is applied.
+    pub balance: i64,
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    version: u32,
+    network: &'a str,
+    rows: &'a [ExportRow],
+}
+
+/// Splits the wallet history into one row per owned input or output.
+///
+/// The fee of a spend is shared by its inputs in proportion to their lovelace and
+/// inputs come before outputs inside a transaction, so the running balance never
+/// counts change before the spend that made it.
+///
+/// # Arguments
+///
+/// * `history` - The wallet history, ordered by block.
+///
+/// # Returns
+///
+/// * `Vec<ExportRow>` - The rows, ordered by block.
+pub fn rows(history: &[HistoryEntry]) -> Vec<ExportRow> {
+    let mut rows: Vec<ExportRow> = Vec::new();
+    let mut balance: i64 = 0;
+    for entry in history {
+        let mut movements: Vec<&Movement> = entry.movements.iter().collect();
+        movements.sort_by_key(|movement| !movement.spend);
+        let fees: Vec<u64> = fee_shares(entry, &movements);
+        for (movement, fee_share) in movements.into_iter().zip(fees) {
+            let direction: Direction = if movement.spend {
+                balance -= movement.lovelace as i64;
+                Direction::Out
+            } else {
+                balance += movement.lovelace as i64;
+                Direction::In
+            };
+            rows.push(ExportRow {
+                timestamp: timestamp(entry.block_time),
+                block_time: entry.block_time,
+                block_height: entry.block_height,
+                tx_hash: entry.tx_hash.clone(),
+                direction,
+                lovelace: movement.lovelace,
+                tokens: movement.tokens.clone(),
+                fee_share,
+                balance,
+            });
+        }
+    }
+    rows
+}
+
+fn fee_shares(entry: &HistoryEntry, movements: &[&Movement]) -> Vec<u64> {
+    let mut shares: Vec<u64> = vec![0; movements.len()];
+    let fee: u64 = match entry.fee {
+        Some(fee) if entry.spend => fee,
+        _ => return shares,
+    };
+    let spent: u128 = entry.spent_lovelace.max(1) as u128;
+    let mut left: u64 = fee;
+    let mut last: Option<usize> = None;
+    for (i, movement) in movements.iter().enumerate().filter(|(_, m)| m.spend) {
+        shares[i] = (fee as u128 * movement.lovelace as u128 / spent) as u64;
+        left -= shares[i];
+        last = Some(i);
+    }
+    // rounding leftovers go to the last input so the shares add up to the fee
+    if let Some(i) = last {
+        shares[i] += left;
+    }
+    shares
+}
+
+fn timestamp(block_time: u64) -> Option<String> {
+    DateTime::<Utc>::from_timestamp(block_time as i64, 0)
+        .filter(|_| block_time > 0)
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+fn plain_time(block_time: u64, suffix: &str) -> String {
+    DateTime::<Utc>::from_timestamp(block_time as i64, 0)
+        .filter(|_| block_time > 0)
+        .map(|time| format!("{}{suffix}", time.format("%Y-%m-%d %H:%M:%S")))
+        .unwrap_or_default()
+}
+
+fn ada(lovelace: u64) -> String {
+    format!("{}.{:06}", lovelace / 1_000_000, lovelace % 1_000_000)
+}
+
+/// The name a tax tool shows for a token, the asset name when it is readable text.
+fn currency(token: &TokenAmount) -> String {
+    hex::decode(&token.asset_name)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_graphic()))
+        .unwrap_or_else(|| format!("{}.{}", token.policy_id, token.asset_name))
+}
+
+/// The ADA of a row and each of its tokens as `(amount, currency, fee)`, the fee on the ADA line.
+///
+/// Tax tools add the fee to the sent amount, so the ADA line leaves the fee share out and the
+/// two together still match what left the wallet.
+fn currency_lines(row: &ExportRow) -> Vec<(String, String, u64)> {
+    let sent: u64 = row.lovelace.saturating_sub(row.fee_share);
+    std::iter::once((ada(sent), "ADA".to_string(), row.fee_share))
+        .chain(
+            row.tokens
+                .iter()
+                .map(|t| (t.amount.to_string(), currency(t), 0)),
+        )
+        .collect()
+}
+
+fn fee_columns(fee: u64) -> (String, String) {
+    if fee > 0 {
+        (ada(fee), "ADA".to_string())
+    } else {
+        (String::new(), String::new())
+    }
+}
+
+/// Writes the rows as a versioned JSON document.
+///
+/// # Arguments
+///
+/// * `rows` - The rows to export.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod.
+///     - `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The JSON document.
+/// * `Err(anyhow::Error)` - If the rows can not be serialized.
+pub fn to_json(rows: &[ExportRow], network_flag: bool) -> Result<String> {
+    let export: JsonExport = JsonExport {
+        version: EXPORT_SCHEMA_VERSION,
+        network: if network_flag { "preprod" } else { "mainnet" },
+        rows,
+    };
+    serde_json::to_string_pretty(&export).context("Failed To Serialize Export")
+}
+
+/// Writes the rows as CSV in the columns of a preset.
+///
+/// # Arguments
+///
+/// * `rows` - The rows to export.
+/// * `preset` - The column layout.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The CSV text, with a header line.
+/// * `Err(anyhow::Error)` - If a line can not be written.
+pub fn to_csv(rows: &[ExportRow], preset: Preset) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    match preset {
+        Preset::Generic => {
+            writer.write_record([
+                "timestamp",
+                "block_height",
+                "tx_hash",
+                "direction",
+                "lovelace",
+                "tokens",
+                "fee_share",
+                "balance",
+            ])?;
+            for row in rows {
+                let tokens: Vec<String> = row
+                    .tokens
+                    .iter()
+                    .map(|t| format!("{}.{}:{}", t.policy_id, t.asset_name, t.amount))
+                    .collect();
+                writer.write_record([
+                    row.timestamp.clone().unwrap_or_default(),
+                    row.block_height.to_string(),
+                    row.tx_hash.clone(),
+                    match row.direction {
+                        Direction::In => "in".to_string(),
+                        Direction::Out => "out".to_string(),
+                    },
+                    row.lovelace.to_string(),
+                    tokens.join(";"),
+                    row.fee_share.to_string(),
+                    row.balance.to_string(),
+                ])?;
+            }
+        }
+        Preset::Koinly => {
+            writer.write_record([
+                "Date",
+                "Sent Amount",
+                "Sent Currency",
+                "Received Amount",
+                "Received Currency",
+                "Fee Amount",
+                "Fee Currency",
+                "Label",
+                "Description",
+                "TxHash",
+            ])?;
+            for row in rows {
+                let date: String = plain_time(row.block_time, " UTC");
+                for (amount, currency, fee) in currency_lines(row) {
+                    let (sent, received) = match row.direction {
+                        Direction::Out => ((amount, currency), (String::new(), String::new())),
+                        Direction::In => ((String::new(), String::new()), (amount, currency)),
+                    };
+                    let (fee_amount, fee_currency) = fee_columns(fee);
+                    writer.write_record([
+                        date.clone(),
+                        sent.0,
+                        sent.1,
+                        received.0,
+                        received.1,
+                        fee_amount,
+                        fee_currency,
+                        String::new(),
+                        "Seedelf Wallet".to_string(),
+                        row.tx_hash.clone(),
+                    ])?;
+                }
+            }
+        }
+        Preset::CoinTracking => {
+            writer.write_record([
+                "Type",
+                "Buy Amount",
+                "Buy Currency",
+                "Sell Amount",
+                "Sell Currency",
+                "Fee",
+                "Fee Currency",
+                "Exchange",
+                "Trade-Group",
+                "Comment",
+                "Date",
+                "Tx-ID",
+            ])?;
+            for row in rows {
+                let date: String = plain_time(row.block_time, "");
+                for (amount, currency, fee) in currency_lines(row) {
+                    let (kind, buy, sell) = match row.direction {
+                        Direction::In => (
+                            "Deposit",
+                            (amount, currency),
+                            (String::new(), String::new()),
+                        ),
+                        Direction::Out => (
+                            "Withdrawal",
+                            (String::new(), String::new()),
+                            (amount, currency),
+                        ),
+                    };
+                    let (fee_amount, fee_currency) = fee_columns(fee);
+                    writer.write_record([
+                        kind.to_string(),
+                        buy.0,
+                        buy.1,
+                        sell.0,
+                        sell.1,
+                        fee_amount,
+                        fee_currency,
+                        "Seedelf".to_string(),
+                        String::new(),
+                        String::new(),
+                        date.clone(),
+                        row.tx_hash.clone(),
+                    ])?;
+                }
+            }
+        }
+    }
+    let bytes: Vec<u8> = writer.into_inner().context("Failed To Write CSV")?;
+    String::from_utf8(bytes).context("Failed To Write CSV")
+}
@@ -0,0 +1,129 @@
+#[cfg(unix)]
+use crate::agent::AgentClient;
+use crate::cache;
+#[cfg(unix)]
+use crate::setup;
+use anyhow::{Context, Result, anyhow};
+use blstrs::Scalar;
+use pallas_txbuilder::BuiltTransaction;
+#[cfg(unix)]
+use parking_lot::Mutex;
+use seedelf_crypto::convert;
+use seedelf_crypto::register::Register;
+use seedelf_crypto::schnorr::{create_proof, random_scalar};
+
+/// The most registers sent to an agent in one request, well under its request limit.
+#[cfg(unix)]
+const AGENT_BATCH: usize = 1000;
+
+/// The key of a wallet, either unlocked in this process or held by a running seedelf-agent.
+///
+/// Everything a command needs from the key goes through here, so with an agent the
+/// scalar never enters the command's process.
+pub enum WalletKey {
+    /// The key unlocked in this process.
+    Local(Scalar),
+    /// A running agent holding the key.
+    #[cfg(unix)]
+    Agent(Mutex<AgentClient>),
+}
+
+impl From<Scalar> for WalletKey {
+    fn from(sk: Scalar) -> Self {
+        WalletKey::Local(sk)
+    }
+}
+
+#[cfg(unix)]
+impl From<AgentClient> for WalletKey {
+    fn from(agent: AgentClient) -> Self {
+        WalletKey::Agent(Mutex::new(agent))
+    }
+}
+
+impl WalletKey {
+    /// The base register of the key.
+    pub fn register(&self) -> Result<Register> {
+        match self {
+            WalletKey::Local(sk) => Register::create(*sk),
+            #[cfg(unix)]
+            WalletKey::Agent(agent) => agent.lock().register(),
+        }
+    }
+
+    /// A fresh register of the key, ready to be used as an output datum.
+    pub fn datum(&self) -> Result<Vec<u8>> {
+        self.register()?.rerandomize()?.to_vec()
+    }
+
+    /// A Schnorr proof of the key behind `register`, bound to `bound`.
+    ///
+    /// # Arguments
+    ///
+    /// * `register` - The register being spent.
+    /// * `bound` - The value the proof is bound to, the signing key hash.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((String, String))` - The `(z, g_r)` of the proof.
+    /// * `Err(anyhow::Error)` - If the proof can not be made.
+    pub fn create_proof(&self, register: Register, bound: String) -> Result<(String, String)> {
+        match self {
+            WalletKey::Local(sk) => create_proof(register, *sk, bound, random_scalar()),
+            #[cfg(unix)]
+            WalletKey::Agent(agent) => agent.lock().create_proof(register, bound),
+        }
+    }
+
+    /// Whether the key owns each register, in order.
+    pub fn owns(&self, registers: Vec<Register>) -> Result<Vec<bool>> {
+        match self {
+            WalletKey::Local(sk) => registers
+                .iter()
+                .map(|register| register.is_owned(*sk))
+                .collect::<Result<Vec<bool>>>()
+                .context("Failed To Construct Points"),
+            #[cfg(unix)]
+            WalletKey::Agent(agent) => {
+                let mut agent = agent.lock();
+                let mut owned: Vec<bool> = Vec::with_capacity(registers.len());
+                for batch in registers.chunks(AGENT_BATCH) {
+                    owned.extend(agent.is_owned(batch.to_vec())?);
+                }
+                Ok(owned)
+            }
+        }
+    }
+
+    /// A value derived from the key under one of the [`cache::DERIVE_TAGS`].
+    pub fn derive(&self, tag: &str) -> Result<[u8; 32]> {
+        match self {
+            WalletKey::Local(sk) => Ok(cache::derive(tag, *sk)),
+            #[cfg(unix)]
+            WalletKey::Agent(agent) => agent.lock().derive(tag),
+        }
+    }
+
+    /// The payment key hash of the key's external address.
+    pub fn key_hash(&self) -> Result<String> {
+        match self {
+            WalletKey::Local(sk) => Ok(convert::secret_key_to_public_key(*sk)),
+            #[cfg(unix)]
+            WalletKey::Agent(agent) => agent.lock().key_hash(),
+        }
+    }
+
+    /// Signs a transaction with the key of the external address.
+    ///
+    /// The agent never signs transactions, so with an agent the wallet is unlocked
+    /// here for the one signature.
+    pub fn sign(&self, tx: BuiltTransaction) -> Result<BuiltTransaction> {
+        let sk: Scalar = match self {
+            WalletKey::Local(sk) => *sk,
+            #[cfg(unix)]
+            WalletKey::Agent(_) => setup::unlock_wallet_interactive(),
+        };
+        tx.sign(convert::secret_key_to_private_key(sk))
+            .map_err(|e| anyhow!("Failed To Sign: {e}"))
+    }
+}
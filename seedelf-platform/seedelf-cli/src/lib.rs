@@ -1,3 +1,13 @@
+#[cfg(unix)]
+pub mod agent;
+pub mod cache;
+pub mod collateral;
 pub mod commands;
+pub mod config;
+pub mod confirm;
+pub mod export;
+pub mod key;
+pub mod session;
 pub mod setup;
+pub mod wallet_file;
 pub mod web_server;
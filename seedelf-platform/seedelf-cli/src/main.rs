@@ -1,21 +1,203 @@
 use clap::{Parser, Subcommand};
+use reqwest::header::{HeaderName, HeaderValue};
 use seedelf_cli::commands;
-use seedelf_cli::setup;
-use seedelf_core::constants::VARIANT;
+use seedelf_cli::commands::util::UtilCommands;
+use seedelf_cli::config::{Config, PROFILE_ENV, Profile};
+use seedelf_cli::setup::{self, PasswordSource};
+use seedelf_core::collateral::{CollateralKind, CollateralSource};
+use seedelf_core::constants::{MAINNET_STAKE_HASH, PREPROD_STAKE_HASH, VARIANT};
+use seedelf_core::evaluate::Evaluation;
 use seedelf_display::text_coloring::{display_blue, display_cyan, display_yellow};
+use seedelf_display::version_control::{disable_update_check, set_update_check_dir};
+use seedelf_koios::blockfrost::Blockfrost;
+use seedelf_koios::koios::{
+    DEFAULT_CONCURRENCY, DEFAULT_RETRIES, DEFAULT_TIMEOUT, Koios, MAX_PAGE_SIZE, parse_header,
+};
+use seedelf_koios::node::NodeClient;
+use seedelf_koios::ogmios::OgmiosKupo;
+use seedelf_koios::provider::{Backend, BackendKind};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "seedelf-cli")]
 #[command(version = env!("CARGO_PKG_VERSION"))]
 #[command(about = "A Cardano Stealth Wallet", long_about = None)]
 struct Cli {
+    /// Directory holding the wallets, config and caches, defaults to ~/.seedelf
+    #[arg(long, global = true, env = setup::HOME_ENV, display_order = 95)]
+    data_dir: Option<PathBuf>,
+
+    /// Wallet to open, defaults to the recorded default or the only wallet
+    #[arg(long, global = true, env = setup::WALLET_ENV, display_order = 96)]
+    wallet: Option<String>,
+
+    /// Profile of the wallet config to use, defaults to the configured profile
+    #[arg(long, global = true, env = PROFILE_ENV, display_order = 97)]
+    profile: Option<String>,
+
     /// Use this flag to interact with the pre-production environment
     #[arg(long, global = true, display_order = 98)]
     preprod: bool,
 
-    /// Use this for different variants of the contract, defaults to most recent variant
-    #[arg(long, global = true, default_value_t = VARIANT, display_order = 99)]
-    variant: u64,
+    /// Use this for different variants of the contract, defaults to most recent variant while balance, history and find scan every variant
+    #[arg(long, global = true, display_order = 99)]
+    variant: Option<u64>,
+
+    /// Chain data backend, either koios, blockfrost, ogmios or node, defaults to koios
+    #[arg(long, global = true, env = "SEEDELF_BACKEND", display_order = 100)]
+    backend: Option<BackendKind>,
+
+    /// Koios API base URL, defaults to the public endpoint for the network
+    #[arg(long, global = true, env = "SEEDELF_KOIOS_URL", display_order = 101)]
+    koios_url: Option<String>,
+
+    /// Bearer token sent to the Koios API
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_KOIOS_TOKEN",
+        hide_env_values = true,
+        display_order = 102
+    )]
+    koios_token: Option<String>,
+
+    /// Extra header sent to the Koios API as `Name: Value`, may be repeated
+    #[arg(
+        long = "koios-header",
+        global = true,
+        env = "SEEDELF_KOIOS_HEADERS",
+        value_delimiter = ';',
+        value_parser = parse_header,
+        display_order = 103
+    )]
+    koios_headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Seconds before a Koios request is abandoned
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_KOIOS_TIMEOUT",
+        default_value_t = DEFAULT_TIMEOUT.as_secs(),
+        display_order = 112
+    )]
+    koios_timeout: u64,
+
+    /// Times a failed Koios query is retried with backoff, 0 turns retrying off
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_KOIOS_RETRIES",
+        default_value_t = DEFAULT_RETRIES,
+        display_order = 113
+    )]
+    koios_retries: u32,
+
+    /// Rows per page when paging through the wallet contract on Koios, at most 1000
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_KOIOS_PAGE_SIZE",
+        default_value_t = MAX_PAGE_SIZE,
+        display_order = 114
+    )]
+    koios_page_size: usize,
+
+    /// Pages of the wallet contract fetched from Koios at once
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_KOIOS_CONCURRENCY",
+        default_value_t = DEFAULT_CONCURRENCY,
+        display_order = 115
+    )]
+    koios_concurrency: usize,
+
+    /// Ignore the wallet cache and rebuild it from the whole contract
+    #[arg(long, global = true, display_order = 116)]
+    rescan: bool,
+
+    /// Blockfrost project id, required for the blockfrost backend
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_BLOCKFROST_PROJECT_ID",
+        hide_env_values = true,
+        display_order = 104
+    )]
+    blockfrost_project_id: Option<String>,
+
+    /// Blockfrost API base URL, defaults to the hosted endpoint for the network
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_BLOCKFROST_URL",
+        display_order = 105
+    )]
+    blockfrost_url: Option<String>,
+
+    /// Ogmios websocket URL for the ogmios backend, defaults to ws://127.0.0.1:1337
+    #[arg(long, global = true, env = "SEEDELF_OGMIOS_URL", display_order = 106)]
+    ogmios_url: Option<String>,
+
+    /// Kupo URL for the ogmios backend, defaults to http://127.0.0.1:1442
+    #[arg(long, global = true, env = "SEEDELF_KUPO_URL", display_order = 107)]
+    kupo_url: Option<String>,
+
+    /// Path of the cardano-node socket for the node backend, or tcp://host:port
+    #[arg(long, global = true, env = "SEEDELF_NODE_SOCKET", display_order = 108)]
+    node_socket: Option<String>,
+
+    /// Where scripts are evaluated, local runs them in-process and remote sends the draft to the backend
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_EVALUATION",
+        default_value_t = Evaluation::Local,
+        display_order = 109
+    )]
+    evaluation: Evaluation,
+
+    /// Who provides the collateral for script spends, either service, external or cip30
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_COLLATERAL",
+        default_value_t = CollateralKind::Service,
+        display_order = 110
+    )]
+    collateral: CollateralKind,
+
+    /// Address of the CIP-30 wallet holding the collateral, required for cip30 collateral
+    #[arg(
+        long,
+        global = true,
+        env = "SEEDELF_COLLATERAL_ADDRESS",
+        display_order = 111
+    )]
+    collateral_address: Option<String>,
+
+    /// Read the wallet password from a file instead of prompting, a trailing newline is ignored
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["password_fd", "password_env"],
+        display_order = 117
+    )]
+    password_file: Option<PathBuf>,
+
+    /// Read the wallet password from an inherited file descriptor instead of prompting
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "password_env",
+        display_order = 118
+    )]
+    password_fd: Option<i32>,
+
+    /// Read the wallet password from SEEDELF_PASSWORD, which is ignored without this flag
+    #[arg(long, global = true, display_order = 119)]
+    password_env: bool,
 
     #[command(subcommand)]
     command: Option<Commands>, // Make command optional
@@ -30,7 +212,7 @@ enum Commands {
     /// Remove a seedelf from the wallet
     Remove(commands::remove::RemoveArgs),
     /// Displays the current wallet information, seedelfs, and balance
-    Balance,
+    Balance(commands::balance::BalanceArgs),
     /// An address sends funds to a seedelf
     Fund(commands::fund::FundArgs),
     /// A seedelf sends funds to a seedelf
@@ -41,70 +223,288 @@ enum Commands {
     Util(commands::util::UtilArgs),
     /// External wallet functions for seedelf-cli
     External(commands::external::ExternalArgs),
+    /// Read and change the wallet config
+    Config(commands::config::ConfigArgs),
+    /// List, create, rename, delete and pick wallets
+    Wallet(commands::wallet::WalletArgs),
 }
 
 #[tokio::main]
 async fn main() {
     // Parse the command line arguments
-    let cli: Cli = Cli::parse();
+    let mut cli: Cli = Cli::parse();
+
+    // every wallet, config and cache file resolves through the seedelf home
+    setup::set_data_dir(cli.data_dir.clone());
+    set_update_check_dir(setup::seedelf_home_path());
+
+    let config: Config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error: {err:#}");
+            std::process::exit(1);
+        }
+    };
+
+    // the config is edited without a wallet or a backend
+    if let Some(Commands::Config(args)) = cli.command {
+        if let Err(err) = commands::config::run(args, cli.profile.as_deref()) {
+            eprintln!("Error: {err:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    setup::select_wallet(cli.wallet.clone());
+    setup::set_password_source(password_source(&cli));
+    if let Some(Commands::Wallet(args)) = cli.command {
+        let preprod: bool = cli.preprod || config.profile(cli.profile.as_deref()).preprod();
+        if let Err(err) = commands::wallet::run(args, preprod) {
+            eprintln!("Error: {err:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if !config.update_check() {
+        disable_update_check();
+    }
+    let profile: Profile = config.profile(cli.profile.as_deref());
+    if let Err(err) = apply_profile(&mut cli, profile) {
+        eprintln!("Error: {err:#}");
+        std::process::exit(1);
+    }
+
+    // every chain read and write goes through this provider
+    let provider: Backend = match chain_provider(&cli) {
+        Ok(provider) => provider,
+        Err(err) => {
+            eprintln!("Error: {err:#}");
+            std::process::exit(1);
+        }
+    };
+
+    let collateral: CollateralSource = match collateral_source(&cli) {
+        Ok(collateral) => collateral,
+        Err(err) => {
+            eprintln!("Error: {err:#}");
+            std::process::exit(1);
+        }
+    };
 
     // Run setup only if the command is not `--help` or `--version`
     if cli.command.is_some() {
         display_blue("Checking For Existing Seedelf Wallet");
         match setup::check_and_prepare_seedelf() {
-            None => {
+            Ok(None) => {
                 let wallet_name: String = setup::prompt_wallet_name();
                 let password: String = setup::is_valid_password();
-                setup::create_wallet(wallet_name.clone(), password);
+                let network: &str = if cli.preprod { "preprod" } else { "mainnet" };
+                if let Err(err) =
+                    setup::create_wallet(wallet_name.clone(), password, Some(network.to_string()))
+                {
+                    eprintln!("Error: {err}");
+                    std::process::exit(1);
+                }
                 display_yellow(format!("Wallet Created: {wallet_name}").as_str());
             }
-            Some(wallet_name) => display_cyan(format!("Found Wallet: {wallet_name}").as_str()),
+            Ok(Some(wallet_name)) => display_cyan(format!("Found Wallet: {wallet_name}").as_str()),
+            Err(err) => {
+                eprintln!("Error: {err}");
+                std::process::exit(1);
+            }
         }
     }
 
-    match cli.command {
+    // commands that build transactions work on one variant
+    let variant: u64 = cli.variant.unwrap_or(VARIANT);
+
+    // a failed command exits non-zero so scripts can tell
+    let result: anyhow::Result<()> = match cli.command {
         Some(Commands::Welcome) => {
             commands::welcome::run().await;
+            Ok(())
         }
-        Some(Commands::Balance) => {
-            if let Err(err) = commands::balance::run(cli.preprod, cli.variant).await {
-                eprintln!("Error: {err}");
-            }
+        Some(Commands::Balance(args)) => {
+            commands::balance::run(args, &provider, cli.preprod, cli.variant, cli.rescan).await
         }
         Some(Commands::Transfer(args)) => {
-            if let Err(err) = commands::transfer::run(args, cli.preprod, cli.variant).await {
-                eprintln!("Error: {err}");
-            }
+            commands::transfer::run(
+                args,
+                &provider,
+                cli.preprod,
+                variant,
+                cli.evaluation,
+                collateral,
+                cli.rescan,
+            )
+            .await
         }
         Some(Commands::Sweep(args)) => {
-            if let Err(err) = commands::sweep::run(args, cli.preprod, cli.variant).await {
-                eprintln!("Error: {err}");
-            }
+            commands::sweep::run(
+                args,
+                &provider,
+                cli.preprod,
+                variant,
+                cli.evaluation,
+                collateral,
+                cli.rescan,
+            )
+            .await
         }
         Some(Commands::Fund(args)) => {
-            if let Err(err) = commands::fund::run(args, cli.preprod, cli.variant).await {
-                eprintln!("Error: {err}");
-            }
+            commands::fund::run(args, &provider, cli.preprod, variant).await
         }
         Some(Commands::Create(args)) => {
-            if let Err(err) = commands::create::run(args, cli.preprod, cli.variant).await {
-                eprintln!("Error: {err}");
-            }
+            commands::create::run(args, &provider, cli.preprod, variant, cli.evaluation).await
         }
         Some(Commands::Remove(args)) => {
-            if let Err(err) = commands::remove::run(args, cli.preprod, cli.variant).await {
-                eprintln!("Error: {err}");
-            }
+            commands::remove::run(
+                args,
+                &provider,
+                cli.preprod,
+                variant,
+                cli.evaluation,
+                collateral,
+            )
+            .await
         }
         Some(Commands::Util(util_command)) => {
-            commands::util::run(util_command, cli.preprod, cli.variant).await
+            commands::util::run(
+                util_command,
+                &provider,
+                cli.preprod,
+                cli.variant,
+                cli.evaluation,
+                collateral,
+                cli.rescan,
+            )
+            .await
         }
         Some(Commands::External(external_command)) => {
-            commands::external::run(external_command, cli.preprod, cli.variant).await
+            commands::external::run(external_command, &provider, cli.preprod, variant).await
         }
+        Some(Commands::Config(_)) | Some(Commands::Wallet(_)) => Ok(()),
         // catch the no command state
         None => {
             println!("No subcommand provided. Use --help for more information.");
+            Ok(())
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("Error: {err:#}");
+        std::process::exit(1);
+    }
+}
+
+/// Fills in what the command line left out from the selected config profile.
+fn apply_profile(cli: &mut Cli, profile: Profile) -> anyhow::Result<()> {
+    cli.preprod |= profile.preprod();
+    cli.variant = cli.variant.or(profile.variant);
+    if cli.backend.is_none() {
+        cli.backend = profile.backend_kind()?;
+    }
+    if let Some(url) = profile.url.clone() {
+        let target: &mut Option<String> = match cli.backend.unwrap_or_default() {
+            BackendKind::Koios => &mut cli.koios_url,
+            BackendKind::Blockfrost => &mut cli.blockfrost_url,
+            BackendKind::OgmiosKupo => &mut cli.ogmios_url,
+            BackendKind::Node => &mut cli.node_socket,
+        };
+        target.get_or_insert(url);
+    }
+    match &mut cli.command {
+        Some(Commands::Create(args)) => args.default_address(profile.address.clone()),
+        Some(Commands::Fund(args)) => args.default_address(profile.address.clone()),
+        Some(Commands::Util(util)) => {
+            if let UtilCommands::History(args) = &mut util.command {
+                args.default_export(profile.output_format()?);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Reads where the wallet password comes from off the command line.
+fn password_source(cli: &Cli) -> PasswordSource {
+    if let Some(path) = &cli.password_file {
+        PasswordSource::File(path.clone())
+    } else if let Some(fd) = cli.password_fd {
+        PasswordSource::Fd(fd)
+    } else if cli.password_env {
+        PasswordSource::Env
+    } else {
+        PasswordSource::Prompt
+    }
+}
+
+/// Reads where the collateral comes from off the command line.
+fn collateral_source(cli: &Cli) -> Result<CollateralSource, String> {
+    match cli.collateral {
+        CollateralKind::Service => Ok(CollateralSource::Service),
+        CollateralKind::External => Ok(CollateralSource::External),
+        CollateralKind::Cip30 => cli
+            .collateral_address
+            .clone()
+            .map(CollateralSource::Cip30)
+            .ok_or_else(|| "CIP-30 Collateral Requires --collateral-address".to_string()),
+    }
+}
+
+/// Builds the chain data backend selected on the command line.
+fn chain_provider(cli: &Cli) -> Result<Backend, String> {
+    match cli.backend.unwrap_or_default() {
+        BackendKind::Koios => {
+            let mut koios: Koios = Koios::new(cli.preprod)
+                .with_timeout(Duration::from_secs(cli.koios_timeout))
+                .with_retries(cli.koios_retries)
+                .with_page_size(cli.koios_page_size)
+                .with_concurrency(cli.koios_concurrency);
+            if let Some(url) = &cli.koios_url {
+                koios = koios.with_base_url(url);
+            }
+            if let Some(token) = &cli.koios_token {
+                koios = koios.with_token(token);
+            }
+            for (name, value) in &cli.koios_headers {
+                koios = koios.with_header(name.clone(), value.clone());
+            }
+            Ok(Backend::Koios(koios))
+        }
+        BackendKind::Blockfrost => {
+            let project_id: &String = cli
+                .blockfrost_project_id
+                .as_ref()
+                .ok_or("The Blockfrost Backend Requires --blockfrost-project-id")?;
+            let mut blockfrost: Blockfrost = Blockfrost::new(cli.preprod, project_id);
+            if let Some(url) = &cli.blockfrost_url {
+                blockfrost = blockfrost.with_base_url(url);
+            }
+            Ok(Backend::Blockfrost(blockfrost))
+        }
+        BackendKind::OgmiosKupo => {
+            let mut ogmios: OgmiosKupo = OgmiosKupo::new(cli.preprod);
+            if let Some(url) = &cli.ogmios_url {
+                ogmios = ogmios.with_ogmios_url(url);
+            }
+            if let Some(url) = &cli.kupo_url {
+                ogmios = ogmios.with_kupo_url(url);
+            }
+            Ok(Backend::OgmiosKupo(ogmios))
+        }
+        BackendKind::Node => {
+            let node: NodeClient = match &cli.node_socket {
+                Some(path) => NodeClient::new(cli.preprod, path),
+                None => NodeClient::from_env(cli.preprod)
+                    .map_err(|_| "The Node Backend Requires --node-socket")?,
+            };
+            let stake_hash: [u8; 28] = if cli.preprod {
+                PREPROD_STAKE_HASH
+            } else {
+                MAINNET_STAKE_HASH
+            };
+            Ok(Backend::Node(node.with_stake_credential(stake_hash)))
         }
     }
 }
@@ -1,64 +1,197 @@
-use aes_gcm::aead::{Aead, AeadCore, KeyInit};
-use aes_gcm::{Aes256Gcm, Key, Nonce};
-use argon2::{Argon2, password_hash::SaltString};
-use base64::Engine;
-use base64::engine::general_purpose::STANDARD;
+#[cfg(unix)]
+use crate::agent::AgentClient;
+use crate::config::Config;
+use crate::key::WalletKey;
+use crate::wallet_file::{self, KdfParams, WalletHeader};
 use blstrs::Scalar;
 use colored::Colorize;
 use dirs::home_dir;
-use ff::PrimeField;
-use rand_core::OsRng;
 use rpassword::read_password;
 use seedelf_crypto::schnorr::random_scalar;
-use serde::{Deserialize, Serialize};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
 use std::fs;
 use std::io::{self, Write};
 use std::path::PathBuf;
-
-/// Data structure for storing wallet information
-#[derive(Serialize, Deserialize)]
-struct Wallet {
-    private_key: String, // Store the scalar as a hex string
+use std::sync::Mutex;
+
+/// Environment variable moving the seedelf home, like `--data-dir`.
+pub const HOME_ENV: &str = "SEEDELF_HOME";
+
+/// The seedelf home given with `--data-dir`, `None` falls back to `SEEDELF_HOME` and `~/.seedelf`.
+static DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Environment variable naming the wallet, like `--wallet`.
+pub const WALLET_ENV: &str = "SEEDELF_WALLET";
+
+/// The wallet picked for this run, `None` falls back to the recorded default.
+static SELECTED_WALLET: Mutex<Option<String>> = Mutex::new(None);
+
+/// Environment variable holding the password, only read with `--password-env`.
+pub const PASSWORD_ENV: &str = "SEEDELF_PASSWORD";
+
+/// Wrong passwords allowed at the prompt before the command gives up.
+pub const MAX_PASSWORD_ATTEMPTS: usize = 3;
+
+/// Where the wallet password comes from when a command unlocks the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PasswordSource {
+    /// Ask on the terminal.
+    #[default]
+    Prompt,
+    /// The contents of a file, given with `--password-file`.
+    File(PathBuf),
+    /// Everything readable from an inherited file descriptor, given with `--password-fd`.
+    Fd(i32),
+    /// [`PASSWORD_ENV`], used only with `--password-env`.
+    Env,
 }
 
-/// Data structure for storing wallet information
-#[derive(Serialize, Deserialize)]
-struct EncryptedData {
-    salt: String,
-    nonce: String,
-    data: String,
+static PASSWORD_SOURCE: Mutex<PasswordSource> = Mutex::new(PasswordSource::Prompt);
+
+/// Moves the seedelf home for this run, as given with `--data-dir`.
+pub fn set_data_dir(data_dir: Option<PathBuf>) {
+    *DATA_DIR.lock().unwrap_or_else(|e| e.into_inner()) = data_dir;
 }
 
+/// The directory holding the wallets, the config and every cache.
+///
+/// `--data-dir` comes first, then `SEEDELF_HOME`, then `.seedelf` in the home directory,
+/// or in the working directory when there is no home directory.
 pub fn seedelf_home_path() -> PathBuf {
-    let home: PathBuf = home_dir().expect("Failed to get home directory");
-    let seedelf_path: PathBuf = home.join(".seedelf");
-    seedelf_path
+    if let Some(data_dir) = DATA_DIR.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+        return data_dir;
+    }
+    if let Some(home) = std::env::var_os(HOME_ENV).filter(|home| !home.is_empty()) {
+        return PathBuf::from(home);
+    }
+    home_dir().unwrap_or_default().join(".seedelf")
+}
+
+/// Loads the protocol parameters, caching them in `.seedelf` for when the backend is unreachable.
+pub async fn protocol_parameters(
+    provider: &impl ChainProvider,
+    network_flag: bool,
+) -> ProtocolParameters {
+    ProtocolParameters::load(provider, network_flag, &seedelf_home_path()).await
+}
+
+/// `.seedelf` also holds caches, only `.wallet` files are wallets.
+fn is_wallet_file(entry: &fs::DirEntry) -> bool {
+    entry.path().extension().is_some_and(|ext| ext == "wallet")
+}
+
+/// The file of a wallet, `<name>.wallet` in `.seedelf`.
+///
+/// # Arguments
+///
+/// * `wallet_name` - The name of the wallet.
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - The wallet file.
+/// * `Err(String)` - If the name is not a valid wallet name, so it can never point outside `.seedelf`.
+pub fn wallet_path(wallet_name: &str) -> Result<PathBuf, String> {
+    if !valid_wallet_name(wallet_name) {
+        return Err(format!(
+            "Invalid Wallet Name: {wallet_name}, it must not be empty, start with a dot or hold spaces or slashes"
+        ));
+    }
+    Ok(seedelf_home_path().join(format!("{wallet_name}.wallet")))
+}
+
+/// Whether a name can be used for a wallet file.
+pub fn valid_wallet_name(wallet_name: &str) -> bool {
+    !wallet_name.is_empty()
+        && !wallet_name.starts_with('.')
+        && !wallet_name
+            .chars()
+            .any(|c| c.is_whitespace() || c == '/' || c == '\\')
+}
+
+/// The names of every wallet in `.seedelf`, sorted.
+pub fn list_wallets() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(seedelf_home_path())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(is_wallet_file)
+                .filter_map(|entry| {
+                    entry
+                        .path()
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .map(str::to_string)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Picks the wallet for this run, as given with `--wallet`.
+pub fn select_wallet(wallet_name: Option<String>) {
+    *SELECTED_WALLET.lock().unwrap_or_else(|e| e.into_inner()) = wallet_name;
+}
+
+/// The name of the wallet to open.
+///
+/// The wallet picked with `--wallet` comes first, then the default recorded in the
+/// config, then the only wallet in `.seedelf`. Several wallets and no choice is an error
+/// so a stray file never decides which key is used.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The wallet name.
+/// * `Err(String)` - If there is no wallet, the chosen one does not exist or the choice is ambiguous.
+pub fn selected_wallet() -> Result<String, String> {
+    let wallets: Vec<String> = list_wallets();
+    let selected: Option<String> = SELECTED_WALLET
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(name) = selected {
+        return if wallets.contains(&name) {
+            Ok(name)
+        } else {
+            Err(format!("Wallet {name} Does Not Exist"))
+        };
+    }
+    if let Some(name) = Config::load().ok().and_then(|config| config.wallet)
+        && wallets.contains(&name)
+    {
+        return Ok(name);
+    }
+    match wallets.as_slice() {
+        [] => Err("No wallet files found in .seedelf directory".to_string()),
+        [only] => Ok(only.clone()),
+        _ => Err(format!(
+            "Found {} Wallets, Pick One With --wallet Or `wallet default <NAME>`",
+            wallets.len()
+        )),
+    }
 }
 
-/// Check if `.seedelf` exists, create it if it doesn't, and handle file logic
-pub fn check_and_prepare_seedelf() -> Option<String> {
+/// Check if `.seedelf` exists, create it if it doesn't, and find the wallet in use
+///
+/// # Returns
+///
+/// * `Ok(Some(String))` - The name of the wallet in use.
+/// * `Ok(None)` - If there is no wallet yet.
+/// * `Err(String)` - If the wallet can not be chosen, see [`selected_wallet`].
+pub fn check_and_prepare_seedelf() -> Result<Option<String>, String> {
     let seedelf_path: PathBuf = seedelf_home_path();
 
     // Check if `.seedelf` exists
     if !seedelf_path.exists() {
-        fs::create_dir_all(&seedelf_path).expect("Failed to create .seedelf directory");
+        fs::create_dir_all(&seedelf_path).map_err(|_| "Failed to create .seedelf directory")?;
     }
 
-    // Check if there are any files in `.seedelf`
-    let contents: Vec<fs::DirEntry> = fs::read_dir(&seedelf_path)
-        .expect("Failed to read .seedelf directory")
-        .filter_map(|entry| entry.ok())
-        .collect::<Vec<_>>();
-
-    if contents.is_empty() {
-        None
+    if list_wallets().is_empty() {
+        Ok(None)
     } else {
-        for entry in &contents {
-            if let Ok(file_name) = entry.file_name().into_string() {
-                return Some(file_name);
-            }
-        }
-        None
+        selected_wallet().map(Some)
     }
 }
 
@@ -67,15 +200,22 @@ pub fn prompt_wallet_name() -> String {
     let mut wallet_name: String = String::new();
     println!("{}", "\nEnter A Wallet Name:".bright_purple());
     io::stdout().flush().unwrap();
-    io::stdin()
+    let read: usize = io::stdin()
         .read_line(&mut wallet_name)
         .expect("Failed to read wallet name");
+    if read == 0 {
+        eprintln!("Error: No Wallet Name Was Entered");
+        std::process::exit(1);
+    }
     let final_name: String = wallet_name
         .split_whitespace() // breaks on any whitespace sequence
         .collect::<Vec<_>>() // collect the pieces
         .join("_");
-    if final_name.is_empty() {
-        println!("{}", "Wallet Must Not Have Empty Name.".red());
+    if !valid_wallet_name(&final_name) {
+        println!(
+            "{}",
+            "Wallet Name Must Not Be Empty, Start With A Dot Or Hold Slashes.".red()
+        );
         return prompt_wallet_name();
     }
     final_name
@@ -86,8 +226,13 @@ pub fn enter_password() -> String {
         "{}",
         "\nEnter A Password To Encrypt The Wallet:".bright_purple()
     );
-    let password: String = read_password().expect("Failed to read password");
-    password
+    // without a terminal there is nobody to ask, so stop instead of panicking
+    read_password().unwrap_or_else(|e| {
+        eprintln!(
+            "Error: Failed To Read Password: {e}\nUse --password-file, --password-fd or --password-env When There Is No Terminal"
+        );
+        std::process::exit(1);
+    })
 }
 
 pub fn is_valid_password() -> String {
@@ -115,129 +260,280 @@ pub fn is_valid_password() -> String {
 }
 
 /// Create a wallet file and save a random private key
-pub fn create_wallet(wallet_name: String, password: String) {
-    // Generate a random private key
-    let sk: Scalar = random_scalar(); // Requires `Field` trait in scope
-    let private_key_bytes: [u8; 32] = sk.to_repr(); // Use `to_repr()` to get canonical bytes
-    let private_key_hex: String = hex::encode(private_key_bytes);
-
-    // Serialize the wallet
-    let wallet: Wallet = Wallet {
-        private_key: private_key_hex,
-    };
-    let wallet_data: String =
-        serde_json::to_string_pretty(&wallet).expect("Failed to serialize wallet");
-
-    let salt: SaltString = SaltString::generate(&mut OsRng);
-    let mut output_key_material: [u8; 32] = [0u8; 32];
-    let _ = Argon2::default().hash_password_into(
-        password.as_bytes(),
-        salt.to_string().as_bytes(),
-        &mut output_key_material,
-    );
-
-    let key = Key::<Aes256Gcm>::from_slice(&output_key_material);
-    let cipher = Aes256Gcm::new(key);
-    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-
-    let encrypted_data = cipher
-        .encrypt(&nonce, wallet_data.as_bytes())
-        .expect("Encryption failed");
-
-    // Save encrypted data, salt, and nonce as JSON
-    let output: EncryptedData = EncryptedData {
-        salt: salt.as_str().to_string(),
-        nonce: STANDARD.encode(nonce),
-        data: STANDARD.encode(encrypted_data),
-    };
-    let output_data: String =
-        serde_json::to_string_pretty(&output).expect("Failed to serialize wallet");
-
+///
+/// # Arguments
+///
+/// * `wallet_name` - The name of the wallet file.
+/// * `password` - The password encrypting the key.
+/// * `network` - The network the wallet is meant for, kept as a hint in the file.
+///
+/// # Returns
+///
+/// * `Ok(())` - Once the wallet file is written.
+/// * `Err(String)` - If the wallet exists already or can not be written.
+pub fn create_wallet(
+    wallet_name: String,
+    password: String,
+    network: Option<String>,
+) -> Result<(), String> {
+    let wallet_path: PathBuf = wallet_path(&wallet_name)?;
     let seedelf_path: PathBuf = seedelf_home_path();
-    let wallet_path = seedelf_path.join(format!("{wallet_name}.wallet"));
+    fs::create_dir_all(&seedelf_path).map_err(|_| "Failed to create .seedelf directory")?;
+    if wallet_path.exists() {
+        return Err(format!("Wallet {wallet_name} Already Exists"));
+    }
+
+    // Generate a random private key
+    let sk: Scalar = random_scalar();
+    let header: WalletHeader = WalletHeader::new(wallet_name, network, KdfParams::default());
+    let output_data: String = wallet_file::seal(&sk, &password, header)?;
 
     // Save to file
-    fs::write(wallet_path.clone(), output_data).expect("Failed to write wallet file");
+    wallet_file::write_atomically(&wallet_path, &output_data)
+        .map_err(|_| "Failed to write wallet file".to_string())
+}
+
+/// The clear header of a wallet, `None` for a v0 file or one that can not be read.
+pub fn wallet_header(wallet_name: &str) -> Option<WalletHeader> {
+    wallet_path(wallet_name)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|wallet_data| wallet_file::read_header(&wallet_data).ok())
+        .flatten()
 }
 
 /// Load the wallet file and deserialize the private key into a Scalar
+///
+/// A v0 wallet file is rewritten in the versioned format once it opens.
 pub fn load_wallet(password: String) -> Result<Scalar, String> {
-    let seedelf_path: PathBuf = seedelf_home_path();
+    // open the chosen wallet by name, never whichever file is listed first
+    let wallet_name: String = selected_wallet()?;
+    let wallet_path: PathBuf = wallet_path(&wallet_name)?;
 
-    // Get the list of files in `.seedelf`
-    let contents: Vec<fs::DirEntry> = fs::read_dir(&seedelf_path)
-        .map_err(|_| "Failed to read .seedelf directory")?
-        .filter_map(|entry| entry.ok())
-        .collect::<Vec<_>>();
+    // Read the wallet file
+    let wallet_data: String =
+        fs::read_to_string(&wallet_path).map_err(|_| "Failed to read wallet file")?;
 
-    if contents.is_empty() {
-        return Err("No wallet files found in .seedelf directory".into());
+    let (scalar, header) = wallet_file::open(&wallet_data, &password)?;
+    if header.is_none() {
+        // the v0 file keeps working if the upgrade can not be written
+        let header: WalletHeader = WalletHeader::new(wallet_name, None, KdfParams::default());
+        if let Ok(upgraded) = wallet_file::seal(&scalar, &password, header) {
+            let _ = wallet_file::write_atomically(&wallet_path, &upgraded);
+        }
     }
+    Ok(scalar)
+}
 
-    // Use the first file in the directory to build the wallet path
-    let first_file: &fs::DirEntry = &contents[0];
-    let wallet_path: PathBuf = first_file.path();
-
-    // Read the wallet file
+/// Re-encrypts a wallet under a new password, replacing the file in one step.
+///
+/// # Arguments
+///
+/// * `wallet_name` - The wallet to change.
+/// * `password` - The current password.
+/// * `new_password` - The new password.
+///
+/// # Returns
+///
+/// * `Ok(())` - Once the new file has replaced the old one.
+/// * `Err(String)` - If the current password is wrong or the file can not be written.
+pub fn change_password(
+    wallet_name: &str,
+    password: &str,
+    new_password: &str,
+) -> Result<(), String> {
+    let wallet_path: PathBuf = wallet_path(wallet_name)?;
     let wallet_data: String =
         fs::read_to_string(&wallet_path).map_err(|_| "Failed to read wallet file")?;
+    let (scalar, header) = wallet_file::open(&wallet_data, password)?;
+    let header: WalletHeader = match header {
+        Some(header) => header.resalted(KdfParams::default()),
+        None => WalletHeader::new(wallet_name.to_string(), None, KdfParams::default()),
+    };
+    let output_data: String = wallet_file::seal(&scalar, new_password, header)?;
+    // the old file stays whole until the rename, a crash leaves one of the two passwords working
+    wallet_file::write_atomically(&wallet_path, &output_data)
+        .map_err(|_| "Failed to write wallet file".to_string())
+}
 
-    // Deserialize the wallet JSON
-    let encrypted_wallet: EncryptedData =
-        serde_json::from_str(&wallet_data).map_err(|_| "Failed to parse wallet JSON")?;
-
-    // Derive the decryption key using the provided salt
-    let salt: SaltString =
-        SaltString::from_b64(&encrypted_wallet.salt).map_err(|_| "Invalid salt format")?;
-    let mut output_key_material: [u8; 32] = [0u8; 32];
-    let _ = Argon2::default().hash_password_into(
-        password.as_bytes(),
-        salt.to_string().as_bytes(),
-        &mut output_key_material,
-    );
-
-    let key = Key::<Aes256Gcm>::from_slice(&output_key_material);
-    let cipher = Aes256Gcm::new(key);
+/// Moves a wallet to a new name, never replacing a wallet that already has it.
+///
+/// The name is part of the authenticated header, so the key is sealed again under
+/// the new name with the same password.
+///
+/// # Arguments
+///
+/// * `wallet_name` - The wallet to rename.
+/// * `new_name` - The name it moves to.
+/// * `password` - The wallet password.
+///
+/// # Returns
+///
+/// * `Ok(())` - Once only the new file is left.
+/// * `Err(String)` - If the password is wrong or a wallet already has the new name.
+pub fn rename_wallet(wallet_name: &str, new_name: &str, password: &str) -> Result<(), String> {
+    let new_path: PathBuf = wallet_path(new_name)?;
+    let wallet_path: PathBuf = wallet_path(wallet_name)?;
+    let wallet_data: String =
+        fs::read_to_string(&wallet_path).map_err(|_| "Failed to read wallet file")?;
+    let (scalar, header) = wallet_file::open(&wallet_data, password)?;
+    let header: WalletHeader = match header {
+        Some(header) => WalletHeader {
+            wallet_name: new_name.to_string(),
+            ..header
+        },
+        None => WalletHeader::new(new_name.to_string(), None, KdfParams::default()),
+    };
+    let output_data: String = wallet_file::seal(&scalar, password, header)?;
+    wallet_file::write_new(&new_path, &output_data).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            format!("Wallet {new_name} Already Exists")
+        } else {
+            "Failed to write wallet file".to_string()
+        }
+    })?;
+    fs::remove_file(&wallet_path).map_err(|_| format!("Failed to remove {wallet_name}"))
+}
 
-    // Decode the nonce and encrypted data from base64
-    let nonce_bytes = STANDARD
-        .decode(&encrypted_wallet.nonce)
-        .map_err(|_| "Failed to decode nonce")?;
-    let nonce = Nonce::from_slice(&nonce_bytes);
+/// Picks where the wallet password is read from for this run.
+pub fn set_password_source(source: PasswordSource) {
+    *PASSWORD_SOURCE.lock().unwrap_or_else(|e| e.into_inner()) = source;
+}
 
-    let encrypted_bytes = STANDARD
-        .decode(&encrypted_wallet.data)
-        .map_err(|_| "Failed to decode encrypted data")?;
+/// Reads the password from a non-interactive source, one trailing newline removed.
+///
+/// # Arguments
+///
+/// * `source` - Where to read from.
+///
+/// # Returns
+///
+/// * `Ok(Some(String))` - The password.
+/// * `Ok(None)` - If the source is the prompt.
+/// * `Err(String)` - If the source can not be read.
+pub fn read_password_source(source: &PasswordSource) -> Result<Option<String>, String> {
+    let password: String = match source {
+        PasswordSource::Prompt => return Ok(None),
+        PasswordSource::File(path) => {
+            warn_if_readable_by_others(path);
+            fs::read_to_string(path)
+                .map_err(|e| format!("Failed To Read Password File {}: {e}", path.display()))?
+        }
+        PasswordSource::Fd(fd) => read_fd(*fd)?,
+        PasswordSource::Env => std::env::var(PASSWORD_ENV)
+            .map_err(|_| format!("--password-env Needs {PASSWORD_ENV} To Be Set"))?,
+    };
+    let password: &str = password
+        .strip_suffix('\n')
+        .map(|p| p.strip_suffix('\r').unwrap_or(p))
+        .unwrap_or(&password);
+    Ok(Some(password.to_string()))
+}
 
-    /* ---- decrypt ---- */
-    let decrypted_data = cipher
-        .decrypt(nonce, encrypted_bytes.as_ref())
-        .map_err(|_| "Failed to decrypt")?;
+/// The password from the source selected for this run, `None` when it is the prompt.
+pub fn current_password_source() -> Option<Result<String, String>> {
+    let source: PasswordSource = PASSWORD_SOURCE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    read_password_source(&source).transpose()
+}
 
-    /* ---- deserialize inner JSON ---- */
-    let wallet: Wallet =
-        serde_json::from_slice(&decrypted_data).map_err(|_| "Failed to parse decrypted JSON")?;
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String, String> {
+    use std::io::Read;
+    use std::mem::ManuallyDrop;
+    use std::os::fd::FromRawFd;
+    if fd <= 2 {
+        return Err(format!(
+            "--password-fd Must Not Be {fd}, Use A Descriptor Above 2"
+        ));
+    }
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(format!("--password-fd {fd} Is Not An Open Descriptor"));
+    }
+    // the descriptor is only borrowed, it is never closed here in case something else owns it
+    let mut file: ManuallyDrop<fs::File> = ManuallyDrop::new(unsafe { fs::File::from_raw_fd(fd) });
+    let mut password: String = String::new();
+    file.read_to_string(&mut password)
+        .map_err(|e| format!("Failed To Read Password From Descriptor {fd}: {e}"))?;
+    Ok(password)
+}
 
-    /* ---- bytes -> Scalar ---- */
-    let key_bytes =
-        hex::decode(wallet.private_key).map_err(|_| "Failed to decode private key hex")?;
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<String, String> {
+    Err("--password-fd Is Only Supported On Unix".to_string())
+}
 
-    Scalar::from_repr(key_bytes.try_into().map_err(|_| "Invalid key length")?)
-        .into_option()
-        .ok_or("Failed to reconstruct Scalar from bytes".into())
+#[cfg(unix)]
+fn warn_if_readable_by_others(path: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Ok(metadata) = fs::metadata(path)
+        && metadata.permissions().mode() & 0o077 != 0
+    {
+        eprintln!(
+            "{}",
+            format!(
+                "Warning: {} Is Readable By Other Users, chmod 600 It",
+                path.display()
+            )
+            .yellow()
+        );
+    }
 }
 
+#[cfg(not(unix))]
+fn warn_if_readable_by_others(_path: &std::path::Path) {}
+
+/// Unlocks the wallet with the password from the selected source.
+///
+/// A non-interactive source gets one try and a wrong password ends the process with a
+/// non-zero exit code. The prompt allows [`MAX_PASSWORD_ATTEMPTS`] tries.
 pub fn unlock_wallet_interactive() -> Scalar {
-    loop {
+    match current_password_source() {
+        Some(Ok(password)) => match load_wallet(password) {
+            Ok(scalar) => return scalar,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        },
+        Some(Err(e)) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+        None => {}
+    }
+    for attempt in 1..=MAX_PASSWORD_ATTEMPTS {
         let password: String = enter_password();
 
         match load_wallet(password) {
-            Ok(scalar) => break scalar,
-            Err(e) => {
+            Ok(scalar) => return scalar,
+            Err(e) if attempt < MAX_PASSWORD_ATTEMPTS => {
                 eprintln!("Error: {e}\nPlease Try Again");
             }
+            Err(e) => eprintln!("Error: {e}"),
         }
     }
+    std::process::exit(1);
+}
+
+/// The running seedelf-agent holding the selected wallet, if one is reachable.
+#[cfg(unix)]
+pub fn wallet_agent() -> Option<AgentClient> {
+    selected_wallet()
+        .ok()
+        .and_then(|wallet| AgentClient::for_wallet(&wallet))
+}
+
+/// The key of the selected wallet, held by a running seedelf-agent when one is reachable
+/// so no password is asked, else unlocked here with [`unlock_wallet_interactive`].
+pub fn wallet_key() -> WalletKey {
+    #[cfg(unix)]
+    if let Some(agent) = wallet_agent() {
+        return WalletKey::from(agent);
+    }
+    WalletKey::Local(unlock_wallet_interactive())
 }
 
 pub fn password_complexity_check(password: String) -> bool {
@@ -0,0 +1,330 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version, password_hash::SaltString};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use blstrs::Scalar;
+use ff::PrimeField;
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The envelope version written by this release, files without a version are v0.
+pub const WALLET_FILE_VERSION: u32 = 1;
+
+/// The only key derivation the envelope knows.
+pub const KDF_ALGORITHM: &str = "argon2id";
+
+/// Data structure for storing wallet information
+#[derive(Serialize, Deserialize)]
+struct Wallet {
+    private_key: String, // Store the scalar as a hex string
+}
+
+/// The v0 wallet file, the key derived with the argon2 defaults of the time.
+#[derive(Deserialize)]
+struct LegacyEnvelope {
+    salt: String,
+    nonce: String,
+    data: String,
+}
+
+/// The Argon2id cost the password is stretched with, stored so it can be raised later.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            algorithm: KDF_ALGORITHM.to_string(),
+            memory_kib: 65_536,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// The v0 cost, `Argon2::default()` of argon2 0.5.
+    pub fn legacy() -> Self {
+        KdfParams {
+            algorithm: KDF_ALGORITHM.to_string(),
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+
+    fn derive_key(&self, password: &str, salt: &str) -> Result<[u8; 32], String> {
+        if self.algorithm != KDF_ALGORITHM {
+            return Err(format!("Unsupported Key Derivation: {}", self.algorithm));
+        }
+        let params: Params =
+            Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+                .map_err(|e| format!("Invalid Key Derivation Parameters: {e}"))?;
+        let mut output_key_material: [u8; 32] = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(
+                password.as_bytes(),
+                salt.as_bytes(),
+                &mut output_key_material,
+            )
+            .map_err(|e| format!("Failed To Derive Key: {e}"))?;
+        Ok(output_key_material)
+    }
+}
+
+/// The clear part of a wallet file, authenticated as associated data so none of it
+/// can be changed without the password.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletHeader {
+    pub version: u32,
+    pub kdf: KdfParams,
+    pub salt: String,
+    /// The name the wallet was created under.
+    pub wallet_name: String,
+    /// The network the wallet was created for, only a hint since keys work on both.
+    pub network: Option<String>,
+    /// Unix time in seconds.
+    pub created_at: u64,
+}
+
+impl WalletHeader {
+    /// A header for a new wallet with a fresh salt.
+    pub fn new(wallet_name: String, network: Option<String>, kdf: KdfParams) -> Self {
+        WalletHeader {
+            version: WALLET_FILE_VERSION,
+            kdf,
+            salt: SaltString::generate(&mut OsRng).as_str().to_string(),
+            wallet_name,
+            network,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The same wallet under a fresh salt and the given cost, for a new password.
+    pub fn resalted(self, kdf: KdfParams) -> Self {
+        WalletHeader {
+            version: WALLET_FILE_VERSION,
+            kdf,
+            salt: SaltString::generate(&mut OsRng).as_str().to_string(),
+            ..self
+        }
+    }
+
+    fn associated_data(&self) -> Result<Vec<u8>, String> {
+        serde_json::to_vec(self).map_err(|_| "Failed to serialize wallet header".to_string())
+    }
+}
+
+/// The versioned wallet file.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    #[serde(flatten)]
+    header: WalletHeader,
+    nonce: String,
+    data: String,
+}
+
+/// Encrypts a wallet key into a versioned wallet file.
+///
+/// # Arguments
+///
+/// * `secret` - The wallet key.
+/// * `password` - The password protecting it.
+/// * `header` - The clear header, bound to the ciphertext.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The wallet file as JSON.
+/// * `Err(String)` - If the key can not be derived or encrypted.
+pub fn seal(secret: &Scalar, password: &str, header: WalletHeader) -> Result<String, String> {
+    let wallet_data: String = serde_json::to_string(&Wallet {
+        private_key: hex::encode(secret.to_repr()),
+    })
+    .map_err(|_| "Failed to serialize wallet")?;
+
+    let key_material: [u8; 32] = header.kdf.derive_key(password, &header.salt)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_material));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let aad: Vec<u8> = header.associated_data()?;
+    let encrypted_data: Vec<u8> = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: wallet_data.as_bytes(),
+                aad: &aad,
+            },
+        )
+        .map_err(|_| "Encryption failed")?;
+
+    let envelope: Envelope = Envelope {
+        header,
+        nonce: STANDARD.encode(nonce),
+        data: STANDARD.encode(encrypted_data),
+    };
+    serde_json::to_string_pretty(&envelope).map_err(|_| "Failed to serialize wallet".to_string())
+}
+
+/// Decrypts a wallet file of any version.
+///
+/// # Arguments
+///
+/// * `wallet_data` - The wallet file as JSON.
+/// * `password` - The password protecting it.
+///
+/// # Returns
+///
+/// * `Ok((Scalar, Some(WalletHeader)))` - The key and the header of a versioned file.
+/// * `Ok((Scalar, None))` - The key of a v0 file, which should be upgraded.
+/// * `Err(String)` - If the file is malformed, tampered with or the password is wrong.
+pub fn open(wallet_data: &str, password: &str) -> Result<(Scalar, Option<WalletHeader>), String> {
+    let value: serde_json::Value =
+        serde_json::from_str(wallet_data).map_err(|_| "Failed to parse wallet JSON")?;
+    if value.get("version").is_none() {
+        let legacy: LegacyEnvelope =
+            serde_json::from_value(value).map_err(|_| "Failed to parse wallet JSON")?;
+        let key_material: [u8; 32] = KdfParams::legacy().derive_key(password, &legacy.salt)?;
+        let secret: Scalar = decrypt(&key_material, &legacy.nonce, &legacy.data, &[])?;
+        return Ok((secret, None));
+    }
+
+    let envelope: Envelope =
+        serde_json::from_value(value).map_err(|_| "Failed to parse wallet JSON")?;
+    if envelope.header.version > WALLET_FILE_VERSION {
+        return Err(format!(
+            "Wallet File Version {} Needs A Newer seedelf-cli",
+            envelope.header.version
+        ));
+    }
+    let key_material: [u8; 32] = envelope
+        .header
+        .kdf
+        .derive_key(password, &envelope.header.salt)?;
+    let aad: Vec<u8> = envelope.header.associated_data()?;
+    let secret: Scalar = decrypt(&key_material, &envelope.nonce, &envelope.data, &aad)?;
+    Ok((secret, Some(envelope.header)))
+}
+
+/// Reads the clear header of a wallet file, `None` for a v0 file.
+pub fn read_header(wallet_data: &str) -> Result<Option<WalletHeader>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(wallet_data).map_err(|_| "Failed to parse wallet JSON")?;
+    if value.get("version").is_none() {
+        return Ok(None);
+    }
+    serde_json::from_value::<Envelope>(value)
+        .map(|envelope| Some(envelope.header))
+        .map_err(|_| "Failed to parse wallet JSON".to_string())
+}
+
+fn decrypt(key_material: &[u8; 32], nonce: &str, data: &str, aad: &[u8]) -> Result<Scalar, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_material));
+
+    // Decode the nonce and encrypted data from base64
+    let nonce_bytes: Vec<u8> = STANDARD
+        .decode(nonce)
+        .map_err(|_| "Failed to decode nonce")?;
+    if nonce_bytes.len() != 12 {
+        return Err("Invalid nonce length".into());
+    }
+    let encrypted_bytes: Vec<u8> = STANDARD
+        .decode(data)
+        .map_err(|_| "Failed to decode encrypted data")?;
+
+    /* ---- decrypt ---- */
+    let decrypted_data: Vec<u8> = cipher
+        .decrypt(
+            Nonce::from_slice(&nonce_bytes),
+            Payload {
+                msg: &encrypted_bytes,
+                aad,
+            },
+        )
+        .map_err(|_| "Failed to decrypt")?;
+
+    /* ---- deserialize inner JSON ---- */
+    let wallet: Wallet =
+        serde_json::from_slice(&decrypted_data).map_err(|_| "Failed to parse decrypted JSON")?;
+
+    /* ---- bytes -> Scalar ---- */
+    let key_bytes =
+        hex::decode(wallet.private_key).map_err(|_| "Failed to decode private key hex")?;
+
+    Scalar::from_repr(key_bytes.try_into().map_err(|_| "Invalid key length")?)
+        .into_option()
+        .ok_or("Failed to reconstruct Scalar from bytes".into())
+}
+
+/// Replaces a file in one step, writing a temporary file next to it and renaming it
+/// over the old one, so a crash never leaves a half written wallet or cache.
+///
+/// The file is readable by its owner only, whatever the umask.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp_path: PathBuf = write_temp(path, contents)?;
+    fs::rename(&tmp_path, path)?;
+    sync_parent(path)
+}
+
+/// Writes a file that must not exist yet, like [`write_atomically`] but failing with
+/// `AlreadyExists` instead of replacing a file that is already there.
+pub fn write_new(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp_path: PathBuf = write_temp(path, contents)?;
+    // a hard link never replaces its target, unlike a rename
+    let linked: io::Result<()> = fs::hard_link(&tmp_path, path);
+    fs::remove_file(&tmp_path)?;
+    linked?;
+    sync_parent(path)
+}
+
+/// Writes the contents to a fresh owner-only temporary file next to `path`.
+fn write_temp(path: &Path, contents: &str) -> io::Result<PathBuf> {
+    let file_name: String = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp_path: PathBuf = path.with_file_name(format!(".{file_name}.tmp"));
+    // a temporary file left by a crash is stale, never reuse it with its old mode
+    match fs::remove_file(&tmp_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    {
+        let mut options: fs::OpenOptions = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file: fs::File = options.open(&tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+    }
+    Ok(tmp_path)
+}
+
+/// Flushes the directory holding `path`, a new entry is only durable once it is on disk.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent: &Path = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
@@ -1,6 +1,9 @@
+use anyhow::{Context, Result, anyhow};
 use mime_guess::MimeGuess;
 use rust_embed::RustEmbed;
+use serde_json::Value;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use tokio::select;
 use tokio::signal;
 use tokio::sync::oneshot;
@@ -25,14 +28,29 @@ pub struct WebServer {
 
 impl WebServer {
     pub async fn start(message: String, network_flag: bool) -> Self {
+        Self::serve(message, network_flag, None).await
+    }
+
+    /// Serves the page, in witness mode when a sender for the witness set is given.
+    async fn serve(
+        message: String,
+        network_flag: bool,
+        witness: Option<oneshot::Sender<String>>,
+    ) -> Self {
         let addr: SocketAddr = ([127, 0, 0, 1], 44203).into();
+        let witness_mode: bool = witness.is_some();
+        let witness: Arc<Mutex<Option<oneshot::Sender<String>>>> = Arc::new(Mutex::new(witness));
 
         // HTML route with injection
         let html = warp::path::end().map(move || {
             let file = Asset::get("index.html").expect("index.html not found");
             let mut html = String::from_utf8(file.data.into_owned()).unwrap();
 
-            let dyn_msg = format!(r#"{{ "message": "{message}" }}"#);
+            let dyn_msg = if witness_mode {
+                format!(r#"{{ "message": "{message}", "witness": true }}"#)
+            } else {
+                format!(r#"{{ "message": "{message}" }}"#)
+            };
             html = html.replace(r#"{ "message": "ACAB000000000000" }"#, &dyn_msg);
 
             let net_repl = if network_flag {
@@ -59,8 +77,21 @@ impl WebServer {
             serve_bytes("favicon.ico", file.data.into_owned())
         });
 
+        // the page posts the wallet's witness set back here in witness mode
+        let witness_route = warp::path("witness")
+            .and(warp::post())
+            .and(warp::body::json())
+            .map(move |body: Value| {
+                if let Some(witness_set) = body.get("witness").and_then(Value::as_str)
+                    && let Some(sender) = witness.lock().ok().and_then(|mut w| w.take())
+                {
+                    let _ = sender.send(witness_set.to_string());
+                }
+                warp::reply()
+            });
+
         // Combine all routes
-        let routes = html.or(js).or(css).or(ico);
+        let routes = html.or(js).or(css).or(ico).or(witness_route);
 
         // create a oneshot channel we can trigger from Tauri
         let (tx, rx) = oneshot::channel::<()>();
@@ -106,6 +137,30 @@ pub async fn run_web_server(message: String, network_flag: bool) {
     }
 }
 
+/// Serves a transaction for a CIP-30 wallet to witness and waits for the witness set.
+///
+/// # Arguments
+///
+/// * `message` - The transaction CBOR to witness.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod.
+///     - `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The witness set CBOR returned by the wallet.
+/// * `Err(anyhow::Error)` - If the server stops before the wallet answers.
+pub async fn request_witness(message: String, network_flag: bool) -> Result<Vec<u8>> {
+    let (sender, receiver) = oneshot::channel::<String>();
+    let mut server: WebServer = WebServer::serve(message, network_flag, Some(sender)).await;
+    // the sender is dropped with the routes if the server is stopped first
+    let witness_set = receiver.await;
+    server.stop().await;
+    let witness_set: String =
+        witness_set.map_err(|_| anyhow!("The Wallet Did Not Witness The Transaction"))?;
+    hex::decode(witness_set).context("Invalid Witness Set")
+}
+
 pub async fn run_web_server_non_blocking(message: String, network_flag: bool) -> WebServer {
     WebServer::start(message, network_flag).await
 }
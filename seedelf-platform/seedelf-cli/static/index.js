@@ -40,6 +40,22 @@ async function initializePage() {
     // the wallet should be enabled and on the correct network
     statusElement.textContent = "Wallet connected successfully!";
 
+    // the wallet only witnesses the collateral, seedelf-cli submits the transaction
+    if (injectedData.witness === true) {
+      try {
+        const witness = await wallet.signTx(injectedData.message, true);
+        await fetch("/witness", {
+          method: "POST",
+          headers: { "content-type": "application/json" },
+          body: JSON.stringify({ witness: witness }),
+        });
+        statusElement.textContent = "Collateral successfully witnessed! seedelf-cli will submit the transaction. Please close this tab.";
+      } catch (error) {
+        statusElement.textContent = "Error: Sign Tx Failure. Message: " + error.message + ".  Refresh page and try again.";
+      }
+      return;
+    }
+
     try {
       const sig_part = await wallet.signTx(injectedData.message);
 
@@ -0,0 +1,100 @@
+#![cfg(unix)]
+
+use blstrs::Scalar;
+use seedelf_cli::agent::{Agent, AgentClient, bind};
+use seedelf_cli::cache;
+use seedelf_cli::key::WalletKey;
+use seedelf_cli::session;
+use seedelf_crypto::register::Register;
+use seedelf_crypto::schnorr::{prove, random_scalar};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::time::Duration;
+
+#[tokio::test]
+async fn agent_answers_without_handing_out_the_key() {
+    let sk: Scalar = random_scalar();
+    let base: Register = Register::create(sk).unwrap();
+    let stranger: Register = Register::create(random_scalar()).unwrap();
+    session::unlock(sk);
+
+    let path = std::env::temp_dir().join(format!("seedelf-agent-{}.sock", std::process::id()));
+    let listener = bind(&path).unwrap();
+    let mode: u32 = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(bind(&path).is_err());
+
+    let socket = path.clone();
+    let server = tokio::spawn(async move {
+        Agent::new("alice".to_string(), Duration::from_secs(60))
+            .with_request_timeout(Duration::from_millis(500))
+            .serve(listener, &socket)
+            .await
+    });
+
+    // a client that connects and says nothing must not hold up the next one
+    let quiet = std::os::unix::net::UnixStream::connect(&path).unwrap();
+
+    let client_path = path.clone();
+    let client = tokio::task::spawn_blocking(move || {
+        let mut agent: AgentClient = AgentClient::connect(&client_path).unwrap();
+        assert_eq!(agent.status().unwrap(), "alice");
+        // a command goes quiet while it scans the chain, its connection must outlive that
+        std::thread::sleep(Duration::from_secs(1));
+        let register: Register = agent.register().unwrap();
+        let owned: Vec<bool> = agent
+            .is_owned(vec![base.rerandomize().unwrap(), stranger])
+            .unwrap();
+        let proof = agent
+            .create_proof(register.clone(), "bound".to_string())
+            .unwrap();
+        // the agent stands in for the local key everywhere a command needs it
+        let key: WalletKey = WalletKey::from(agent);
+        let local: WalletKey = WalletKey::from(sk);
+        for tag in cache::DERIVE_TAGS {
+            assert_eq!(key.derive(tag).unwrap(), local.derive(tag).unwrap());
+        }
+        assert!(key.derive("anything-else").is_err());
+        assert_eq!(key.key_hash().unwrap(), local.key_hash().unwrap());
+        assert!(key.owns(vec![register.clone()]).unwrap()[0]);
+
+        // nothing on the socket signs transactions with the external key
+        let mut raw = std::os::unix::net::UnixStream::connect(&client_path).unwrap();
+        raw.write_all(b"{\"request\":\"sign_tx\",\"tx_hash\":\"00\"}\n")
+            .unwrap();
+        let mut reply: String = String::new();
+        BufReader::new(raw).read_line(&mut reply).unwrap();
+        assert!(reply.contains("\"response\":\"error\""));
+
+        // but a request that stalls halfway is dropped
+        let mut stalled = std::os::unix::net::UnixStream::connect(&client_path).unwrap();
+        stalled.write_all(b"{\"request\"").unwrap();
+        std::thread::sleep(Duration::from_secs(1));
+        assert_eq!(stalled.read(&mut [0u8; 64]).unwrap(), 0);
+
+        AgentClient::connect(&client_path).unwrap().lock().unwrap();
+        (register, owned, proof)
+    });
+    // well inside the time the server waits on the quiet client
+    let (register, owned, (z, g_r)) = tokio::time::timeout(Duration::from_secs(5), client)
+        .await
+        .expect("The Quiet Client Held Up The Agent")
+        .unwrap();
+
+    server.await.unwrap().unwrap();
+    drop(quiet);
+    assert!(register.is_owned(sk).unwrap());
+    assert_eq!(owned, vec![true, false]);
+    assert!(
+        prove(
+            &register.generator,
+            &register.public_value,
+            &z,
+            &g_r,
+            "bound"
+        )
+        .unwrap()
+    );
+    assert!(!session::is_unlocked());
+    assert!(!path.exists());
+}
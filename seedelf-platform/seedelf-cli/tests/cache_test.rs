@@ -0,0 +1,348 @@
+use seedelf_cli::cache::{self, HistoryEntry, PENDING_EXPIRY, PendingTx, WalletCache};
+use seedelf_cli::key::WalletKey;
+use seedelf_cli::setup::set_data_dir;
+use seedelf_core::constants::{Config, get_config};
+use seedelf_crypto::register::Register;
+use seedelf_crypto::schnorr::random_scalar;
+use seedelf_koios::koios::{
+    Asset, BlockchainTip, CredentialTx, InlineDatum, RegisterValue, ResolvedDatum, TxResponse,
+    TxStatus, UtxoResponse,
+};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
+use serde_json::{Value, json};
+use std::io;
+use std::path::PathBuf;
+
+const CONTRACT: &str = "94bca9c099e84ffd90d150316bb44c31a78702239076a0a80ea4a469";
+const POLICY: &str = "84967d911e1a10d5b4a38441879f374a07f340945bcf9e7697485255";
+
+fn utxo(tx_hash: &str, tx_index: u64, register: &Register, seedelf: Option<&str>) -> UtxoResponse {
+    UtxoResponse {
+        tx_hash: tx_hash.to_string(),
+        tx_index,
+        payment_cred: CONTRACT.to_string(),
+        value: "5000000".to_string(),
+        inline_datum: Some(InlineDatum {
+            bytes: String::new(),
+            value: json!({
+                "constructor": 0,
+                "fields": [{"bytes": register.generator}, {"bytes": register.public_value}]
+            }),
+        }),
+        asset_list: seedelf.map(|name| {
+            vec![Asset {
+                policy_id: POLICY.to_string(),
+                asset_name: name.to_string(),
+                quantity: "1".to_string(),
+                ..Default::default()
+            }]
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn new_transactions_update_the_owned_utxos() {
+    let sk = random_scalar();
+    let mine: Register = Register::create(sk).unwrap().rerandomize().unwrap();
+    let theirs: Register = Register::create(random_scalar()).unwrap();
+    let mut cache: WalletCache = WalletCache {
+        wallet_contract_hash: CONTRACT.to_string(),
+        history: Some(Vec::new()),
+        ..Default::default()
+    };
+
+    let create: CredentialTx = CredentialTx {
+        tx_hash: "aa".repeat(32),
+        block_height: 10,
+        outputs: vec![
+            utxo(&"aa".repeat(32), 0, &mine, Some("5eed0e1f")),
+            utxo(&"aa".repeat(32), 1, &mine, None),
+            utxo(&"aa".repeat(32), 2, &theirs, None),
+        ],
+        ..Default::default()
+    };
+    let spend: CredentialTx = CredentialTx {
+        tx_hash: "bb".repeat(32),
+        block_height: 11,
+        inputs: vec![utxo(&"aa".repeat(32), 1, &mine, None)],
+        outputs: vec![utxo(&"bb".repeat(32), 0, &theirs, None)],
+        ..Default::default()
+    };
+    let txs: Vec<CredentialTx> = vec![create, spend];
+    cache.apply(&txs, &sk.into(), POLICY).unwrap();
+    // a second sync over the same blocks changes nothing
+    cache.apply(&txs, &sk.into(), POLICY).unwrap();
+
+    assert_eq!(cache.utxos.len(), 1);
+    assert_eq!(cache.seedelfs, vec!["5eed0e1f".to_string()]);
+    assert!(cache.wallet_utxos(POLICY).is_empty());
+    let history = cache.history.unwrap();
+    assert_eq!(history.len(), 2);
+    assert!(history[0].receive && !history[0].spend);
+    assert!(history[1].spend && !history[1].receive);
+}
+
+#[test]
+fn only_the_wallet_key_opens_the_cache() {
+    let sk = random_scalar();
+    let path = std::env::temp_dir().join(format!("seedelf-cache-test-{}.db", std::process::id()));
+    let cache: WalletCache = WalletCache {
+        wallet_contract_hash: CONTRACT.to_string(),
+        synced_block: 42,
+        seedelfs: vec!["5eed0e1f".to_string()],
+        ..Default::default()
+    };
+    cache.save(&path, &sk.into()).unwrap();
+
+    let stored: String = std::fs::read_to_string(&path).unwrap();
+    assert!(!stored.contains("5eed0e1f"));
+    assert_eq!(
+        WalletCache::load(&path, &sk.into()).unwrap().synced_block,
+        42
+    );
+    assert!(WalletCache::load(&path, &random_scalar().into()).is_none());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn pending_transactions_hide_their_inputs_until_spent() {
+    let sk = random_scalar();
+    let mine: Register = Register::create(sk).unwrap().rerandomize().unwrap();
+    let funding: UtxoResponse = utxo(&"aa".repeat(32), 0, &mine, None);
+    let change: UtxoResponse = utxo(&"bb".repeat(32), 1, &mine, None);
+    let mut cache: WalletCache = WalletCache {
+        wallet_contract_hash: CONTRACT.to_string(),
+        utxos: vec![funding],
+        pending: vec![
+            PendingTx {
+                tx_hash: "bb".repeat(32),
+                submitted_at: 1_000,
+                inputs: vec![("aa".repeat(32), 0)],
+                outputs: vec![change.clone()],
+            },
+            // chained off the change of the first one
+            PendingTx {
+                tx_hash: "cc".repeat(32),
+                submitted_at: 1_000,
+                inputs: vec![("bb".repeat(32), 1)],
+                outputs: Vec::new(),
+            },
+        ],
+        ..Default::default()
+    };
+
+    let mut view: WalletCache = cache.clone();
+    view.settle_pending(1_000 + 60);
+    assert_eq!(view.pending.len(), 2);
+    view.apply_pending(POLICY);
+    assert!(view.utxos.is_empty());
+
+    // once the funding utxo is spent on chain only the change is left to wait on
+    cache.utxos = vec![change];
+    cache.settle_pending(1_000 + 60);
+    assert_eq!(cache.pending.len(), 1);
+    cache.settle_pending(1_000 + PENDING_EXPIRY + 1);
+    assert!(cache.pending.is_empty());
+}
+
+#[test]
+fn history_entries_net_out_the_wallet_side() {
+    let sk = random_scalar();
+    let mine: Register = Register::create(sk).unwrap().rerandomize().unwrap();
+    let theirs: Register = Register::create(random_scalar()).unwrap();
+    let token = |amount: &str| Asset {
+        policy_id: POLICY.to_string(),
+        asset_name: "acab".to_string(),
+        quantity: amount.to_string(),
+        ..Default::default()
+    };
+    let tx: TxResponse = TxResponse {
+        tx_hash: "aa".repeat(32),
+        block_height: 10,
+        block_time: 1_700_000_000,
+        fee: Some(200_000),
+        input_registers: vec![mine.clone()],
+        output_registers: vec![mine.clone(), theirs.clone()],
+        inputs: vec![RegisterValue {
+            register: mine.clone(),
+            lovelace: 10_000_000,
+            assets: vec![token("5")],
+            ..Default::default()
+        }],
+        outputs: vec![
+            RegisterValue {
+                register: theirs,
+                lovelace: 3_000_000,
+                assets: vec![token("2")],
+                ..Default::default()
+            },
+            RegisterValue {
+                register: mine,
+                lovelace: 6_800_000,
+                assets: vec![token("3")],
+                ..Default::default()
+            },
+        ],
+    };
+
+    let entry: HistoryEntry = HistoryEntry::from_tx(&tx, &sk.into()).unwrap().unwrap();
+    assert!(entry.spend && entry.receive);
+    assert_eq!(entry.net_lovelace(), -3_200_000);
+    assert_eq!(
+        entry.net_tokens(),
+        vec![(POLICY.to_string(), "acab".to_string(), -2)]
+    );
+    assert!(
+        HistoryEntry::from_tx(&tx, &random_scalar().into())
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn past_utxos_are_replayed_from_the_history() {
+    let sk = random_scalar();
+    let mine: Register = Register::create(sk).unwrap().rerandomize().unwrap();
+    let mut cache: WalletCache = WalletCache {
+        wallet_contract_hash: CONTRACT.to_string(),
+        history: Some(Vec::new()),
+        ..Default::default()
+    };
+    let txs: Vec<CredentialTx> = vec![
+        CredentialTx {
+            tx_hash: "aa".repeat(32),
+            block_height: 10,
+            outputs: vec![
+                utxo(&"aa".repeat(32), 0, &mine, None),
+                utxo(&"aa".repeat(32), 1, &mine, None),
+            ],
+            ..Default::default()
+        },
+        CredentialTx {
+            tx_hash: "bb".repeat(32),
+            block_height: 20,
+            inputs: vec![utxo(&"aa".repeat(32), 0, &mine, None)],
+            ..Default::default()
+        },
+    ];
+    cache.apply(&txs, &sk.into(), POLICY).unwrap();
+    let history: Vec<HistoryEntry> = cache.history.unwrap();
+
+    assert!(cache::utxos_at(&history, |tx| tx.block_height <= 9).is_empty());
+    assert_eq!(
+        cache::utxos_at(&history, |tx| tx.block_height <= 19).len(),
+        2
+    );
+    let now = cache::utxos_at(&history, |tx| tx.block_height <= 20);
+    assert_eq!(now.len(), 1);
+    assert_eq!((now[0].tx_index, now[0].lovelace), (1, 5_000_000));
+}
+
+/// A backend that can only list the contract, so every sync rescans.
+struct ScanOnly {
+    tip: u64,
+    utxos: Vec<UtxoResponse>,
+}
+
+impl ChainProvider for ScanOnly {
+    type Error = io::Error;
+
+    async fn tip(&self) -> Result<Vec<BlockchainTip>, Self::Error> {
+        Ok(vec![BlockchainTip {
+            hash: String::new(),
+            epoch_no: 0,
+            abs_slot: 0,
+            epoch_slot: 0,
+            block_no: self.tip,
+            block_time: 0,
+        }])
+    }
+
+    async fn credential_utxos(&self, _credential: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Ok(self.utxos.clone())
+    }
+
+    async fn address_utxos(&self, _address: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    async fn utxo_info(&self, _utxo: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    async fn datum_from_datum_hash(
+        &self,
+        _datum_hash: String,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    async fn evaluate_transaction(&self, _tx_cbor: String) -> Result<Value, Self::Error> {
+        Err(io::Error::other("evaluation not supported"))
+    }
+
+    async fn submit_tx(&self, _tx_cbor: String) -> Result<Value, Self::Error> {
+        Err(io::Error::other("submission not supported"))
+    }
+
+    async fn address_transactions(&self, _address: String) -> Result<Vec<TxResponse>, Self::Error> {
+        Err(io::Error::other("history not supported"))
+    }
+
+    async fn transaction_status(&self, _tx_hash: String) -> Result<Vec<TxStatus>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    async fn protocol_parameters(&self) -> Result<Option<ProtocolParameters>, Self::Error> {
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn a_rescan_saves_nothing_that_can_roll_back() {
+    let data_dir: PathBuf =
+        std::env::temp_dir().join(format!("seedelf-rescan-test-{}", std::process::id()));
+    set_data_dir(Some(data_dir.clone()));
+    let sk = random_scalar();
+    let mine: Register = Register::create(sk).unwrap().rerandomize().unwrap();
+    let key: WalletKey = WalletKey::from(sk);
+    let config: Config = get_config(1, true).unwrap();
+    let settled = UtxoResponse {
+        block_height: 100,
+        ..utxo("aa", 0, &mine, None)
+    };
+    let recent = UtxoResponse {
+        block_height: 999,
+        ..utxo("bb", 0, &mine, None)
+    };
+    let provider = ScanOnly {
+        tip: 1000,
+        utxos: vec![settled, recent],
+    };
+
+    let view: WalletCache = cache::sync(&provider, &config, &key, true, true)
+        .await
+        .unwrap();
+    assert_eq!(view.utxos.len(), 2);
+    let path: PathBuf = cache::cache_path(&key, &config, true).unwrap();
+    let saved: WalletCache = WalletCache::load(&path, &key).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode: u32 = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    assert!(saved.synced_block < 999);
+    assert_eq!(
+        saved
+            .utxos
+            .iter()
+            .map(|u| u.tx_hash.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["aa"]
+    );
+    set_data_dir(None);
+    let _ = std::fs::remove_dir_all(data_dir);
+}
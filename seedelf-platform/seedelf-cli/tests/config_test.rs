@@ -0,0 +1,42 @@
+use seedelf_cli::config::Config;
+use seedelf_koios::provider::BackendKind;
+
+#[test]
+fn profile_keys_round_trip_through_the_file() {
+    let mut config: Config = Config::default();
+    config.set("profile", "test", None).unwrap();
+    config.set("network", "preprod", None).unwrap();
+    config.set("backend", "blockfrost", None).unwrap();
+    config.set("main.variant", "1", None).unwrap();
+    config.set("update_check", "false", None).unwrap();
+    assert!(config.set("network", "testnet", None).is_err());
+    assert!(config.set("colour", "blue", None).is_err());
+
+    let path = std::env::temp_dir().join(format!("seedelf-config-{}.toml", std::process::id()));
+    config.save_to(&path).unwrap();
+    let loaded: Config = Config::load_from(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, config);
+
+    let profile = loaded.profile(None);
+    assert!(profile.preprod());
+    assert_eq!(
+        profile.backend_kind().unwrap(),
+        Some(BackendKind::Blockfrost)
+    );
+    assert_eq!(loaded.profile(Some("main")).variant, Some(1));
+    assert_eq!(
+        loaded.get("test.backend", Some("main")).unwrap().as_deref(),
+        Some("blockfrost")
+    );
+    assert!(!loaded.update_check());
+
+    let mut cleared: Config = loaded.clone();
+    cleared.set("main.variant", "", None).unwrap();
+    assert!(
+        !cleared
+            .list()
+            .iter()
+            .any(|(key, _)| key.starts_with("main."))
+    );
+}
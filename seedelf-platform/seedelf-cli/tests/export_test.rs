@@ -0,0 +1,123 @@
+use seedelf_cli::cache::{HistoryEntry, Movement, TokenAmount};
+use seedelf_cli::export::{self, Direction, ExportRow, Preset};
+use serde_json::Value;
+
+const POLICY: &str = "84967d911e1a10d5b4a38441879f374a07f340945bcf9e7697485255";
+
+fn movement(spend: bool, lovelace: u64, tokens: Vec<TokenAmount>) -> Movement {
+    Movement {
+        spend,
+        lovelace,
+        tokens,
+        ..Default::default()
+    }
+}
+
+fn history() -> Vec<HistoryEntry> {
+    let hosky = TokenAmount {
+        policy_id: POLICY.to_string(),
+        asset_name: hex::encode("HOSKY"),
+        amount: 100,
+    };
+    vec![
+        HistoryEntry {
+            tx_hash: "aa".repeat(32),
+            block_height: 10,
+            block_time: 1_700_000_000,
+            receive: true,
+            received_lovelace: 10_000_000,
+            fee: Some(180_000),
+            movements: vec![
+                movement(false, 6_000_000, vec![hosky.clone()]),
+                movement(false, 4_000_000, Vec::new()),
+            ],
+            ..Default::default()
+        },
+        HistoryEntry {
+            tx_hash: "bb".repeat(32),
+            block_height: 11,
+            block_time: 1_700_000_100,
+            spend: true,
+            receive: true,
+            spent_lovelace: 10_000_000,
+            received_lovelace: 6_800_000,
+            fee: Some(200_001),
+            // change listed first, the export still applies the spends first
+            movements: vec![
+                movement(false, 6_800_000, Vec::new()),
+                movement(true, 6_000_000, vec![hosky]),
+                movement(true, 4_000_000, Vec::new()),
+            ],
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn rows_share_the_fee_and_keep_a_running_balance() {
+    let rows: Vec<ExportRow> = export::rows(&history());
+    assert_eq!(rows.len(), 5);
+    // a receive pays no fee even when the backend reports one
+    assert!(rows[..2].iter().all(|row| row.fee_share == 0));
+    assert_eq!(rows[2].direction, Direction::Out);
+    assert_eq!(rows[3].direction, Direction::Out);
+    assert_eq!(rows[2].fee_share + rows[3].fee_share, 200_001);
+    assert_eq!(rows[2].fee_share, 120_000);
+    assert_eq!(
+        rows.iter().map(|row| row.balance).collect::<Vec<i64>>(),
+        vec![6_000_000, 10_000_000, 4_000_000, 0, 6_800_000]
+    );
+    assert_eq!(rows[0].timestamp.as_deref(), Some("2023-11-14T22:13:20Z"));
+}
+
+#[test]
+fn exports_keep_their_layout() {
+    let rows: Vec<ExportRow> = export::rows(&history());
+
+    let json: Value = serde_json::from_str(&export::to_json(&rows, false).unwrap()).unwrap();
+    assert_eq!(json["version"], export::EXPORT_SCHEMA_VERSION);
+    assert_eq!(json["network"], "mainnet");
+    assert_eq!(json["rows"][2]["direction"], "out");
+    assert_eq!(json["rows"][0]["tokens"][0]["amount"], 100);
+
+    let generic: String = export::to_csv(&rows, Preset::Generic).unwrap();
+    assert_eq!(
+        generic.lines().next().unwrap(),
+        "timestamp,block_height,tx_hash,direction,lovelace,tokens,fee_share,balance"
+    );
+    assert_eq!(generic.lines().count(), 6);
+
+    // tokens get their own line in the tax tool layouts
+    let koinly: String = export::to_csv(&rows, Preset::Koinly).unwrap();
+    assert_eq!(koinly.lines().count(), 8);
+    assert!(koinly.contains(",100,HOSKY,"));
+    let cointracking: String = export::to_csv(&rows, Preset::CoinTracking).unwrap();
+    assert!(cointracking.contains("Withdrawal,,,5.880000,ADA,0.120000,ADA,Seedelf"));
+}
+
+#[test]
+fn sent_amount_and_fee_add_up_to_the_outflow() {
+    let entries: Vec<HistoryEntry> = history();
+    let rows: Vec<ExportRow> = export::rows(&entries);
+    let koinly: String = export::to_csv(&rows, Preset::Koinly).unwrap();
+    let lovelace = |field: &str| -> i64 {
+        if field.is_empty() {
+            0
+        } else {
+            (field.parse::<f64>().unwrap() * 1_000_000.0).round() as i64
+        }
+    };
+
+    let tx: &HistoryEntry = &entries[1];
+    let outflow: i64 = koinly
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').collect::<Vec<&str>>())
+        .filter(|fields| fields[9] == tx.tx_hash && fields[2] != "HOSKY" && fields[4] != "HOSKY")
+        .map(|fields| lovelace(fields[1]) + lovelace(fields[5]) - lovelace(fields[3]))
+        .sum();
+    assert_eq!(
+        outflow,
+        tx.spent_lovelace as i64 - tx.received_lovelace as i64
+    );
+}
@@ -1,7 +1,9 @@
 use seedelf_core::address;
 use seedelf_core::assets::{Asset, asset_id_to_asset};
 use seedelf_core::constants::{ADA_HANDLE_POLICY_ID, Config, get_config};
-use seedelf_koios::koios::{ada_handle_address, asset_history, datum_from_datum_hash, nft_utxo};
+use seedelf_koios::koios::{
+    Koios, ada_handle_address, asset_history, datum_from_datum_hash, nft_utxo, parse_header,
+};
 use serde_json::Value;
 #[test]
 fn tx_hash_result() {
@@ -48,12 +50,12 @@ async fn find_nft_utxo() {
     .await;
     let utxo = nft_utxo.unwrap().first().unwrap().clone();
     println!("{:?}", utxo.tx_hash);
-    if utxo.inline_datum.is_none() {
-        if let Some(datum_hash) = utxo.datum_hash {
-            println!("Datum Hash: {}", datum_hash);
-            let datum = datum_from_datum_hash(datum_hash, false).await;
-            println!("Datum: {:?}", datum);
-        }
+    if utxo.inline_datum.is_none()
+        && let Some(datum_hash) = utxo.datum_hash
+    {
+        println!("Datum Hash: {}", datum_hash);
+        let datum = datum_from_datum_hash(datum_hash, false).await;
+        println!("Datum: {:?}", datum);
     }
 }
 
@@ -72,3 +74,28 @@ async fn test_asset_history() {
     let history = asset_history(pid, tkn, true, 5).await;
     assert_eq!(history.unwrap().len(), 5)
 }
+
+#[test]
+fn custom_koios_base_url() {
+    assert_eq!(
+        Koios::new(true).base_url(),
+        "https://preprod.koios.rest/api/v1"
+    );
+    let koios: Koios = Koios::new(false).with_base_url("https://koios.example.com/api/v1/");
+    assert_eq!(koios.base_url(), "https://koios.example.com/api/v1")
+}
+
+#[test]
+fn koios_header_parsing() {
+    let (name, value) = parse_header("X-Region: eu-west").unwrap();
+    assert_eq!(name.as_str(), "x-region");
+    assert_eq!(value.to_str().unwrap(), "eu-west");
+    assert!(parse_header("no separator").is_err());
+    assert!(parse_header("bad name: value").is_err())
+}
+
+#[test]
+fn koios_debug_hides_token() {
+    let koios: Koios = Koios::new(true).with_token("super-secret");
+    assert!(!format!("{koios:?}").contains("super-secret"))
+}
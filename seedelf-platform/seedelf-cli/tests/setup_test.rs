@@ -1,33 +1,37 @@
-use seedelf_cli::setup::password_complexity_check;
+use seedelf_cli::setup::{
+    PasswordSource, password_complexity_check, read_password_source, seedelf_home_path,
+    set_data_dir, valid_wallet_name, wallet_path,
+};
+use std::path::PathBuf;
 
 #[test]
 fn test_short_password() {
     let pw: String = "i@G37xzM".to_string();
-    assert_eq!(password_complexity_check(pw), false)
+    assert!(!password_complexity_check(pw))
 }
 
 #[test]
 fn test_no_lowercase() {
     let pw: String = "I@G37XZM@QCGK3G".to_string();
-    assert_eq!(password_complexity_check(pw), false)
+    assert!(!password_complexity_check(pw))
 }
 
 #[test]
 fn test_no_uppercase() {
     let pw: String = "i@g37xzm@qcgk3g".to_string();
-    assert_eq!(password_complexity_check(pw), false)
+    assert!(!password_complexity_check(pw))
 }
 
 #[test]
 fn test_no_special() {
     let pw: String = "iaG37xzMaqcgk3g".to_string();
-    assert_eq!(password_complexity_check(pw), false)
+    assert!(!password_complexity_check(pw))
 }
 
 #[test]
 fn test_good_password() {
     let pw: String = "i@G37xzM@qcgk3g".to_string();
-    assert_eq!(password_complexity_check(pw), true)
+    assert!(password_complexity_check(pw))
 }
 
 #[test]
@@ -83,3 +87,33 @@ fn test_remove_white_spaces_trailing() {
     let expected: String = "Single_Space".to_string();
     assert_eq!(expected, final_name)
 }
+
+#[test]
+fn wallet_names_stay_inside_the_seedelf_home() {
+    assert!(valid_wallet_name("alice_2"));
+    assert!(!valid_wallet_name(""));
+    assert!(!valid_wallet_name(".hidden"));
+    assert!(!valid_wallet_name("../alice"));
+    assert!(!valid_wallet_name("bob smith"));
+}
+
+#[test]
+fn data_dir_moves_every_wallet_file() {
+    let data_dir: PathBuf = std::env::temp_dir().join("seedelf-data-dir-test");
+    set_data_dir(Some(data_dir.clone()));
+    assert_eq!(seedelf_home_path(), data_dir);
+    assert_eq!(wallet_path("alice").unwrap(), data_dir.join("alice.wallet"));
+    assert!(wallet_path("../../x").is_err());
+    set_data_dir(None);
+}
+
+#[test]
+fn password_files_lose_only_their_trailing_newline() {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("seedelf-password-{}", std::process::id()));
+    std::fs::write(&path, "i@G37xzM@qcgk3g \r\n").unwrap();
+    let password = read_password_source(&PasswordSource::File(path.clone())).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(password.as_deref(), Some("i@G37xzM@qcgk3g "));
+    assert_eq!(read_password_source(&PasswordSource::Prompt).unwrap(), None);
+}
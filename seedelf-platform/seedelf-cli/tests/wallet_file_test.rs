@@ -0,0 +1,129 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit};
+use aes_gcm::{Aes256Gcm, Key};
+use argon2::{Argon2, password_hash::SaltString};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use blstrs::Scalar;
+use ff::PrimeField;
+use rand_core::OsRng;
+use seedelf_cli::setup::{rename_wallet, set_data_dir, wallet_path};
+use seedelf_cli::wallet_file::{self, KdfParams, WALLET_FILE_VERSION, WalletHeader};
+use seedelf_crypto::schnorr::random_scalar;
+use serde_json::{Value, json};
+
+const PASSWORD: &str = "i@G37xzM@qcgk3g";
+
+/// Cheap parameters so the tests do not spend seconds in argon2.
+fn cheap_kdf() -> KdfParams {
+    KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        ..KdfParams::default()
+    }
+}
+
+#[test]
+fn sealed_wallets_open_with_their_password_only() {
+    let sk: Scalar = random_scalar();
+    let header: WalletHeader = WalletHeader::new(
+        "alice".to_string(),
+        Some("preprod".to_string()),
+        cheap_kdf(),
+    );
+    let sealed: String = wallet_file::seal(&sk, PASSWORD, header.clone()).unwrap();
+
+    let (opened, opened_header) = wallet_file::open(&sealed, PASSWORD).unwrap();
+    assert_eq!(opened, sk);
+    assert_eq!(opened_header, Some(header));
+    assert!(wallet_file::open(&sealed, "wrong-password").is_err());
+
+    // the header is authenticated, so renaming it inside the file breaks decryption
+    let mut tampered: Value = serde_json::from_str(&sealed).unwrap();
+    tampered["wallet_name"] = json!("mallory");
+    assert!(wallet_file::open(&tampered.to_string(), PASSWORD).is_err());
+}
+
+#[test]
+fn v0_wallets_still_open() {
+    let sk: Scalar = random_scalar();
+    let plain: String = json!({ "private_key": hex::encode(sk.to_repr()) }).to_string();
+    let salt: SaltString = SaltString::generate(&mut OsRng);
+    let mut key_material: [u8; 32] = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(
+            PASSWORD.as_bytes(),
+            salt.as_str().as_bytes(),
+            &mut key_material,
+        )
+        .unwrap();
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_material));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let data: Vec<u8> = cipher.encrypt(&nonce, plain.as_bytes()).unwrap();
+    let v0: String = json!({
+        "salt": salt.as_str(),
+        "nonce": STANDARD.encode(nonce),
+        "data": STANDARD.encode(data),
+    })
+    .to_string();
+
+    assert_eq!(wallet_file::read_header(&v0).unwrap(), None);
+    let (opened, header) = wallet_file::open(&v0, PASSWORD).unwrap();
+    assert_eq!(opened, sk);
+    assert_eq!(header, None);
+
+    let upgraded: String = wallet_file::seal(
+        &opened,
+        PASSWORD,
+        WalletHeader::new("legacy".to_string(), None, cheap_kdf()),
+    )
+    .unwrap();
+    let header: WalletHeader = wallet_file::read_header(&upgraded).unwrap().unwrap();
+    assert_eq!(header.version, WALLET_FILE_VERSION);
+    assert_eq!(wallet_file::open(&upgraded, PASSWORD).unwrap().0, sk);
+}
+
+#[cfg(unix)]
+#[test]
+fn wallet_files_are_private() {
+    use std::os::unix::fs::PermissionsExt;
+    let path = std::env::temp_dir().join(format!("seedelf-private-{}.wallet", std::process::id()));
+    wallet_file::write_atomically(&path, "{}").unwrap();
+    // replacing keeps it private too
+    wallet_file::write_atomically(&path, "{}").unwrap();
+    let mode: u32 = std::fs::metadata(&path).unwrap().permissions().mode();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn renaming_never_replaces_another_wallet() {
+    let data_dir = std::env::temp_dir().join(format!("seedelf-rename-{}", std::process::id()));
+    std::fs::create_dir_all(&data_dir).unwrap();
+    set_data_dir(Some(data_dir.clone()));
+    let alice: Scalar = random_scalar();
+    let write = |name: &str, sk: &Scalar| {
+        let header: WalletHeader = WalletHeader::new(name.to_string(), None, cheap_kdf());
+        let sealed: String = wallet_file::seal(sk, PASSWORD, header).unwrap();
+        wallet_file::write_atomically(&wallet_path(name).unwrap(), &sealed).unwrap();
+        sealed
+    };
+    write("alice", &alice);
+    let bob: String = write("bob", &random_scalar());
+
+    assert!(rename_wallet("alice", "bob", PASSWORD).is_err());
+    assert_eq!(
+        std::fs::read_to_string(wallet_path("bob").unwrap()).unwrap(),
+        bob
+    );
+    assert!(wallet_path("alice").unwrap().exists());
+
+    rename_wallet("alice", "carol", PASSWORD).unwrap();
+    assert!(!wallet_path("alice").unwrap().exists());
+    let carol: String = std::fs::read_to_string(wallet_path("carol").unwrap()).unwrap();
+    let (opened, header) = wallet_file::open(&carol, PASSWORD).unwrap();
+    assert_eq!(opened, alice);
+    assert_eq!(header.unwrap().wallet_name, "carol");
+
+    set_data_dir(None);
+    std::fs::remove_dir_all(&data_dir).unwrap();
+}
@@ -0,0 +1,292 @@
+use crate::address;
+use crate::constants::{COLLATERAL_HASH, COLLATERAL_LOVELACE};
+use crate::transaction::collateral_input;
+use anyhow::{Context, Result, anyhow, bail};
+use pallas_addresses::{Address, ShelleyPaymentPart};
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_crypto::key::ed25519::PublicKey;
+use pallas_primitives::Fragment;
+use pallas_primitives::conway::{Tx, WitnessSet};
+use pallas_txbuilder::{BuiltTransaction, Input, Output, StagingTransaction};
+use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
+use std::fmt;
+use std::str::FromStr;
+
+/// Who puts up the collateral for a script transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollateralKind {
+    /// The shared giveme.my UTxO, witnessed by its API.
+    #[default]
+    Service,
+    /// A UTxO at the wallet's external address, witnessed with the wallet key.
+    External,
+    /// A UTxO from a CIP-30 wallet, witnessed in the browser.
+    Cip30,
+}
+
+impl FromStr for CollateralKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "service" | "giveme" => Ok(CollateralKind::Service),
+            "external" => Ok(CollateralKind::External),
+            "cip30" | "cip-30" => Ok(CollateralKind::Cip30),
+            other => Err(format!(
+                "Unknown Collateral: {other}, expected service, external or cip30"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CollateralKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollateralKind::Service => write!(f, "service"),
+            CollateralKind::External => write!(f, "external"),
+            CollateralKind::Cip30 => write!(f, "cip30"),
+        }
+    }
+}
+
+/// Where the collateral comes from, with the address of a CIP-30 wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum CollateralSource {
+    #[default]
+    Service,
+    External,
+    Cip30(String),
+}
+
+impl CollateralSource {
+    pub fn kind(&self) -> CollateralKind {
+        match self {
+            CollateralSource::Service => CollateralKind::Service,
+            CollateralSource::External => CollateralKind::External,
+            CollateralSource::Cip30(_) => CollateralKind::Cip30,
+        }
+    }
+}
+
+/// A collateral UTxO selected for a transaction.
+#[derive(Debug, Clone)]
+pub struct Collateral {
+    pub kind: CollateralKind,
+    pub input: Input,
+    pub lovelace: u64,
+    /// The collateral return goes back here.
+    pub address: Address,
+    /// The payment key hash that has to witness the collateral input.
+    pub signer: Hash<28>,
+}
+
+impl Collateral {
+    /// The shared collateral UTxO of the giveme.my service holding `lovelace`.
+    ///
+    /// Use `service_collateral` to look the lovelace up on chain.
+    pub fn service(network_flag: bool, lovelace: u64) -> Self {
+        Collateral {
+            kind: CollateralKind::Service,
+            input: collateral_input(network_flag),
+            lovelace,
+            address: address::collateral_address(network_flag),
+            signer: Hash::new(COLLATERAL_HASH),
+        }
+    }
+
+    /// Uses a UTxO held by a payment key as the collateral.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Who witnesses the collateral.
+    /// * `utxo` - A lovelace only UTxO at a payment key address.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Collateral)` - The collateral.
+    /// * `Err(anyhow::Error)` - If the UTxO is not at a payment key address.
+    pub fn from_utxo(kind: CollateralKind, utxo: &UtxoResponse) -> Result<Self> {
+        let tx_hash: [u8; 32] = hex::decode(&utxo.tx_hash)
+            .context("Invalid Transaction Hash")?
+            .try_into()
+            .map_err(|_| anyhow!("Transaction Hash Is Not 32 Bytes"))?;
+        let addr: Address = Address::from_bech32(&utxo.address).context("Invalid UTxO Address")?;
+        let signer: Hash<28> = match &addr {
+            Address::Shelley(shelley) => match shelley.payment() {
+                ShelleyPaymentPart::Key(hash) => *hash,
+                ShelleyPaymentPart::Script(_) => bail!("Collateral Must Be Held By A Payment Key"),
+            },
+            _ => bail!("Collateral Must Be At A Shelley Address"),
+        };
+        Ok(Collateral {
+            kind,
+            input: Input::new(Hash::new(tx_hash), utxo.tx_index),
+            lovelace: utxo.value.parse().context("Invalid UTxO Value")?,
+            address: addr,
+            signer,
+        })
+    }
+
+    /// The lovelace the collateral puts at stake for a fee.
+    pub fn total(&self, fee: u64, params: &ProtocolParameters) -> u64 {
+        (fee * params.collateral_percentage).div_ceil(100)
+    }
+
+    /// The output returning what is not at stake if a script fails.
+    pub fn return_output(&self, fee: u64, params: &ProtocolParameters) -> Output {
+        Output::new(
+            self.address.clone(),
+            self.lovelace.saturating_sub(self.total(fee, params)),
+        )
+    }
+
+    /// Adds the collateral input, the collateral return and its signer to a transaction.
+    pub fn stage(
+        &self,
+        tx: StagingTransaction,
+        fee: u64,
+        params: &ProtocolParameters,
+    ) -> StagingTransaction {
+        tx.collateral_input(self.input.clone())
+            .collateral_output(self.return_output(fee, params))
+            .disclosed_signer(self.signer)
+    }
+}
+
+/// Picks the collateral from a list of UTxOs.
+///
+/// The smallest lovelace only UTxO holding at least `COLLATERAL_LOVELACE` is used so
+/// the least value is put at stake.
+pub fn pick_collateral_utxo(utxos: &[UtxoResponse]) -> Option<&UtxoResponse> {
+    utxos
+        .iter()
+        .filter(|utxo| {
+            utxo.asset_list
+                .as_ref()
+                .is_none_or(|assets| assets.is_empty())
+        })
+        .filter(|utxo| utxo.reference_script.is_none())
+        .filter(|utxo| utxo.value.parse::<u64>().unwrap_or_default() >= COLLATERAL_LOVELACE)
+        .min_by_key(|utxo| utxo.value.parse::<u64>().unwrap_or_default())
+}
+
+/// Looks up the shared collateral UTxO of the giveme.my service.
+///
+/// # Arguments
+///
+/// * `provider` - The chain data backend.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod.
+///     - `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `Ok(Collateral)` - The collateral with the lovelace the UTxO holds.
+/// * `Err(anyhow::Error)` - If the UTxO can not be found.
+pub async fn service_collateral(
+    provider: &impl ChainProvider,
+    network_flag: bool,
+) -> Result<Collateral> {
+    let input: Input = collateral_input(network_flag);
+    let utxo: String = format!("{}#{}", hex::encode(input.tx_hash.0), input.txo_index);
+    let utxos: Vec<UtxoResponse> = provider
+        .utxo_info(&utxo)
+        .await
+        .context("Failed To Get The Collateral UTxO")?;
+    let lovelace: u64 = utxos
+        .first()
+        .ok_or_else(|| anyhow!("The Collateral UTxO {utxo} Was Not Found"))?
+        .value
+        .parse()
+        .context("Invalid UTxO Value")?;
+    Ok(Collateral::service(network_flag, lovelace))
+}
+
+/// Finds the collateral for a transaction.
+///
+/// # Arguments
+///
+/// * `provider` - The chain data backend.
+/// * `source` - Where the collateral comes from.
+/// * `key_hash` - The payment key hash of the wallet, its external address holds the `External` collateral.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod.
+///     - `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `Ok(Collateral)` - The collateral.
+/// * `Err(anyhow::Error)` - If no suitable UTxO exists at the collateral address.
+pub async fn select_collateral(
+    provider: &impl ChainProvider,
+    source: &CollateralSource,
+    key_hash: &str,
+    network_flag: bool,
+) -> Result<Collateral> {
+    let addr: String = match source {
+        CollateralSource::Service => return service_collateral(provider, network_flag).await,
+        CollateralSource::External => address::dapp_address(key_hash.to_string(), network_flag)?
+            .to_bech32()
+            .map_err(|e| anyhow!("{e}"))?,
+        CollateralSource::Cip30(addr) => addr.clone(),
+    };
+    let utxos: Vec<UtxoResponse> = provider
+        .address_utxos(&addr)
+        .await
+        .context("Failed To Get Collateral UTxOs")?;
+    let utxo: &UtxoResponse = pick_collateral_utxo(&utxos).ok_or_else(|| {
+        anyhow!(
+            "No Collateral Found, {addr} Needs A UTxO With At Least {COLLATERAL_LOVELACE} Lovelace And No Tokens"
+        )
+    })?;
+    Collateral::from_utxo(source.kind(), utxo)
+}
+
+/// Sets the total collateral of a built transaction.
+///
+/// The transaction builder has no field for it, so the body is patched and the
+/// hash recomputed. This must happen before the transaction is signed.
+pub fn set_total_collateral(mut tx: BuiltTransaction, total: u64) -> Result<BuiltTransaction> {
+    let mut decoded: Tx =
+        Tx::decode_fragment(&tx.tx_bytes.0).map_err(|e| anyhow!("Invalid Transaction: {e}"))?;
+    decoded.transaction_body.total_collateral = Some(total);
+    let body: Vec<u8> = decoded
+        .transaction_body
+        .encode_fragment()
+        .map_err(|e| anyhow!("Failed To Encode Body: {e}"))?;
+    tx.tx_hash.0 = *Hasher::<256>::hash(&body);
+    tx.tx_bytes.0 = decoded
+        .encode_fragment()
+        .map_err(|e| anyhow!("Failed To Encode Transaction: {e}"))?;
+    Ok(tx)
+}
+
+/// Adds the key witnesses of a CIP-30 `signTx` witness set to a transaction.
+pub fn add_vkey_witnesses(
+    mut tx: BuiltTransaction,
+    witness_set: &[u8],
+) -> Result<BuiltTransaction> {
+    let witnesses: WitnessSet = WitnessSet::decode_fragment(witness_set)
+        .map_err(|e| anyhow!("Invalid Witness Set: {e}"))?;
+    let vkeys = witnesses
+        .vkeywitness
+        .ok_or_else(|| anyhow!("The Wallet Returned No Signatures"))?;
+    for witness in vkeys.to_vec() {
+        let vkey: [u8; 32] = witness
+            .vkey
+            .to_vec()
+            .try_into()
+            .map_err(|_| anyhow!("Invalid Witness Key"))?;
+        let signature: [u8; 64] = witness
+            .signature
+            .to_vec()
+            .try_into()
+            .map_err(|_| anyhow!("Invalid Witness Signature"))?;
+        tx = tx
+            .add_signature(PublicKey::from(vkey), signature)
+            .map_err(|e| anyhow!("Failed To Add Witness: {e}"))?;
+    }
+    Ok(tx)
+}
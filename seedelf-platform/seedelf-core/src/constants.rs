@@ -10,6 +10,10 @@ pub struct Contract {
     pub seedelf_policy_id: String,
     pub wallet_contract_size: u64,
     pub seedelf_contract_size: u64,
+    /// The `cborHex` of the compiled wallet contract in `seedelf-contracts/contracts`.
+    pub wallet_contract_cbor: String,
+    /// The `cborHex` of the compiled seedelf contract in `seedelf-contracts/contracts`.
+    pub seedelf_contract_cbor: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +59,39 @@ pub fn get_config(variant: u64, network: bool) -> Option<Config> {
                     .to_string(),
                 wallet_contract_size: 629,
                 seedelf_contract_size: 519,
+                wallet_contract_cbor: concat!(
+                    "59027559027201010033232323232323232225333003323232323253330083370e900118049baa0011323322",
+                    "53323300c3001300d375400c2646464646464a66602a60300042a666024600e60266ea80204c8c94cccccc06",
+                    "c00854ccc050c024c054dd5001099299980c000801099299999980e80080180180180189919299980d800802",
+                    "8992999999810000803003003003099299980e9810001899299980d9919199999911111192999812180c9812",
+                    "9baa001132325333026301b30273754002266ee4008cddb1bbb005337706eb4c0acc0a0dd50009bbb0071630",
+                    "08379066e28cdc519b8a007004006003337706eb4c0a4c098dd50009bbb006163006003232533301f533301f",
+                    "337129000000899b880014820a02020383ffffffffef7f97fefe0693ef3aaa02efa21222c39e660ea77f3aa6",
+                    "9a77b6f838528099ba548000cc08cdd4000a5eb805300103d87a800033794944004dd71800980f9baa00b375",
+                    "c6004603e6ea802cdd71800980f9baa017375c6004603e6ea805cdd71801980f9baa01723022001230213022",
+                    "00113322323300100100322533302200114a026644a66604266e3c0080145288998020020009bae302400130",
+                    "250013758604060426042604260426042604260426042603a6ea8058dd71800980e9baa01514a04604060426",
+                    "04200200e6eb8004c074004c07400cdd7000980d000980b1baa00200100100100100114a2602e60286ea8020",
+                    "5288b1bae30160013016002375c602800260280046eb8c048004c038dd50031b874800058c038004c038c03c",
+                    "004c028dd50008b1806180680198058011805001180500098029baa00114984d9595cd2ab9d5573caae7d5d0",
+                    "2ba15744ae9130010544acabcafe0001",
+                )
+                .to_string(),
+                seedelf_contract_cbor: concat!(
+                    "590207590204010100332323232323232225333003323232323253330083370e900018049baa001132323233",
+                    "22533300d533300d00214a22002266601aa66601a004200229412825114a06464a66602060260042646464a6",
+                    "6602066e3cdd7180a8020040a99980819b8f3337189000240100049101045eed0e1f0013370e00290008a501",
+                    "4a06eb4c050c054008dd7180980098098008b1bac30110013001375660206022602260226022601a6ea8018c",
+                    "8c8c8c94ccc048c0540084c8c8c8c8c8c94ccc054cdc78069bae301a007153330153371e00200a266e1d2002",
+                    "00414a02940c8ccc004cdc519b8a4881045eed0e1f0033300100c48001201c337166eb4c068c06cc05cdd518",
+                    "01180b9baa003375c6004602e6ea8c008c05cdd5001a4000901f111199b8c0023370066e0400400920020032",
+                    "30190013017006375a602c602e0046eb8c054004c05400458dd6180980098019bab301230133013301330130",
+                    "0237586022002601a6ea80188c8cc004004008894ccc04000452f5c0264666444646600200200644a66602c0",
+                    "022006264660306e9ccc060dd48031980c180a8009980c180b000a5eb80cc00c00cc068008c060004dd71807",
+                    "8009bab30100013300300330140023012001375c0066eb8c034c028dd50008b1806180680198058011805001",
+                    "180500098029baa00114984d9595cd2ab9d5573caae7d5d02ba1574498010544acabcafe0001",
+                )
+                .to_string(),
             };
             Some(Config {
                 contract,
@@ -65,6 +102,27 @@ pub fn get_config(variant: u64, network: bool) -> Option<Config> {
     }
 }
 
+/// The variants to scan, one picked variant or every registered one, oldest first.
+///
+/// # Arguments
+///
+/// * `variant` - A single variant, `None` for all of them.
+/// * `network` - `true` for Preprod, `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `Vec<(u64, Config)>` - Each variant with its config, empty for an unknown variant.
+pub fn scan_variants(variant: Option<u64>, network: bool) -> Vec<(u64, Config)> {
+    match variant {
+        Some(variant) => get_config(variant, network)
+            .map(|config| vec![(variant, config)])
+            .unwrap_or_default(),
+        None => (1..=VARIANT)
+            .filter_map(|variant| get_config(variant, network).map(|config| (variant, config)))
+            .collect(),
+    }
+}
+
 // support the [LOGIC] stakepool
 pub const PREPROD_STAKE_HASH: [u8; 28] =
     hex!("86c769419aaa673c963da04e4b5bae448d490e2ceac902cb82e4da76");
@@ -82,6 +140,9 @@ pub const PREPROD_COLLATERAL_UTXO: [u8; 32] =
 pub const MAINNET_COLLATERAL_UTXO: [u8; 32] =
     hex!("e62351eacbdd001aee77a91805840d2b81f77feebbf2439fb01b79e76c42c839");
 
+// the least lovelace a collateral utxo picked from a wallet should hold
+pub const COLLATERAL_LOVELACE: u64 = 5_000_000;
+
 // ADA Handle Policy Ids
 pub const ADA_HANDLE_POLICY_ID: &str = "f0ff48bbb7bbe9d59a40f1ce90e9e9d0ff5002ec48f232b49ca0fb9a";
 
@@ -89,35 +150,5 @@ pub const ADA_HANDLE_POLICY_ID: &str = "f0ff48bbb7bbe9d59a40f1ce90e9e9d0ff5002ec
 pub const MAXIMUM_WALLET_UTXOS: u64 = 20;
 pub const MAXIMUM_TOKENS_PER_UTXO: u64 = 20;
 
-// protocol parameters
+// the fixed size the ledger adds to every output, the rest of the parameters come from the chain
 pub const OVERHEAD_COST: u64 = 160;
-pub const UTXO_COST_PER_BYTE: u64 = 4_310;
-
-pub const MEM_COST_NUMERATOR: u64 = 577;
-pub const MEM_COST_DENOMINATOR: u64 = 10_000;
-pub const CPU_COST_NUMERATOR: u64 = 721;
-pub const CPU_COST_DENOMINATOR: u64 = 10_000_000;
-
-pub fn plutus_v3_cost_model() -> Vec<i64> {
-    vec![
-        100788, 420, 1, 1, 1000, 173, 0, 1, 1000, 59957, 4, 1, 11183, 32, 201305, 8356, 4, 16000,
-        100, 16000, 100, 16000, 100, 16000, 100, 16000, 100, 16000, 100, 100, 100, 16000, 100,
-        94375, 32, 132994, 32, 61462, 4, 72010, 178, 0, 1, 22151, 32, 91189, 769, 4, 2, 85848,
-        123203, 7305, -900, 1716, 549, 57, 85848, 0, 1, 1, 1000, 42921, 4, 2, 24548, 29498, 38, 1,
-        898148, 27279, 1, 51775, 558, 1, 39184, 1000, 60594, 1, 141895, 32, 83150, 32, 15299, 32,
-        76049, 1, 13169, 4, 22100, 10, 28999, 74, 1, 28999, 74, 1, 43285, 552, 1, 44749, 541, 1,
-        33852, 32, 68246, 32, 72362, 32, 7243, 32, 7391, 32, 11546, 32, 85848, 123203, 7305, -900,
-        1716, 549, 57, 85848, 0, 1, 90434, 519, 0, 1, 74433, 32, 85848, 123203, 7305, -900, 1716,
-        549, 57, 85848, 0, 1, 1, 85848, 123203, 7305, -900, 1716, 549, 57, 85848, 0, 1, 955506,
-        213312, 0, 2, 270652, 22588, 4, 1457325, 64566, 4, 20467, 1, 4, 0, 141992, 32, 100788, 420,
-        1, 1, 81663, 32, 59498, 32, 20142, 32, 24588, 32, 20744, 32, 25933, 32, 24623, 32,
-        43053543, 10, 53384111, 14333, 10, 43574283, 26308, 10, 16000, 100, 16000, 100, 962335, 18,
-        2780678, 6, 442008, 1, 52538055, 3756, 18, 267929, 18, 76433006, 8868, 18, 52948122, 18,
-        1995836, 36, 3227919, 12, 901022, 1, 166917843, 4307, 36, 284546, 36, 158221314, 26549, 36,
-        74698472, 36, 333849714, 1, 254006273, 72, 2174038, 72, 2261318, 64571, 4, 207616, 8310, 4,
-        1293828, 28716, 63, 0, 1, 1006041, 43623, 251, 0, 1, 100181, 726, 719, 0, 1, 100181, 726,
-        719, 0, 1, 100181, 726, 719, 0, 1, 107878, 680, 0, 1, 95336, 1, 281145, 18848, 0, 1,
-        180194, 159, 1, 1, 158519, 8942, 0, 1, 159378, 8813, 0, 1, 107490, 3298, 1, 106057, 655, 1,
-        1964219, 24520, 3,
-    ]
-}
@@ -0,0 +1,303 @@
+use crate::address;
+use crate::transaction::{extract_budgets, reference_utxo};
+use anyhow::{Context, Result, anyhow, bail};
+use pallas_addresses::Address;
+use pallas_codec::minicbor::{Decoder, Encoder};
+use pallas_crypto::hash::Hash;
+use pallas_primitives::Fragment;
+use pallas_primitives::conway::TransactionInput;
+use pallas_txbuilder::{Input, Output, ScriptKind};
+use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+use uplc::tx::eval_phase_two_raw;
+
+/// Where the execution units for a transaction's scripts come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Evaluation {
+    /// Run the scripts in-process with the resolved inputs, nothing leaves the machine.
+    #[default]
+    Local,
+    /// Send the draft transaction to the chain provider's evaluator.
+    Remote,
+}
+
+/// Environment variable selecting the evaluation, `local` or `remote`.
+pub const EVALUATION_ENV: &str = "SEEDELF_EVALUATION";
+
+impl FromStr for Evaluation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "local" => Ok(Evaluation::Local),
+            "remote" => Ok(Evaluation::Remote),
+            other => Err(format!(
+                "Unknown Evaluation: {other}, expected local or remote"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Evaluation::Local => write!(f, "local"),
+            Evaluation::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+impl Evaluation {
+    /// Reads `SEEDELF_EVALUATION`, defaulting to local evaluation.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var(EVALUATION_ENV) {
+            Ok(name) if !name.trim().is_empty() => name.parse(),
+            _ => Ok(Evaluation::default()),
+        }
+    }
+}
+
+/// The (zero_time, zero_slot, slot_length) of the Shelley era, used for validity ranges.
+const MAINNET_SLOT_CONFIG: (u64, u64, u32) = (1_596_059_091_000, 4_492_800, 1_000);
+const PREPROD_SLOT_CONFIG: (u64, u64, u32) = (1_655_769_600_000, 86_400, 1_000);
+
+/// Converts a UTxO in the Koios shape into the input and output it resolves to.
+///
+/// # Arguments
+///
+/// * `utxo` - The UTxO being spent or referenced.
+///
+/// # Returns
+///
+/// * `Ok((Input, Output))` - The input and the output it points at.
+/// * `Err(anyhow::Error)` - If the address, value, assets or datum are malformed.
+pub fn resolve_utxo(utxo: &UtxoResponse) -> Result<(Input, Output)> {
+    let tx_hash: [u8; 32] = hex::decode(&utxo.tx_hash)
+        .context("Invalid Transaction Hash")?
+        .try_into()
+        .map_err(|_| anyhow!("Transaction Hash Is Not 32 Bytes"))?;
+    let input: Input = Input::new(Hash::new(tx_hash), utxo.tx_index);
+
+    let addr: Address = Address::from_bech32(&utxo.address).context("Invalid UTxO Address")?;
+    let lovelace: u64 = utxo.value.parse().context("Invalid UTxO Value")?;
+    let mut output: Output = Output::new(addr, lovelace);
+
+    for asset in utxo.asset_list.iter().flatten() {
+        let policy_id: [u8; 28] = hex::decode(&asset.policy_id)
+            .context("Invalid Policy Id")?
+            .try_into()
+            .map_err(|_| anyhow!("Policy Id Is Not 28 Bytes"))?;
+        output = output
+            .add_asset(
+                Hash::new(policy_id),
+                hex::decode(&asset.asset_name).context("Invalid Asset Name")?,
+                asset.quantity.parse().context("Invalid Asset Amount")?,
+            )
+            .context("Failed To Add Asset")?;
+    }
+
+    // a datum resolved from its hash stays a hash on the output
+    if let Some(datum) = utxo.inline_datum.as_ref().filter(|_| !utxo.datum_resolved) {
+        output = output.set_inline_datum(hex::decode(&datum.bytes).context("Invalid Datum")?);
+    } else if let Some(datum_hash) = &utxo.datum_hash {
+        let datum_hash: [u8; 32] = hex::decode(datum_hash)
+            .context("Invalid Datum Hash")?
+            .try_into()
+            .map_err(|_| anyhow!("Datum Hash Is Not 32 Bytes"))?;
+        output = output.set_datum_hash(Hash::new(datum_hash));
+    }
+
+    if let Some(script) = &utxo.reference_script {
+        let kind: Option<ScriptKind> = match script.get("type").and_then(Value::as_str) {
+            Some("plutusV1") => Some(ScriptKind::PlutusV1),
+            Some("plutusV2") => Some(ScriptKind::PlutusV2),
+            Some("plutusV3") => Some(ScriptKind::PlutusV3),
+            _ => None,
+        };
+        if let (Some(kind), Some(bytes)) = (kind, script.get("bytes").and_then(Value::as_str)) {
+            output = output.set_inline_script(kind, hex::decode(bytes).context("Invalid Script")?);
+        }
+    }
+
+    Ok((input, output))
+}
+
+/// Builds the reference input holding a compiled contract.
+///
+/// Only the script on the reference output matters to the evaluation, so the
+/// address and lovelace are placeholders.
+///
+/// # Arguments
+///
+/// * `reference` - The transaction hash of the reference UTxO.
+/// * `contract_cbor` - The `cborHex` of the compiled contract, as found in its `.plutus` file.
+///
+/// # Returns
+///
+/// * `Ok((Input, Output))` - The reference input and an output carrying the script.
+/// * `Err(anyhow::Error)` - If the contract is not valid hex wrapped CBOR.
+pub fn resolve_reference(reference: [u8; 32], contract_cbor: &str) -> Result<(Input, Output)> {
+    let envelope: Vec<u8> = hex::decode(contract_cbor).context("Invalid Contract Hex")?;
+    // the .plutus files wrap the script bytes one more time
+    let script: Vec<u8> = Decoder::new(&envelope)
+        .bytes()
+        .map_err(|e| anyhow!("Invalid Contract CBOR: {e}"))?
+        .to_vec();
+    let output: Output = Output::new(address::dummy_base_address(), 20_000_000)
+        .set_inline_script(ScriptKind::PlutusV3, script);
+    Ok((reference_utxo(reference), output))
+}
+
+/// Resolves the spent UTxOs and the contract reference inputs of a transaction.
+///
+/// # Arguments
+///
+/// * `utxos` - The UTxOs being spent.
+/// * `references` - Each reference UTxO with the `cborHex` of the contract it holds.
+///
+/// # Returns
+///
+/// * `Ok(Vec<(Input, Output)>)` - Every input the evaluation needs.
+/// * `Err(anyhow::Error)` - If any of them can not be resolved.
+pub fn resolve_inputs(
+    utxos: &[UtxoResponse],
+    references: &[([u8; 32], &str)],
+) -> Result<Vec<(Input, Output)>> {
+    let mut resolved: Vec<(Input, Output)> =
+        utxos.iter().map(resolve_utxo).collect::<Result<_>>()?;
+    for (reference, contract_cbor) in references {
+        resolved.push(resolve_reference(*reference, contract_cbor)?);
+    }
+    Ok(resolved)
+}
+
+/// Evaluates every script in a transaction in-process.
+///
+/// The budgets come back in the same order and shape as [`extract_budgets`]:
+/// spends first, then mints, each ordered by redeemer index.
+///
+/// # Arguments
+///
+/// * `tx_bytes` - The draft transaction.
+/// * `resolved` - Every spent and referenced input with the output it points at.
+/// * `params` - The protocol parameters providing the cost model and transaction budget.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod/Testnet.
+///     - `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `Ok(Vec<(u64, u64)>)` - The `(cpu, mem)` budget of every redeemer.
+/// * `Err(anyhow::Error)` - If an input is missing or a script fails.
+pub fn evaluate_locally(
+    tx_bytes: &[u8],
+    resolved: &[(Input, Output)],
+    params: &ProtocolParameters,
+    network_flag: bool,
+) -> Result<Vec<(u64, u64)>> {
+    let mut utxos: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+    for (input, output) in resolved {
+        let input_cbor: Vec<u8> = TransactionInput {
+            transaction_id: Hash::new(input.tx_hash.0),
+            index: input.txo_index,
+        }
+        .encode_fragment()
+        .map_err(|e| anyhow!("Failed To Encode Input: {e}"))?;
+        let output_cbor: Vec<u8> = output
+            .build_babbage_raw()
+            .context("Failed To Construct Babbage CBOR")?
+            .encode_fragment()
+            .map_err(|e| anyhow!("Failed To Encode Output: {e}"))?;
+        utxos.push((input_cbor, output_cbor));
+    }
+
+    // the cost models are keyed by language, plutus v3 is 2
+    let mut cost_models: Encoder<Vec<u8>> = Encoder::new(Vec::new());
+    cost_models
+        .map(1)
+        .and_then(|e| e.u8(2))
+        .and_then(|e| e.array(params.plutus_v3_cost_model.len() as u64))
+        .map_err(|e| anyhow!("Failed To Encode Cost Models: {e}"))?;
+    for cost in &params.plutus_v3_cost_model {
+        cost_models
+            .i64(*cost)
+            .map_err(|e| anyhow!("Failed To Encode Cost Models: {e}"))?;
+    }
+
+    let slot_config: (u64, u64, u32) = if network_flag {
+        PREPROD_SLOT_CONFIG
+    } else {
+        MAINNET_SLOT_CONFIG
+    };
+
+    let results = eval_phase_two_raw(
+        tx_bytes,
+        &utxos,
+        Some(&cost_models.into_writer()),
+        (
+            params.max_tx_execution_steps,
+            params.max_tx_execution_memory,
+        ),
+        slot_config,
+        false,
+        |_| (),
+    )
+    .map_err(|e| anyhow!("Script Evaluation Failed: {e}"))?;
+
+    let mut budgets: Vec<((u8, u32), (u64, u64))> = Vec::new();
+    for (redeemer, result) in results {
+        // a redeemer is [tag, index, data, ex_units]
+        let mut d: Decoder = Decoder::new(&redeemer);
+        d.array().map_err(|e| anyhow!("Invalid Redeemer: {e}"))?;
+        let tag: u8 = d.u8().map_err(|e| anyhow!("Invalid Redeemer: {e}"))?;
+        let index: u32 = d.u32().map_err(|e| anyhow!("Invalid Redeemer: {e}"))?;
+        let cost = result.cost();
+        budgets.push(((tag, index), (cost.cpu as u64, cost.mem as u64)));
+    }
+    budgets.sort_by_key(|(pointer, _)| *pointer);
+    Ok(budgets.into_iter().map(|(_, budget)| budget).collect())
+}
+
+/// Gets the execution units of a draft transaction, locally or from the provider.
+///
+/// # Arguments
+///
+/// * `provider` - The chain data backend, only used for `Evaluation::Remote`.
+/// * `tx_bytes` - The draft transaction.
+/// * `resolved` - Every spent and referenced input with the output it points at.
+/// * `params` - The protocol parameters providing the cost model and transaction budget.
+/// * `network_flag` - A boolean flag specifying the network:
+///     - `true` for Preprod/Testnet.
+///     - `false` for Mainnet.
+/// * `evaluation` - Where the scripts are run.
+///
+/// # Returns
+///
+/// * `Ok(Vec<(u64, u64)>)` - The `(cpu, mem)` budget of every redeemer.
+/// * `Err(anyhow::Error)` - If the evaluation fails or is rejected.
+pub async fn evaluate_transaction(
+    provider: &impl ChainProvider,
+    tx_bytes: &[u8],
+    resolved: &[(Input, Output)],
+    params: &ProtocolParameters,
+    network_flag: bool,
+    evaluation: Evaluation,
+) -> Result<Vec<(u64, u64)>> {
+    match evaluation {
+        Evaluation::Local => evaluate_locally(tx_bytes, resolved, params, network_flag),
+        Evaluation::Remote => {
+            let execution_units: Value = provider
+                .evaluate_transaction(hex::encode(tx_bytes))
+                .await
+                .context("Failed To Evaluate Transaction")?;
+            if let Some(error) = execution_units.get("error") {
+                bail!("{error}");
+            }
+            Ok(extract_budgets(&execution_units))
+        }
+    }
+}
@@ -1,6 +1,9 @@
 pub mod address;
 pub mod assets;
+pub mod collateral;
 pub mod constants;
 pub mod data_structures;
+pub mod evaluate;
+pub mod lifecycle;
 pub mod transaction;
 pub mod utxos;
@@ -0,0 +1,163 @@
+use anyhow::{Context, Result, bail};
+use seedelf_koios::koios::TxStatus;
+use seedelf_koios::provider::ChainProvider;
+use std::time::{Duration, Instant};
+
+/// Confirmations after which a transaction is treated as final.
+pub const DEFAULT_CONFIRMATION_DEPTH: u64 = 10;
+
+/// Time between two status queries.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(20);
+
+/// How long a transaction may go unseen before the tracker gives up on it.
+pub const DEFAULT_INCLUSION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Where a submitted transaction stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxState {
+    /// Submitted but not in a block yet.
+    Pending,
+    /// In a block, with this many confirmations.
+    Confirmed(u64),
+    /// Reached the requested depth.
+    Final(u64),
+    /// It was in a block but the chain no longer has it.
+    RolledBack,
+}
+
+/// Follows a submitted transaction until it is deep enough in the chain.
+///
+/// A rollback is noticed when a transaction that had confirmations is no longer
+/// found. The wallet validator ties every spend to the one-time `vkh` of the
+/// transaction, so a dropped transaction can not be replayed in another shape, but
+/// its identical signed CBOR stays valid while the inputs are unspent and can be
+/// resubmitted with [`TxTracker::resubmit`].
+#[derive(Debug, Clone)]
+pub struct TxTracker {
+    pub tx_hash: String,
+    /// The signed transaction, hex encoded.
+    pub tx_cbor: String,
+    /// Confirmations required before the transaction is final.
+    pub depth: u64,
+    pub poll_interval: Duration,
+    /// How long the transaction may stay out of a block.
+    pub inclusion_timeout: Duration,
+    /// The most confirmations seen since the last submission.
+    highest: u64,
+    submitted_at: Instant,
+}
+
+impl TxTracker {
+    pub fn new(tx_hash: String, tx_cbor: String) -> Self {
+        TxTracker {
+            tx_hash,
+            tx_cbor,
+            depth: DEFAULT_CONFIRMATION_DEPTH,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            inclusion_timeout: DEFAULT_INCLUSION_TIMEOUT,
+            highest: 0,
+            submitted_at: Instant::now(),
+        }
+    }
+
+    pub fn with_depth(mut self, depth: u64) -> Self {
+        self.depth = depth.max(1);
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn with_inclusion_timeout(mut self, inclusion_timeout: Duration) -> Self {
+        self.inclusion_timeout = inclusion_timeout;
+        self
+    }
+
+    /// Turns a confirmation count into a state, remembering what was seen before.
+    ///
+    /// # Arguments
+    ///
+    /// * `confirmations` - The confirmations reported for the transaction, `None` if unknown.
+    ///
+    /// # Returns
+    ///
+    /// * `TxState` - The state of the transaction.
+    pub fn observe(&mut self, confirmations: Option<u64>) -> TxState {
+        match confirmations.filter(|n| *n > 0) {
+            Some(n) => {
+                self.highest = self.highest.max(n);
+                if n >= self.depth {
+                    TxState::Final(n)
+                } else {
+                    TxState::Confirmed(n)
+                }
+            }
+            None if self.highest > 0 => TxState::RolledBack,
+            None => TxState::Pending,
+        }
+    }
+
+    /// Queries the transaction once.
+    pub async fn poll(&mut self, provider: &impl ChainProvider) -> Result<TxState> {
+        let status: Vec<TxStatus> = provider
+            .transaction_status(self.tx_hash.clone())
+            .await
+            .context("Failed To Get Transaction Status")?;
+        let confirmations: Option<u64> = status
+            .iter()
+            .find(|s| s.tx_hash == self.tx_hash)
+            .or(status.first())
+            .and_then(|s| s.num_confirmations);
+        Ok(self.observe(confirmations))
+    }
+
+    /// Polls until the transaction is final or rolled back.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The chain data backend.
+    /// * `on_change` - Called with every new state, e.g. to show progress.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TxState)` - Either `TxState::Final` or `TxState::RolledBack`.
+    /// * `Err(anyhow::Error)` - If the status can not be read or the transaction never lands.
+    pub async fn follow(
+        &mut self,
+        provider: &impl ChainProvider,
+        mut on_change: impl FnMut(TxState),
+    ) -> Result<TxState> {
+        let mut last: Option<TxState> = None;
+        loop {
+            let state: TxState = self.poll(provider).await?;
+            if last != Some(state) {
+                on_change(state);
+                last = Some(state);
+            }
+            match state {
+                TxState::Final(_) | TxState::RolledBack => return Ok(state),
+                TxState::Pending if self.submitted_at.elapsed() > self.inclusion_timeout => {
+                    bail!(
+                        "Transaction {} Not On Chain After {} Seconds",
+                        self.tx_hash,
+                        self.inclusion_timeout.as_secs()
+                    )
+                }
+                _ => tokio::time::sleep(self.poll_interval).await,
+            }
+        }
+    }
+
+    /// Submits the identical signed transaction again, e.g. after a rollback.
+    pub async fn resubmit(&mut self, provider: &impl ChainProvider) -> Result<()> {
+        provider
+            .submit_tx(self.tx_cbor.clone())
+            .await
+            .context("Resubmission Failed")?;
+        self.highest = 0;
+        self.submitted_at = Instant::now();
+        Ok(())
+    }
+}
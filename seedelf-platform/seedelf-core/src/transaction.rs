@@ -1,9 +1,6 @@
 use crate::address;
 use crate::assets::Assets;
-use crate::constants::{
-    CPU_COST_DENOMINATOR, CPU_COST_NUMERATOR, MAINNET_COLLATERAL_UTXO, MEM_COST_DENOMINATOR,
-    MEM_COST_NUMERATOR, OVERHEAD_COST, PREPROD_COLLATERAL_UTXO, UTXO_COST_PER_BYTE,
-};
+use crate::constants::{MAINNET_COLLATERAL_UTXO, OVERHEAD_COST, PREPROD_COLLATERAL_UTXO};
 use anyhow::{Context, Result, anyhow};
 use hex_literal::hex;
 use pallas_addresses::Address;
@@ -11,6 +8,8 @@ use pallas_crypto::hash::Hash;
 use pallas_primitives::Fragment;
 use pallas_txbuilder::{Input, Output};
 use seedelf_crypto::{register::Register, schnorr};
+use seedelf_koios::koios::UtxoResponse;
+use seedelf_koios::parameters::ProtocolParameters;
 use serde_json::Value;
 
 /// Calculates the minimum required UTXO for a given output.
@@ -22,11 +21,12 @@ use serde_json::Value;
 /// # Arguments
 ///
 /// * `output` - An `Output` representing the transaction output.
+/// * `params` - The protocol parameters providing the cost per byte.
 ///
 /// # Returns
 ///
 /// * `u64` - The minimum required UTXO value in lovelace.
-pub fn calculate_min_required_utxo(output: Output) -> Result<u64> {
+pub fn calculate_min_required_utxo(output: Output, params: &ProtocolParameters) -> Result<u64> {
     // we need the output in the post alonzo form so we can encode it
     let output_cbor_length: u64 = output
         .build_babbage_raw()
@@ -37,7 +37,7 @@ pub fn calculate_min_required_utxo(output: Output) -> Result<u64> {
         .try_into()
         .context("Failed To Get CBOR Length")?;
     // sum the overhead and length times the cost per byte
-    Ok((OVERHEAD_COST + output_cbor_length) * UTXO_COST_PER_BYTE)
+    Ok((OVERHEAD_COST + output_cbor_length) * params.coins_per_utxo_byte)
 }
 
 /// Creates a collateral input for a transaction based on the network.
@@ -121,20 +121,19 @@ pub fn seedelf_token_name(label: String, inputs: Option<&Vec<Input>>) -> Result<
 /// Computes the computation fee for a transaction.
 ///
 /// This function calculates the total computation fee based on the memory and CPU units consumed.
-/// It applies a cost model where memory and CPU costs are scaled using pre-defined numerators
-/// and denominators.
+/// Memory and CPU units are priced with the execution unit prices from the protocol parameters.
 ///
 /// # Arguments
 ///
 /// * `mem_units` - The number of memory units consumed.
 /// * `cpu_units` - The number of CPU units consumed.
+/// * `params` - The protocol parameters providing the execution unit prices.
 ///
 /// # Returns
 ///
 /// * `u64` - The total computation fee as a sum of the memory and CPU costs.
-pub fn computation_fee(mem_units: u64, cpu_units: u64) -> u64 {
-    (MEM_COST_NUMERATOR * mem_units / MEM_COST_DENOMINATOR)
-        + (CPU_COST_NUMERATOR * cpu_units / CPU_COST_DENOMINATOR)
+pub fn computation_fee(mem_units: u64, cpu_units: u64, params: &ProtocolParameters) -> u64 {
+    params.script_fee(mem_units, cpu_units)
 }
 
 /// Extracts CPU and memory budgets from a JSON value.
@@ -183,14 +182,15 @@ pub fn extract_budgets(value: &Value) -> Vec<(u64, u64)> {
 /// * `budgets` - A vector of tuples where each tuple contains:
 ///     - `u64` - CPU units.
 ///     - `u64` - Memory units.
+/// * `params` - The protocol parameters providing the execution unit prices.
 ///
 /// # Returns
 ///
 /// * `u64` - The total computation fee for all provided budgets.
-pub fn total_computation_fee(budgets: Vec<(u64, u64)>) -> u64 {
+pub fn total_computation_fee(budgets: Vec<(u64, u64)>, params: &ProtocolParameters) -> u64 {
     let mut fee: u64 = 0;
     for (cpu, mem) in budgets.into_iter() {
-        fee += computation_fee(mem, cpu);
+        fee += computation_fee(mem, cpu, params);
     }
     fee
 }
@@ -205,10 +205,14 @@ pub fn total_computation_fee(budgets: Vec<(u64, u64)>) -> u64 {
 /// The function then calculates the minimum required lovelace using the
 /// `calculate_min_required_utxo` function.
 ///
+/// # Arguments
+///
+/// * `params` - The protocol parameters providing the cost per byte.
+///
 /// # Returns
 ///
 /// * `u64` - The minimum lovelace required for the transaction output.
-pub fn seedelf_minimum_lovelace() -> Result<u64> {
+pub fn seedelf_minimum_lovelace(params: &ProtocolParameters) -> Result<u64> {
     // a very long token name
     let token_name: Vec<u8> = [
         94, 237, 14, 31, 1, 66, 250, 134, 20, 230, 198, 12, 121, 19, 73, 107, 154, 156, 226, 154,
@@ -228,7 +232,7 @@ pub fn seedelf_minimum_lovelace() -> Result<u64> {
         .context("Staging Output Failed")?;
 
     // use the staging output to calculate the minimum required lovelace
-    calculate_min_required_utxo(staging_output)
+    calculate_min_required_utxo(staging_output, params)
 }
 
 /// Calculates the minimum lovelace required for a wallet transaction output with assets.
@@ -243,11 +247,15 @@ pub fn seedelf_minimum_lovelace() -> Result<u64> {
 /// # Arguments
 ///
 /// * `tokens` - An `Assets` struct containing a list of assets (policy ID, token name, and amount).
+/// * `params` - The protocol parameters providing the cost per byte.
 ///
 /// # Returns
 ///
 /// * `u64` - The minimum lovelace required for the transaction output.
-pub fn wallet_minimum_lovelace_with_assets(tokens: Assets) -> Result<u64> {
+pub fn wallet_minimum_lovelace_with_assets(
+    tokens: Assets,
+    params: &ProtocolParameters,
+) -> Result<u64> {
     let mut staging_output: Output = Output::new(address::dummy_base_address(), 5_000_000)
         .set_inline_datum(
             Register::create(schnorr::random_scalar())
@@ -264,7 +272,7 @@ pub fn wallet_minimum_lovelace_with_assets(tokens: Assets) -> Result<u64> {
     }
 
     // use the staging output to calculate the minimum required lovelace
-    calculate_min_required_utxo(staging_output)
+    calculate_min_required_utxo(staging_output, params)
 }
 
 /// Calculates the minimum lovelace required for a given address with assets.
@@ -277,11 +285,16 @@ pub fn wallet_minimum_lovelace_with_assets(tokens: Assets) -> Result<u64> {
 ///
 /// * `address` - A string slice containing the Bech32-encoded address.
 /// * `tokens` - An `Assets` struct containing a list of assets (policy ID, token name, and amount).
+/// * `params` - The protocol parameters providing the cost per byte.
 ///
 /// # Returns
 ///
 /// * `u64` - The minimum lovelace required for the transaction output.
-pub fn address_minimum_lovelace_with_assets(address: &str, tokens: Assets) -> Result<u64> {
+pub fn address_minimum_lovelace_with_assets(
+    address: &str,
+    tokens: Assets,
+    params: &ProtocolParameters,
+) -> Result<u64> {
     let addr: Address =
         Address::from_bech32(address).context("Address Failed To Convert To Bech32")?;
     let mut staging_output: Output = Output::new(addr, 5_000_000);
@@ -293,5 +306,28 @@ pub fn address_minimum_lovelace_with_assets(address: &str, tokens: Assets) -> Re
     }
 
     // use the staging output to calculate the minimum required lovelace
-    calculate_min_required_utxo(staging_output)
+    calculate_min_required_utxo(staging_output, params)
+}
+
+/// The datum a spent UTxO has to bring in the witness set.
+///
+/// Only a datum resolved from a datum hash is needed, an inline datum already
+/// sits on the output.
+///
+/// # Arguments
+///
+/// * `utxo` - The UTxO being spent.
+///
+/// # Returns
+///
+/// * `Ok(Some(Vec<u8>))` - The datum CBOR for a datum hash output.
+/// * `Ok(None)` - If the datum is inline or missing.
+/// * `Err(anyhow::Error)` - If the resolved datum is not valid hex.
+pub fn witness_datum(utxo: &UtxoResponse) -> Result<Option<Vec<u8>>> {
+    match &utxo.inline_datum {
+        Some(datum) if utxo.datum_resolved => Ok(Some(
+            hex::decode(&datum.bytes).context("Invalid Resolved Datum")?,
+        )),
+        _ => Ok(None),
+    }
 }
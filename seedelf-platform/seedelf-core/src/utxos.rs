@@ -3,25 +3,169 @@ use crate::constants::{MAXIMUM_TOKENS_PER_UTXO, MAXIMUM_WALLET_UTXOS};
 use crate::transaction::wallet_minimum_lovelace_with_assets;
 use anyhow::{Context, Ok, Result, anyhow, bail};
 use blstrs::Scalar;
+use futures_util::TryStreamExt;
 use hex;
 use seedelf_crypto::register::Register;
-use seedelf_koios::koios::{
-    UtxoResponse, address_utxos, contains_policy_id, credential_utxos, extract_bytes_with_logging,
-};
+use seedelf_koios::datum::resolve_datum_hashes;
+use seedelf_koios::koios::{UtxoResponse, contains_policy_id, extract_bytes_with_logging};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
+use std::pin::pin;
 
 pub async fn get_credential_utxos(
+    provider: &impl ChainProvider,
     wallet_contract_hash: [u8; 28],
-    network_flag: bool,
 ) -> Result<Vec<UtxoResponse>> {
-    let utxos: Vec<UtxoResponse> =
-        credential_utxos(hex::encode(wallet_contract_hash).as_str(), network_flag)
-            .await
-            .context("Failed To Get Credential UTxOs")?;
+    let utxos: Vec<UtxoResponse> = provider
+        .credential_utxos(hex::encode(wallet_contract_hash).as_str())
+        .await
+        .context("Failed To Get Credential UTxOs")?;
     Ok(utxos)
 }
 
-pub async fn get_address_utxos(address: &str, network_flag: bool) -> Result<Vec<UtxoResponse>> {
-    let utxos: Vec<UtxoResponse> = address_utxos(address, network_flag)
+/// Feeds each page of UTxOs at the wallet contract to `visit` until it returns `false`.
+///
+/// Only one page is held at a time, so the ownership checks below never need the
+/// whole contract in memory. UTxOs holding only a datum hash get their datum
+/// resolved first, a backend that can not resolve them leaves them unreadable.
+///
+/// # Arguments
+///
+/// * `provider` - The chain data backend.
+/// * `wallet_contract_hash` - The payment credential of the wallet contract.
+/// * `visit` - Called with every page, returning `Ok(false)` stops the fetch early.
+///
+/// # Returns
+///
+/// * `Ok(())` - Once every page was visited or `visit` asked to stop.
+/// * `Err(anyhow::Error)` - If a page could not be fetched or `visit` failed.
+pub async fn for_each_credential_page(
+    provider: &impl ChainProvider,
+    wallet_contract_hash: [u8; 28],
+    mut visit: impl FnMut(Vec<UtxoResponse>) -> Result<bool>,
+) -> Result<()> {
+    let payment_credential: String = hex::encode(wallet_contract_hash);
+    let mut pages = pin!(provider.credential_utxo_pages(&payment_credential));
+    let mut warned: bool = false;
+    while let Some(mut page) = pages
+        .try_next()
+        .await
+        .context("Failed To Get Credential UTxOs")?
+    {
+        if let Err(err) = resolve_datum_hashes(provider, &mut page).await
+            && !warned
+        {
+            eprintln!("Warning: Datum Hashes Were Not Resolved: {err}");
+            warned = true;
+        }
+        if !visit(page)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Streams the wallet contract for the utxos owned by some scalar, see [`collect_wallet_utxos`].
+pub async fn scan_wallet_utxos(
+    provider: &impl ChainProvider,
+    wallet_contract_hash: [u8; 28],
+    sk: Scalar,
+    seedelf_policy_id: &str,
+) -> Result<Vec<UtxoResponse>> {
+    let mut usable_utxos: Vec<UtxoResponse> = Vec::new();
+    for_each_credential_page(provider, wallet_contract_hash, |page| {
+        usable_utxos.extend(collect_wallet_utxos(sk, seedelf_policy_id, page)?);
+        // stop fetching once a single tx can not spend any more
+        usable_utxos.truncate(MAXIMUM_WALLET_UTXOS as usize);
+        Ok(usable_utxos.len() < MAXIMUM_WALLET_UTXOS as usize)
+    })
+    .await?;
+    Ok(usable_utxos)
+}
+
+/// Streams the wallet contract for every utxo owned by some scalar, see [`collect_all_wallet_utxos`].
+pub async fn scan_all_wallet_utxos(
+    provider: &impl ChainProvider,
+    wallet_contract_hash: [u8; 28],
+    sk: Scalar,
+    seedelf_policy_id: &str,
+) -> Result<Vec<UtxoResponse>> {
+    let mut all_utxos: Vec<UtxoResponse> = Vec::new();
+    for_each_credential_page(provider, wallet_contract_hash, |page| {
+        all_utxos.extend(collect_all_wallet_utxos(sk, seedelf_policy_id, page)?);
+        Ok(true)
+    })
+    .await?;
+    Ok(all_utxos)
+}
+
+/// Streams the wallet contract for a specific seedelf, see [`find_seedelf_utxo`].
+pub async fn scan_seedelf_utxo(
+    provider: &impl ChainProvider,
+    wallet_contract_hash: [u8; 28],
+    seedelf: &str,
+    seedelf_policy_id: &str,
+) -> Result<Option<UtxoResponse>> {
+    let mut found: Option<UtxoResponse> = None;
+    for_each_credential_page(provider, wallet_contract_hash, |page| {
+        found = find_seedelf_utxo(seedelf.to_string(), seedelf_policy_id, page)?;
+        Ok(found.is_none())
+    })
+    .await?;
+    Ok(found)
+}
+
+/// Streams the wallet contract once for the datums of some seedelfs.
+///
+/// # Arguments
+///
+/// * `provider` - The chain data backend.
+/// * `wallet_contract_hash` - The payment credential of the wallet contract.
+/// * `seedelfs` - The seedelf names to find the datums of.
+/// * `seedelf_policy_id` - The seedelf policy id.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Option<Register>>)` - The datum of each seedelf, in order.
+/// * `Err(anyhow::Error)` - If a page could not be fetched.
+pub async fn scan_seedelf_datums(
+    provider: &impl ChainProvider,
+    wallet_contract_hash: [u8; 28],
+    seedelfs: &[String],
+    seedelf_policy_id: &str,
+) -> Result<Vec<Option<Register>>> {
+    let mut seedelf_datums: Vec<Option<Register>> = vec![None; seedelfs.len()];
+    for_each_credential_page(provider, wallet_contract_hash, |page| {
+        for utxo in &page {
+            let asset_name: Option<&String> = utxo.asset_list.as_ref().and_then(|vec| {
+                vec.iter()
+                    .find(|asset| asset.policy_id == seedelf_policy_id)
+                    .map(|asset| &asset.asset_name)
+            });
+            let Some(asset_name) = asset_name else {
+                continue;
+            };
+            for (seedelf, datum) in seedelfs.iter().zip(seedelf_datums.iter_mut()) {
+                if seedelf == asset_name
+                    && let Some(register) = extract_bytes_with_logging(&utxo.inline_datum)
+                {
+                    *datum = Some(register);
+                }
+            }
+        }
+        // stop once every receiver is found
+        Ok(seedelf_datums.iter().any(Option::is_none))
+    })
+    .await?;
+    Ok(seedelf_datums)
+}
+
+pub async fn get_address_utxos(
+    provider: &impl ChainProvider,
+    address: &str,
+) -> Result<Vec<UtxoResponse>> {
+    let utxos: Vec<UtxoResponse> = provider
+        .address_utxos(address)
         .await
         .context("Failed To Get Address UTxOs")?;
     Ok(utxos)
@@ -214,14 +358,16 @@ pub fn select(
     utxos: Vec<UtxoResponse>,
     lovelace: u64,
     tokens: Assets,
+    params: &ProtocolParameters,
 ) -> Result<Vec<UtxoResponse>> {
-    do_select(utxos, lovelace, tokens, lovelace).context("Do Select Failed")
+    do_select(utxos, lovelace, tokens, lovelace, params).context("Do Select Failed")
 }
 pub fn do_select(
     mut utxos: Vec<UtxoResponse>,
     lovelace: u64,
     tokens: Assets,
     lovelace_goal: u64,
+    params: &ProtocolParameters,
 ) -> Result<Vec<UtxoResponse>> {
     let mut selected_utxos: Vec<UtxoResponse> = Vec::new();
 
@@ -301,7 +447,7 @@ pub fn do_select(
                 .separate(tokens.clone())
                 .context("Can't Separate Assets")?;
             let number_of_change_assets: u64 = change_assets.len();
-            let minimum: u64 = wallet_minimum_lovelace_with_assets(change_assets.clone())
+            let minimum: u64 = wallet_minimum_lovelace_with_assets(change_assets.clone(), params)
                 .context("Invalid Minimum Lovelace")?;
             // we need to calculate how many multiple change utxos we need
             let multiplier: u64 = if number_of_change_assets > MAXIMUM_TOKENS_PER_UTXO {
@@ -322,6 +468,7 @@ pub fn do_select(
                     lovelace + multiplier * minimum,
                     tokens.clone(),
                     lovelace_goal,
+                    params,
                 );
             }
         }
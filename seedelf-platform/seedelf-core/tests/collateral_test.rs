@@ -0,0 +1,74 @@
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_primitives::Fragment;
+use pallas_primitives::conway::Tx;
+use pallas_txbuilder::{BuildConway, BuiltTransaction, Input, Output, StagingTransaction};
+use seedelf_core::collateral::{
+    Collateral, CollateralKind, pick_collateral_utxo, set_total_collateral,
+};
+use seedelf_koios::koios::{Asset, UtxoResponse};
+use seedelf_koios::parameters::ProtocolParameters;
+
+const KEY_ADDRESS: &str = "addr_test1qrwejm9pza929cedhwkcsprtgs8l2carehs8z6jkse2qp344c43tmm0md55r4ufmxknr24kq6jkvt6spq60edeuhtf4sn2scds";
+
+fn utxo(tx_index: u64, lovelace: &str, with_token: bool) -> UtxoResponse {
+    UtxoResponse {
+        tx_hash: "ab".repeat(32),
+        tx_index,
+        address: KEY_ADDRESS.to_string(),
+        value: lovelace.to_string(),
+        asset_list: with_token.then(|| {
+            vec![Asset {
+                policy_id: "cd".repeat(28),
+                asset_name: "acab".to_string(),
+                quantity: "1".to_string(),
+                ..Default::default()
+            }]
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn smallest_lovelace_only_utxo_is_the_collateral() {
+    let utxos: Vec<UtxoResponse> = vec![
+        utxo(0, "20000000", false),
+        utxo(1, "6000000", true),
+        utxo(2, "4000000", false),
+        utxo(3, "7000000", false),
+    ];
+    assert_eq!(pick_collateral_utxo(&utxos).unwrap().tx_index, 3);
+    assert!(pick_collateral_utxo(&utxos[1..3]).is_none());
+}
+
+#[test]
+fn collateral_return_leaves_the_percentage_at_stake() {
+    let collateral: Collateral =
+        Collateral::from_utxo(CollateralKind::External, &utxo(0, "5000000", false)).unwrap();
+    let params: ProtocolParameters = ProtocolParameters::default();
+    assert_eq!(collateral.total(200_001, &params), 300_002);
+    assert_eq!(
+        collateral.return_output(200_001, &params).lovelace,
+        5_000_000 - 300_002
+    );
+}
+
+#[test]
+fn total_collateral_is_set_on_the_body() {
+    let collateral: Collateral = Collateral::service(true, 5_000_000);
+    let params: ProtocolParameters = ProtocolParameters::default();
+    let draft: StagingTransaction = StagingTransaction::new()
+        .input(Input::new(Hash::new([1u8; 32]), 0))
+        .output(Output::new(collateral.address.clone(), 2_000_000))
+        .fee(200_000);
+    let tx: BuiltTransaction = collateral
+        .stage(draft, 200_000, &params)
+        .build_conway_raw()
+        .unwrap();
+
+    let patched: BuiltTransaction = set_total_collateral(tx, 300_000).unwrap();
+    let decoded: Tx = Tx::decode_fragment(&patched.tx_bytes.0).unwrap();
+    assert_eq!(decoded.transaction_body.total_collateral, Some(300_000));
+    assert!(decoded.transaction_body.collateral_return.is_some());
+    let body: Vec<u8> = decoded.transaction_body.encode_fragment().unwrap();
+    assert_eq!(patched.tx_hash.0, *Hasher::<256>::hash(&body));
+}
@@ -0,0 +1,13 @@
+use seedelf_core::constants::{VARIANT, scan_variants};
+
+#[test]
+fn every_registered_variant_is_scanned_by_default() {
+    let variants: Vec<u64> = scan_variants(None, true)
+        .into_iter()
+        .map(|(variant, _)| variant)
+        .collect();
+    assert_eq!(variants.first(), Some(&1));
+    assert_eq!(variants.last(), Some(&VARIANT));
+    assert_eq!(scan_variants(Some(VARIANT), false).len(), 1);
+    assert!(scan_variants(Some(0), false).is_empty());
+}
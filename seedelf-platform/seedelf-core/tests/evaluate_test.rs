@@ -0,0 +1,112 @@
+use pallas_crypto::hash::{Hash, Hasher};
+use pallas_txbuilder::{BuildConway, BuiltTransaction, ExUnits, Input, Output, StagingTransaction};
+use seedelf_core::address;
+use seedelf_core::constants::{Config, get_config};
+use seedelf_core::data_structures;
+use seedelf_core::evaluate::{evaluate_locally, resolve_reference, resolve_utxo};
+use seedelf_core::transaction::{collateral_input, reference_utxo};
+use seedelf_crypto::register::Register;
+use seedelf_crypto::schnorr::{create_proof, random_scalar};
+use seedelf_koios::koios::{InlineDatum, UtxoResponse};
+use seedelf_koios::parameters::ProtocolParameters;
+use serde_json::Value;
+
+fn contract_file(name: &str) -> String {
+    let path: String = format!(
+        "{}/../../seedelf-contracts/contracts/{name}.plutus",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let envelope: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    envelope["cborHex"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn bundled_contracts_match_the_compiled_scripts() {
+    let config: Config = get_config(1, true).unwrap();
+    assert_eq!(
+        config.contract.wallet_contract_cbor,
+        contract_file("wallet_contract")
+    );
+    assert_eq!(
+        config.contract.seedelf_contract_cbor,
+        contract_file("seedelf_contract")
+    );
+}
+
+/// Builds a wallet spend, proven with the owner's key or with a stranger's.
+fn draft_spend(proven: bool) -> (Vec<u8>, Vec<(Input, Output)>) {
+    let config: Config = get_config(1, true).unwrap();
+    let owner = random_scalar();
+    let register: Register = Register::create(owner).unwrap().rerandomize().unwrap();
+    let wallet_addr = address::wallet_contract(true, config.contract.wallet_contract_hash);
+
+    let utxo: UtxoResponse = UtxoResponse {
+        tx_hash: "ab".repeat(32),
+        tx_index: 0,
+        address: wallet_addr.to_bech32().unwrap(),
+        value: "5000000".to_string(),
+        inline_datum: Some(InlineDatum {
+            bytes: hex::encode(register.to_vec().unwrap()),
+            value: Value::Null,
+        }),
+        ..Default::default()
+    };
+    let (input, output) = resolve_utxo(&utxo).unwrap();
+
+    let pkh: Hash<28> = Hasher::<224>::hash(&[7u8; 32]);
+    let proving_key = if proven { owner } else { random_scalar() };
+    let (z, g_r) = create_proof(
+        register.clone(),
+        proving_key,
+        hex::encode(pkh),
+        random_scalar(),
+    )
+    .unwrap();
+    let redeemer: Vec<u8> =
+        data_structures::create_spend_redeemer(z, g_r, hex::encode(pkh)).unwrap();
+
+    let tx: BuiltTransaction = StagingTransaction::new()
+        .input(input.clone())
+        .output(Output::new(wallet_addr, 4_800_000).set_inline_datum(register.to_vec().unwrap()))
+        .fee(200_000)
+        .collateral_input(collateral_input(true))
+        .reference_input(reference_utxo(config.reference.wallet_reference_utxo))
+        .language_view(
+            pallas_txbuilder::ScriptKind::PlutusV3,
+            ProtocolParameters::default().plutus_v3_cost_model,
+        )
+        .disclosed_signer(pkh)
+        .add_spend_redeemer(
+            input.clone(),
+            redeemer,
+            Some(ExUnits {
+                mem: 14_000_000,
+                steps: 10_000_000_000,
+            }),
+        )
+        .build_conway_raw()
+        .unwrap();
+
+    let reference = resolve_reference(
+        config.reference.wallet_reference_utxo,
+        &config.contract.wallet_contract_cbor,
+    )
+    .unwrap();
+    (tx.tx_bytes.0, vec![(input, output), reference])
+}
+
+#[test]
+fn wallet_spend_evaluates_locally() {
+    let (tx, resolved) = draft_spend(true);
+    let budgets = evaluate_locally(&tx, &resolved, &ProtocolParameters::default(), true).unwrap();
+    assert_eq!(budgets.len(), 1);
+    let (cpu, mem) = budgets[0];
+    assert!(cpu > 0 && cpu < 10_000_000_000);
+    assert!(mem > 0 && mem < 14_000_000);
+}
+
+#[test]
+fn wallet_spend_with_a_bad_proof_fails() {
+    let (tx, resolved) = draft_spend(false);
+    assert!(evaluate_locally(&tx, &resolved, &ProtocolParameters::default(), true).is_err());
+}
@@ -0,0 +1,18 @@
+use seedelf_core::lifecycle::{TxState, TxTracker};
+
+#[test]
+fn confirmations_climb_to_the_requested_depth() {
+    let mut tracker: TxTracker = TxTracker::new("ab".repeat(32), String::new()).with_depth(3);
+    assert_eq!(tracker.observe(None), TxState::Pending);
+    assert_eq!(tracker.observe(Some(0)), TxState::Pending);
+    assert_eq!(tracker.observe(Some(1)), TxState::Confirmed(1));
+    assert_eq!(tracker.observe(Some(3)), TxState::Final(3));
+}
+
+#[test]
+fn a_confirmed_transaction_that_disappears_was_rolled_back() {
+    let mut tracker: TxTracker = TxTracker::new("ab".repeat(32), String::new()).with_depth(5);
+    assert_eq!(tracker.observe(Some(2)), TxState::Confirmed(2));
+    assert_eq!(tracker.observe(None), TxState::RolledBack);
+    assert_eq!(tracker.observe(Some(1)), TxState::Confirmed(1));
+}
@@ -1,12 +1,207 @@
+use pallas_crypto::hash::Hasher;
 use seedelf_core::assets::{Asset, Assets, string_to_u64};
+use seedelf_core::collateral::{Collateral, service_collateral};
+use seedelf_core::constants::{COLLATERAL_HASH, PREPROD_COLLATERAL_UTXO};
+use seedelf_core::transaction;
 use seedelf_core::utxos;
+use seedelf_crypto::register::Register;
+use seedelf_crypto::schnorr::random_scalar;
+use seedelf_koios::datum::cbor_to_json;
+use seedelf_koios::koios::{
+    BlockchainTip, Koios, ResolvedDatum, TxResponse, TxStatus, UtxoResponse,
+};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::ChainProvider;
+use serde_json::Value;
+use std::io;
+
+/// An in-memory provider that serves a fixed set of utxos and datums.
+struct StaticProvider {
+    utxos: Vec<UtxoResponse>,
+    datums: Vec<ResolvedDatum>,
+}
+
+impl ChainProvider for StaticProvider {
+    type Error = io::Error;
+
+    async fn tip(&self) -> Result<Vec<BlockchainTip>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    async fn credential_utxos(
+        &self,
+        payment_credential: &str,
+    ) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Ok(self
+            .utxos
+            .iter()
+            .filter(|u| u.payment_cred == payment_credential)
+            .cloned()
+            .collect())
+    }
+
+    async fn address_utxos(&self, address: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Ok(self
+            .utxos
+            .iter()
+            .filter(|u| u.address == address)
+            .cloned()
+            .collect())
+    }
+
+    async fn utxo_info(&self, utxo: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Ok(self
+            .utxos
+            .iter()
+            .filter(|u| format!("{}#{}", u.tx_hash, u.tx_index) == utxo)
+            .cloned()
+            .collect())
+    }
+
+    async fn datum_from_datum_hash(
+        &self,
+        datum_hash: String,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        Ok(self
+            .datums
+            .iter()
+            .filter(|d| d.datum_hash.as_deref() == Some(datum_hash.as_str()))
+            .cloned()
+            .collect())
+    }
+
+    async fn evaluate_transaction(&self, _tx_cbor: String) -> Result<Value, Self::Error> {
+        Err(io::Error::other("evaluation not supported"))
+    }
+
+    async fn submit_tx(&self, _tx_cbor: String) -> Result<Value, Self::Error> {
+        Err(io::Error::other("submission not supported"))
+    }
+
+    async fn address_transactions(&self, _address: String) -> Result<Vec<TxResponse>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    async fn transaction_status(&self, _tx_hash: String) -> Result<Vec<TxStatus>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    async fn protocol_parameters(&self) -> Result<Option<ProtocolParameters>, Self::Error> {
+        Ok(Some(ProtocolParameters::default()))
+    }
+}
+
+fn resolved_datum(datum_hash: &str, datum: &[u8]) -> ResolvedDatum {
+    ResolvedDatum {
+        datum_hash: Some(datum_hash.to_string()),
+        value: cbor_to_json(&hex::encode(datum)).unwrap(),
+        bytes: Some(hex::encode(datum)),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn datum_hash_utxos_are_resolved_while_scanning() {
+    let sk = random_scalar();
+    let contract: [u8; 28] = [3u8; 28];
+    let datum: Vec<u8> = Register::create(sk)
+        .unwrap()
+        .rerandomize()
+        .unwrap()
+        .to_vec()
+        .unwrap();
+    let datum_hash: String = hex::encode(Hasher::<256>::hash(&datum));
+    // served under a hash it does not have, so it must be ignored
+    let forged: Vec<u8> = Register::create(sk).unwrap().to_vec().unwrap();
+    let forged_hash: String = "ff".repeat(32);
+
+    let utxo = |tx_hash: &str, datum_hash: &str| UtxoResponse {
+        tx_hash: tx_hash.to_string(),
+        datum_hash: Some(datum_hash.to_string()),
+        ..static_utxo("addr_test1c", &hex::encode(contract), 5_000_000)
+    };
+    let provider = StaticProvider {
+        utxos: vec![
+            utxo(&"aa".repeat(32), &datum_hash),
+            utxo(&"bb".repeat(32), &forged_hash),
+        ],
+        datums: vec![
+            resolved_datum(&datum_hash, &datum),
+            resolved_datum(&forged_hash, &forged),
+        ],
+    };
+
+    let found: Vec<UtxoResponse> =
+        utxos::scan_all_wallet_utxos(&provider, contract, sk, &"cd".repeat(28))
+            .await
+            .unwrap();
+    assert_eq!(found.len(), 1);
+    assert!(found[0].datum_resolved);
+    assert_eq!(transaction::witness_datum(&found[0]).unwrap(), Some(datum));
+}
+
+fn static_utxo(address: &str, payment_cred: &str, lovelace: u64) -> UtxoResponse {
+    UtxoResponse {
+        address: address.to_string(),
+        payment_cred: payment_cred.to_string(),
+        value: lovelace.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn get_utxos_from_custom_provider() {
+    let provider = StaticProvider {
+        utxos: vec![
+            static_utxo("addr_test1a", &hex::encode([1u8; 28]), 5_000_000),
+            static_utxo("addr_test1b", &hex::encode([2u8; 28]), 7_000_000),
+            static_utxo("addr_test1b", &hex::encode([2u8; 28]), 3_000_000),
+        ],
+        datums: Vec::new(),
+    };
+
+    let credential = utxos::get_credential_utxos(&provider, [1u8; 28])
+        .await
+        .unwrap();
+    assert_eq!(credential.len(), 1);
+    assert_eq!(credential[0].value, "5000000");
+
+    let address = utxos::get_address_utxos(&provider, "addr_test1b")
+        .await
+        .unwrap();
+    assert_eq!(address.len(), 2);
+}
+
+#[tokio::test]
+async fn service_collateral_holds_what_is_on_chain() {
+    let mut utxo: UtxoResponse = static_utxo("addr_test1d", &hex::encode(COLLATERAL_HASH), 0);
+    utxo.tx_hash = hex::encode(PREPROD_COLLATERAL_UTXO);
+    utxo.value = "7250000".to_string();
+    let provider = StaticProvider {
+        utxos: vec![utxo],
+        datums: Vec::new(),
+    };
+    let collateral: Collateral = service_collateral(&provider, true).await.unwrap();
+    assert_eq!(collateral.lovelace, 7_250_000);
+    assert_eq!(collateral.input, transaction::collateral_input(true));
+    // mainnet has its own utxo, which this provider does not hold
+    assert!(service_collateral(&provider, false).await.is_err());
+}
 
 #[tokio::test]
 async fn find_first_large_utxo() {
     let addr: &str = "addr_test1qrwejm9pza929cedhwkcsprtgs8l2carehs8z6jkse2qp344c43tmm0md55r4ufmxknr24kq6jkvt6spq60edeuhtf4sn2scds";
-    let every_utxo = utxos::get_address_utxos(addr, true).await.unwrap();
+    let every_utxo = utxos::get_address_utxos(&Koios::new(true), addr)
+        .await
+        .unwrap();
     let utxo_vector = utxos::collect_address_utxos(every_utxo).unwrap();
-    let selected_utxos = utxos::select(utxo_vector, 4_446_456, Assets::new()).unwrap();
+    let selected_utxos = utxos::select(
+        utxo_vector,
+        4_446_456,
+        Assets::new(),
+        &ProtocolParameters::default(),
+    )
+    .unwrap();
     for utxo in selected_utxos {
         println!("large {:?}", string_to_u64(utxo.value));
     }
@@ -15,9 +210,17 @@ async fn find_first_large_utxo() {
 #[tokio::test]
 async fn find_many_utxos() {
     let addr: &str = "addr_test1qrwejm9pza929cedhwkcsprtgs8l2carehs8z6jkse2qp344c43tmm0md55r4ufmxknr24kq6jkvt6spq60edeuhtf4sn2scds";
-    let every_utxo = utxos::get_address_utxos(addr, true).await.unwrap();
+    let every_utxo = utxos::get_address_utxos(&Koios::new(true), addr)
+        .await
+        .unwrap();
     let utxo_vector = utxos::collect_address_utxos(every_utxo).unwrap();
-    let selected_utxos = utxos::select(utxo_vector, 2_000_000_000, Assets::new()).unwrap();
+    let selected_utxos = utxos::select(
+        utxo_vector,
+        2_000_000_000,
+        Assets::new(),
+        &ProtocolParameters::default(),
+    )
+    .unwrap();
     for utxo in selected_utxos {
         println!("many {:?}", string_to_u64(utxo.value));
     }
@@ -26,7 +229,9 @@ async fn find_many_utxos() {
 #[tokio::test]
 async fn find_nft_and_ada() {
     let addr: &str = "addr_test1qrwejm9pza929cedhwkcsprtgs8l2carehs8z6jkse2qp344c43tmm0md55r4ufmxknr24kq6jkvt6spq60edeuhtf4sn2scds";
-    let every_utxo = utxos::get_address_utxos(addr, true).await.unwrap();
+    let every_utxo = utxos::get_address_utxos(&Koios::new(true), addr)
+        .await
+        .unwrap();
     let utxo_vector = utxos::collect_address_utxos(every_utxo).unwrap();
     let tokens: Assets = Assets::new()
         .add(
@@ -38,7 +243,13 @@ async fn find_nft_and_ada() {
             .unwrap(),
         )
         .unwrap();
-    let selected_utxos = utxos::select(utxo_vector, 5_000_000, tokens).unwrap();
+    let selected_utxos = utxos::select(
+        utxo_vector,
+        5_000_000,
+        tokens,
+        &ProtocolParameters::default(),
+    )
+    .unwrap();
 
     for utxo in selected_utxos {
         println!("nft {:?}", string_to_u64(utxo.value));
@@ -1,10 +1,13 @@
 use crate::text_coloring::{display_cyan, display_white, display_yellow};
-use crate::version_control::{compare_versions, get_latest_version};
+use crate::version_control::{
+    compare_versions, latest_version_cached, unix_now, update_check_disabled, update_check_path,
+};
 use blstrs::Scalar;
 use colored::Colorize;
-use seedelf_koios::koios::{
-    UtxoResponse, contains_policy_id, credential_utxos, extract_bytes_with_logging, tip,
-};
+use futures_util::StreamExt;
+use seedelf_koios::koios::{UtxoResponse, contains_policy_id, extract_bytes_with_logging};
+use seedelf_koios::provider::ChainProvider;
+use std::pin::pin;
 
 pub fn webserver_address() {
     display_cyan("Starting Web Server At:");
@@ -12,29 +15,34 @@ pub fn webserver_address() {
     display_yellow("Hit Ctrl-C To Stop Web Server");
 }
 
+/// Tells the user about a newer release.
+///
+/// The lookup is cached for a day and never fails a command, so the wallet works
+/// without network. `SEEDELF_NO_UPDATE_CHECK=1`, or `update_check = false` in the
+/// wallet config, skips it entirely.
 pub async fn is_their_an_update() {
-    match get_latest_version().await {
-        Ok(tag) => {
-            if !compare_versions(env!("CARGO_PKG_VERSION"), &tag) {
-                println!(
-                    "\n{} {}\n{}",
-                    "A new version is available:".bold().bright_blue(),
-                    tag.yellow(),
-                    "Please update to the newest version of Seedelf"
-                        .bold()
-                        .bright_blue(),
-                );
-            }
-        }
-        Err(err) => {
-            eprintln!("Failed to fetch newest version: {err}\nWait a few moments and try again.");
-            std::process::exit(1);
-        }
+    if update_check_disabled() {
+        return;
+    }
+    let Some(path) = update_check_path() else {
+        return;
+    };
+    if let Some(tag) = latest_version_cached(&path, unix_now()).await
+        && !compare_versions(env!("CARGO_PKG_VERSION"), &tag)
+    {
+        println!(
+            "\n{} {}\n{}",
+            "A new version is available:".bold().bright_blue(),
+            tag.yellow(),
+            "Please update to the newest version of Seedelf"
+                .bold()
+                .bright_blue(),
+        );
     }
 }
 
-pub async fn block_number_and_time(network_flag: bool) {
-    match tip(network_flag).await {
+pub async fn block_number_and_time(provider: &impl ChainProvider) {
+    match provider.tip().await {
         Ok(tips) => {
             if let Some(tip) = tips.first() {
                 println!(
@@ -90,15 +98,17 @@ pub fn extract_all_owned_seedelfs(
 }
 
 pub async fn all_seedelfs(
+    provider: &impl ChainProvider,
     sk: Scalar,
-    network_flag: bool,
     wallet_contract_hash: &str,
     seedelf_policy_id: &str,
 ) -> Vec<String> {
-    match credential_utxos(wallet_contract_hash, network_flag).await {
-        Ok(utxos) => extract_all_owned_seedelfs(sk, seedelf_policy_id, utxos),
-        Err(_) => Vec::new(),
+    let mut seedelfs: Vec<String> = Vec::new();
+    let mut pages = pin!(provider.credential_utxo_pages(wallet_contract_hash));
+    while let Some(Ok(utxos)) = pages.next().await {
+        seedelfs.extend(extract_all_owned_seedelfs(sk, seedelf_policy_id, utxos));
     }
+    seedelfs
 }
 
 /// Print each seedelf name in bright yellow.
@@ -120,7 +130,7 @@ pub fn seedelf_label(seedelf: String) {
 
 pub fn hex_to_ascii(hex: &str) -> Result<String, &'static str> {
     // Ensure the length of the hex string is even
-    if hex.len() % 2 != 0 {
+    if !hex.len().is_multiple_of(2) {
         return Err("Hex string must have an even length");
     }
 
@@ -1,11 +1,28 @@
 use reqwest::Client;
 use semver::Version;
-use serde_json::Value;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Environment variable that turns the update check off, e.g. `SEEDELF_NO_UPDATE_CHECK=1`.
+pub const NO_UPDATE_CHECK_ENV: &str = "SEEDELF_NO_UPDATE_CHECK";
+
+static UPDATE_CHECK_OFF: AtomicBool = AtomicBool::new(false);
+
+static UPDATE_CHECK_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Seconds between two release lookups.
+pub const UPDATE_CHECK_INTERVAL: u64 = 24 * 60 * 60;
+
+/// How long the release lookup may take before it is given up.
+pub const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
 
 pub async fn get_latest_version() -> Result<String, Box<dyn std::error::Error>> {
     let url = "https://api.github.com/repos/logical-mechanism/Seedelf-Wallet/releases/latest";
 
-    let client = Client::new();
+    let client = Client::builder().timeout(UPDATE_CHECK_TIMEOUT).build()?;
     let response = client
         .get(url)
         .header("User-Agent", "seedelf") // GitHub requires this
@@ -30,3 +47,91 @@ pub fn compare_versions(local: &str, latest: &str) -> bool {
         _ => false, // If either version can't be parsed, assume it's not up to date
     }
 }
+
+/// Turns the update check off for the rest of the run, e.g. from the wallet config.
+pub fn disable_update_check() {
+    UPDATE_CHECK_OFF.store(true, Ordering::Relaxed);
+}
+
+/// Whether the update check was turned off with [`disable_update_check`] or [`NO_UPDATE_CHECK_ENV`].
+pub fn update_check_disabled() -> bool {
+    UPDATE_CHECK_OFF.load(Ordering::Relaxed)
+        || std::env::var(NO_UPDATE_CHECK_ENV).is_ok_and(|value| {
+            !matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "" | "0" | "false" | "no"
+            )
+        })
+}
+
+/// Keeps the release lookup in this directory, the seedelf home of the wallet.
+pub fn set_update_check_dir(dir: PathBuf) {
+    *UPDATE_CHECK_DIR.lock().unwrap_or_else(|e| e.into_inner()) = Some(dir);
+}
+
+/// Where the result of the last release lookup is kept, in the directory set with
+/// [`set_update_check_dir`], else `SEEDELF_HOME`, else `~/.seedelf`.
+pub fn update_check_path() -> Option<PathBuf> {
+    UPDATE_CHECK_DIR
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .or_else(|| {
+            std::env::var_os("SEEDELF_HOME")
+                .filter(|home| !home.is_empty())
+                .map(PathBuf::from)
+        })
+        .or_else(|| dirs::home_dir().map(|home| home.join(".seedelf")))
+        .map(|dir| dir.join("update-check.json"))
+}
+
+/// The newest release, looked up at most once per [`UPDATE_CHECK_INTERVAL`].
+///
+/// Failed lookups are remembered as well, so a machine without network only
+/// tries once a day and keeps the last release it saw.
+///
+/// # Arguments
+///
+/// * `path` - The file holding the last lookup.
+/// * `now` - The current unix time in seconds.
+///
+/// # Returns
+///
+/// * `Some(String)` - The newest release tag known.
+/// * `None` - If no lookup has succeeded yet.
+pub async fn latest_version_cached(path: &Path, now: u64) -> Option<String> {
+    let cached: Option<Value> = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok());
+    let checked_at: u64 = cached
+        .as_ref()
+        .and_then(|c| c.get("checked_at"))
+        .and_then(Value::as_u64)
+        .unwrap_or_default();
+    let known: Option<String> = cached
+        .as_ref()
+        .and_then(|c| c.get("latest"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    if cached.is_some() && now.saturating_sub(checked_at) < UPDATE_CHECK_INTERVAL {
+        return known;
+    }
+
+    let latest: Option<String> = get_latest_version().await.ok().or(known);
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(
+        path,
+        json!({ "checked_at": now, "latest": latest }).to_string(),
+    );
+    latest
+}
+
+/// The current unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -68,7 +68,7 @@ fn test_fail_label_to_ascii2() {
         "5eed0e1f00000acab00000018732122c62aea887cd16d743c3045e524f019aea".to_string();
     let answer: String = ".........2.,b..".to_string();
     let substring: String = seedelf[8..38].to_string();
-    if answer.chars().next() == Some('.') {
+    if answer.starts_with('.') {
         assert_eq!(true, true)
     } else {
         assert_eq!(false, true)
@@ -1,18 +1,20 @@
-use seedelf_display::version_control::{compare_versions, get_latest_version};
+use seedelf_display::version_control::{
+    UPDATE_CHECK_INTERVAL, compare_versions, get_latest_version, latest_version_cached,
+};
 
 #[test]
 fn same_version() {
-    assert_eq!(compare_versions("0.4.6", "0.4.6"), true)
+    assert!(compare_versions("0.4.6", "0.4.6"))
 }
 
 #[test]
 fn need_to_update() {
-    assert_eq!(compare_versions("0.4.5", "0.4.6"), false)
+    assert!(!compare_versions("0.4.5", "0.4.6"))
 }
 
 #[test]
 fn major_minor_tests() {
-    assert_eq!(compare_versions("0.5.5", "0.4.6"), true)
+    assert!(compare_versions("0.5.5", "0.4.6"))
 }
 
 #[tokio::test]
@@ -22,3 +24,13 @@ async fn get_latest_version_from_github() {
         Err(e) => panic!("Failed to fetch latest version: {}", e),
     }
 }
+
+#[tokio::test]
+async fn fresh_update_check_is_read_from_the_cache() {
+    let path =
+        std::env::temp_dir().join(format!("seedelf-update-check-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"checked_at":1000,"latest":"9.9.9"}"#).unwrap();
+    let latest = latest_version_cached(&path, 1000 + UPDATE_CHECK_INTERVAL - 1).await;
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(latest.as_deref(), Some("9.9.9"));
+}
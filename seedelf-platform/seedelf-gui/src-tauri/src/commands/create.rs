@@ -1,7 +1,11 @@
+use crate::provider;
 use crate::session;
 use seedelf_cli::commands::create::{CreateSeedelfOutput, build_create_seedelf};
+use seedelf_cli::setup;
 use seedelf_core::constants::{Config, VARIANT, get_config};
 use seedelf_core::transaction;
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::Backend;
 
 #[tauri::command(async)]
 pub async fn create_seedelf(network_flag: bool, addr: String, label: String) -> String {
@@ -12,14 +16,27 @@ pub async fn create_seedelf(network_flag: bool, addr: String, label: String) ->
         }
     };
 
+    let provider: Backend = provider::backend(network_flag);
+    let params: ProtocolParameters = setup::protocol_parameters(&provider, network_flag).await;
     let CreateSeedelfOutput {
         tx_cbor,
         total_lovelace,
         cpu_units,
         mem_units,
         ..
-    } = match session::with_key(|sk| build_create_seedelf(config, network_flag, addr, label, *sk))
-        .await
+    } = match session::with_key(|sk| {
+        build_create_seedelf(
+            config,
+            &provider,
+            network_flag,
+            &params,
+            provider::evaluation(),
+            addr,
+            label,
+            *sk,
+        )
+    })
+    .await
     {
         Ok(v) => v,
         _ => return String::new(),
@@ -27,7 +44,8 @@ pub async fn create_seedelf(network_flag: bool, addr: String, label: String) ->
 
     // prob should be a function that returns this min
     let tmp_fee: u64 = 205_000;
-    let lovelace_goal: u64 = transaction::seedelf_minimum_lovelace().unwrap_or_default() + tmp_fee;
+    let lovelace_goal: u64 =
+        transaction::seedelf_minimum_lovelace(&params).unwrap_or_default() + tmp_fee;
     if total_lovelace < lovelace_goal {
         return String::new();
     }
@@ -1,8 +1,14 @@
 // core named this sweep, but in the gui it will be extract
+use crate::provider;
 use crate::session;
 use seedelf_cli::commands::sweep::{SweepSeedelfOutput, build_sweep_seedelf};
+use seedelf_cli::key::WalletKey;
+use seedelf_cli::setup;
 use seedelf_core::assets::Assets;
+use seedelf_core::collateral::{Collateral, service_collateral};
 use seedelf_core::constants::{Config, VARIANT, get_config};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::Backend;
 
 #[tauri::command(async)]
 pub async fn extract_seedelf(
@@ -17,21 +23,33 @@ pub async fn extract_seedelf(
             return String::new();
         }
     };
-    let SweepSeedelfOutput { tx_hash, .. } = match session::with_key(|sk| {
+    let provider: Backend = provider::backend(network_flag);
+    let params: ProtocolParameters = setup::protocol_parameters(&provider, network_flag).await;
+    let collateral: Collateral = match service_collateral(&provider, network_flag).await {
+        Ok(c) => c,
+        Err(_) => return String::new(),
+    };
+    let SweepSeedelfOutput { tx_hash, .. } = match session::with_key(|sk| async move {
         build_sweep_seedelf(
             config,
+            &provider,
             network_flag,
+            &params,
+            provider::evaluation(),
+            &collateral,
             address,
             lovelace,
             Assets::new(),
             None,
-            *sk,
+            &WalletKey::from(*sk),
             send_all,
+            false,
         )
+        .await
     })
     .await
     {
-        Ok(v) => v,
+        Ok(Ok(v)) => v,
         _ => return String::new(),
     };
 
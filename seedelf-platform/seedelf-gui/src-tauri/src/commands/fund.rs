@@ -1,6 +1,10 @@
+use crate::provider;
 use seedelf_cli::commands::fund::{FundSeedelfOutput, build_fund_seedelf};
+use seedelf_cli::setup;
 use seedelf_core::assets::Assets;
 use seedelf_core::constants::{Config, VARIANT, get_config};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::Backend;
 
 #[tauri::command(async)]
 pub async fn fund_seedelf(
@@ -15,13 +19,17 @@ pub async fn fund_seedelf(
             return String::new();
         }
     };
+    let provider: Backend = provider::backend(network_flag);
+    let params: ProtocolParameters = setup::protocol_parameters(&provider, network_flag).await;
     let FundSeedelfOutput {
         tx_cbor,
         usable_utxos,
         ..
     } = build_fund_seedelf(
         config,
+        &provider,
         network_flag,
+        &params,
         user_address,
         seedelf,
         lovelace,
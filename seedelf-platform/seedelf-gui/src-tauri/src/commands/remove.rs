@@ -1,6 +1,12 @@
+use crate::provider;
 use crate::session;
 use seedelf_cli::commands::remove::{RemoveSeedelfOutput, build_remove_seedelf};
+use seedelf_cli::key::WalletKey;
+use seedelf_cli::setup;
+use seedelf_core::collateral::{Collateral, service_collateral};
 use seedelf_core::constants::{Config, VARIANT, get_config};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::Backend;
 
 #[tauri::command(async)]
 pub async fn remove_seedelf(network_flag: bool, addr: String, seedelf: String) -> String {
@@ -11,6 +17,12 @@ pub async fn remove_seedelf(network_flag: bool, addr: String, seedelf: String) -
         }
     };
 
+    let provider: Backend = provider::backend(network_flag);
+    let params: ProtocolParameters = setup::protocol_parameters(&provider, network_flag).await;
+    let collateral: Collateral = match service_collateral(&provider, network_flag).await {
+        Ok(c) => c,
+        Err(_) => return String::new(),
+    };
     let RemoveSeedelfOutput {
         tx_hash,
         mint_cpu_units,
@@ -18,10 +30,23 @@ pub async fn remove_seedelf(network_flag: bool, addr: String, seedelf: String) -
         spend_cpu_units,
         spend_mem_units,
         ..
-    } = match session::with_key(|sk| build_remove_seedelf(config, network_flag, addr, seedelf, *sk))
+    } = match session::with_key(|sk| async move {
+        build_remove_seedelf(
+            config,
+            &provider,
+            network_flag,
+            &params,
+            provider::evaluation(),
+            &collateral,
+            addr,
+            seedelf,
+            &WalletKey::from(*sk),
+        )
         .await
+    })
+    .await
     {
-        Ok(v) => v,
+        Ok(Ok(v)) => v,
         _ => return String::new(),
     };
 
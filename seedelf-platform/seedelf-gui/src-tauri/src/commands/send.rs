@@ -1,8 +1,14 @@
 // core named this transfer, but in the gui it will be send
+use crate::provider;
 use crate::session;
 use seedelf_cli::commands::transfer::{TransferSeedelfOutput, build_transfer_seedelf};
+use seedelf_cli::key::WalletKey;
+use seedelf_cli::setup;
 use seedelf_core::assets::Assets;
+use seedelf_core::collateral::{Collateral, service_collateral};
 use seedelf_core::constants::{Config, VARIANT, get_config};
+use seedelf_koios::parameters::ProtocolParameters;
+use seedelf_koios::provider::Backend;
 
 #[tauri::command(async)]
 pub async fn send_seedelf(
@@ -16,24 +22,36 @@ pub async fn send_seedelf(
             return String::new();
         }
     };
+    let provider: Backend = provider::backend(network_flag);
+    let params: ProtocolParameters = setup::protocol_parameters(&provider, network_flag).await;
+    let collateral: Collateral = match service_collateral(&provider, network_flag).await {
+        Ok(c) => c,
+        Err(_) => return String::new(),
+    };
     let TransferSeedelfOutput {
         tx_hash,
         usable_utxos,
         ..
-    } = match session::with_key(|sk| {
+    } = match session::with_key(|sk| async move {
         build_transfer_seedelf(
             config,
+            &provider,
             network_flag,
+            &params,
+            provider::evaluation(),
+            &collateral,
             seedelfs.clone(),
             lovelaces,
             vec![Assets::new(); seedelfs.len()],
             None,
-            *sk,
+            &WalletKey::from(*sk),
+            false,
         )
+        .await
     })
     .await
     {
-        Ok(v) => v,
+        Ok(Ok(v)) => v,
         _ => return String::new(),
     };
     if usable_utxos.is_empty() {
@@ -1,3 +1,4 @@
+use crate::provider;
 use seedelf_core::constants::{Config, VARIANT, get_config};
 
 #[tauri::command(async)]
@@ -8,3 +9,9 @@ pub async fn get_seedelf_policy_id(network_flag: bool) -> String {
     };
     config.contract.seedelf_policy_id
 }
+
+/// The network of the wallet config profile, `None` when it sets none.
+#[tauri::command]
+pub fn get_default_network() -> Option<String> {
+    provider::profile().network
+}
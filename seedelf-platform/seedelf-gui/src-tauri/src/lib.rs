@@ -1,6 +1,7 @@
 pub mod address;
 pub mod commands;
 pub mod constants;
+pub mod provider;
 pub mod session;
 pub mod setup;
 pub mod transaction;
@@ -11,6 +12,10 @@ pub mod webserver;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // --data-dir moves the seedelf home like in the cli, SEEDELF_HOME is read by the cli crate
+    seedelf_cli::setup::set_data_dir(data_dir_arg(std::env::args()));
+    // the GUI opens the wallet named by SEEDELF_WALLET, else the recorded default
+    seedelf_cli::setup::select_wallet(std::env::var(seedelf_cli::setup::WALLET_ENV).ok());
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
@@ -40,6 +45,7 @@ pub fn run() {
             commands::extract::extract_seedelf,
             // constants.rs
             constants::get_seedelf_policy_id,
+            constants::get_default_network,
             // webserver.rs
             webserver::open_web_server,
             webserver::close_web_server,
@@ -49,3 +55,16 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// The value of `--data-dir <PATH>` or `--data-dir=<PATH>` on the command line.
+fn data_dir_arg(mut args: impl Iterator<Item = String>) -> Option<std::path::PathBuf> {
+    while let Some(arg) = args.next() {
+        if arg == "--data-dir" {
+            return args.next().map(Into::into);
+        }
+        if let Some(path) = arg.strip_prefix("--data-dir=") {
+            return Some(path.into());
+        }
+    }
+    None
+}
@@ -0,0 +1,57 @@
+use once_cell::sync::OnceCell;
+use seedelf_cli::config::{Config, PROFILE_ENV, Profile};
+use seedelf_core::constants::{MAINNET_STAKE_HASH, PREPROD_STAKE_HASH};
+use seedelf_core::evaluate::Evaluation;
+use seedelf_koios::koios::Koios;
+use seedelf_koios::provider::Backend;
+
+static BACKEND: OnceCell<(Backend, Backend)> = OnceCell::new();
+
+/// The wallet config profile named by `SEEDELF_PROFILE`, else the configured one.
+pub fn profile() -> Profile {
+    let selected: Option<String> = std::env::var(PROFILE_ENV).ok();
+    Config::load()
+        .map(|config| config.profile(selected.as_deref()))
+        .unwrap_or_default()
+}
+
+fn from_env(network_flag: bool) -> Backend {
+    // an explicit SEEDELF_BACKEND wins over the config profile
+    let backend: Result<Backend, String> = match std::env::var("SEEDELF_BACKEND") {
+        Ok(name) if !name.trim().is_empty() => Backend::from_env(network_flag),
+        _ => match profile().backend(network_flag) {
+            Ok(Some(backend)) => Ok(backend),
+            Ok(None) => Backend::from_env(network_flag),
+            Err(err) => Err(err.to_string()),
+        },
+    };
+    match backend {
+        // the wallet contract address carries the stake key, and the node only looks up full addresses
+        Ok(Backend::Node(node)) => Backend::Node(node.with_stake_credential(if network_flag {
+            PREPROD_STAKE_HASH
+        } else {
+            MAINNET_STAKE_HASH
+        })),
+        Ok(backend) => backend,
+        Err(_) => Backend::Koios(Koios::new(network_flag)),
+    }
+}
+
+/// The chain data backend for a network, selected by `SEEDELF_BACKEND` or the wallet
+/// config profile and configured from the `SEEDELF_KOIOS_*`, `SEEDELF_BLOCKFROST_*` or
+/// `SEEDELF_OGMIOS_URL`, `SEEDELF_KUPO_URL` and `SEEDELF_NODE_SOCKET` environment.
+///
+/// The backends are built once so every command shares the same connection pool.
+pub fn backend(network_flag: bool) -> Backend {
+    let (preprod, mainnet) = BACKEND.get_or_init(|| (from_env(true), from_env(false)));
+    if network_flag {
+        preprod.clone()
+    } else {
+        mainnet.clone()
+    }
+}
+
+/// Where scripts are evaluated, from `SEEDELF_EVALUATION`, local unless it is set to remote.
+pub fn evaluation() -> Evaluation {
+    Evaluation::from_env().unwrap_or_default()
+}
@@ -5,7 +5,7 @@ use seedelf_cli::setup::{
 };
 
 #[tauri::command(async)]
-pub fn check_if_wallet_exists() -> Option<String> {
+pub fn check_if_wallet_exists() -> Result<Option<String>, String> {
     check_and_prepare_seedelf()
 }
 
@@ -15,8 +15,8 @@ pub fn check_password_complexity(password: String) -> bool {
 }
 
 #[tauri::command(async)]
-pub fn create_new_wallet(wallet_name: String, password: String) {
-    create_wallet(wallet_name, password)
+pub fn create_new_wallet(wallet_name: String, password: String) -> Result<(), String> {
+    create_wallet(wallet_name, password, None)
 }
 
 #[tauri::command]
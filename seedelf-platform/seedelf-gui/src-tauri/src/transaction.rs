@@ -1,9 +1,12 @@
-use seedelf_koios::koios;
+use crate::provider;
 use seedelf_koios::koios::TxStatus;
+use seedelf_koios::provider::{Backend, ChainProvider};
 
 #[tauri::command(async)]
 pub async fn transaction_status(network_flag: bool, tx_hash: String) -> u64 {
-    let status: Vec<TxStatus> = koios::transaction_status(network_flag, tx_hash)
+    let provider: Backend = provider::backend(network_flag);
+    let status: Vec<TxStatus> = provider
+        .transaction_status(tx_hash)
         .await
         .unwrap_or_default();
     if status.is_empty() {
@@ -1,3 +1,4 @@
+use seedelf_cli::cache::HistoryEntry;
 use seedelf_koios::koios::TxResponse;
 use serde::Serialize;
 
@@ -11,4 +12,6 @@ pub enum UTxOSide {
 pub struct TxResponseWithSide {
     pub side: UTxOSide,
     pub tx: TxResponse,
+    /// What the wallet spent and received in the transaction.
+    pub entry: HistoryEntry,
 }
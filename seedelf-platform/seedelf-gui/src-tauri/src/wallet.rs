@@ -1,18 +1,15 @@
+use crate::provider;
 use crate::session;
 use crate::types::{TxResponseWithSide, UTxOSide};
-use blstrs::Scalar;
 use pallas_addresses::Address;
+use seedelf_cli::cache::HistoryEntry;
+use seedelf_cli::key::WalletKey;
 use seedelf_core::address;
 use seedelf_core::constants::{Config, VARIANT, get_config};
 use seedelf_core::utxos;
-use seedelf_crypto::register::Register;
 use seedelf_display::display;
-use seedelf_koios::koios;
 use seedelf_koios::koios::{TxResponse, UtxoResponse};
-
-fn any_owned(regs: &[Register], scalar: &Scalar) -> bool {
-    regs.iter().any(|r| matches!(r.is_owned(*scalar), Ok(true)))
-}
+use seedelf_koios::provider::{Backend, ChainProvider};
 
 #[tauri::command(async)]
 pub async fn get_wallet_history(network_flag: bool) -> Vec<TxResponseWithSide> {
@@ -26,8 +23,9 @@ pub async fn get_wallet_history(network_flag: bool) -> Vec<TxResponseWithSide> {
     let wallet_addr: Address =
         address::wallet_contract(network_flag, config.contract.wallet_contract_hash);
 
+    let provider: Backend = provider::backend(network_flag);
     let all_txs: Vec<TxResponse> =
-        match koios::address_transactions(network_flag, wallet_addr.to_string()).await {
+        match provider.address_transactions(wallet_addr.to_string()).await {
             Ok(v) => v,
             Err(_) => {
                 return Vec::new();
@@ -35,22 +33,17 @@ pub async fn get_wallet_history(network_flag: bool) -> Vec<TxResponseWithSide> {
         };
 
     session::with_key_sync(|sk| {
+        let key: WalletKey = WalletKey::from(*sk);
         let filtered: Vec<TxResponseWithSide> = all_txs
             .into_iter()
             .filter_map(|tx| {
-                if any_owned(&tx.input_registers, sk) {
-                    Some(TxResponseWithSide {
-                        side: UTxOSide::Input,
-                        tx,
-                    })
-                } else if any_owned(&tx.output_registers, sk) {
-                    Some(TxResponseWithSide {
-                        side: UTxOSide::Output,
-                        tx,
-                    })
+                let entry: HistoryEntry = HistoryEntry::from_tx(&tx, &key).ok()??;
+                let side: UTxOSide = if entry.spend {
+                    UTxOSide::Input
                 } else {
-                    None
-                }
+                    UTxOSide::Output
+                };
+                Some(TxResponseWithSide { side, tx, entry })
             })
             .collect();
         filtered
@@ -68,7 +61,8 @@ pub async fn get_every_utxo(network_flag: bool) -> Vec<UtxoResponse> {
     };
 
     // this is all the utxos in the contract
-    utxos::get_credential_utxos(config.contract.wallet_contract_hash, network_flag)
+    let provider: Backend = provider::backend(network_flag);
+    utxos::get_credential_utxos(&provider, config.contract.wallet_contract_hash)
         .await
         .unwrap_or_default()
 }
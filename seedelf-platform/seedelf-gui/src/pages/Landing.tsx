@@ -14,9 +14,17 @@ export function LandingPage() {
 
   useEffect(() => {
     const checkWallet = async () => {
-      const walletExists = await invoke<WalletExistsResult>(
-        "check_if_wallet_exists",
-      );
+      let walletExists: WalletExistsResult;
+      try {
+        walletExists = await invoke<WalletExistsResult>(
+          "check_if_wallet_exists",
+        );
+      } catch (e) {
+        // several wallets and no default, pick one with the cli
+        setVariant("error");
+        setMessage(e as string);
+        return;
+      }
 
       if (walletExists) {
         setVariant("success");
@@ -26,6 +26,18 @@ function txUrl(txHash: string, network: string) {
 
 const PAGE_SIZE_OPTIONS = [10, 20, 50, 100];
 
+function formatAda(lovelace: number, signed: boolean = false) {
+  const ada = (Math.abs(lovelace) / 1_000_000).toFixed(6);
+  if (lovelace < 0) return `-${ada} ₳`;
+  return signed ? `+${ada} ₳` : `${ada} ₳`;
+}
+
+function formatTime(blockTime: number) {
+  return blockTime > 0
+    ? `${new Date(blockTime * 1000).toISOString().replace("T", " ").slice(0, 19)} UTC`
+    : "Unknown Time";
+}
+
 export function History() {
   const { history } = useOutletContext<OutletContextType>();
   const { network } = useNetwork();
@@ -220,6 +232,29 @@ export function History() {
                     <Copy />
                   </button>
                 </div>
+                <div className="mt-3 flex flex-wrap justify-center gap-x-6 gap-y-1 text-sm">
+                  <span className="opacity-80">
+                    {formatTime(h.entry.block_time)}
+                  </span>
+                  <span
+                    className={`font-semibold ${
+                      h.entry.received_lovelace < h.entry.spent_lovelace
+                        ? colorClasses.indigo.text
+                        : colorClasses.teal.text
+                    }`}
+                  >
+                    Net{" "}
+                    {formatAda(
+                      h.entry.received_lovelace - h.entry.spent_lovelace,
+                      true,
+                    )}
+                  </span>
+                  {h.entry.spend && h.entry.fee !== null && (
+                    <span className="opacity-80">
+                      Fee {formatAda(h.entry.fee)}
+                    </span>
+                  )}
+                </div>
               </li>
             ))}
           </ul>
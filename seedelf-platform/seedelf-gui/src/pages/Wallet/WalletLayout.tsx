@@ -89,6 +89,15 @@ export function WalletLayout() {
     }
   };
 
+  useEffect(() => {
+    // the wallet config picks the network until one is chosen here
+    if (localStorage.getItem("network") === null) {
+      invoke<Network | null>("get_default_network").then((n) => {
+        if (n === "mainnet" || n === "preprod") setNetwork(n);
+      });
+    }
+  }, []);
+
   useEffect(() => {
     // store it locally
     localStorage.setItem("network", network);
@@ -10,16 +10,57 @@ export interface Register {
   public_value: string;
 }
 
+export interface RegisterValue {
+  tx_hash: string;
+  tx_index: number;
+  register: Register;
+  lovelace: number; // u64
+  assets: Asset[];
+}
+
 export interface TxResponse {
   tx_hash: string;
   block_height: number;
+  block_time: number; // u64, unix seconds
+  fee: number | null; // u64
   input_registers: Register[];
   output_registers: Register[];
+  inputs: RegisterValue[];
+  outputs: RegisterValue[];
+}
+
+export interface TokenAmount {
+  policy_id: string;
+  asset_name: string;
+  amount: number; // u64
+}
+
+export interface Movement {
+  spend: boolean;
+  tx_hash: string;
+  tx_index: number;
+  lovelace: number; // u64
+  tokens: TokenAmount[];
+}
+
+export interface HistoryEntry {
+  tx_hash: string;
+  block_height: number;
+  block_time: number;
+  spend: boolean;
+  receive: boolean;
+  spent_lovelace: number;
+  received_lovelace: number;
+  spent_tokens: TokenAmount[];
+  received_tokens: TokenAmount[];
+  fee: number | null;
+  movements: Movement[];
 }
 
 export interface TxResponseWithSide {
   side: UTxOSide;
   tx: TxResponse;
+  entry: HistoryEntry;
 }
 
 export interface Asset {
@@ -50,4 +91,5 @@ export interface UtxoResponse {
   reference_script: unknown | null;
   asset_list: Asset[] | null;
   is_spent: boolean;
+  datum_resolved: boolean; // inline_datum came from datum_hash
 }
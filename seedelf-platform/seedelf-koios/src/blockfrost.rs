@@ -0,0 +1,528 @@
+use crate::datum::inline_datum_from_cbor;
+use crate::koios::{
+    Asset, BlockchainTip, ResolvedDatum, TxInfoResponse, TxResponse, TxStatus, UtxoResponse,
+};
+use crate::parameters::ProtocolParameters;
+use crate::provider::ChainProvider;
+use bech32::{ToBase32, Variant};
+use pallas_addresses::Address;
+use reqwest::{Client, Error, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::fmt;
+
+/// Environment variable holding the Blockfrost project id.
+pub const BLOCKFROST_PROJECT_ID_ENV: &str = "SEEDELF_BLOCKFROST_PROJECT_ID";
+/// Environment variable holding a custom Blockfrost base URL.
+pub const BLOCKFROST_URL_ENV: &str = "SEEDELF_BLOCKFROST_URL";
+
+/// Blockfrost returns at most this many items per page.
+const PAGE_SIZE: usize = 100;
+
+/// An error from the Blockfrost backend.
+#[derive(Debug)]
+pub enum BlockfrostError {
+    /// The request failed or the response was not the expected JSON.
+    Http(Error),
+    /// Blockfrost refused a submitted transaction, holding its error body.
+    Rejected(String),
+}
+
+impl fmt::Display for BlockfrostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockfrostError::Http(err) => write!(f, "{err}"),
+            BlockfrostError::Rejected(body) => {
+                write!(f, "Transaction Rejected: {}", body.trim())
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlockfrostError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BlockfrostError::Http(err) => Some(err),
+            BlockfrostError::Rejected(_) => None,
+        }
+    }
+}
+
+impl From<Error> for BlockfrostError {
+    fn from(err: Error) -> Self {
+        BlockfrostError::Http(err)
+    }
+}
+
+/// A Blockfrost API client.
+///
+/// Answers the same queries as `Koios` and maps Blockfrost's JSON into the Koios
+/// shapes, so the rest of the wallet does not care which one is in use.
+#[derive(Clone)]
+pub struct Blockfrost {
+    base_url: String,
+    project_id: String,
+    client: Client,
+}
+
+impl fmt::Debug for Blockfrost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print the project id
+        f.debug_struct("Blockfrost")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A single asset amount on a Blockfrost output.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct BlockfrostAmount {
+    pub unit: String,
+    pub quantity: String,
+}
+
+/// A transaction output as Blockfrost returns it.
+///
+/// Outputs under `/txs/{hash}/utxos` do not carry their own `tx_hash`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct BlockfrostUtxo {
+    pub address: String,
+    #[serde(default)]
+    pub tx_hash: String,
+    pub output_index: u64,
+    pub amount: Vec<BlockfrostAmount>,
+    pub data_hash: Option<String>,
+    pub inline_datum: Option<String>,
+    pub reference_script_hash: Option<String>,
+    #[serde(default)]
+    pub consumed_by_tx: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockfrostBlock {
+    hash: String,
+    epoch: Option<u64>,
+    slot: Option<u64>,
+    epoch_slot: Option<u64>,
+    height: Option<u64>,
+    time: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockfrostTx {
+    block_height: u64,
+    #[serde(default)]
+    block_time: u64,
+    #[serde(default)]
+    fees: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockfrostAddressTx {
+    tx_hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockfrostTxUtxos {
+    hash: String,
+    inputs: Vec<BlockfrostUtxo>,
+    outputs: Vec<BlockfrostUtxo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockfrostDatumCbor {
+    cbor: String,
+}
+
+impl Blockfrost {
+    /// Creates a client for the hosted Blockfrost API on Preprod (`true`) or Mainnet (`false`).
+    pub fn new(network_flag: bool, project_id: impl Into<String>) -> Self {
+        Self {
+            base_url: Self::public_url(network_flag),
+            project_id: project_id.into(),
+            client: Client::new(),
+        }
+    }
+
+    /// Creates a client from the `SEEDELF_BLOCKFROST_*` environment variables.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Blockfrost)` - The configured client.
+    /// * `Err(String)` - If no project id is set.
+    pub fn from_env(network_flag: bool) -> Result<Self, String> {
+        let project_id: String = std::env::var(BLOCKFROST_PROJECT_ID_ENV)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .ok_or_else(|| format!("{BLOCKFROST_PROJECT_ID_ENV} Is Not Set"))?;
+        let blockfrost: Blockfrost = Self::new(network_flag, project_id);
+        match std::env::var(BLOCKFROST_URL_ENV) {
+            Ok(url) if !url.trim().is_empty() => Ok(blockfrost.with_base_url(url)),
+            _ => Ok(blockfrost),
+        }
+    }
+
+    /// The hosted Blockfrost base URL for a network.
+    pub fn public_url(network_flag: bool) -> String {
+        let network: &str = if network_flag { "preprod" } else { "mainnet" };
+        format!("https://cardano-{network}.blockfrost.io/api/v0")
+    }
+
+    /// Uses a different base URL, e.g. a self-hosted Blockfrost instance.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Uses an existing `reqwest::Client` so its connection pool is shared.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// The base URL every endpoint is joined onto.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get(&self, endpoint: &str) -> RequestBuilder {
+        self.client
+            .get(format!("{}/{endpoint}", self.base_url))
+            .header("project_id", &self.project_id)
+    }
+
+    fn post(&self, endpoint: &str) -> RequestBuilder {
+        self.client
+            .post(format!("{}/{endpoint}", self.base_url))
+            .header("project_id", &self.project_id)
+    }
+
+    /// GETs an endpoint, treating a 404 as "nothing there" instead of an error.
+    async fn get_json<T: DeserializeOwned>(&self, endpoint: &str) -> Result<Option<T>, Error> {
+        let response: Response = self.get(endpoint).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// GETs every page of a paginated endpoint.
+    async fn get_all_pages<T: DeserializeOwned>(&self, endpoint: &str) -> Result<Vec<T>, Error> {
+        let mut all: Vec<T> = Vec::new();
+        let separator: char = if endpoint.contains('?') { '&' } else { '?' };
+        let mut page: u64 = 1;
+        loop {
+            let items: Vec<T> = self
+                .get_json(&format!(
+                    "{endpoint}{separator}page={page}&count={PAGE_SIZE}"
+                ))
+                .await?
+                .unwrap_or_default();
+            let done: bool = items.len() < PAGE_SIZE;
+            all.extend(items);
+            if done {
+                break;
+            }
+            page += 1;
+        }
+        Ok(all)
+    }
+
+    async fn latest_block(&self) -> Result<Option<BlockfrostBlock>, Error> {
+        self.get_json("blocks/latest").await
+    }
+}
+
+/// Maps a Blockfrost output onto the Koios `UtxoResponse` shape.
+///
+/// Blockfrost does not report the block of an output, so `epoch_no`, `block_height`
+/// and `block_time` are zero.
+///
+/// # Arguments
+///
+/// * `utxo` - The Blockfrost output.
+/// * `tx_hash` - The transaction hash to use when the output does not carry one.
+///
+/// # Returns
+///
+/// * `UtxoResponse` - The output in the Koios shape.
+pub fn utxo_response(utxo: BlockfrostUtxo, tx_hash: &str) -> UtxoResponse {
+    let mut lovelace: String = "0".to_string();
+    let mut asset_list: Vec<Asset> = Vec::new();
+    for amount in utxo.amount {
+        if amount.unit == "lovelace" {
+            lovelace = amount.quantity;
+        } else if amount.unit.len() >= 56 {
+            let (policy_id, asset_name) = amount.unit.split_at(56);
+            asset_list.push(Asset {
+                decimals: 0,
+                quantity: amount.quantity,
+                policy_id: policy_id.to_string(),
+                asset_name: asset_name.to_string(),
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    let payment_cred: String = match Address::from_bech32(&utxo.address) {
+        Ok(Address::Shelley(shelley)) => shelley.payment().to_hex(),
+        _ => String::new(),
+    };
+
+    UtxoResponse {
+        tx_hash: if utxo.tx_hash.is_empty() {
+            tx_hash.to_string()
+        } else {
+            utxo.tx_hash
+        },
+        tx_index: utxo.output_index,
+        address: utxo.address,
+        value: lovelace,
+        stake_address: None,
+        payment_cred,
+        epoch_no: 0,
+        block_height: 0,
+        block_time: 0,
+        datum_hash: utxo.data_hash,
+        inline_datum: utxo
+            .inline_datum
+            .as_deref()
+            .and_then(inline_datum_from_cbor),
+        reference_script: utxo
+            .reference_script_hash
+            .map(|hash| json!({ "hash": hash })),
+        datum_resolved: false,
+        asset_list: Some(asset_list),
+        is_spent: utxo.consumed_by_tx.is_some(),
+    }
+}
+
+/// Rewrites a Blockfrost evaluation into the Ogmios v6 JSON-RPC shape.
+///
+/// Blockfrost answers in the older Ogmios v5 format unless asked otherwise, so both
+/// shapes are accepted and the result always has the `result` array of
+/// `{validator, budget}` items that `extract_budgets` expects, or an `error`.
+///
+/// # Arguments
+///
+/// * `value` - The raw evaluation response.
+///
+/// # Returns
+///
+/// * `Value` - The response in the Ogmios v6 shape.
+pub fn evaluation_to_ogmios(value: Value) -> Value {
+    if value.get("result").is_some_and(|r| r.is_array()) || value.get("error").is_some() {
+        return value;
+    }
+
+    let result: &Value = value.get("result").unwrap_or(&Value::Null);
+    if let Some(units) = result.get("EvaluationResult").and_then(|r| r.as_object()) {
+        let budgets: Vec<Value> = units
+            .iter()
+            .map(|(validator, budget)| {
+                let (purpose, index) = validator.split_once(':').unwrap_or((validator, "0"));
+                json!({
+                    "validator": {
+                        "purpose": purpose,
+                        "index": index.parse::<u64>().unwrap_or_default(),
+                    },
+                    "budget": {
+                        "memory": budget.get("memory").cloned().unwrap_or_default(),
+                        "cpu": budget.get("steps").cloned().unwrap_or_default(),
+                    }
+                })
+            })
+            .collect();
+        return json!({ "jsonrpc": "2.0", "method": "evaluateTransaction", "result": budgets });
+    }
+
+    let failure: Value = result
+        .get("EvaluationFailure")
+        .cloned()
+        .unwrap_or_else(|| value.clone());
+    json!({
+        "jsonrpc": "2.0",
+        "method": "evaluateTransaction",
+        "error": { "message": "Evaluation Failed", "data": failure }
+    })
+}
+
+/// Encodes a script hash as a CIP-5 `script1...` credential for Blockfrost's address endpoints.
+fn script_credential(payment_credential: &str) -> Option<String> {
+    let bytes: Vec<u8> = hex::decode(payment_credential).ok()?;
+    bech32::encode("script", bytes.to_base32(), Variant::Bech32).ok()
+}
+
+/// Puts a Blockfrost output into the Koios `tx_info` JSON shape used by `TxResponse`.
+fn tx_info_output(utxo: &BlockfrostUtxo) -> Value {
+    let utxo: UtxoResponse = utxo_response(utxo.clone(), "");
+    let inline_datum: Value = utxo
+        .inline_datum
+        .map(|d| json!({ "bytes": d.bytes, "value": d.value }))
+        .unwrap_or(Value::Null);
+    json!({
+        "tx_hash": utxo.tx_hash,
+        "tx_index": utxo.tx_index,
+        "inline_datum": inline_datum,
+        "value": utxo.value,
+        "asset_list": utxo.asset_list,
+    })
+}
+
+impl ChainProvider for Blockfrost {
+    type Error = BlockfrostError;
+
+    async fn tip(&self) -> Result<Vec<BlockchainTip>, Self::Error> {
+        Ok(self
+            .latest_block()
+            .await?
+            .map(|block| BlockchainTip {
+                hash: block.hash,
+                epoch_no: block.epoch.unwrap_or_default(),
+                abs_slot: block.slot.unwrap_or_default(),
+                epoch_slot: block.epoch_slot.unwrap_or_default(),
+                block_no: block.height.unwrap_or_default(),
+                block_time: block.time,
+            })
+            .into_iter()
+            .collect())
+    }
+
+    async fn credential_utxos(
+        &self,
+        payment_credential: &str,
+    ) -> Result<Vec<UtxoResponse>, Self::Error> {
+        // the wallet contract is a script, so the credential is always a script hash
+        let Some(credential) = script_credential(payment_credential) else {
+            return Ok(Vec::new());
+        };
+        let utxos: Vec<BlockfrostUtxo> = self
+            .get_all_pages(&format!("addresses/{credential}/utxos"))
+            .await?;
+        Ok(utxos.into_iter().map(|u| utxo_response(u, "")).collect())
+    }
+
+    async fn address_utxos(&self, address: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        let utxos: Vec<BlockfrostUtxo> = self
+            .get_all_pages(&format!("addresses/{address}/utxos"))
+            .await?;
+        Ok(utxos.into_iter().map(|u| utxo_response(u, "")).collect())
+    }
+
+    async fn utxo_info(&self, utxo: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        let Some((tx_hash, index)) = utxo.split_once('#') else {
+            return Ok(Vec::new());
+        };
+        let index: u64 = index.parse().unwrap_or(u64::MAX);
+        let tx: Option<BlockfrostTxUtxos> = self.get_json(&format!("txs/{tx_hash}/utxos")).await?;
+        Ok(tx
+            .into_iter()
+            .flat_map(|tx| tx.outputs)
+            .filter(|output| output.output_index == index)
+            .map(|output| utxo_response(output, tx_hash))
+            .collect())
+    }
+
+    async fn datum_from_datum_hash(
+        &self,
+        datum_hash: String,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        let datum: Option<BlockfrostDatumCbor> = self
+            .get_json(&format!("scripts/datum/{datum_hash}/cbor"))
+            .await?;
+        Ok(datum
+            .and_then(|d| inline_datum_from_cbor(&d.cbor))
+            .map(|d| ResolvedDatum {
+                datum_hash: Some(datum_hash),
+                creation_tx_hash: String::new(),
+                value: d.value,
+                bytes: Some(d.bytes),
+            })
+            .into_iter()
+            .collect())
+    }
+
+    async fn evaluate_transaction(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        let response: Response = self
+            .post("utils/txs/evaluate?version=6")
+            .header("Content-Type", "application/cbor")
+            .body(tx_cbor)
+            .send()
+            .await?;
+        let value: Value = response.json().await?;
+        Ok(evaluation_to_ogmios(value))
+    }
+
+    async fn submit_tx(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        // Decode the hex string into binary data
+        let data: Vec<u8> = hex::decode(&tx_cbor)
+            .map_err(|e| BlockfrostError::Rejected(format!("Invalid Transaction Hex: {e}")))?;
+
+        let response: Response = self
+            .post("tx/submit")
+            .header("Content-Type", "application/cbor")
+            .body(data)
+            .send()
+            .await?;
+
+        // a refused transaction comes back as a 400 with the ledger error in the body
+        if !response.status().is_success() {
+            return Err(BlockfrostError::Rejected(response.text().await?));
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn address_transactions(&self, address: String) -> Result<Vec<TxResponse>, Self::Error> {
+        let txs: Vec<BlockfrostAddressTx> = self
+            .get_all_pages(&format!("addresses/{address}/transactions?order=desc"))
+            .await?;
+
+        let mut all_txs: Vec<TxResponse> = Vec::new();
+        for tx in txs {
+            let Some(detail) = self
+                .get_json::<BlockfrostTx>(&format!("txs/{}", tx.tx_hash))
+                .await?
+            else {
+                continue;
+            };
+            let Some(utxos) = self
+                .get_json::<BlockfrostTxUtxos>(&format!("txs/{}/utxos", tx.tx_hash))
+                .await?
+            else {
+                continue;
+            };
+            all_txs.push(TxResponse::from_info_response(TxInfoResponse {
+                tx_hash: utxos.hash,
+                block_height: detail.block_height,
+                block_time: detail.block_time,
+                fee: detail.fees,
+                inputs: utxos.inputs.iter().map(tx_info_output).collect(),
+                outputs: utxos.outputs.iter().map(tx_info_output).collect(),
+            }));
+        }
+
+        Ok(all_txs)
+    }
+
+    async fn transaction_status(&self, tx_hash: String) -> Result<Vec<TxStatus>, Self::Error> {
+        let tx: Option<BlockfrostTx> = self.get_json(&format!("txs/{tx_hash}")).await?;
+        let num_confirmations: Option<u64> = match tx {
+            Some(tx) => self
+                .latest_block()
+                .await?
+                .and_then(|block| block.height)
+                .map(|height| height.saturating_sub(tx.block_height) + 1),
+            None => None,
+        };
+        Ok(vec![TxStatus {
+            tx_hash,
+            num_confirmations,
+        }])
+    }
+
+    async fn protocol_parameters(&self) -> Result<Option<ProtocolParameters>, Self::Error> {
+        let parameters: Option<Value> = self.get_json("epochs/latest/parameters").await?;
+        Ok(parameters.and_then(|parameters| ProtocolParameters::from_blockfrost(&parameters)))
+    }
+}
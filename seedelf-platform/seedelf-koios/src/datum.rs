@@ -0,0 +1,182 @@
+use crate::koios::{InlineDatum, ResolvedDatum, UtxoResponse};
+use crate::provider::ChainProvider;
+use pallas_codec::minicbor;
+use pallas_crypto::hash::Hasher;
+use pallas_primitives::{BigInt, PlutusData};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Datum hashes sent in one request.
+pub const DATUM_BATCH_SIZE: usize = 50;
+
+/// Datums resolved so far. They are content addressed, so an entry never goes stale.
+static RESOLVED_DATUMS: LazyLock<Mutex<HashMap<String, InlineDatum>>> =
+    LazyLock::new(Default::default);
+
+/// Fills in the datum of every UTxO that only carries a datum hash.
+///
+/// Unknown hashes are resolved in batches of [`DATUM_BATCH_SIZE`] and remembered
+/// for the rest of the process. A datum whose bytes do not hash to the requested
+/// hash is dropped. Resolved UTxOs are marked with `datum_resolved` so a spend
+/// knows to put the datum in the witness set.
+///
+/// # Arguments
+///
+/// * `provider` - The chain data backend.
+/// * `utxos` - The UTxOs to fill in.
+///
+/// # Returns
+///
+/// * `Ok(())` - Once every resolvable datum is filled in.
+/// * `Err(P::Error)` - If a batch could not be fetched.
+pub async fn resolve_datum_hashes<P: ChainProvider>(
+    provider: &P,
+    utxos: &mut [UtxoResponse],
+) -> Result<(), P::Error> {
+    let mut missing: Vec<String> = {
+        let resolved = RESOLVED_DATUMS.lock().unwrap_or_else(|e| e.into_inner());
+        utxos
+            .iter()
+            .filter(|utxo| utxo.inline_datum.is_none())
+            .filter_map(|utxo| utxo.datum_hash.clone())
+            .filter(|hash| !resolved.contains_key(hash))
+            .collect()
+    };
+    missing.sort_unstable();
+    missing.dedup();
+
+    for batch in missing.chunks(DATUM_BATCH_SIZE) {
+        let datums: Vec<ResolvedDatum> = provider.datums_from_datum_hashes(batch.to_vec()).await?;
+        let mut resolved = RESOLVED_DATUMS.lock().unwrap_or_else(|e| e.into_inner());
+        for datum in datums {
+            if let (Some(hash), Some(bytes)) = (datum.datum_hash, datum.bytes)
+                && hashes_to(&bytes, &hash)
+            {
+                resolved.insert(
+                    hash,
+                    InlineDatum {
+                        bytes,
+                        value: datum.value,
+                    },
+                );
+            }
+        }
+    }
+
+    let resolved = RESOLVED_DATUMS.lock().unwrap_or_else(|e| e.into_inner());
+    for utxo in utxos.iter_mut().filter(|utxo| utxo.inline_datum.is_none()) {
+        if let Some(datum) = utxo.datum_hash.as_ref().and_then(|hash| resolved.get(hash)) {
+            utxo.inline_datum = Some(datum.clone());
+            utxo.datum_resolved = true;
+        }
+    }
+    Ok(())
+}
+
+fn hashes_to(bytes: &str, datum_hash: &str) -> bool {
+    hex::decode(bytes).is_ok_and(|bytes| hex::encode(Hasher::<256>::hash(&bytes)) == datum_hash)
+}
+
+/// Converts a CBOR encoded datum into the detailed JSON schema Koios returns.
+///
+/// Backends that only serve raw datum bytes go through this so `Register`
+/// extraction works the same no matter where the datum came from.
+///
+/// # Arguments
+///
+/// * `cbor` - The hex encoded datum.
+///
+/// # Returns
+///
+/// * `Some(Value)` - The datum as `{"constructor": .., "fields": [..]}` style JSON.
+/// * `None` - If the hex or CBOR is invalid.
+pub fn cbor_to_json(cbor: &str) -> Option<Value> {
+    let bytes: Vec<u8> = hex::decode(cbor).ok()?;
+    let data: PlutusData = minicbor::decode(&bytes).ok()?;
+    Some(plutus_data_to_json(&data))
+}
+
+/// Builds the Koios `inline_datum` object from a CBOR encoded datum.
+///
+/// # Arguments
+///
+/// * `cbor` - The hex encoded datum.
+///
+/// # Returns
+///
+/// * `Some(InlineDatum)` - The raw bytes together with the decoded JSON value.
+/// * `None` - If the datum can not be decoded.
+pub fn inline_datum_from_cbor(cbor: &str) -> Option<InlineDatum> {
+    Some(InlineDatum {
+        bytes: cbor.to_string(),
+        value: cbor_to_json(cbor)?,
+    })
+}
+
+/// Converts `PlutusData` into the detailed JSON schema.
+pub fn plutus_data_to_json(data: &PlutusData) -> Value {
+    match data {
+        PlutusData::Constr(constr) => {
+            let constructor: u64 = match constr.tag {
+                121..=127 => constr.tag - 121,
+                1280..=1400 => constr.tag - 1280 + 7,
+                _ => constr.any_constructor.unwrap_or_default(),
+            };
+            let fields: Vec<Value> = constr.fields.iter().map(plutus_data_to_json).collect();
+            json!({ "constructor": constructor, "fields": fields })
+        }
+        PlutusData::Map(pairs) => {
+            let entries: Vec<Value> = pairs
+                .iter()
+                .map(|(k, v)| json!({ "k": plutus_data_to_json(k), "v": plutus_data_to_json(v) }))
+                .collect();
+            json!({ "map": entries })
+        }
+        PlutusData::Array(items) => {
+            let list: Vec<Value> = items.iter().map(plutus_data_to_json).collect();
+            json!({ "list": list })
+        }
+        PlutusData::BigInt(BigInt::Int(int)) => int_to_json(i128::from(*int)),
+        PlutusData::BigInt(BigInt::BigUInt(bytes)) => big_to_json(bytes, false),
+        PlutusData::BigInt(BigInt::BigNInt(bytes)) => big_to_json(bytes, true),
+        PlutusData::BoundedBytes(bytes) => json!({ "bytes": hex::encode(bytes.as_slice()) }),
+    }
+}
+
+fn int_to_json(n: i128) -> Value {
+    // json numbers stop at 64 bits so anything bigger is kept as a string
+    if let Ok(n) = i64::try_from(n) {
+        json!({ "int": n })
+    } else if let Ok(n) = u64::try_from(n) {
+        json!({ "int": n })
+    } else {
+        json!({ "int": n.to_string() })
+    }
+}
+
+fn big_to_json(bytes: &[u8], negative: bool) -> Value {
+    let trimmed: &[u8] = match bytes.iter().position(|b| *b != 0) {
+        Some(start) => &bytes[start..],
+        None => &[],
+    };
+    let magnitude: Option<i128> = if trimmed.len() <= 16 {
+        i128::try_from(
+            trimmed
+                .iter()
+                .fold(0u128, |acc, b| (acc << 8) | u128::from(*b)),
+        )
+        .ok()
+    } else {
+        None
+    };
+    // a cbor negative bignum holds n for the value -1 - n
+    match magnitude {
+        Some(n) if negative => int_to_json(-1 - n),
+        Some(n) => int_to_json(n),
+        None => {
+            let sign: &str = if negative { "-1-" } else { "" };
+            json!({ "int": format!("{sign}0x{}", hex::encode(trimmed)) })
+        }
+    }
+}
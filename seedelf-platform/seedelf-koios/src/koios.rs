@@ -1,8 +1,400 @@
+use crate::parameters::ProtocolParameters;
+use futures_util::future;
+use futures_util::stream::{self, Stream, StreamExt, TryStreamExt};
 use hex;
-use reqwest::{Client, Error, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use seedelf_crypto::register::Register;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::time::Duration;
+
+/// Environment variable holding a custom Koios base URL.
+pub const KOIOS_URL_ENV: &str = "SEEDELF_KOIOS_URL";
+/// Environment variable holding a Koios bearer token.
+pub const KOIOS_TOKEN_ENV: &str = "SEEDELF_KOIOS_TOKEN";
+/// Environment variable holding extra Koios headers, `Name: Value` separated by `;`.
+pub const KOIOS_HEADERS_ENV: &str = "SEEDELF_KOIOS_HEADERS";
+/// Environment variable holding the Koios request timeout in seconds.
+pub const KOIOS_TIMEOUT_ENV: &str = "SEEDELF_KOIOS_TIMEOUT";
+/// Environment variable holding how many times a failed Koios query is retried.
+pub const KOIOS_RETRIES_ENV: &str = "SEEDELF_KOIOS_RETRIES";
+/// Environment variable holding the rows per page of a paginated Koios query.
+pub const KOIOS_PAGE_SIZE_ENV: &str = "SEEDELF_KOIOS_PAGE_SIZE";
+/// Environment variable holding how many pages are fetched at once.
+pub const KOIOS_CONCURRENCY_ENV: &str = "SEEDELF_KOIOS_CONCURRENCY";
+
+/// How long a single request may take before it is abandoned.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// How many times an idempotent query is retried after a transient failure.
+pub const DEFAULT_RETRIES: u32 = 3;
+/// The most rows Koios returns for a single request.
+pub const MAX_PAGE_SIZE: usize = 1000;
+/// How many pages of a paginated query are fetched at once.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+// the first retry waits about half a second, doubling up to the cap
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_CAP: Duration = Duration::from_secs(16);
+const RETRY_AFTER_CAP: Duration = Duration::from_secs(60);
+
+/// The error returned by a Koios request.
+#[derive(Debug)]
+pub enum KoiosError {
+    /// The request could not be sent, or no response came back in time.
+    Transport(reqwest::Error),
+    /// The server answered with an unexpected HTTP status.
+    Status { status: StatusCode, body: String },
+    /// The server kept answering 429 Too Many Requests.
+    RateLimited { retry_after: Option<Duration> },
+    /// The response was not the expected JSON.
+    Decode(serde_json::Error),
+    /// The node refused a submitted transaction, holding the ledger error.
+    NodeRejected(String),
+}
+
+impl fmt::Display for KoiosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KoiosError::Transport(err) if err.is_timeout() => write!(f, "Request Timed Out: {err}"),
+            KoiosError::Transport(err) => write!(f, "Request Failed: {err}"),
+            KoiosError::Status { status, body } => {
+                write!(f, "Server Returned {status}: {}", body.trim())
+            }
+            KoiosError::RateLimited {
+                retry_after: Some(wait),
+            } => write!(f, "Rate Limited, Retry After {}s", wait.as_secs()),
+            KoiosError::RateLimited { retry_after: None } => write!(f, "Rate Limited"),
+            KoiosError::Decode(err) => write!(f, "Invalid Response: {err}"),
+            KoiosError::NodeRejected(body) => write!(f, "Transaction Rejected: {}", body.trim()),
+        }
+    }
+}
+
+impl std::error::Error for KoiosError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KoiosError::Transport(err) => Some(err),
+            KoiosError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for KoiosError {
+    fn from(err: reqwest::Error) -> Self {
+        KoiosError::Transport(err)
+    }
+}
+
+impl From<serde_json::Error> for KoiosError {
+    fn from(err: serde_json::Error) -> Self {
+        KoiosError::Decode(err)
+    }
+}
+
+impl KoiosError {
+    /// Whether sending the same request again may succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            KoiosError::Transport(err) => {
+                err.is_timeout() || err.is_connect() || err.is_request() || err.is_body()
+            }
+            KoiosError::Status { status, .. } => status.is_server_error(),
+            KoiosError::RateLimited { .. } => true,
+            KoiosError::Decode(_) | KoiosError::NodeRejected(_) => false,
+        }
+    }
+}
+
+/// How long to wait before retry number `attempt`, counting from zero.
+///
+/// The wait doubles from half a second up to a cap, or follows the server's
+/// `Retry-After`, and up to half of it again is added at random so many clients
+/// do not retry in lockstep.
+pub fn backoff_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    let delay: Duration = match retry_after {
+        Some(wait) => wait.min(RETRY_AFTER_CAP),
+        None => BACKOFF_BASE
+            .saturating_mul(1 << attempt.min(16))
+            .min(BACKOFF_CAP),
+    };
+    let spread: u64 = delay.as_millis() as u64 / 2 + 1;
+    let jitter: u64 = RandomState::new().hash_one(attempt) % spread;
+    delay + Duration::from_millis(jitter)
+}
+
+/// Sends a request, retrying transient failures up to `retries` times.
+///
+/// # Arguments
+///
+/// * `request` - The request, its body must not be a stream so it can be cloned.
+/// * `retries` - How many times to retry, zero for anything that is not idempotent.
+///
+/// # Returns
+///
+/// * `Ok(String)` - The body of a successful response.
+/// * `Err(KoiosError)` - The last failure once the retries run out.
+async fn send(request: RequestBuilder, retries: u32) -> Result<String, KoiosError> {
+    let mut attempt: u32 = 0;
+    loop {
+        let result: Result<String, KoiosError> = match request.try_clone() {
+            Some(request) => send_once(request).await,
+            None => return send_once(request).await,
+        };
+        match result {
+            Err(err) if attempt < retries && err.is_transient() => {
+                let retry_after: Option<Duration> = match err {
+                    KoiosError::RateLimited { retry_after } => retry_after,
+                    _ => None,
+                };
+                tokio::time::sleep(backoff_delay(attempt, retry_after)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn send_once(request: RequestBuilder) -> Result<String, KoiosError> {
+    let response: Response = request.send().await?;
+    let status: StatusCode = response.status();
+    let retry_after: Option<Duration> = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    let body: String = response.text().await?;
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(KoiosError::RateLimited { retry_after });
+    }
+    if !status.is_success() {
+        return Err(KoiosError::Status { status, body });
+    }
+    Ok(body)
+}
+
+/// A Koios API client.
+///
+/// Holds the base URL, an optional bearer token, any extra request headers and a
+/// shared `reqwest::Client`, so a self-hosted instance, a paid tier or a regional
+/// mirror can be used in place of the public endpoint. Cloning is cheap and the
+/// clones share the same connection pool.
+///
+/// Every request has a timeout and queries are retried with backoff when Koios
+/// is unreachable, overloaded or rate limiting. Submissions are only sent once.
+///
+/// This is the default `ChainProvider` for the wallet.
+#[derive(Clone)]
+pub struct Koios {
+    base_url: String,
+    token: Option<String>,
+    headers: HeaderMap,
+    client: Client,
+    timeout: Duration,
+    retries: u32,
+    page_size: usize,
+    concurrency: usize,
+}
+
+impl fmt::Debug for Koios {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print the token
+        f.debug_struct("Koios")
+            .field("base_url", &self.base_url)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .field("page_size", &self.page_size)
+            .field("concurrency", &self.concurrency)
+            .finish()
+    }
+}
+
+impl Koios {
+    /// Creates a client for the public Koios API on Preprod (`true`) or Mainnet (`false`).
+    pub fn new(network_flag: bool) -> Self {
+        Self {
+            base_url: Self::public_url(network_flag),
+            token: None,
+            headers: HeaderMap::new(),
+            client: Client::new(),
+            timeout: DEFAULT_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            page_size: MAX_PAGE_SIZE,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Creates a client from the `SEEDELF_KOIOS_*` environment variables, falling back
+    /// to the public Koios API for anything that is not set.
+    ///
+    /// # Arguments
+    ///
+    /// * `network_flag` - A boolean flag specifying the network:
+    ///     - `true` for Preprod/Testnet.
+    ///     - `false` for Mainnet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Koios)` - The configured client.
+    /// * `Err(String)` - If a header or any of the numeric settings is malformed.
+    pub fn from_env(network_flag: bool) -> Result<Self, String> {
+        let mut koios: Koios = Self::new(network_flag);
+        if let Some(url) = env_value(KOIOS_URL_ENV) {
+            koios = koios.with_base_url(url);
+        }
+        if let Some(token) = env_value(KOIOS_TOKEN_ENV) {
+            koios = koios.with_token(token);
+        }
+        if let Some(headers) = env_value(KOIOS_HEADERS_ENV) {
+            for header in headers.split(';').filter(|h| !h.trim().is_empty()) {
+                let (name, value) = parse_header(header)?;
+                koios = koios.with_header(name, value);
+            }
+        }
+        if let Some(seconds) = env_value(KOIOS_TIMEOUT_ENV) {
+            let seconds: u64 = seconds
+                .trim()
+                .parse()
+                .map_err(|e| format!("Invalid {KOIOS_TIMEOUT_ENV}: {e}"))?;
+            koios = koios.with_timeout(Duration::from_secs(seconds));
+        }
+        if let Some(retries) = env_value(KOIOS_RETRIES_ENV) {
+            let retries: u32 = retries
+                .trim()
+                .parse()
+                .map_err(|e| format!("Invalid {KOIOS_RETRIES_ENV}: {e}"))?;
+            koios = koios.with_retries(retries);
+        }
+        if let Some(page_size) = env_value(KOIOS_PAGE_SIZE_ENV) {
+            let page_size: usize = page_size
+                .trim()
+                .parse()
+                .map_err(|e| format!("Invalid {KOIOS_PAGE_SIZE_ENV}: {e}"))?;
+            koios = koios.with_page_size(page_size);
+        }
+        if let Some(concurrency) = env_value(KOIOS_CONCURRENCY_ENV) {
+            let concurrency: usize = concurrency
+                .trim()
+                .parse()
+                .map_err(|e| format!("Invalid {KOIOS_CONCURRENCY_ENV}: {e}"))?;
+            koios = koios.with_concurrency(concurrency);
+        }
+        Ok(koios)
+    }
+
+    /// The public Koios base URL for a network.
+    pub fn public_url(network_flag: bool) -> String {
+        let network: &str = if network_flag { "preprod" } else { "api" };
+        format!("https://{network}.koios.rest/api/v1")
+    }
+
+    /// Uses a different base URL, e.g. `https://koios.example.com/api/v1`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every request.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Sends an extra header with every request.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Uses an existing `reqwest::Client` so its connection pool is shared.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Abandons any single request that takes longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retries a failed query up to `retries` times, zero turns retrying off.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Requests `page_size` rows per page of a paginated query, at most 1000.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        self
+    }
+
+    /// Fetches up to `concurrency` pages of a paginated query at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The base URL every endpoint is joined onto.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get(&self, endpoint: &str) -> RequestBuilder {
+        self.authorize(self.client.get(format!("{}/{endpoint}", self.base_url)))
+    }
+
+    fn post(&self, endpoint: &str) -> RequestBuilder {
+        self.authorize(self.client.post(format!("{}/{endpoint}", self.base_url)))
+    }
+
+    /// Sends an idempotent query and decodes its JSON response.
+    async fn query<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, KoiosError> {
+        let body: String = send(request, self.retries).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        let request: RequestBuilder = request.headers(self.headers.clone()).timeout(self.timeout);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+fn env_value(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// Parses a `Name: Value` string into a request header.
+///
+/// # Arguments
+///
+/// * `header` - The header in `Name: Value` form.
+///
+/// # Returns
+///
+/// * `Ok((HeaderName, HeaderValue))` - The parsed header.
+/// * `Err(String)` - If the separator is missing or the name or value is invalid.
+pub fn parse_header(header: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| format!("Header Must Be `Name: Value`: {header}"))?;
+    let name: HeaderName = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|e| format!("Invalid Header Name {name}: {e}"))?;
+    let value: HeaderValue =
+        HeaderValue::from_str(value.trim()).map_err(|e| format!("Invalid Header Value: {e}"))?;
+    Ok((name, value))
+}
 
 /// Represents the latest blockchain tip information from Koios.
 #[derive(Deserialize, Debug)]
@@ -29,21 +421,13 @@ pub struct BlockchainTip {
 /// # Returns
 ///
 /// * `Ok(Vec<BlockchainTip>)` - A vector containing the latest blockchain tip data.
-/// * `Err(Error)` - If the API request or JSON parsing fails.
-pub async fn tip(network_flag: bool) -> Result<Vec<BlockchainTip>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    let url: String = format!("https://{network}.koios.rest/api/v1/tip");
-
-    // Make the GET request and parse the JSON response
-    let response: Vec<BlockchainTip> = reqwest::get(&url)
-        .await?
-        .json::<Vec<BlockchainTip>>()
-        .await?;
-
-    Ok(response)
+/// * `Err(KoiosError)` - If the API request or JSON parsing fails.
+pub async fn tip(network_flag: bool) -> Result<Vec<BlockchainTip>, KoiosError> {
+    Koios::new(network_flag).tip().await
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct Asset {
     pub decimals: u8,
     pub quantity: String,
@@ -74,121 +458,31 @@ pub struct UtxoResponse {
     pub reference_script: Option<Value>, // Flexible for arbitrary scripts
     pub asset_list: Option<Vec<Asset>>,
     pub is_spent: bool,
+    /// `inline_datum` was resolved from `datum_hash`, a spend must put it in the witness set.
+    #[serde(default)]
+    pub datum_resolved: bool,
 }
 
 /// Fetches the UTXOs associated with a given payment credential from the Koios API.
 ///
-/// This function collects all UTXOs (Unspent Transaction Outputs) related to the specified
-/// payment credential by paginating through the Koios API results.
-///
-/// # Arguments
-///
-/// * `payment_credential` - A string slice representing the payment credential to search for.
-/// * `network_flag` - A boolean flag specifying the network:
-///     - `true` for Preprod/Testnet.
-///     - `false` for Mainnet.
-///
-/// # Returns
-///
-/// * `Ok(Vec<UtxoResponse>)` - A vector containing all UTXOs associated with the payment credential.
-/// * `Err(Error)` - If the API request or JSON parsing fails.
-///
-/// # Behavior
-///
-/// The function paginates through the UTXO results, starting with an offset of zero
-/// and incrementing by 1000 until no further results are returned.
+/// See [`Koios::credential_utxos`].
 pub async fn credential_utxos(
     payment_credential: &str,
     network_flag: bool,
-) -> Result<Vec<UtxoResponse>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    // this is searching the wallet contract. We have to collect the entire utxo set to search it.
-    let url: String = format!("https://{network}.koios.rest/api/v1/credential_utxos");
-    let client: Client = reqwest::Client::new();
-
-    // Prepare the request payload
-    let payload: Value = serde_json::json!({
-        "_payment_credentials": [payment_credential],
-        "_extended": true
-    });
-
-    let mut all_utxos: Vec<UtxoResponse> = Vec::new();
-    let mut offset: i32 = 0;
-
-    loop {
-        // Make the POST request
-        let response: Response = client
-            .post(url.clone())
-            .header("accept", "application/json")
-            .header("content-type", "application/json")
-            .query(&[("offset", offset.to_string())])
-            .json(&payload)
-            .send()
-            .await?;
-
-        let mut utxos: Vec<UtxoResponse> = response.json().await?;
-        // Break the loop if no more results
-        if utxos.is_empty() {
-            break;
-        }
-
-        // Append the retrieved UTXOs to the main list
-        all_utxos.append(&mut utxos);
-
-        // Increment the offset by 1000 (page size)
-        offset += 1000;
-    }
-
-    Ok(all_utxos)
+) -> Result<Vec<UtxoResponse>, KoiosError> {
+    Koios::new(network_flag)
+        .credential_utxos(payment_credential)
+        .await
 }
 
 /// Fetches the UTXOs associated with a specific address from the Koios API.
 ///
-/// This function retrieves up to 1000 UTXOs for the given address. The `_extended` flag
-/// is enabled in the payload to include detailed UTXO information.
-///
-/// # Arguments
-///
-/// * `address` - A string slice representing the Cardano address to query.
-/// * `network_flag` - A boolean flag specifying the network:
-///     - `true` for Preprod/Testnet.
-///     - `false` for Mainnet.
-///
-/// # Returns
-///
-/// * `Ok(Vec<UtxoResponse>)` - A vector containing the UTXOs associated with the given address.
-/// * `Err(Error)` - If the API request or JSON parsing fails.
-///
-/// # Notes
-///
-/// The function assumes a maximum of 1000 UTXOs per address, as per CIP-30 wallets.
-/// If an address exceeds this limit, the wallet is likely mismanaged.
-pub async fn address_utxos(address: &str, network_flag: bool) -> Result<Vec<UtxoResponse>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    // this will limit to 1000 utxos which is ok for an address as that is a cip30 wallet
-    // if you have 1000 utxos in that wallets that cannot pay for anything then something
-    // is wrong in that wallet
-    let url: String = format!("https://{network}.koios.rest/api/v1/address_utxos");
-    let client: Client = reqwest::Client::new();
-
-    // Prepare the request payload
-    let payload: Value = serde_json::json!({
-        "_addresses": [address],
-        "_extended": true
-    });
-
-    // Make the POST request
-    let response: Response = client
-        .post(url)
-        .header("accept", "application/json")
-        .header("content-type", "application/json")
-        .json(&payload)
-        .send()
-        .await?;
-
-    let utxos: Vec<UtxoResponse> = response.json().await?;
-
-    Ok(utxos)
+/// See [`Koios::address_utxos`].
+pub async fn address_utxos(
+    address: &str,
+    network_flag: bool,
+) -> Result<Vec<UtxoResponse>, KoiosError> {
+    Koios::new(network_flag).address_utxos(address).await
 }
 
 /// Extracts byte values from an `InlineDatum` with detailed logging.
@@ -269,53 +563,12 @@ pub fn contains_policy_id(asset_list: &Option<Vec<Asset>>, target_policy_id: &st
 
 /// Evaluates a transaction using the Koios API.
 ///
-/// This function sends a CBOR-encoded transaction to the Koios API for evaluation.
-/// The API uses Ogmios to validate and evaluate the transaction. The target network
-/// is determined by the `network_flag`.
-///
-/// # Arguments
-///
-/// * `tx_cbor` - A string containing the CBOR-encoded transaction.
-/// * `network_flag` - A boolean flag specifying the network:
-///     - `true` for Preprod/Testnet.
-///     - `false` for Mainnet.
-///
-/// # Returns
-///
-/// * `Ok(Value)` - A JSON response containing the evaluation result.
-/// * `Err(Error)` - If the API request fails or the JSON parsing fails.
-///
-/// # Behavior
-///
-/// The function constructs a JSON-RPC request payload and sends a POST request
-/// to the Koios Ogmios endpoint.
-pub async fn evaluate_transaction(tx_cbor: String, network_flag: bool) -> Result<Value, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-
-    // Prepare the request payload
-    let payload: Value = serde_json::json!({
-        "jsonrpc": "2.0",
-        "method": "evaluateTransaction",
-        "params": {
-            "transaction": {
-                "cbor": tx_cbor
-            }
-        }
-    });
-
-    let url: String = format!("https://{network}.koios.rest/api/v1/ogmios");
-    let client: Client = reqwest::Client::new();
-
-    // Make the POST request
-    let response: Response = client
-        .post(url)
-        .header("accept", "application/json")
-        .header("content-type", "application/json")
-        .json(&payload)
-        .send()
-        .await?;
-
-    response.json().await
+/// See [`Koios::evaluate_transaction`].
+pub async fn evaluate_transaction(
+    tx_cbor: String,
+    network_flag: bool,
+) -> Result<Value, KoiosError> {
+    Koios::new(network_flag).evaluate_transaction(tx_cbor).await
 }
 
 /// Submits a transaction body to witness collateral using a specified API endpoint.
@@ -333,13 +586,13 @@ pub async fn evaluate_transaction(tx_cbor: String, network_flag: bool) -> Result
 /// # Returns
 ///
 /// * `Ok(Value)` - A JSON response from the API, containing collateral witnessing results.
-/// * `Err(Error)` - If the API request fails or the response JSON parsing fails.
+/// * `Err(KoiosError)` - If the API request fails or the response JSON parsing fails.
 ///
 /// # Behavior
 ///
 /// The function constructs a JSON payload containing the transaction body and sends
 /// it to the specified API endpoint using a POST request.
-pub async fn witness_collateral(tx_cbor: String, network_flag: bool) -> Result<Value, Error> {
+pub async fn witness_collateral(tx_cbor: String, network_flag: bool) -> Result<Value, KoiosError> {
     let network: &str = if network_flag { "preprod" } else { "mainnet" };
     let url: String = format!("https://www.giveme.my/{network}/collateral/");
     let client: Client = reqwest::Client::new();
@@ -348,55 +601,21 @@ pub async fn witness_collateral(tx_cbor: String, network_flag: bool) -> Result<V
         "tx_body": tx_cbor,
     });
 
-    // Make the POST request
-    let response: Response = client
+    // Make the POST request, the service signs each body once so it is not retried
+    let request: RequestBuilder = client
         .post(url)
         .header("content-type", "application/json")
-        .json(&payload)
-        .send()
-        .await?;
+        .timeout(DEFAULT_TIMEOUT)
+        .json(&payload);
 
-    response.json().await
+    Ok(serde_json::from_str(&send(request, 0).await?)?)
 }
 
 /// Submits a CBOR-encoded transaction to the Koios API.
 ///
-/// This function decodes the provided CBOR-encoded transaction from a hex string into binary
-/// data and sends it to the Koios API for submission. The target network (Preprod or Mainnet)
-/// is determined by the `network_flag`.
-///
-/// # Arguments
-///
-/// * `tx_cbor` - A string containing the hex-encoded CBOR transaction.
-/// * `network_flag` - A boolean flag specifying the network:
-///     - `true` for Preprod.
-///     - `false` for Mainnet.
-///
-/// # Returns
-///
-/// * `Ok(Value)` - A JSON response from the API indicating the result of the transaction submission.
-/// * `Err(Error)` - If the API request fails or the response JSON parsing fails.
-///
-/// # Behavior
-///
-/// - Decodes the transaction CBOR hex string into raw binary data.
-/// - Sends the binary data as the body of a POST request with `Content-Type: application/cbor`.
-pub async fn submit_tx(tx_cbor: String, network_flag: bool) -> Result<Value, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    let url: String = format!("https://{network}.koios.rest/api/v1/submittx");
-    let client: Client = reqwest::Client::new();
-
-    // Decode the hex string into binary data
-    let data: Vec<u8> = hex::decode(&tx_cbor).unwrap();
-
-    let response: Response = client
-        .post(url)
-        .header("Content-Type", "application/cbor")
-        .body(data) // Send the raw binary data as the body of the request
-        .send()
-        .await?;
-
-    response.json().await
+/// See [`Koios::submit_tx`].
+pub async fn submit_tx(tx_cbor: String, network_flag: bool) -> Result<Value, KoiosError> {
+    Koios::new(network_flag).submit_tx(tx_cbor).await
 }
 
 pub async fn ada_handle_address(
@@ -407,88 +626,19 @@ pub async fn ada_handle_address(
     wallet_addr: String,
     ada_handle_policy_id: &str,
 ) -> Result<String, String> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    let token_name: String = if cip68_flag {
-        "000de140".to_string() + &hex::encode(asset_name.clone())
-    } else {
-        hex::encode(asset_name.clone())
-    };
-    let url: String = format!(
-        "https://{network}.koios.rest/api/v1/asset_nft_address?_asset_policy={ada_handle_policy_id}&_asset_name={token_name}",
-    );
-    let client: Client = reqwest::Client::new();
-
-    let response: Response = match client
-        .get(url)
-        .header("Content-Type", "application/json")
-        .send()
+    Koios::new(network_flag)
+        .ada_handle_address(
+            asset_name,
+            cip68_flag,
+            variant,
+            wallet_addr,
+            ada_handle_policy_id,
+        )
         .await
-    {
-        Ok(resp) => resp,
-        Err(err) => return Err(format!("HTTP request failed: {err}")),
-    };
-
-    let outcome: Value = response.json().await.unwrap();
-    let vec_outcome = serde_json::from_value::<Vec<serde_json::Value>>(outcome)
-        .expect("Failed to parse outcome as Vec<Value>");
-
-    // Borrow from the longer-lived variable
-    let payment_address = match vec_outcome
-        .first()
-        .and_then(|obj| obj.get("payment_address"))
-        .and_then(|val| val.as_str())
-    {
-        Some(address) => address,
-        None => {
-            if cip68_flag {
-                return Err("Payment address not found".to_string());
-            } else {
-                return Box::pin(ada_handle_address(
-                    asset_name,
-                    network_flag,
-                    !cip68_flag,
-                    variant,
-                    wallet_addr,
-                    ada_handle_policy_id,
-                ))
-                .await;
-            }
-        }
-    };
-
-    if payment_address == wallet_addr {
-        Err("ADA Handle Is In Wallet Address".to_string())
-    } else {
-        Ok(payment_address.to_string())
-    }
 }
 
-pub async fn utxo_info(utxo: &str, network_flag: bool) -> Result<Vec<UtxoResponse>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    // this will limit to 1000 utxos which is ok for an address as that is a cip30 wallet
-    // if you have 1000 utxos in that wallets that cannot pay for anything then something
-    // is wrong in that wallet
-    let url: String = format!("https://{network}.koios.rest/api/v1/utxo_info");
-    let client: Client = reqwest::Client::new();
-
-    // Prepare the request payload
-    let payload: Value = serde_json::json!({
-        "_utxo_refs": [utxo],
-        "_extended": true
-    });
-
-    // Make the POST request
-    let response: Response = client
-        .post(url)
-        .header("accept", "application/json")
-        .header("content-type", "application/json")
-        .json(&payload)
-        .send()
-        .await?;
-
-    let utxos: Vec<UtxoResponse> = response.json().await?;
-
-    Ok(utxos)
+pub async fn utxo_info(utxo: &str, network_flag: bool) -> Result<Vec<UtxoResponse>, KoiosError> {
+    Koios::new(network_flag).utxo_info(utxo).await
 }
 
 // make it so it only works for nfts
@@ -496,33 +646,10 @@ pub async fn nft_utxo(
     policy_id: String,
     token_name: String,
     network_flag: bool,
-) -> Result<Vec<UtxoResponse>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    let url: String = format!("https://{network}.koios.rest/api/v1/asset_utxos");
-    let client: Client = reqwest::Client::new();
-
-    // Prepare the request payload
-    let payload: Value = serde_json::json!({
-        "_asset_list": [[policy_id, token_name]],
-        "_extended": true
-    });
-
-    // Make the POST request
-    let response: Response = client
-        .post(url)
-        .header("accept", "application/json")
-        .header("content-type", "application/json")
-        .json(&payload)
-        .send()
-        .await?;
-
-    let utxos: Vec<UtxoResponse> = response.json().await?;
-
-    if utxos.len() > 1 {
-        return Ok(vec![]);
-    }
-
-    Ok(utxos)
+) -> Result<Vec<UtxoResponse>, KoiosError> {
+    Koios::new(network_flag)
+        .nft_utxo(policy_id, token_name)
+        .await
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -536,27 +663,10 @@ pub struct ResolvedDatum {
 pub async fn datum_from_datum_hash(
     datum_hash: String,
     network_flag: bool,
-) -> Result<Vec<ResolvedDatum>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    let url: String = format!("https://{network}.koios.rest/api/v1/datum_info");
-    let client: Client = reqwest::Client::new();
-
-    // Prepare the request payload
-    let payload: Value = serde_json::json!({
-        "_datum_hashes": [datum_hash],
-    });
-
-    // Make the POST request
-    let response: Response = client
-        .post(url)
-        .header("accept", "application/json")
-        .header("content-type", "application/json")
-        .json(&payload)
-        .send()
-        .await?;
-
-    let datums: Vec<ResolvedDatum> = response.json().await?;
-    Ok(datums)
+) -> Result<Vec<ResolvedDatum>, KoiosError> {
+    Koios::new(network_flag)
+        .datum_from_datum_hash(datum_hash)
+        .await
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -572,22 +682,10 @@ pub async fn asset_history(
     token_name: String,
     network_flag: bool,
     limit: u64,
-) -> Result<Vec<History>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    let url: String = format!(
-        "https://{network}.koios.rest/api/v1/asset_txs?_asset_policy={policy_id}&_asset_name={token_name}&_after_block_height=50000&_history=true&limit={limit}"
-    );
-    let client: Client = reqwest::Client::new();
-
-    // Make the POST request
-    let response: Response = client
-        .get(url)
-        .header("content-type", "application/json")
-        .send()
-        .await?;
-
-    let data: Vec<History> = response.json().await.unwrap();
-    Ok(data)
+) -> Result<Vec<History>, KoiosError> {
+    Koios::new(network_flag)
+        .asset_history(policy_id, token_name, limit)
+        .await
 }
 
 pub fn extract_bytes_from_value_with_logging(value: &Value) -> Option<Register> {
@@ -621,45 +719,166 @@ pub fn extract_bytes_from_value_with_logging(value: &Value) -> Option<Register>
 pub struct TxInfoResponse {
     pub tx_hash: String,
     pub block_height: u64,
+    #[serde(default)]
+    pub block_time: u64,
+    /// The fee in lovelace as Koios sends it, empty when unknown.
+    #[serde(default)]
+    pub fee: String,
     pub inputs: Vec<serde_json::Value>,
     pub outputs: Vec<serde_json::Value>,
 }
 
+/// A register in a transaction with the value it holds.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RegisterValue {
+    /// The UTxO as `tx_hash#tx_index`, empty when the backend leaves it out.
+    pub tx_hash: String,
+    pub tx_index: u64,
+    pub register: Register,
+    pub lovelace: u64,
+    pub assets: Vec<Asset>,
+}
+
+impl RegisterValue {
+    /// Reads an input or output in the `tx_info` JSON shape, `None` without a register.
+    fn from_value(utxo: &Value) -> Option<Self> {
+        let register: Register = utxo
+            .get("inline_datum")
+            .and_then(extract_bytes_from_value_with_logging)?;
+        Some(RegisterValue {
+            tx_hash: utxo
+                .get("tx_hash")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            tx_index: utxo
+                .get("tx_index")
+                .and_then(Value::as_u64)
+                .unwrap_or_default(),
+            register,
+            lovelace: utxo
+                .get("value")
+                .and_then(Value::as_str)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+            assets: utxo
+                .get("asset_list")
+                .cloned()
+                .and_then(|assets| serde_json::from_value(assets).ok())
+                .unwrap_or_default(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct TxResponse {
     pub tx_hash: String,
     pub block_height: u64,
+    pub block_time: u64,
+    /// The fee in lovelace, `None` when the backend does not report it.
+    pub fee: Option<u64>,
     pub input_registers: Vec<Register>,
     pub output_registers: Vec<Register>,
+    /// The inputs holding a register, with their value.
+    pub inputs: Vec<RegisterValue>,
+    /// The outputs holding a register, with their value.
+    pub outputs: Vec<RegisterValue>,
 }
 
 impl TxResponse {
     pub fn from_info_response(info: TxInfoResponse) -> Self {
-        let input_registers = info
+        let inputs: Vec<RegisterValue> = info
             .inputs
             .iter()
-            .filter_map(|input| {
-                input
-                    .get("inline_datum")
-                    .and_then(extract_bytes_from_value_with_logging)
-            })
+            .filter_map(RegisterValue::from_value)
             .collect();
 
-        let output_registers = info
+        let outputs: Vec<RegisterValue> = info
             .outputs
             .iter()
-            .filter_map(|output| {
-                output
-                    .get("inline_datum")
-                    .and_then(extract_bytes_from_value_with_logging)
-            })
+            .filter_map(RegisterValue::from_value)
             .collect();
 
         TxResponse {
             tx_hash: info.tx_hash,
             block_height: info.block_height,
-            input_registers,
-            output_registers,
+            block_time: info.block_time,
+            fee: info.fee.parse().ok(),
+            input_registers: inputs.iter().map(|i| i.register.clone()).collect(),
+            output_registers: outputs.iter().map(|o| o.register.clone()).collect(),
+            inputs,
+            outputs,
+        }
+    }
+}
+
+/// A transaction that touched a payment credential.
+///
+/// The inputs and outputs are in the `UtxoResponse` shape, carrying the block of the
+/// transaction, so the ones at the credential can be fed to the ownership checks.
+#[derive(Debug, Clone, Default)]
+pub struct CredentialTx {
+    pub tx_hash: String,
+    pub block_height: u64,
+    pub block_time: u64,
+    /// The fee in lovelace.
+    pub fee: u64,
+    pub inputs: Vec<UtxoResponse>,
+    pub outputs: Vec<UtxoResponse>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+struct PaymentAddr {
+    bech32: String,
+    cred: String,
+}
+
+/// An input or output as `tx_info` reports it.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+struct TxInfoUtxo {
+    payment_addr: PaymentAddr,
+    stake_addr: Option<String>,
+    tx_hash: String,
+    tx_index: u64,
+    value: String,
+    datum_hash: Option<String>,
+    inline_datum: Option<InlineDatum>,
+    reference_script: Option<Value>,
+    asset_list: Option<Vec<Asset>>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+struct TxInfoDetail {
+    tx_hash: String,
+    epoch_no: u64,
+    block_height: Option<u64>,
+    block_time: u64,
+    fee: String,
+    inputs: Vec<TxInfoUtxo>,
+    outputs: Vec<TxInfoUtxo>,
+}
+
+impl TxInfoUtxo {
+    fn into_utxo(self, tx: &TxInfoDetail, is_spent: bool) -> UtxoResponse {
+        UtxoResponse {
+            tx_hash: self.tx_hash,
+            tx_index: self.tx_index,
+            address: self.payment_addr.bech32,
+            value: self.value,
+            stake_address: self.stake_addr,
+            payment_cred: self.payment_addr.cred,
+            epoch_no: tx.epoch_no,
+            block_height: tx.block_height.unwrap_or_default(),
+            block_time: tx.block_time,
+            datum_hash: self.datum_hash,
+            inline_datum: self.inline_datum,
+            reference_script: self.reference_script,
+            asset_list: self.asset_list,
+            is_spent,
+            datum_resolved: false,
         }
     }
 }
@@ -668,108 +887,590 @@ impl TxResponse {
 pub async fn address_transactions(
     network_flag: bool,
     address: String,
-) -> Result<Vec<TxResponse>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
-    let address_tx_url: String = format!("https://{network}.koios.rest/api/v1/address_txs");
+) -> Result<Vec<TxResponse>, KoiosError> {
+    Koios::new(network_flag).address_transactions(address).await
+}
 
-    let tx_info_url: String = format!("https://{network}.koios.rest/api/v1/tx_info");
-    let client: Client = reqwest::Client::new();
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxStatus {
+    pub tx_hash: String,
+    pub num_confirmations: Option<u64>,
+}
 
-    // Prepare the request payload
-    let address_payload: Value = serde_json::json!({
-        "_addresses": [address],
-    });
+/// Returns the number of confirmations for a transaction.
+///
+pub async fn transaction_status(
+    network_flag: bool,
+    tx_hash: String,
+) -> Result<Vec<TxStatus>, KoiosError> {
+    Koios::new(network_flag).transaction_status(tx_hash).await
+}
 
-    let mut all_txs: Vec<TxResponse> = Vec::new();
-    let mut offset: i32 = 0;
-    let shift: i32 = 65;
+impl Koios {
+    /// Fetches the latest blockchain tip.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<BlockchainTip>)` - A vector containing the latest blockchain tip data.
+    /// * `Err(KoiosError)` - If the API request or JSON parsing fails.
+    pub async fn tip(&self) -> Result<Vec<BlockchainTip>, KoiosError> {
+        // Make the GET request and parse the JSON response
+        let response: Vec<BlockchainTip> = self.query(self.get("tip")).await?;
+
+        Ok(response)
+    }
 
-    loop {
-        let address_response: Response = client
-            .post(address_tx_url.clone())
-            .header("accept", "application/json")
-            .header("content-type", "application/json")
-            .query(&[("offset", offset.to_string()), ("limit", shift.to_string())])
-            .json(&address_payload)
-            .send()
+    /// Fetches the UTXOs associated with a given payment credential.
+    ///
+    /// This function collects all UTXOs (Unspent Transaction Outputs) related to the specified
+    /// payment credential by paginating through the Koios API results.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment_credential` - A string slice representing the payment credential to search for.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UtxoResponse>)` - A vector containing all UTXOs associated with the payment credential.
+    /// * `Err(KoiosError)` - If the API request or JSON parsing fails.
+    ///
+    /// # Behavior
+    ///
+    /// Every page from [`Koios::credential_utxo_pages`] is concatenated, prefer the
+    /// pages themselves when the UTxOs are only filtered.
+    pub async fn credential_utxos(
+        &self,
+        payment_credential: &str,
+    ) -> Result<Vec<UtxoResponse>, KoiosError> {
+        self.credential_utxo_pages(payment_credential)
+            .try_concat()
+            .await
+    }
+
+    /// Streams the UTXOs associated with a given payment credential a page at a time.
+    ///
+    /// Pages of `page_size` rows are requested in windows of `concurrency` at once
+    /// and yielded in order. The stream ends after the first page that is not full or
+    /// the first error, and no window is requested after the one holding it.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment_credential` - A string slice representing the payment credential to search for.
+    ///
+    /// # Returns
+    ///
+    /// A stream of pages, each `Ok(Vec<UtxoResponse>)` or the `Err(KoiosError)` that ended it.
+    pub fn credential_utxo_pages<'a>(
+        &'a self,
+        payment_credential: &'a str,
+    ) -> impl Stream<Item = Result<Vec<UtxoResponse>, KoiosError>> + Send + 'a {
+        // Prepare the request payload
+        let payload: Value = serde_json::json!({
+            "_payment_credentials": [payment_credential],
+            "_extended": true
+        });
+        let page_size: usize = self.page_size;
+        let concurrency: usize = self.concurrency;
+
+        stream::unfold(Some(0), move |window: Option<usize>| {
+            let payload: Value = payload.clone();
+            async move {
+                let first: usize = window? * concurrency;
+                let mut pages: Vec<Result<Vec<UtxoResponse>, KoiosError>> =
+                    future::join_all((first..first + concurrency).map(|page: usize| {
+                        // pages are fetched out of order so the rows need a stable order
+                        self.query::<Vec<UtxoResponse>>(
+                            self.post("credential_utxos")
+                                .header("accept", "application/json")
+                                .header("content-type", "application/json")
+                                .query(&[
+                                    ("offset", (page * page_size).to_string()),
+                                    ("limit", page_size.to_string()),
+                                    ("order", "tx_hash.asc,tx_index.asc".to_string()),
+                                ])
+                                .json(&payload),
+                        )
+                    }))
+                    .await;
+                let last: Option<usize> = pages.iter().position(|page| {
+                    page.as_ref()
+                        .map_or(true, |utxos: &Vec<UtxoResponse>| utxos.len() < page_size)
+                });
+                let next: Option<usize> = match last {
+                    Some(last) => {
+                        pages.truncate(last + 1);
+                        None
+                    }
+                    None => window.map(|window| window + 1),
+                };
+                Some((stream::iter(pages), next))
+            }
+        })
+        .flatten()
+    }
+
+    /// Fetches the UTXOs associated with a specific address.
+    ///
+    /// This function retrieves up to 1000 UTXOs for the given address. The `_extended` flag
+    /// is enabled in the payload to include detailed UTXO information.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - A string slice representing the Cardano address to query.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UtxoResponse>)` - A vector containing the UTXOs associated with the given address.
+    /// * `Err(KoiosError)` - If the API request or JSON parsing fails.
+    ///
+    /// # Notes
+    ///
+    /// The function assumes a maximum of 1000 UTXOs per address, as per CIP-30 wallets.
+    /// If an address exceeds this limit, the wallet is likely mismanaged.
+    pub async fn address_utxos(&self, address: &str) -> Result<Vec<UtxoResponse>, KoiosError> {
+        // this will limit to 1000 utxos which is ok for an address as that is a cip30 wallet
+        // if you have 1000 utxos in that wallets that cannot pay for anything then something
+        // is wrong in that wallet
+
+        // Prepare the request payload
+        let payload: Value = serde_json::json!({
+            "_addresses": [address],
+            "_extended": true
+        });
+
+        // Make the POST request
+        let utxos: Vec<UtxoResponse> = self
+            .query(
+                self.post("address_utxos")
+                    .header("accept", "application/json")
+                    .header("content-type", "application/json")
+                    .json(&payload),
+            )
             .await?;
 
-        let utxos: Vec<History> = address_response.json().await?;
-        // Break the loop if no more results
-        if utxos.is_empty() {
-            break;
-        }
+        Ok(utxos)
+    }
 
-        let tx_hashes: Vec<String> = utxos.iter().map(|h| h.tx_hash.clone()).collect();
-
-        let tx_info_payload: Value = serde_json::json!({
-            "_tx_hashes": tx_hashes,
-            "_inputs": true,
-            "_metadata": false,
-            "_assets": false,
-            "_withdrawals": false,
-            "_certs": false,
-            "_scripts": true,
-            "_bytecode": false
+    /// Evaluates a transaction.
+    ///
+    /// This function sends a CBOR-encoded transaction to the Koios API for evaluation.
+    /// The API uses Ogmios to validate and evaluate the transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx_cbor` - A string containing the CBOR-encoded transaction.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Value)` - A JSON response containing the evaluation result.
+    /// * `Err(KoiosError)` - If the API request fails or the JSON parsing fails.
+    ///
+    /// # Behavior
+    ///
+    /// The function constructs a JSON-RPC request payload and sends a POST request
+    /// to the Koios Ogmios endpoint.
+    pub async fn evaluate_transaction(&self, tx_cbor: String) -> Result<Value, KoiosError> {
+        // Prepare the request payload
+        let payload: Value = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "evaluateTransaction",
+            "params": {
+                "transaction": {
+                    "cbor": tx_cbor
+                }
+            }
         });
 
-        let tx_info_response: Response = client
-            .post(tx_info_url.clone())
+        // Make the POST request
+        let request: RequestBuilder = self
+            .post("ogmios")
             .header("accept", "application/json")
             .header("content-type", "application/json")
-            .json(&tx_info_payload)
-            .send()
+            .json(&payload);
+
+        match send(request, self.retries).await {
+            Ok(body) => Ok(serde_json::from_str(&body)?),
+            // a failed evaluation comes back as an error status holding the ogmios error
+            Err(KoiosError::Status { status, body }) if status.is_client_error() => {
+                match serde_json::from_str::<Value>(&body) {
+                    Ok(response) => Ok(response),
+                    Err(_) => Err(KoiosError::Status { status, body }),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Submits a CBOR-encoded transaction.
+    ///
+    /// This function decodes the provided CBOR-encoded transaction from a hex string into binary
+    /// data and sends it to the Koios API for submission.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx_cbor` - A string containing the hex-encoded CBOR transaction.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Value)` - A JSON response from the API indicating the result of the transaction submission.
+    /// * `Err(KoiosError)` - If the API request fails, the response JSON parsing fails or the
+    ///   node rejects the transaction, in which case the ledger error is in `NodeRejected`.
+    ///
+    /// # Behavior
+    ///
+    /// - Decodes the transaction CBOR hex string into raw binary data.
+    /// - Sends the binary data as the body of a POST request with `Content-Type: application/cbor`.
+    pub async fn submit_tx(&self, tx_cbor: String) -> Result<Value, KoiosError> {
+        // Decode the hex string into binary data
+        let data: Vec<u8> = hex::decode(&tx_cbor)
+            .map_err(|e| KoiosError::NodeRejected(format!("Invalid Transaction Hex: {e}")))?;
+
+        let request: RequestBuilder = self
+            .post("submittx")
+            .header("Content-Type", "application/cbor")
+            .body(data); // Send the raw binary data as the body of the request
+
+        // a submission is never retried, the caller decides what to do with a failure
+        match send(request, 0).await {
+            Ok(body) => Ok(serde_json::from_str(&body)?),
+            Err(KoiosError::Status { status, body }) if status.is_client_error() => {
+                Err(KoiosError::NodeRejected(body))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn ada_handle_address(
+        &self,
+        asset_name: String,
+        cip68_flag: bool,
+        variant: u64,
+        wallet_addr: String,
+        ada_handle_policy_id: &str,
+    ) -> Result<String, String> {
+        let token_name: String = if cip68_flag {
+            "000de140".to_string() + &hex::encode(asset_name.clone())
+        } else {
+            hex::encode(asset_name.clone())
+        };
+        let endpoint: String = format!(
+            "asset_nft_address?_asset_policy={ada_handle_policy_id}&_asset_name={token_name}",
+        );
+
+        let vec_outcome: Vec<Value> = self
+            .query(
+                self.get(&endpoint)
+                    .header("Content-Type", "application/json"),
+            )
+            .await
+            .map_err(|err| format!("HTTP request failed: {err}"))?;
+
+        // Borrow from the longer-lived variable
+        let payment_address = match vec_outcome
+            .first()
+            .and_then(|obj| obj.get("payment_address"))
+            .and_then(|val| val.as_str())
+        {
+            Some(address) => address,
+            None => {
+                if cip68_flag {
+                    return Err("Payment address not found".to_string());
+                } else {
+                    return Box::pin(self.ada_handle_address(
+                        asset_name,
+                        !cip68_flag,
+                        variant,
+                        wallet_addr,
+                        ada_handle_policy_id,
+                    ))
+                    .await;
+                }
+            }
+        };
+
+        if payment_address == wallet_addr {
+            Err("ADA Handle Is In Wallet Address".to_string())
+        } else {
+            Ok(payment_address.to_string())
+        }
+    }
+
+    pub async fn utxo_info(&self, utxo: &str) -> Result<Vec<UtxoResponse>, KoiosError> {
+        // Prepare the request payload
+        let payload: Value = serde_json::json!({
+            "_utxo_refs": [utxo],
+            "_extended": true
+        });
+
+        // Make the POST request
+        let utxos: Vec<UtxoResponse> = self
+            .query(
+                self.post("utxo_info")
+                    .header("accept", "application/json")
+                    .header("content-type", "application/json")
+                    .json(&payload),
+            )
             .await?;
 
-        let txs: Vec<TxInfoResponse> = tx_info_response.json().await?;
-        let mut tx_responses: Vec<TxResponse> = txs
-            .into_iter()
-            .map(TxResponse::from_info_response)
-            .collect();
+        Ok(utxos)
+    }
+
+    // make it so it only works for nfts
+    pub async fn nft_utxo(
+        &self,
+        policy_id: String,
+        token_name: String,
+    ) -> Result<Vec<UtxoResponse>, KoiosError> {
+        // Prepare the request payload
+        let payload: Value = serde_json::json!({
+            "_asset_list": [[policy_id, token_name]],
+            "_extended": true
+        });
+
+        // Make the POST request
+        let utxos: Vec<UtxoResponse> = self
+            .query(
+                self.post("asset_utxos")
+                    .header("accept", "application/json")
+                    .header("content-type", "application/json")
+                    .json(&payload),
+            )
+            .await?;
 
-        // Append the retrieved UTXOs to the main list
-        all_txs.append(&mut tx_responses);
+        if utxos.len() > 1 {
+            return Ok(vec![]);
+        }
 
-        // Increment the offset by shift
-        offset += shift;
+        Ok(utxos)
     }
 
-    Ok(all_txs)
-}
+    pub async fn datum_from_datum_hash(
+        &self,
+        datum_hash: String,
+    ) -> Result<Vec<ResolvedDatum>, KoiosError> {
+        self.datums_from_datum_hashes(vec![datum_hash]).await
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TxStatus {
-    pub tx_hash: String,
-    pub num_confirmations: Option<u64>,
-}
+    /// Resolves many datum hashes with a single `datum_info` request.
+    pub async fn datums_from_datum_hashes(
+        &self,
+        datum_hashes: Vec<String>,
+    ) -> Result<Vec<ResolvedDatum>, KoiosError> {
+        // Prepare the request payload
+        let payload: Value = serde_json::json!({
+            "_datum_hashes": datum_hashes,
+        });
 
-/// Returns the number of confirmations for a transaction.
-///
-pub async fn transaction_status(
-    network_flag: bool,
-    tx_hash: String,
-) -> Result<Vec<TxStatus>, Error> {
-    let network: &str = if network_flag { "preprod" } else { "api" };
+        // Make the POST request
+        let datums: Vec<ResolvedDatum> = self
+            .query(
+                self.post("datum_info")
+                    .header("accept", "application/json")
+                    .header("content-type", "application/json")
+                    .json(&payload),
+            )
+            .await?;
+        Ok(datums)
+    }
 
-    let tx_status_url: String = format!("https://{network}.koios.rest/api/v1/tx_status");
+    pub async fn asset_history(
+        &self,
+        policy_id: String,
+        token_name: String,
+        limit: u64,
+    ) -> Result<Vec<History>, KoiosError> {
+        let endpoint: String = format!(
+            "asset_txs?_asset_policy={policy_id}&_asset_name={token_name}&_after_block_height=50000&_history=true&limit={limit}"
+        );
+
+        // Make the GET request
+        let data: Vec<History> = self
+            .query(
+                self.get(&endpoint)
+                    .header("content-type", "application/json"),
+            )
+            .await?;
+        Ok(data)
+    }
 
-    let client: Client = reqwest::Client::new();
+    /// Return transaction history of some address.
+    pub async fn address_transactions(
+        &self,
+        address: String,
+    ) -> Result<Vec<TxResponse>, KoiosError> {
+        // Prepare the request payload
+        let address_payload: Value = serde_json::json!({
+            "_addresses": [address],
+        });
 
-    // Prepare the request payload
-    let tx_payload: Value = serde_json::json!({
-        "_tx_hashes": [tx_hash],
-    });
+        let mut all_txs: Vec<TxResponse> = Vec::new();
+        let mut offset: i32 = 0;
+        let shift: i32 = 65;
+
+        loop {
+            let utxos: Vec<History> = self
+                .query(
+                    self.post("address_txs")
+                        .header("accept", "application/json")
+                        .header("content-type", "application/json")
+                        .query(&[("offset", offset.to_string()), ("limit", shift.to_string())])
+                        .json(&address_payload),
+                )
+                .await?;
+            // Break the loop if no more results
+            if utxos.is_empty() {
+                break;
+            }
 
-    let response: Response = client
-        .post(tx_status_url)
-        .header("accept", "application/json")
-        .header("content-type", "application/json")
-        .json(&tx_payload)
-        .send()
-        .await?;
+            let tx_hashes: Vec<String> = utxos.iter().map(|h| h.tx_hash.clone()).collect();
+
+            let tx_info_payload: Value = serde_json::json!({
+                "_tx_hashes": tx_hashes,
+                "_inputs": true,
+                "_metadata": false,
+                "_assets": true,
+                "_withdrawals": false,
+                "_certs": false,
+                "_scripts": true,
+                "_bytecode": false
+            });
+
+            let txs: Vec<TxInfoResponse> = self
+                .query(
+                    self.post("tx_info")
+                        .header("accept", "application/json")
+                        .header("content-type", "application/json")
+                        .json(&tx_info_payload),
+                )
+                .await?;
+            let mut tx_responses: Vec<TxResponse> = txs
+                .into_iter()
+                .map(TxResponse::from_info_response)
+                .collect();
+
+            // Append the retrieved UTXOs to the main list
+            all_txs.append(&mut tx_responses);
+
+            // Increment the offset by shift
+            offset += shift;
+        }
+
+        Ok(all_txs)
+    }
+
+    /// Fetches every transaction that touched a payment credential after a block.
+    ///
+    /// # Arguments
+    ///
+    /// * `payment_credential` - The payment credential, e.g. the wallet contract hash.
+    /// * `after_block_height` - Only transactions in later blocks are returned.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<CredentialTx>)` - The transactions ordered by block, with their inputs and outputs.
+    /// * `Err(KoiosError)` - If the API request or JSON parsing fails.
+    pub async fn credential_transactions(
+        &self,
+        payment_credential: &str,
+        after_block_height: u64,
+    ) -> Result<Vec<CredentialTx>, KoiosError> {
+        let payload: Value = serde_json::json!({
+            "_payment_credentials": [payment_credential],
+            "_after_block_height": after_block_height,
+        });
 
-    let status: Vec<TxStatus> = response.json().await?;
+        let mut tx_hashes: Vec<String> = Vec::new();
+        let mut offset: usize = 0;
+        loop {
+            let page: Vec<History> = self
+                .query(
+                    self.post("credential_txs")
+                        .header("accept", "application/json")
+                        .header("content-type", "application/json")
+                        .query(&[
+                            ("offset", offset.to_string()),
+                            ("limit", self.page_size.to_string()),
+                            ("order", "block_height.asc,tx_hash.asc".to_string()),
+                        ])
+                        .json(&payload),
+                )
+                .await?;
+            let done: bool = page.len() < self.page_size;
+            tx_hashes.extend(page.into_iter().map(|h| h.tx_hash));
+            if done {
+                break;
+            }
+            offset += self.page_size;
+        }
 
-    //
-    Ok(status)
+        let mut txs: Vec<CredentialTx> = Vec::new();
+        // tx_info answers for a bounded number of hashes at a time
+        for chunk in tx_hashes.chunks(50) {
+            let details: Vec<TxInfoDetail> = self
+                .query(
+                    self.post("tx_info")
+                        .header("accept", "application/json")
+                        .header("content-type", "application/json")
+                        .json(&serde_json::json!({
+                            "_tx_hashes": chunk,
+                            "_inputs": true,
+                            "_metadata": false,
+                            "_assets": true,
+                            "_withdrawals": false,
+                            "_certs": false,
+                            "_scripts": true,
+                            "_bytecode": false
+                        })),
+                )
+                .await?;
+            for detail in details {
+                txs.push(CredentialTx {
+                    tx_hash: detail.tx_hash.clone(),
+                    block_height: detail.block_height.unwrap_or_default(),
+                    block_time: detail.block_time,
+                    fee: detail.fee.parse().unwrap_or_default(),
+                    inputs: detail
+                        .inputs
+                        .iter()
+                        .cloned()
+                        .map(|input| input.into_utxo(&detail, true))
+                        .collect(),
+                    outputs: detail
+                        .outputs
+                        .iter()
+                        .cloned()
+                        .map(|output| output.into_utxo(&detail, false))
+                        .collect(),
+                });
+            }
+        }
+        txs.sort_by_key(|tx| tx.block_height);
+        Ok(txs)
+    }
+
+    /// Returns the number of confirmations for a transaction.
+    pub async fn transaction_status(&self, tx_hash: String) -> Result<Vec<TxStatus>, KoiosError> {
+        // Prepare the request payload
+        let tx_payload: Value = serde_json::json!({
+            "_tx_hashes": [tx_hash],
+        });
+
+        let status: Vec<TxStatus> = self
+            .query(
+                self.post("tx_status")
+                    .header("accept", "application/json")
+                    .header("content-type", "application/json")
+                    .json(&tx_payload),
+            )
+            .await?;
+
+        Ok(status)
+    }
+
+    /// Fetches the current protocol parameters in the `cardano-cli` format.
+    pub async fn protocol_parameters(&self) -> Result<Option<ProtocolParameters>, KoiosError> {
+        let response: Value = self.query(self.get("cli_protocol_params")).await?;
+
+        Ok(ProtocolParameters::from_cardano_cli(&response))
+    }
 }
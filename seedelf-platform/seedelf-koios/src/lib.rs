@@ -1 +1,7 @@
+pub mod blockfrost;
+pub mod datum;
 pub mod koios;
+pub mod node;
+pub mod ogmios;
+pub mod parameters;
+pub mod provider;
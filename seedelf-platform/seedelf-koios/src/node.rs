@@ -0,0 +1,761 @@
+use crate::datum::inline_datum_from_cbor;
+use crate::koios::{Asset, BlockchainTip, ResolvedDatum, TxResponse, TxStatus, UtxoResponse};
+use crate::ogmios::{slot_to_epoch, slot_to_posix};
+use crate::parameters::ProtocolParameters;
+use crate::provider::ChainProvider;
+use pallas_addresses::Address;
+use pallas_codec::minicbor::data::{Tag, Type};
+use pallas_codec::minicbor::{Decoder, Encoder, decode, encode};
+use pallas_primitives::conway::MintedDatumOption;
+use pallas_traverse::{Era, MultiEraBlock, MultiEraOutput, MultiEraTx};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Environment variable holding the path of the node socket.
+pub const NODE_SOCKET_ENV: &str = "SEEDELF_NODE_SOCKET";
+/// The socket variable the cardano-cli uses, read when `SEEDELF_NODE_SOCKET` is unset.
+pub const CARDANO_NODE_SOCKET_ENV: &str = "CARDANO_NODE_SOCKET_PATH";
+
+/// The network magic of Mainnet.
+pub const MAINNET_MAGIC: u64 = 764_824_073;
+/// The network magic of Preprod.
+pub const PREPROD_MAGIC: u64 = 1;
+
+/// Mini-protocol numbers on the node-to-client multiplexer.
+const HANDSHAKE: u16 = 0;
+const CHAIN_SYNC: u16 = 5;
+const TX_SUBMISSION: u16 = 6;
+const STATE_QUERY: u16 = 7;
+
+/// The largest payload put into a single multiplexer frame.
+const MAX_SEGMENT: usize = 12_288;
+
+/// Node-to-client versions 16 through 19, the ones that speak Conway.
+const VERSIONS: [u64; 4] = [0x8010, 0x8011, 0x8012, 0x8013];
+
+/// An error from the node socket or one of the mini-protocols.
+#[derive(Debug)]
+pub enum NodeError {
+    /// The socket could not be opened, read or written.
+    Io(std::io::Error),
+    /// The node sent a message that could not be decoded.
+    Decode(String),
+    /// The node refused every version offered in the handshake.
+    Handshake(String),
+    /// The node could not answer a local state query.
+    Query(String),
+    /// The node has no way to answer this without an indexer.
+    Unsupported(&'static str),
+    /// The node refused a submitted transaction, holding its hex encoded reason.
+    Rejected(String),
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeError::Io(err) => write!(f, "Node Socket Failed: {err}"),
+            NodeError::Decode(err) => write!(f, "Invalid Node Message: {err}"),
+            NodeError::Handshake(err) => write!(f, "Node Handshake Refused: {err}"),
+            NodeError::Query(err) => write!(f, "Node Query Failed: {err}"),
+            NodeError::Unsupported(what) => write!(f, "The Node Backend Can Not {what}"),
+            NodeError::Rejected(reason) => write!(f, "Transaction Rejected: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for NodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NodeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for NodeError {
+    fn from(err: std::io::Error) -> Self {
+        NodeError::Io(err)
+    }
+}
+
+impl From<decode::Error> for NodeError {
+    fn from(err: decode::Error) -> Self {
+        NodeError::Decode(err.to_string())
+    }
+}
+
+/// A backend that talks to a local cardano-node over its node-to-client socket.
+///
+/// UTxOs and the tip come from local-state-query, transactions go out through
+/// local-tx-submission and new blocks can be followed with chain-sync. Nothing
+/// leaves the machine, but a bare node keeps no index, so history, datum lookups
+/// and script evaluation are not available.
+#[derive(Debug, Clone)]
+pub struct NodeClient {
+    socket_path: String,
+    network_magic: u64,
+    network_flag: bool,
+    stake_credentials: Vec<[u8; 28]>,
+}
+
+/// A block event from chain-sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainEvent {
+    /// A new block was added on top of the chain.
+    RollForward {
+        slot: u64,
+        hash: String,
+        height: u64,
+        block: Vec<u8>,
+    },
+    /// The chain switched forks; everything after this point is gone.
+    RollBackward { slot: u64, hash: String },
+}
+
+trait Bearer: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Bearer for T {}
+
+/// Encodes a message; writing into a vector can not fail.
+fn encode_with(
+    f: impl FnOnce(&mut Encoder<Vec<u8>>) -> Result<(), encode::Error<Infallible>>,
+) -> Vec<u8> {
+    let mut encoder: Encoder<Vec<u8>> = Encoder::new(Vec::new());
+    let _ = f(&mut encoder);
+    encoder.into_writer()
+}
+
+/// The length of the first complete CBOR item in a buffer, if one has arrived yet.
+fn complete_message(buffer: &[u8]) -> Result<Option<usize>, NodeError> {
+    if buffer.is_empty() {
+        return Ok(None);
+    }
+    let mut d: Decoder = Decoder::new(buffer);
+    match d.skip() {
+        Ok(()) => Ok(Some(d.position())),
+        Err(err) if err.is_end_of_input() => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The node-to-client multiplexer: frames mini-protocol messages onto one socket.
+struct Mux {
+    bearer: Box<dyn Bearer>,
+    buffers: HashMap<u16, Vec<u8>>,
+    started: Instant,
+}
+
+impl Mux {
+    async fn connect(socket_path: &str) -> Result<Self, NodeError> {
+        let bearer: Box<dyn Bearer> = match socket_path.strip_prefix("tcp://") {
+            Some(address) => Box::new(TcpStream::connect(address).await?),
+            None => Self::connect_unix(socket_path).await?,
+        };
+        Ok(Self {
+            bearer,
+            buffers: HashMap::new(),
+            started: Instant::now(),
+        })
+    }
+
+    #[cfg(unix)]
+    async fn connect_unix(socket_path: &str) -> Result<Box<dyn Bearer>, NodeError> {
+        Ok(Box::new(
+            tokio::net::UnixStream::connect(socket_path).await?,
+        ))
+    }
+
+    #[cfg(not(unix))]
+    async fn connect_unix(_socket_path: &str) -> Result<Box<dyn Bearer>, NodeError> {
+        Err(NodeError::Unsupported(
+            "Use A Unix Socket Here, Expose The Node As tcp://host:port Instead",
+        ))
+    }
+
+    async fn send(&mut self, protocol: u16, message: &[u8]) -> Result<(), NodeError> {
+        for segment in message.chunks(MAX_SEGMENT) {
+            let mut frame: Vec<u8> = Vec::with_capacity(segment.len() + 8);
+            // the timestamp is only a hint for the peer so wrapping is fine
+            frame.extend((self.started.elapsed().as_micros() as u32).to_be_bytes());
+            frame.extend(protocol.to_be_bytes());
+            frame.extend((segment.len() as u16).to_be_bytes());
+            frame.extend(segment);
+            self.bearer.write_all(&frame).await?;
+        }
+        self.bearer.flush().await?;
+        Ok(())
+    }
+
+    /// Reads frames until a whole message for `protocol` has arrived.
+    async fn recv(&mut self, protocol: u16) -> Result<Vec<u8>, NodeError> {
+        loop {
+            if let Some(buffer) = self.buffers.get_mut(&protocol)
+                && let Some(length) = complete_message(buffer)?
+            {
+                return Ok(buffer.drain(..length).collect());
+            }
+            let mut header: [u8; 8] = [0; 8];
+            self.bearer.read_exact(&mut header).await?;
+            // the high bit only marks the responder side
+            let id: u16 = u16::from_be_bytes([header[4], header[5]]) & 0x7fff;
+            let length: usize = u16::from_be_bytes([header[6], header[7]]) as usize;
+            let mut payload: Vec<u8> = vec![0; length];
+            self.bearer.read_exact(&mut payload).await?;
+            self.buffers.entry(id).or_default().extend(payload);
+        }
+    }
+}
+
+/// Reads the message tag of a mini-protocol message, leaving the decoder on its first field.
+fn message_tag(d: &mut Decoder) -> Result<u64, NodeError> {
+    d.array()?;
+    Ok(d.u64()?)
+}
+
+/// Decodes a chain point, `[]` for the origin or `[slot, hash]`.
+fn decode_point(d: &mut Decoder) -> Result<(u64, String), NodeError> {
+    match d.array()? {
+        Some(0) => Ok((0, String::new())),
+        _ => Ok((d.u64()?, hex::encode(d.bytes()?))),
+    }
+}
+
+/// Encodes a chain point, the origin when the hash is empty.
+fn encode_point(
+    e: &mut Encoder<Vec<u8>>,
+    slot: u64,
+    hash: &str,
+) -> Result<(), encode::Error<Infallible>> {
+    match hex::decode(hash) {
+        Ok(bytes) if !bytes.is_empty() => {
+            e.array(2)?.u64(slot)?.bytes(&bytes)?;
+        }
+        _ => {
+            e.array(0)?;
+        }
+    }
+    Ok(())
+}
+
+/// A connection that finished the handshake.
+struct NodeSession {
+    mux: Mux,
+    acquired: bool,
+}
+
+impl NodeSession {
+    async fn connect(client: &NodeClient) -> Result<Self, NodeError> {
+        let mut mux: Mux = Mux::connect(&client.socket_path).await?;
+        let propose: Vec<u8> = encode_with(|e| {
+            e.array(2)?.u8(0)?.map(VERSIONS.len() as u64)?;
+            for version in VERSIONS {
+                e.u64(version)?
+                    .array(2)?
+                    .u64(client.network_magic)?
+                    .bool(false)?;
+            }
+            Ok(())
+        });
+        mux.send(HANDSHAKE, &propose).await?;
+
+        let reply: Vec<u8> = mux.recv(HANDSHAKE).await?;
+        let mut d: Decoder = Decoder::new(&reply);
+        match message_tag(&mut d)? {
+            1 => Ok(Self {
+                mux,
+                acquired: false,
+            }),
+            _ => Err(NodeError::Handshake(hex::encode(&reply))),
+        }
+    }
+
+    /// Runs a local state query against the volatile tip, returning the raw result.
+    async fn query(&mut self, query: Vec<u8>) -> Result<Vec<u8>, NodeError> {
+        if !self.acquired {
+            self.mux
+                .send(STATE_QUERY, &encode_with(|e| e.array(1)?.u8(8).map(|_| ())))
+                .await?;
+            let reply: Vec<u8> = self.mux.recv(STATE_QUERY).await?;
+            if message_tag(&mut Decoder::new(&reply))? != 1 {
+                return Err(NodeError::Query(format!(
+                    "Could Not Acquire The Tip: {}",
+                    hex::encode(&reply)
+                )));
+            }
+            self.acquired = true;
+        }
+
+        self.mux.send(STATE_QUERY, &query).await?;
+        let reply: Vec<u8> = self.mux.recv(STATE_QUERY).await?;
+        let mut d: Decoder = Decoder::new(&reply);
+        if message_tag(&mut d)? != 4 {
+            return Err(NodeError::Query(hex::encode(&reply)));
+        }
+        Ok(reply[d.position()..].to_vec())
+    }
+
+    /// The hard fork era index the node is in, 6 for Conway.
+    async fn current_era(&mut self) -> Result<u16, NodeError> {
+        let result: Vec<u8> = self
+            .query(encode_with(|e| {
+                e.array(2)?.u8(3)?.array(2)?.u8(0)?.array(2)?.u8(2)?;
+                e.array(1)?.u8(1)?;
+                Ok(())
+            }))
+            .await?;
+        Ok(Decoder::new(&result).u16()?)
+    }
+
+    /// Runs an era specific query, stripping the era match wrapper from the result.
+    async fn block_query(
+        &mut self,
+        era: u16,
+        query: impl FnOnce(&mut Encoder<Vec<u8>>) -> Result<(), encode::Error<Infallible>>,
+    ) -> Result<Vec<u8>, NodeError> {
+        let result: Vec<u8> = self
+            .query(encode_with(|e| {
+                e.array(2)?.u8(3)?.array(2)?.u8(0)?.array(2)?.u8(0)?;
+                e.array(2)?.u16(era)?;
+                query(e)
+            }))
+            .await?;
+        let mut d: Decoder = Decoder::new(&result);
+        if d.array()? != Some(1) {
+            return Err(NodeError::Query("The Node Changed Era".to_string()));
+        }
+        Ok(result[d.position()..].to_vec())
+    }
+
+    async fn utxos_by_address(
+        &mut self,
+        addresses: &[Vec<u8>],
+    ) -> Result<Vec<UtxoResponse>, NodeError> {
+        let era: u16 = self.current_era().await?;
+        let result: Vec<u8> = self
+            .block_query(era, |e| {
+                e.array(2)?.u8(6)?.array(addresses.len() as u64)?;
+                for address in addresses {
+                    e.bytes(address)?;
+                }
+                Ok(())
+            })
+            .await?;
+        decode_utxos(&result, era)
+    }
+
+    async fn utxos_by_input(
+        &mut self,
+        inputs: &[(Vec<u8>, u64)],
+    ) -> Result<Vec<UtxoResponse>, NodeError> {
+        let era: u16 = self.current_era().await?;
+        let result: Vec<u8> = self
+            .block_query(era, |e| {
+                e.array(2)?.u8(15)?.array(inputs.len() as u64)?;
+                for (tx_hash, index) in inputs {
+                    e.array(2)?.bytes(tx_hash)?.u64(*index)?;
+                }
+                Ok(())
+            })
+            .await?;
+        decode_utxos(&result, era)
+    }
+
+    /// Says goodbye to every mini-protocol that was used.
+    async fn close(mut self) {
+        if self.acquired {
+            let _ = self
+                .mux
+                .send(STATE_QUERY, &encode_with(|e| e.array(1)?.u8(5).map(|_| ())))
+                .await;
+        }
+        let _ = self
+            .mux
+            .send(STATE_QUERY, &encode_with(|e| e.array(1)?.u8(7).map(|_| ())))
+            .await;
+    }
+}
+
+/// Decodes a `GetUTxOByAddress` or `GetUTxOByTxIn` result into the Koios shape.
+///
+/// # Arguments
+///
+/// * `result` - The CBOR map of `[tx_hash, index]` to transaction output.
+/// * `era` - The hard fork era index the outputs were queried in.
+///
+/// # Returns
+///
+/// * `Ok(Vec<UtxoResponse>)` - Every output in the map.
+/// * `Err(NodeError)` - If the map or an output can not be decoded.
+pub fn decode_utxos(result: &[u8], era: u16) -> Result<Vec<UtxoResponse>, NodeError> {
+    // block wrappers count from the epoch boundary block so they sit one above the era index
+    let era: Era = Era::try_from(era + 1).map_err(|e| NodeError::Decode(e.to_string()))?;
+    let mut d: Decoder = Decoder::new(result);
+    let length: Option<u64> = d.map()?;
+
+    let mut utxos: Vec<UtxoResponse> = Vec::new();
+    loop {
+        match length {
+            Some(length) if utxos.len() as u64 >= length => break,
+            None if d.datatype()? == Type::Break => break,
+            _ => {}
+        }
+        d.array()?;
+        let tx_hash: String = hex::encode(d.bytes()?);
+        let tx_index: u64 = d.u64()?;
+        let start: usize = d.position();
+        d.skip()?;
+        let output: MultiEraOutput = MultiEraOutput::decode(era, &result[start..d.position()])?;
+        utxos.push(output_response(&output, tx_hash, tx_index));
+    }
+    Ok(utxos)
+}
+
+/// Maps a ledger output onto the Koios `UtxoResponse` shape.
+pub fn output_response(output: &MultiEraOutput, tx_hash: String, tx_index: u64) -> UtxoResponse {
+    let address: Option<Address> = output.address().ok();
+    let payment_cred: String = match &address {
+        Some(Address::Shelley(shelley)) => shelley.payment().to_hex(),
+        _ => String::new(),
+    };
+
+    let asset_list: Vec<Asset> = output
+        .value()
+        .assets()
+        .iter()
+        .flat_map(|policy| policy.assets())
+        .map(|asset| Asset {
+            decimals: 0,
+            quantity: asset.output_coin().unwrap_or_default().to_string(),
+            policy_id: asset.policy().to_string(),
+            asset_name: hex::encode(asset.name()),
+            fingerprint: String::new(),
+        })
+        .collect();
+
+    let (datum_hash, inline_datum) = match output.datum() {
+        Some(MintedDatumOption::Hash(hash)) => (Some(hash.to_string()), None),
+        Some(MintedDatumOption::Data(data)) => (
+            None,
+            inline_datum_from_cbor(&hex::encode(data.0.raw_cbor())),
+        ),
+        None => (None, None),
+    };
+
+    UtxoResponse {
+        tx_hash,
+        tx_index,
+        address: address.map(|a| a.to_string()).unwrap_or_default(),
+        value: output.value().coin().to_string(),
+        stake_address: None,
+        payment_cred,
+        epoch_no: 0,
+        block_height: 0,
+        block_time: 0,
+        datum_hash,
+        inline_datum,
+        reference_script: output.script_ref().map(|_| json!({})),
+        asset_list: Some(asset_list),
+        is_spent: false,
+        datum_resolved: false,
+    }
+}
+
+/// Follows the chain from the tip with chain-sync.
+pub struct ChainFollower {
+    mux: Mux,
+}
+
+impl ChainFollower {
+    /// Waits for the next block event, blocking at the tip until a new block arrives.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ChainEvent)` - The next roll forward or roll backward.
+    /// * `Err(NodeError)` - If the socket fails or the message can not be decoded.
+    pub async fn next_event(&mut self) -> Result<ChainEvent, NodeError> {
+        self.mux
+            .send(CHAIN_SYNC, &encode_with(|e| e.array(1)?.u8(0).map(|_| ())))
+            .await?;
+        loop {
+            let reply: Vec<u8> = self.mux.recv(CHAIN_SYNC).await?;
+            let mut d: Decoder = Decoder::new(&reply);
+            match message_tag(&mut d)? {
+                // await reply, the answer follows once the node has a block
+                1 => continue,
+                2 => {
+                    if d.tag()? != Tag::new(24) {
+                        return Err(NodeError::Decode("Expected A Wrapped Block".to_string()));
+                    }
+                    let block: Vec<u8> = d.bytes()?.to_vec();
+                    let decoded: MultiEraBlock = MultiEraBlock::decode(&block)
+                        .map_err(|e| NodeError::Decode(e.to_string()))?;
+                    return Ok(ChainEvent::RollForward {
+                        slot: decoded.slot(),
+                        hash: decoded.hash().to_string(),
+                        height: decoded.number(),
+                        block,
+                    });
+                }
+                3 => {
+                    let (slot, hash) = decode_point(&mut d)?;
+                    return Ok(ChainEvent::RollBackward { slot, hash });
+                }
+                _ => return Err(NodeError::Decode(hex::encode(&reply))),
+            }
+        }
+    }
+}
+
+impl NodeClient {
+    /// Creates a backend for the node socket at `socket_path` on Preprod (`true`) or Mainnet (`false`).
+    ///
+    /// A `tcp://host:port` path connects to a socket forwarded over TCP instead.
+    pub fn new(network_flag: bool, socket_path: impl Into<String>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            network_magic: if network_flag {
+                PREPROD_MAGIC
+            } else {
+                MAINNET_MAGIC
+            },
+            network_flag,
+            stake_credentials: Vec::new(),
+        }
+    }
+
+    /// Creates a backend from `SEEDELF_NODE_SOCKET` or `CARDANO_NODE_SOCKET_PATH`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(NodeClient)` - The configured backend.
+    /// * `Err(String)` - If neither variable is set.
+    pub fn from_env(network_flag: bool) -> Result<Self, String> {
+        [NODE_SOCKET_ENV, CARDANO_NODE_SOCKET_ENV]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|v| !v.trim().is_empty()))
+            .map(|path| Self::new(network_flag, path))
+            .ok_or_else(|| format!("{NODE_SOCKET_ENV} Is Not Set"))
+    }
+
+    /// Uses a different network magic, e.g. for a private testnet.
+    pub fn with_network_magic(mut self, network_magic: u64) -> Self {
+        self.network_magic = network_magic;
+        self
+    }
+
+    /// Also looks for credential UTxOs delegated to this stake key.
+    ///
+    /// The node can only look UTxOs up by full address, so a payment credential
+    /// query covers the enterprise address plus one base address per stake key.
+    pub fn with_stake_credential(mut self, stake_hash: [u8; 28]) -> Self {
+        if !self.stake_credentials.contains(&stake_hash) {
+            self.stake_credentials.push(stake_hash);
+        }
+        self
+    }
+
+    /// The path of the node socket.
+    pub fn socket_path(&self) -> &str {
+        &self.socket_path
+    }
+
+    /// Every address a script credential may hold UTxOs at.
+    fn script_addresses(&self, script_hash: &[u8]) -> Vec<Vec<u8>> {
+        let network: u8 = if self.network_flag { 0 } else { 1 };
+        // enterprise script address, then base addresses with a script payment part and key stake part
+        let mut addresses: Vec<Vec<u8>> = vec![[&[0x70 | network], script_hash].concat()];
+        for stake_hash in &self.stake_credentials {
+            addresses.push([&[0x10 | network], script_hash, stake_hash.as_slice()].concat());
+        }
+        addresses
+    }
+
+    /// Starts following the chain from the current tip.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ChainFollower)` - A follower positioned at the tip.
+    /// * `Err(NodeError)` - If the node can not be reached.
+    pub async fn follow(&self) -> Result<ChainFollower, NodeError> {
+        let mut mux: Mux = NodeSession::connect(self).await?.mux;
+
+        // intersecting nothing is the cheapest way to learn the tip
+        mux.send(
+            CHAIN_SYNC,
+            &encode_with(|e| e.array(2)?.u8(4)?.array(0).map(|_| ())),
+        )
+        .await?;
+        let reply: Vec<u8> = mux.recv(CHAIN_SYNC).await?;
+        let mut d: Decoder = Decoder::new(&reply);
+        message_tag(&mut d)?;
+        d.array()?;
+        let (slot, hash) = decode_point(&mut d)?;
+
+        mux.send(
+            CHAIN_SYNC,
+            &encode_with(|e| {
+                e.array(2)?.u8(4)?.array(1)?;
+                encode_point(e, slot, &hash)
+            }),
+        )
+        .await?;
+        let reply: Vec<u8> = mux.recv(CHAIN_SYNC).await?;
+        if message_tag(&mut Decoder::new(&reply))? != 5 {
+            return Err(NodeError::Query("The Tip Moved Away".to_string()));
+        }
+        Ok(ChainFollower { mux })
+    }
+
+    /// Fetches the current protocol parameters as the raw CBOR the node returns.
+    pub async fn protocol_parameters_cbor(&self) -> Result<Vec<u8>, NodeError> {
+        let mut session: NodeSession = NodeSession::connect(self).await?;
+        let era: u16 = session.current_era().await?;
+        let result: Vec<u8> = session
+            .block_query(era, |e| e.array(1)?.u8(3).map(|_| ()))
+            .await?;
+        session.close().await;
+        Ok(result)
+    }
+}
+
+impl ChainProvider for NodeClient {
+    type Error = NodeError;
+
+    async fn tip(&self) -> Result<Vec<BlockchainTip>, Self::Error> {
+        let mut session: NodeSession = NodeSession::connect(self).await?;
+        let point: Vec<u8> = session
+            .query(encode_with(|e| {
+                e.array(2)?.u8(3)?.array(1)?.u8(3).map(|_| ())
+            }))
+            .await?;
+        let block_no: Vec<u8> = session
+            .query(encode_with(|e| {
+                e.array(2)?.u8(3)?.array(1)?.u8(2).map(|_| ())
+            }))
+            .await?;
+        session.close().await;
+
+        let (slot, hash) = decode_point(&mut Decoder::new(&point))?;
+        let mut d: Decoder = Decoder::new(&block_no);
+        // the block number comes wrapped as [1, n], or [0] at the origin
+        let block_no: u64 = match d.array()? {
+            Some(2) => {
+                d.u8()?;
+                d.u64()?
+            }
+            _ => 0,
+        };
+        let (epoch_no, epoch_slot) = slot_to_epoch(slot, self.network_flag);
+        Ok(vec![BlockchainTip {
+            hash,
+            epoch_no,
+            abs_slot: slot,
+            epoch_slot,
+            block_no,
+            block_time: slot_to_posix(slot, self.network_flag),
+        }])
+    }
+
+    async fn credential_utxos(
+        &self,
+        payment_credential: &str,
+    ) -> Result<Vec<UtxoResponse>, Self::Error> {
+        let script_hash: Vec<u8> =
+            hex::decode(payment_credential).map_err(|e| NodeError::Query(e.to_string()))?;
+        let mut session: NodeSession = NodeSession::connect(self).await?;
+        let utxos: Vec<UtxoResponse> = session
+            .utxos_by_address(&self.script_addresses(&script_hash))
+            .await?;
+        session.close().await;
+        Ok(utxos)
+    }
+
+    async fn address_utxos(&self, address: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        let address: Address =
+            Address::from_bech32(address).map_err(|e| NodeError::Query(e.to_string()))?;
+        let mut session: NodeSession = NodeSession::connect(self).await?;
+        let utxos: Vec<UtxoResponse> = session.utxos_by_address(&[address.to_vec()]).await?;
+        session.close().await;
+        Ok(utxos)
+    }
+
+    async fn utxo_info(&self, utxo: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        let Some((tx_hash, index)) = utxo.split_once('#') else {
+            return Ok(Vec::new());
+        };
+        let (Ok(tx_hash), Ok(index)) = (hex::decode(tx_hash), index.parse::<u64>()) else {
+            return Ok(Vec::new());
+        };
+        let mut session: NodeSession = NodeSession::connect(self).await?;
+        let utxos: Vec<UtxoResponse> = session.utxos_by_input(&[(tx_hash, index)]).await?;
+        session.close().await;
+        Ok(utxos)
+    }
+
+    async fn datum_from_datum_hash(
+        &self,
+        _datum_hash: String,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        Err(NodeError::Unsupported("Resolve Datum Hashes"))
+    }
+
+    async fn evaluate_transaction(&self, _tx_cbor: String) -> Result<Value, Self::Error> {
+        Err(NodeError::Unsupported("Evaluate Scripts"))
+    }
+
+    async fn submit_tx(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        let tx: Vec<u8> = hex::decode(&tx_cbor).map_err(|e| NodeError::Decode(e.to_string()))?;
+        let tx_hash: String = MultiEraTx::decode(&tx)
+            .map_err(|e| NodeError::Decode(e.to_string()))?
+            .hash()
+            .to_string();
+
+        let mut session: NodeSession = NodeSession::connect(self).await?;
+        let era: u16 = session.current_era().await?;
+        session
+            .mux
+            .send(
+                TX_SUBMISSION,
+                &encode_with(|e| {
+                    e.array(2)?.u8(0)?.array(2)?.u16(era)?;
+                    e.tag(Tag::new(24))?.bytes(&tx)?;
+                    Ok(())
+                }),
+            )
+            .await?;
+        let reply: Vec<u8> = session.mux.recv(TX_SUBMISSION).await?;
+        let _ = session
+            .mux
+            .send(
+                TX_SUBMISSION,
+                &encode_with(|e| e.array(1)?.u8(3).map(|_| ())),
+            )
+            .await;
+        session.close().await;
+
+        match message_tag(&mut Decoder::new(&reply))? {
+            1 => Ok(Value::String(tx_hash)),
+            _ => Err(NodeError::Rejected(hex::encode(&reply))),
+        }
+    }
+
+    async fn address_transactions(&self, _address: String) -> Result<Vec<TxResponse>, Self::Error> {
+        Err(NodeError::Unsupported("Look Up Transaction History"))
+    }
+
+    async fn transaction_status(&self, _tx_hash: String) -> Result<Vec<TxStatus>, Self::Error> {
+        // an unspent output proves little, it may be spent since or belong to someone else
+        Err(NodeError::Unsupported("Count Confirmations"))
+    }
+
+    async fn protocol_parameters(&self) -> Result<Option<ProtocolParameters>, Self::Error> {
+        Ok(ProtocolParameters::from_cbor(
+            &self.protocol_parameters_cbor().await?,
+        ))
+    }
+}
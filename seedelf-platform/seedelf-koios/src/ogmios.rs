@@ -0,0 +1,675 @@
+use crate::datum::inline_datum_from_cbor;
+use crate::koios::{
+    Asset, BlockchainTip, ResolvedDatum, TxInfoResponse, TxResponse, TxStatus, UtxoResponse,
+};
+use crate::parameters::ProtocolParameters;
+use crate::provider::ChainProvider;
+use futures_util::{SinkExt, StreamExt, TryStreamExt, stream};
+use pallas_addresses::Address;
+use reqwest::{Client, Response, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+
+/// Environment variable holding the Ogmios websocket URL.
+pub const OGMIOS_URL_ENV: &str = "SEEDELF_OGMIOS_URL";
+/// Environment variable holding the Kupo base URL.
+pub const KUPO_URL_ENV: &str = "SEEDELF_KUPO_URL";
+
+/// Where Ogmios listens by default.
+pub const DEFAULT_OGMIOS_URL: &str = "ws://127.0.0.1:1337";
+/// Where Kupo listens by default.
+pub const DEFAULT_KUPO_URL: &str = "http://127.0.0.1:1442";
+
+/// How many datums are fetched from Kupo at the same time.
+const DATUM_CONCURRENCY: usize = 16;
+
+/// An error from the Ogmios websocket or the Kupo HTTP API.
+#[derive(Debug)]
+pub enum OgmiosError {
+    /// The Kupo request failed.
+    Http(reqwest::Error),
+    /// The Ogmios websocket failed.
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+    /// Ogmios sent something that is not JSON.
+    Json(serde_json::Error),
+    /// Ogmios closed the connection before answering.
+    Closed,
+    /// The node refused a submitted transaction, holding the JSON-RPC error.
+    Rejected(String),
+}
+
+impl fmt::Display for OgmiosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OgmiosError::Http(err) => write!(f, "Kupo Request Failed: {err}"),
+            OgmiosError::WebSocket(err) => write!(f, "Ogmios Connection Failed: {err}"),
+            OgmiosError::Json(err) => write!(f, "Invalid Ogmios Response: {err}"),
+            OgmiosError::Closed => write!(f, "Ogmios Closed The Connection"),
+            OgmiosError::Rejected(error) => write!(f, "Transaction Rejected: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for OgmiosError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OgmiosError::Http(err) => Some(err),
+            OgmiosError::WebSocket(err) => Some(err.as_ref()),
+            OgmiosError::Json(err) => Some(err),
+            OgmiosError::Closed | OgmiosError::Rejected(_) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for OgmiosError {
+    fn from(err: reqwest::Error) -> Self {
+        OgmiosError::Http(err)
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for OgmiosError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        OgmiosError::WebSocket(Box::new(err))
+    }
+}
+
+impl From<serde_json::Error> for OgmiosError {
+    fn from(err: serde_json::Error) -> Self {
+        OgmiosError::Json(err)
+    }
+}
+
+/// A self-hosted backend: Ogmios for evaluation and submission, Kupo for UTxO queries.
+///
+/// Both run next to a local cardano-node, so no third party sees which UTxOs the
+/// wallet is interested in.
+#[derive(Debug, Clone)]
+pub struct OgmiosKupo {
+    ogmios_url: String,
+    kupo_url: String,
+    network_flag: bool,
+    client: Client,
+}
+
+/// A point on chain as Kupo reports it.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct KupoPoint {
+    pub slot_no: u64,
+    pub header_hash: String,
+    /// Only set on `spent_at`, the transaction that spent the output.
+    #[serde(default)]
+    pub transaction_id: Option<String>,
+}
+
+/// The value held by a Kupo match.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct KupoValue {
+    pub coins: u64,
+    /// Keyed by `policy_id.asset_name`, or just `policy_id` for an empty asset name.
+    #[serde(default)]
+    pub assets: BTreeMap<String, u64>,
+}
+
+/// An output matched by Kupo.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct KupoMatch {
+    pub transaction_id: String,
+    pub output_index: u64,
+    pub address: String,
+    pub value: KupoValue,
+    pub datum_hash: Option<String>,
+    /// `inline` or `hash` when the output has a datum.
+    pub datum_type: Option<String>,
+    pub script_hash: Option<String>,
+    pub created_at: KupoPoint,
+    pub spent_at: Option<KupoPoint>,
+}
+
+impl KupoMatch {
+    /// The datum hash of an inline datum, the only kind Kupo has to be asked for.
+    fn inline_datum_hash(&self) -> Option<&str> {
+        match self.datum_type.as_deref() {
+            Some("inline") => self.datum_hash.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KupoDatum {
+    datum: String,
+}
+
+type OgmiosSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// A websocket connection to Ogmios speaking JSON-RPC.
+struct OgmiosSession {
+    socket: OgmiosSocket,
+    next_id: u64,
+}
+
+impl OgmiosSession {
+    async fn connect(url: &str) -> Result<Self, OgmiosError> {
+        let (socket, _) = connect_async(url).await?;
+        Ok(Self { socket, next_id: 0 })
+    }
+
+    /// Sends a request and waits for the response with the same id.
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, OgmiosError> {
+        self.next_id += 1;
+        let id: u64 = self.next_id;
+        let mut request: Value = json!({ "jsonrpc": "2.0", "method": method, "id": id });
+        if !params.is_null() {
+            request["params"] = params;
+        }
+        self.socket.send(Message::Text(request.to_string())).await?;
+
+        while let Some(message) = self.socket.next().await {
+            let text: String = match message? {
+                Message::Text(text) => text,
+                Message::Binary(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let response: Value = serde_json::from_str(&text)?;
+            if response.get("id").and_then(Value::as_u64) == Some(id) {
+                return Ok(response);
+            }
+        }
+        Err(OgmiosError::Closed)
+    }
+
+    /// The height of the block at a point, found by intersecting there and reading one block forward.
+    async fn block_height_at(&mut self, slot: u64, id: &str) -> Result<Option<u64>, OgmiosError> {
+        let intersection: Value = self
+            .request(
+                "findIntersection",
+                json!({ "points": [{ "slot": slot, "id": id }] }),
+            )
+            .await?;
+        if intersection.get("error").is_some() {
+            // the point was rolled back or is older than the node keeps
+            return Ok(None);
+        }
+        // the first answer after an intersection is always a roll backward onto it
+        self.request("nextBlock", Value::Null).await?;
+        let next: Value = self.request("nextBlock", Value::Null).await?;
+        Ok(next["result"]["block"]["height"]
+            .as_u64()
+            .map(|height| height.saturating_sub(1)))
+    }
+
+    async fn close(mut self) {
+        let _ = self.socket.close(None).await;
+    }
+}
+
+impl OgmiosKupo {
+    /// Creates a backend using the default local Ogmios and Kupo ports.
+    pub fn new(network_flag: bool) -> Self {
+        Self {
+            ogmios_url: DEFAULT_OGMIOS_URL.to_string(),
+            kupo_url: DEFAULT_KUPO_URL.to_string(),
+            network_flag,
+            client: Client::new(),
+        }
+    }
+
+    /// Creates a backend from `SEEDELF_OGMIOS_URL` and `SEEDELF_KUPO_URL`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(OgmiosKupo)` - The configured backend, using the default ports for anything unset.
+    /// * `Err(String)` - Never at the moment, kept for parity with the other backends.
+    pub fn from_env(network_flag: bool) -> Result<Self, String> {
+        let mut backend: OgmiosKupo = Self::new(network_flag);
+        if let Ok(url) = std::env::var(OGMIOS_URL_ENV)
+            && !url.trim().is_empty()
+        {
+            backend = backend.with_ogmios_url(url);
+        }
+        if let Ok(url) = std::env::var(KUPO_URL_ENV)
+            && !url.trim().is_empty()
+        {
+            backend = backend.with_kupo_url(url);
+        }
+        Ok(backend)
+    }
+
+    /// Uses a different Ogmios URL; `http` URLs are switched to `ws`.
+    pub fn with_ogmios_url(mut self, url: impl Into<String>) -> Self {
+        let url: String = url.into().trim_end_matches('/').to_string();
+        self.ogmios_url = match url.strip_prefix("http") {
+            Some(rest) => format!("ws{rest}"),
+            None => url,
+        };
+        self
+    }
+
+    /// Uses a different Kupo URL.
+    pub fn with_kupo_url(mut self, url: impl Into<String>) -> Self {
+        self.kupo_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Uses an existing `reqwest::Client` so its connection pool is shared.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// The Ogmios websocket URL.
+    pub fn ogmios_url(&self) -> &str {
+        &self.ogmios_url
+    }
+
+    /// The Kupo base URL.
+    pub fn kupo_url(&self) -> &str {
+        &self.kupo_url
+    }
+
+    /// GETs a Kupo endpoint, treating a 404 as "nothing there" instead of an error.
+    async fn kupo<T: DeserializeOwned>(&self, endpoint: &str) -> Result<Option<T>, OgmiosError> {
+        let response: Response = self
+            .client
+            .get(format!("{}/{endpoint}", self.kupo_url))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    async fn matches(&self, pattern: &str) -> Result<Vec<KupoMatch>, OgmiosError> {
+        Ok(self
+            .kupo(&format!("matches/{pattern}"))
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn datum(&self, datum_hash: &str) -> Result<Option<String>, OgmiosError> {
+        let datum: Option<KupoDatum> = self.kupo(&format!("datums/{datum_hash}")).await?;
+        Ok(datum.map(|d| d.datum))
+    }
+
+    /// Fetches the inline datum of every match, keyed by datum hash.
+    async fn inline_datums(
+        &self,
+        matches: &[KupoMatch],
+    ) -> Result<HashMap<String, String>, OgmiosError> {
+        let mut hashes: Vec<String> = matches
+            .iter()
+            .filter_map(KupoMatch::inline_datum_hash)
+            .map(str::to_string)
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+
+        stream::iter(hashes)
+            .map(|hash: String| async move {
+                let datum: Option<String> = self.datum(&hash).await?;
+                Ok::<_, OgmiosError>(datum.map(|d| (hash, d)))
+            })
+            .buffer_unordered(DATUM_CONCURRENCY)
+            .try_filter_map(|entry| async move { Ok(entry) })
+            .try_collect()
+            .await
+    }
+
+    /// Queries Kupo and maps every match onto the Koios shape with its datum resolved.
+    async fn utxos(&self, pattern: &str) -> Result<Vec<UtxoResponse>, OgmiosError> {
+        let matches: Vec<KupoMatch> = self.matches(pattern).await?;
+        let datums: HashMap<String, String> = self.inline_datums(&matches).await?;
+        Ok(matches
+            .into_iter()
+            .map(|m| {
+                let datum: Option<String> = m
+                    .inline_datum_hash()
+                    .and_then(|hash| datums.get(hash).cloned());
+                kupo_utxo_response(m, datum.as_deref())
+            })
+            .collect())
+    }
+}
+
+/// Maps a Kupo match onto the Koios `UtxoResponse` shape.
+///
+/// Kupo only knows the slot an output was created in, so `epoch_no`, `block_height`
+/// and `block_time` are zero.
+///
+/// # Arguments
+///
+/// * `utxo` - The Kupo match.
+/// * `inline_datum` - The CBOR of the inline datum, when the match has one.
+///
+/// # Returns
+///
+/// * `UtxoResponse` - The output in the Koios shape.
+pub fn kupo_utxo_response(utxo: KupoMatch, inline_datum: Option<&str>) -> UtxoResponse {
+    let asset_list: Vec<Asset> = utxo
+        .value
+        .assets
+        .iter()
+        .map(|(unit, quantity)| {
+            let (policy_id, asset_name) = unit.split_once('.').unwrap_or((unit, ""));
+            Asset {
+                decimals: 0,
+                quantity: quantity.to_string(),
+                policy_id: policy_id.to_string(),
+                asset_name: asset_name.to_string(),
+                fingerprint: String::new(),
+            }
+        })
+        .collect();
+
+    let payment_cred: String = match Address::from_bech32(&utxo.address) {
+        Ok(Address::Shelley(shelley)) => shelley.payment().to_hex(),
+        _ => String::new(),
+    };
+
+    UtxoResponse {
+        tx_hash: utxo.transaction_id,
+        tx_index: utxo.output_index,
+        address: utxo.address,
+        value: utxo.value.coins.to_string(),
+        stake_address: None,
+        payment_cred,
+        epoch_no: 0,
+        block_height: 0,
+        block_time: 0,
+        datum_hash: utxo.datum_hash,
+        inline_datum: inline_datum.and_then(inline_datum_from_cbor),
+        reference_script: utxo.script_hash.map(|hash| json!({ "hash": hash })),
+        datum_resolved: false,
+        asset_list: Some(asset_list),
+        is_spent: utxo.spent_at.is_some(),
+    }
+}
+
+/// Rewrites an Ogmios `submitTransaction` response into what `submit_tx` returns.
+///
+/// # Arguments
+///
+/// * `response` - The raw JSON-RPC response.
+///
+/// # Returns
+///
+/// * `Ok(Value)` - The transaction id as a JSON string.
+/// * `Err(OgmiosError)` - The JSON-RPC error, or the whole response when it holds neither.
+pub fn submission_to_tx_hash(response: Value) -> Result<Value, OgmiosError> {
+    if let Some(id) = response["result"]["transaction"]["id"].as_str() {
+        return Ok(Value::String(id.to_string()));
+    }
+    match response.get("error") {
+        Some(error) => Err(OgmiosError::Rejected(error.to_string())),
+        None => Err(OgmiosError::Rejected(response.to_string())),
+    }
+}
+
+/// The first Shelley slot, its epoch and its POSIX time on Preprod (`true`) or Mainnet (`false`).
+fn shelley_start(network_flag: bool) -> (u64, u64, u64) {
+    if network_flag {
+        (86_400, 4, 1_655_769_600)
+    } else {
+        (4_492_800, 208, 1_596_059_091)
+    }
+}
+
+/// Slots per epoch since Shelley.
+const EPOCH_LENGTH: u64 = 432_000;
+
+/// Converts a slot into its POSIX time.
+///
+/// # Arguments
+///
+/// * `slot` - An absolute slot at or after the start of Shelley.
+/// * `network_flag` - `true` for Preprod, `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `u64` - The POSIX time in seconds.
+pub fn slot_to_posix(slot: u64, network_flag: bool) -> u64 {
+    let (start_slot, _, start_time) = shelley_start(network_flag);
+    start_time + slot.saturating_sub(start_slot)
+}
+
+/// Converts a slot into its epoch and the slot within that epoch.
+///
+/// # Arguments
+///
+/// * `slot` - An absolute slot at or after the start of Shelley.
+/// * `network_flag` - `true` for Preprod, `false` for Mainnet.
+///
+/// # Returns
+///
+/// * `(u64, u64)` - The epoch number and the epoch slot.
+pub fn slot_to_epoch(slot: u64, network_flag: bool) -> (u64, u64) {
+    let (start_slot, start_epoch, _) = shelley_start(network_flag);
+    let since: u64 = slot.saturating_sub(start_slot);
+    (start_epoch + since / EPOCH_LENGTH, since % EPOCH_LENGTH)
+}
+
+/// Puts a Kupo match into the Koios `tx_info` JSON shape used by `TxResponse`.
+fn tx_info_output(utxo: &KupoMatch, inline_datum: Option<&str>) -> Value {
+    let utxo: UtxoResponse = kupo_utxo_response(utxo.clone(), inline_datum);
+    let inline_datum: Value = utxo
+        .inline_datum
+        .map(|d| json!({ "bytes": d.bytes, "value": d.value }))
+        .unwrap_or(Value::Null);
+    json!({
+        "tx_hash": utxo.tx_hash,
+        "tx_index": utxo.tx_index,
+        "inline_datum": inline_datum,
+        "value": utxo.value,
+        "asset_list": utxo.asset_list,
+    })
+}
+
+/// The transaction being collected for a hash, started at the point it was first seen.
+fn tx_entry<'a>(
+    txs: &'a mut HashMap<String, (KupoPoint, TxInfoResponse)>,
+    tx_hash: &str,
+    point: &KupoPoint,
+) -> &'a mut TxInfoResponse {
+    &mut txs
+        .entry(tx_hash.to_string())
+        .or_insert_with(|| {
+            (
+                point.clone(),
+                TxInfoResponse {
+                    tx_hash: tx_hash.to_string(),
+                    ..Default::default()
+                },
+            )
+        })
+        .1
+}
+
+impl ChainProvider for OgmiosKupo {
+    type Error = OgmiosError;
+
+    async fn tip(&self) -> Result<Vec<BlockchainTip>, Self::Error> {
+        let mut session: OgmiosSession = OgmiosSession::connect(&self.ogmios_url).await?;
+        let tip: Value = session.request("queryNetwork/tip", Value::Null).await?;
+        let height: Value = session
+            .request("queryNetwork/blockHeight", Value::Null)
+            .await?;
+        session.close().await;
+
+        let Some(slot) = tip["result"]["slot"].as_u64() else {
+            return Ok(Vec::new());
+        };
+        let (epoch_no, epoch_slot) = slot_to_epoch(slot, self.network_flag);
+        Ok(vec![BlockchainTip {
+            hash: tip["result"]["id"].as_str().unwrap_or_default().to_string(),
+            epoch_no,
+            abs_slot: slot,
+            epoch_slot,
+            block_no: height["result"].as_u64().unwrap_or_default(),
+            block_time: slot_to_posix(slot, self.network_flag),
+        }])
+    }
+
+    async fn credential_utxos(
+        &self,
+        payment_credential: &str,
+    ) -> Result<Vec<UtxoResponse>, Self::Error> {
+        self.utxos(&format!("{payment_credential}/*?unspent")).await
+    }
+
+    async fn address_utxos(&self, address: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        self.utxos(&format!("{address}?unspent")).await
+    }
+
+    async fn utxo_info(&self, utxo: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        let Some((tx_hash, index)) = utxo.split_once('#') else {
+            return Ok(Vec::new());
+        };
+        self.utxos(&format!("{index}@{tx_hash}")).await
+    }
+
+    async fn datum_from_datum_hash(
+        &self,
+        datum_hash: String,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        let datum: Option<String> = self.datum(&datum_hash).await?;
+        Ok(datum
+            .as_deref()
+            .and_then(inline_datum_from_cbor)
+            .map(|d| ResolvedDatum {
+                datum_hash: Some(datum_hash),
+                creation_tx_hash: String::new(),
+                value: d.value,
+                bytes: Some(d.bytes),
+            })
+            .into_iter()
+            .collect())
+    }
+
+    async fn evaluate_transaction(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        let mut session: OgmiosSession = OgmiosSession::connect(&self.ogmios_url).await?;
+        let response: Value = session
+            .request(
+                "evaluateTransaction",
+                json!({ "transaction": { "cbor": tx_cbor } }),
+            )
+            .await?;
+        session.close().await;
+        Ok(response)
+    }
+
+    async fn submit_tx(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        let mut session: OgmiosSession = OgmiosSession::connect(&self.ogmios_url).await?;
+        let response: Value = session
+            .request(
+                "submitTransaction",
+                json!({ "transaction": { "cbor": tx_cbor } }),
+            )
+            .await?;
+        session.close().await;
+        submission_to_tx_hash(response)
+    }
+
+    async fn address_transactions(&self, address: String) -> Result<Vec<TxResponse>, Self::Error> {
+        // every output the address ever held, spent or not
+        let matches: Vec<KupoMatch> = self.matches(&address).await?;
+        let datums: HashMap<String, String> = self.inline_datums(&matches).await?;
+
+        // a transaction shows up as the creator of some matches and the spender of others
+        let mut txs: HashMap<String, (KupoPoint, TxInfoResponse)> = HashMap::new();
+        for m in &matches {
+            let datum: Option<&str> = m
+                .inline_datum_hash()
+                .and_then(|hash| datums.get(hash))
+                .map(String::as_str);
+            tx_entry(&mut txs, &m.transaction_id, &m.created_at)
+                .outputs
+                .push(tx_info_output(m, datum));
+            if let Some(spent) = &m.spent_at
+                && let Some(spender) = &spent.transaction_id
+            {
+                tx_entry(&mut txs, spender, spent)
+                    .inputs
+                    .push(tx_info_output(m, datum));
+            }
+        }
+
+        let mut txs: Vec<(KupoPoint, TxInfoResponse)> = txs.into_values().collect();
+        txs.sort_by_key(|(point, _)| std::cmp::Reverse(point.slot_no));
+
+        // kupo has no block heights so ask ogmios once per block
+        let mut heights: HashMap<String, u64> = HashMap::new();
+        if !txs.is_empty() {
+            let mut session: OgmiosSession = OgmiosSession::connect(&self.ogmios_url).await?;
+            for (point, _) in &txs {
+                if heights.contains_key(&point.header_hash) {
+                    continue;
+                }
+                if let Some(height) = session
+                    .block_height_at(point.slot_no, &point.header_hash)
+                    .await?
+                {
+                    heights.insert(point.header_hash.clone(), height);
+                }
+            }
+            session.close().await;
+        }
+
+        Ok(txs
+            .into_iter()
+            .map(|(point, mut info)| {
+                info.block_height = heights.get(&point.header_hash).copied().unwrap_or_default();
+                info.block_time = slot_to_posix(point.slot_no, self.network_flag);
+                TxResponse::from_info_response(info)
+            })
+            .collect())
+    }
+
+    async fn transaction_status(&self, tx_hash: String) -> Result<Vec<TxStatus>, Self::Error> {
+        // kupo only knows a transaction when one of its outputs matches its patterns
+        let created: Option<KupoPoint> = self
+            .matches(&format!("*@{tx_hash}"))
+            .await?
+            .into_iter()
+            .next()
+            .map(|m| m.created_at);
+
+        let num_confirmations: Option<u64> = match created {
+            Some(point) => {
+                let mut session: OgmiosSession = OgmiosSession::connect(&self.ogmios_url).await?;
+                let tip_height: Option<u64> = session
+                    .request("queryNetwork/blockHeight", Value::Null)
+                    .await?["result"]
+                    .as_u64();
+                let height: Option<u64> = session
+                    .block_height_at(point.slot_no, &point.header_hash)
+                    .await?;
+                session.close().await;
+                tip_height
+                    .zip(height)
+                    .map(|(tip, height)| tip.saturating_sub(height) + 1)
+            }
+            None => None,
+        };
+        Ok(vec![TxStatus {
+            tx_hash,
+            num_confirmations,
+        }])
+    }
+
+    async fn protocol_parameters(&self) -> Result<Option<ProtocolParameters>, Self::Error> {
+        let mut session: OgmiosSession = OgmiosSession::connect(&self.ogmios_url).await?;
+        let response: Value = session
+            .request("queryLedgerState/protocolParameters", Value::Null)
+            .await?;
+        session.close().await;
+        Ok(ProtocolParameters::from_ogmios(&response["result"]))
+    }
+}
@@ -0,0 +1,375 @@
+use crate::provider::ChainProvider;
+use pallas_codec::minicbor::Decoder;
+use pallas_codec::minicbor::data::{Tag, Type};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// A non-negative rational number, the way the ledger stores prices.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Ratio {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl Ratio {
+    pub const fn new(numerator: u64, denominator: u64) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// `floor(self * units)`.
+    pub fn floor_mul(&self, units: u64) -> u64 {
+        if self.denominator == 0 {
+            return 0;
+        }
+        (u128::from(self.numerator) * u128::from(units) / u128::from(self.denominator)) as u64
+    }
+
+    /// Parses `"577/10000"`, `0.0577`, `"0.0577"` or `15`.
+    fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(number) => match number.as_u64() {
+                Some(whole) => Some(Self::new(whole, 1)),
+                None => Self::from_decimal(number.as_f64()?),
+            },
+            Value::String(text) => match text.split_once('/') {
+                Some((numerator, denominator)) => Some(Self::new(
+                    numerator.trim().parse().ok()?,
+                    denominator.trim().parse().ok()?,
+                )),
+                None => Self::from_decimal(text.trim().parse().ok()?),
+            },
+            _ => None,
+        }
+    }
+
+    /// APIs hand prices out as floats, which are exact to well past ten decimals.
+    fn from_decimal(decimal: f64) -> Option<Self> {
+        const SCALE: u64 = 10_000_000_000;
+        if !decimal.is_finite() || decimal < 0.0 {
+            return None;
+        }
+        let numerator: u64 = (decimal * SCALE as f64).round() as u64;
+        let divisor: u64 = gcd(numerator, SCALE);
+        Some(Self::new(numerator / divisor, SCALE / divisor))
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a.max(1) } else { gcd(b, a % b) }
+}
+
+/// Reference scripts are priced in tiers of this many bytes.
+const REFERENCE_SCRIPT_TIER_SIZE: u64 = 25_600;
+
+/// The protocol parameters the wallet needs to build and price transactions.
+///
+/// Loaded from the chain with [`ProtocolParameters::load`]; the `Default` is a
+/// bundled copy of the mainnet parameters used when nothing better is available.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ProtocolParameters {
+    /// Lovelace per transaction byte.
+    pub min_fee_a: u64,
+    /// Lovelace per transaction.
+    pub min_fee_b: u64,
+    /// Lovelace per byte of a serialized output.
+    pub coins_per_utxo_byte: u64,
+    /// Lovelace per unit of script memory.
+    pub price_memory: Ratio,
+    /// Lovelace per unit of script cpu.
+    pub price_steps: Ratio,
+    /// Lovelace per byte of reference script in the first tier.
+    pub min_fee_ref_script_cost_per_byte: Ratio,
+    pub max_tx_size: u64,
+    pub max_tx_execution_memory: u64,
+    pub max_tx_execution_steps: u64,
+    pub collateral_percentage: u64,
+    pub max_collateral_inputs: u64,
+    pub plutus_v3_cost_model: Vec<i64>,
+}
+
+impl Default for ProtocolParameters {
+    fn default() -> Self {
+        Self {
+            min_fee_a: 44,
+            min_fee_b: 155_381,
+            coins_per_utxo_byte: 4_310,
+            price_memory: Ratio::new(577, 10_000),
+            price_steps: Ratio::new(721, 10_000_000),
+            min_fee_ref_script_cost_per_byte: Ratio::new(15, 1),
+            max_tx_size: 16_384,
+            max_tx_execution_memory: 14_000_000,
+            max_tx_execution_steps: 10_000_000_000,
+            collateral_percentage: 150,
+            max_collateral_inputs: 3,
+            plutus_v3_cost_model: PLUTUS_V3_COST_MODEL.to_vec(),
+        }
+    }
+}
+
+impl ProtocolParameters {
+    /// The size based part of the fee for a transaction of `tx_size` bytes.
+    pub fn min_fee(&self, tx_size: u64) -> u64 {
+        self.min_fee_a * tx_size + self.min_fee_b
+    }
+
+    /// The fee for running a script with the given execution units.
+    pub fn script_fee(&self, mem_units: u64, cpu_units: u64) -> u64 {
+        self.price_memory.floor_mul(mem_units) + self.price_steps.floor_mul(cpu_units)
+    }
+
+    /// The Conway fee for the reference scripts a transaction uses.
+    ///
+    /// Every 25,600 bytes the price per byte goes up by a factor of 1.2, and the
+    /// total is rounded down once at the end, like the ledger does.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The combined size in bytes of every reference script in the transaction.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The reference script fee in lovelace.
+    pub fn reference_script_fee(&self, size: u64) -> u64 {
+        // work over a shared denominator so every tier stays exact
+        let mut denominator: u128 = u128::from(self.min_fee_ref_script_cost_per_byte.denominator);
+        let mut price: u128 = u128::from(self.min_fee_ref_script_cost_per_byte.numerator);
+        let mut total: u128 = 0;
+        let mut remaining: u64 = size;
+        if denominator == 0 {
+            return 0;
+        }
+        while remaining >= REFERENCE_SCRIPT_TIER_SIZE {
+            total += u128::from(REFERENCE_SCRIPT_TIER_SIZE) * price;
+            remaining -= REFERENCE_SCRIPT_TIER_SIZE;
+            // multiply the price by 6 / 5
+            total *= 5;
+            denominator *= 5;
+            price *= 6;
+        }
+        total += u128::from(remaining) * price;
+        (total / denominator) as u64
+    }
+
+    /// Reads the `cardano-cli query protocol-parameters` JSON, as served by Koios.
+    pub fn from_cardano_cli(value: &Value) -> Option<Self> {
+        let defaults: Self = Self::default();
+        Some(Self {
+            min_fee_a: number(&value["txFeePerByte"])?,
+            min_fee_b: number(&value["txFeeFixed"])?,
+            coins_per_utxo_byte: number(&value["utxoCostPerByte"])?,
+            price_memory: Ratio::from_json(&value["executionUnitPrices"]["priceMemory"])?,
+            price_steps: Ratio::from_json(&value["executionUnitPrices"]["priceSteps"])?,
+            min_fee_ref_script_cost_per_byte: Ratio::from_json(
+                &value["minFeeRefScriptCostPerByte"],
+            )
+            .unwrap_or(defaults.min_fee_ref_script_cost_per_byte),
+            max_tx_size: number(&value["maxTxSize"]).unwrap_or(defaults.max_tx_size),
+            max_tx_execution_memory: number(&value["maxTxExecutionUnits"]["memory"])
+                .unwrap_or(defaults.max_tx_execution_memory),
+            max_tx_execution_steps: number(&value["maxTxExecutionUnits"]["steps"])
+                .unwrap_or(defaults.max_tx_execution_steps),
+            collateral_percentage: number(&value["collateralPercentage"])
+                .unwrap_or(defaults.collateral_percentage),
+            max_collateral_inputs: number(&value["maxCollateralInputs"])
+                .unwrap_or(defaults.max_collateral_inputs),
+            plutus_v3_cost_model: cost_model(&value["costModels"]["PlutusV3"])?,
+        })
+    }
+
+    /// Reads Blockfrost's `epochs/latest/parameters`.
+    pub fn from_blockfrost(value: &Value) -> Option<Self> {
+        let defaults: Self = Self::default();
+        Some(Self {
+            min_fee_a: number(&value["min_fee_a"])?,
+            min_fee_b: number(&value["min_fee_b"])?,
+            coins_per_utxo_byte: number(&value["coins_per_utxo_size"])?,
+            price_memory: Ratio::from_json(&value["price_mem"])?,
+            price_steps: Ratio::from_json(&value["price_step"])?,
+            min_fee_ref_script_cost_per_byte: Ratio::from_json(
+                &value["min_fee_ref_script_cost_per_byte"],
+            )
+            .unwrap_or(defaults.min_fee_ref_script_cost_per_byte),
+            max_tx_size: number(&value["max_tx_size"]).unwrap_or(defaults.max_tx_size),
+            max_tx_execution_memory: number(&value["max_tx_ex_mem"])
+                .unwrap_or(defaults.max_tx_execution_memory),
+            max_tx_execution_steps: number(&value["max_tx_ex_steps"])
+                .unwrap_or(defaults.max_tx_execution_steps),
+            collateral_percentage: number(&value["collateral_percent"])
+                .unwrap_or(defaults.collateral_percentage),
+            max_collateral_inputs: number(&value["max_collateral_inputs"])
+                .unwrap_or(defaults.max_collateral_inputs),
+            plutus_v3_cost_model: cost_model(&value["cost_models_raw"]["PlutusV3"])?,
+        })
+    }
+
+    /// Reads the `result` of Ogmios' `queryLedgerState/protocolParameters`.
+    pub fn from_ogmios(value: &Value) -> Option<Self> {
+        let defaults: Self = Self::default();
+        Some(Self {
+            min_fee_a: number(&value["minFeeCoefficient"])?,
+            min_fee_b: number(&value["minFeeConstant"]["ada"]["lovelace"])?,
+            coins_per_utxo_byte: number(&value["minUtxoDepositCoefficient"])?,
+            price_memory: Ratio::from_json(&value["scriptExecutionPrices"]["memory"])?,
+            price_steps: Ratio::from_json(&value["scriptExecutionPrices"]["cpu"])?,
+            min_fee_ref_script_cost_per_byte: Ratio::from_json(
+                &value["minFeeReferenceScripts"]["base"],
+            )
+            .unwrap_or(defaults.min_fee_ref_script_cost_per_byte),
+            max_tx_size: number(&value["maxTransactionSize"]["bytes"])
+                .unwrap_or(defaults.max_tx_size),
+            max_tx_execution_memory: number(&value["maxExecutionUnitsPerTransaction"]["memory"])
+                .unwrap_or(defaults.max_tx_execution_memory),
+            max_tx_execution_steps: number(&value["maxExecutionUnitsPerTransaction"]["cpu"])
+                .unwrap_or(defaults.max_tx_execution_steps),
+            collateral_percentage: number(&value["collateralPercentage"])
+                .unwrap_or(defaults.collateral_percentage),
+            max_collateral_inputs: number(&value["maxCollateralInputs"])
+                .unwrap_or(defaults.max_collateral_inputs),
+            plutus_v3_cost_model: cost_model(&value["plutusCostModels"]["plutus:v3"])?,
+        })
+    }
+
+    /// Reads the Conway parameters array a node returns for `GetCurrentPParams`.
+    pub fn from_cbor(cbor: &[u8]) -> Option<Self> {
+        let mut d: Decoder = Decoder::new(cbor);
+        let length: u64 = d.array().ok()??;
+        let mut parameters: Self = Self::default();
+        let mut cost_model: Option<Vec<i64>> = None;
+        for index in 0..length {
+            match index {
+                0 => parameters.min_fee_a = d.u64().ok()?,
+                1 => parameters.min_fee_b = d.u64().ok()?,
+                3 => parameters.max_tx_size = d.u64().ok()?,
+                14 => parameters.coins_per_utxo_byte = d.u64().ok()?,
+                15 => cost_model = cbor_cost_model(&mut d, 2),
+                16 => {
+                    d.array().ok()?;
+                    parameters.price_memory = cbor_ratio(&mut d)?;
+                    parameters.price_steps = cbor_ratio(&mut d)?;
+                }
+                17 => {
+                    d.array().ok()?;
+                    parameters.max_tx_execution_memory = d.u64().ok()?;
+                    parameters.max_tx_execution_steps = d.u64().ok()?;
+                }
+                20 => parameters.collateral_percentage = d.u64().ok()?,
+                21 => parameters.max_collateral_inputs = d.u64().ok()?,
+                30 => parameters.min_fee_ref_script_cost_per_byte = cbor_ratio(&mut d)?,
+                _ => d.skip().ok()?,
+            }
+        }
+        parameters.plutus_v3_cost_model = cost_model?;
+        Some(parameters)
+    }
+
+    /// Fetches the parameters from the provider, falling back to the cache and then the bundled copy.
+    ///
+    /// Every successful fetch refreshes the cache in `cache_dir`, so a wallet that is
+    /// briefly offline keeps pricing transactions with the last parameters it saw.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The chain data backend.
+    /// * `network_flag` - A boolean flag specifying the network:
+    ///     - `true` for Preprod/Testnet.
+    ///     - `false` for Mainnet.
+    /// * `cache_dir` - Where the cached parameters are kept.
+    ///
+    /// # Returns
+    ///
+    /// * `ProtocolParameters` - The freshest parameters available.
+    pub async fn load(provider: &impl ChainProvider, network_flag: bool, cache_dir: &Path) -> Self {
+        let path: PathBuf = cache_path(cache_dir, network_flag);
+        match provider.protocol_parameters().await {
+            Ok(Some(parameters)) => {
+                if let Ok(json) = serde_json::to_string_pretty(&parameters) {
+                    let _ = std::fs::create_dir_all(cache_dir);
+                    let _ = std::fs::write(&path, json);
+                }
+                parameters
+            }
+            _ => std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The cache file for a network's parameters.
+pub fn cache_path(cache_dir: &Path, network_flag: bool) -> PathBuf {
+    let network: &str = if network_flag { "preprod" } else { "mainnet" };
+    cache_dir.join(format!("protocol-parameters-{network}.json"))
+}
+
+/// A whole number that may come as a JSON number or a string.
+fn number(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+fn cost_model(value: &Value) -> Option<Vec<i64>> {
+    value.as_array()?.iter().map(Value::as_i64).collect()
+}
+
+/// Decodes a `tag 30 [numerator, denominator]` rational.
+fn cbor_ratio(d: &mut Decoder) -> Option<Ratio> {
+    if d.datatype().ok()? == Type::Tag && d.tag().ok()? != Tag::new(30) {
+        return None;
+    }
+    match d.datatype().ok()? {
+        Type::Array | Type::ArrayIndef => {
+            d.array().ok()?;
+            Some(Ratio::new(d.u64().ok()?, d.u64().ok()?))
+        }
+        _ => Some(Ratio::new(d.u64().ok()?, 1)),
+    }
+}
+
+/// Decodes the cost model map and keeps the one for the given language.
+fn cbor_cost_model(d: &mut Decoder, language: u64) -> Option<Vec<i64>> {
+    let entries: u64 = d.map().ok()??;
+    let mut found: Option<Vec<i64>> = None;
+    for _ in 0..entries {
+        let key: u64 = d.u64().ok()?;
+        if key == language {
+            let mut model: Vec<i64> = Vec::new();
+            for item in d.array_iter::<i64>().ok()? {
+                model.push(item.ok()?);
+            }
+            found = Some(model);
+        } else {
+            d.skip().ok()?;
+        }
+    }
+    found
+}
+
+/// The Plutus V3 cost model on mainnet, used by the bundled parameters.
+pub const PLUTUS_V3_COST_MODEL: [i64; 297] = [
+    100788, 420, 1, 1, 1000, 173, 0, 1, 1000, 59957, 4, 1, 11183, 32, 201305, 8356, 4, 16000, 100,
+    16000, 100, 16000, 100, 16000, 100, 16000, 100, 16000, 100, 100, 100, 16000, 100, 94375, 32,
+    132994, 32, 61462, 4, 72010, 178, 0, 1, 22151, 32, 91189, 769, 4, 2, 85848, 123203, 7305, -900,
+    1716, 549, 57, 85848, 0, 1, 1, 1000, 42921, 4, 2, 24548, 29498, 38, 1, 898148, 27279, 1, 51775,
+    558, 1, 39184, 1000, 60594, 1, 141895, 32, 83150, 32, 15299, 32, 76049, 1, 13169, 4, 22100, 10,
+    28999, 74, 1, 28999, 74, 1, 43285, 552, 1, 44749, 541, 1, 33852, 32, 68246, 32, 72362, 32,
+    7243, 32, 7391, 32, 11546, 32, 85848, 123203, 7305, -900, 1716, 549, 57, 85848, 0, 1, 90434,
+    519, 0, 1, 74433, 32, 85848, 123203, 7305, -900, 1716, 549, 57, 85848, 0, 1, 1, 85848, 123203,
+    7305, -900, 1716, 549, 57, 85848, 0, 1, 955506, 213312, 0, 2, 270652, 22588, 4, 1457325, 64566,
+    4, 20467, 1, 4, 0, 141992, 32, 100788, 420, 1, 1, 81663, 32, 59498, 32, 20142, 32, 24588, 32,
+    20744, 32, 25933, 32, 24623, 32, 43053543, 10, 53384111, 14333, 10, 43574283, 26308, 10, 16000,
+    100, 16000, 100, 962335, 18, 2780678, 6, 442008, 1, 52538055, 3756, 18, 267929, 18, 76433006,
+    8868, 18, 52948122, 18, 1995836, 36, 3227919, 12, 901022, 1, 166917843, 4307, 36, 284546, 36,
+    158221314, 26549, 36, 74698472, 36, 333849714, 1, 254006273, 72, 2174038, 72, 2261318, 64571,
+    4, 207616, 8310, 4, 1293828, 28716, 63, 0, 1, 1006041, 43623, 251, 0, 1, 100181, 726, 719, 0,
+    1, 100181, 726, 719, 0, 1, 100181, 726, 719, 0, 1, 107878, 680, 0, 1, 95336, 1, 281145, 18848,
+    0, 1, 180194, 159, 1, 1, 158519, 8942, 0, 1, 159378, 8813, 0, 1, 107490, 3298, 1, 106057, 655,
+    1, 1964219, 24520, 3,
+];
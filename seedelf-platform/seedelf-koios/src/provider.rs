@@ -0,0 +1,520 @@
+use crate::blockfrost::{Blockfrost, BlockfrostError};
+use crate::koios::{
+    BlockchainTip, CredentialTx, Koios, KoiosError, ResolvedDatum, TxResponse, TxStatus,
+    UtxoResponse,
+};
+use crate::node::{NodeClient, NodeError};
+use crate::ogmios::{OgmiosError, OgmiosKupo};
+use crate::parameters::ProtocolParameters;
+use futures_util::stream::{self, BoxStream, Stream, StreamExt, TryStreamExt};
+use serde_json::Value;
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+
+/// The transaction hash in a `submit_tx` response, `None` unless it is 64 hex characters.
+///
+/// # Arguments
+///
+/// * `response` - What `submit_tx` returned.
+///
+/// # Returns
+///
+/// * `Some(String)` - The hash of the accepted transaction.
+/// * `None` - If the response is anything else, e.g. an error object.
+pub fn submitted_tx_hash(response: &Value) -> Option<String> {
+    response
+        .as_str()
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(str::to_string)
+}
+
+/// A source of chain data and a sink for transactions.
+///
+/// Every chain read and write the wallet performs goes through this trait so the
+/// backend can be swapped out, e.g. for a self-hosted indexer or an in-memory
+/// stand-in during tests. Implementations return data in the Koios shapes
+/// (`UtxoResponse`, `TxResponse`, ...) since the rest of the wallet is built on them.
+pub trait ChainProvider: Send + Sync {
+    /// The error returned when a query or submission fails.
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Fetches the latest blockchain tip.
+    fn tip(&self) -> impl Future<Output = Result<Vec<BlockchainTip>, Self::Error>> + Send;
+
+    /// Fetches every UTxO locked by a payment credential.
+    fn credential_utxos(
+        &self,
+        payment_credential: &str,
+    ) -> impl Future<Output = Result<Vec<UtxoResponse>, Self::Error>> + Send;
+
+    /// Fetches the UTxOs locked by a payment credential a page at a time.
+    ///
+    /// Consumers that filter the UTxOs, like the ownership checks, should use this
+    /// so the whole set never has to be held at once. A backend without pagination
+    /// yields everything as a single page.
+    fn credential_utxo_pages<'a>(
+        &'a self,
+        payment_credential: &'a str,
+    ) -> impl Stream<Item = Result<Vec<UtxoResponse>, Self::Error>> + Send + 'a {
+        stream::once(self.credential_utxos(payment_credential))
+    }
+
+    /// Fetches every UTxO held by an address.
+    fn address_utxos(
+        &self,
+        address: &str,
+    ) -> impl Future<Output = Result<Vec<UtxoResponse>, Self::Error>> + Send;
+
+    /// Fetches a single UTxO in the `tx_hash#tx_index` format.
+    fn utxo_info(
+        &self,
+        utxo: &str,
+    ) -> impl Future<Output = Result<Vec<UtxoResponse>, Self::Error>> + Send;
+
+    /// Resolves a datum from its hash.
+    fn datum_from_datum_hash(
+        &self,
+        datum_hash: String,
+    ) -> impl Future<Output = Result<Vec<ResolvedDatum>, Self::Error>> + Send;
+
+    /// Resolves many datums, one hash at a time unless the backend has a batch query.
+    fn datums_from_datum_hashes(
+        &self,
+        datum_hashes: Vec<String>,
+    ) -> impl Future<Output = Result<Vec<ResolvedDatum>, Self::Error>> + Send {
+        async move {
+            let mut datums: Vec<ResolvedDatum> = Vec::with_capacity(datum_hashes.len());
+            for datum_hash in datum_hashes {
+                datums.extend(self.datum_from_datum_hash(datum_hash).await?);
+            }
+            Ok(datums)
+        }
+    }
+
+    /// Evaluates a transaction, returning the Ogmios `evaluateTransaction` JSON-RPC response.
+    fn evaluate_transaction(
+        &self,
+        tx_cbor: String,
+    ) -> impl Future<Output = Result<Value, Self::Error>> + Send;
+
+    /// Submits a transaction, returning the transaction hash as a JSON string on success.
+    fn submit_tx(&self, tx_cbor: String)
+    -> impl Future<Output = Result<Value, Self::Error>> + Send;
+
+    /// Fetches the transaction history of an address.
+    fn address_transactions(
+        &self,
+        address: String,
+    ) -> impl Future<Output = Result<Vec<TxResponse>, Self::Error>> + Send;
+
+    /// Fetches the number of confirmations for a transaction.
+    fn transaction_status(
+        &self,
+        tx_hash: String,
+    ) -> impl Future<Output = Result<Vec<TxStatus>, Self::Error>> + Send;
+
+    /// Fetches the transactions that touched a payment credential after a block, so
+    /// a local copy of the UTxO set can be brought up to date without a full scan.
+    ///
+    /// `None` means the backend can not answer this and the caller has to rescan.
+    fn credential_transactions(
+        &self,
+        _payment_credential: &str,
+        _after_block_height: u64,
+    ) -> impl Future<Output = Result<Option<Vec<CredentialTx>>, Self::Error>> + Send {
+        async { Ok(None) }
+    }
+
+    /// Fetches the current protocol parameters, `None` if the response could not be read.
+    fn protocol_parameters(
+        &self,
+    ) -> impl Future<Output = Result<Option<ProtocolParameters>, Self::Error>> + Send;
+}
+
+impl ChainProvider for Koios {
+    type Error = KoiosError;
+
+    async fn tip(&self) -> Result<Vec<BlockchainTip>, Self::Error> {
+        Koios::tip(self).await
+    }
+
+    async fn credential_utxos(
+        &self,
+        payment_credential: &str,
+    ) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Koios::credential_utxos(self, payment_credential).await
+    }
+
+    fn credential_utxo_pages<'a>(
+        &'a self,
+        payment_credential: &'a str,
+    ) -> impl Stream<Item = Result<Vec<UtxoResponse>, Self::Error>> + Send + 'a {
+        Koios::credential_utxo_pages(self, payment_credential)
+    }
+
+    async fn address_utxos(&self, address: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Koios::address_utxos(self, address).await
+    }
+
+    async fn utxo_info(&self, utxo: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        Koios::utxo_info(self, utxo).await
+    }
+
+    async fn datum_from_datum_hash(
+        &self,
+        datum_hash: String,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        Koios::datum_from_datum_hash(self, datum_hash).await
+    }
+
+    async fn datums_from_datum_hashes(
+        &self,
+        datum_hashes: Vec<String>,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        Koios::datums_from_datum_hashes(self, datum_hashes).await
+    }
+
+    async fn evaluate_transaction(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        Koios::evaluate_transaction(self, tx_cbor).await
+    }
+
+    async fn submit_tx(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        Koios::submit_tx(self, tx_cbor).await
+    }
+
+    async fn address_transactions(&self, address: String) -> Result<Vec<TxResponse>, Self::Error> {
+        Koios::address_transactions(self, address).await
+    }
+
+    async fn transaction_status(&self, tx_hash: String) -> Result<Vec<TxStatus>, Self::Error> {
+        Koios::transaction_status(self, tx_hash).await
+    }
+
+    async fn credential_transactions(
+        &self,
+        payment_credential: &str,
+        after_block_height: u64,
+    ) -> Result<Option<Vec<CredentialTx>>, Self::Error> {
+        Koios::credential_transactions(self, payment_credential, after_block_height)
+            .await
+            .map(Some)
+    }
+
+    async fn protocol_parameters(&self) -> Result<Option<ProtocolParameters>, Self::Error> {
+        Koios::protocol_parameters(self).await
+    }
+}
+
+/// The chain data backends the wallet can be pointed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackendKind {
+    #[default]
+    Koios,
+    Blockfrost,
+    OgmiosKupo,
+    Node,
+}
+
+/// Environment variable selecting the backend, `koios`, `blockfrost`, `ogmios` or `node`.
+pub const BACKEND_ENV: &str = "SEEDELF_BACKEND";
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "koios" => Ok(BackendKind::Koios),
+            "blockfrost" => Ok(BackendKind::Blockfrost),
+            "ogmios" | "kupo" | "ogmios-kupo" => Ok(BackendKind::OgmiosKupo),
+            "node" => Ok(BackendKind::Node),
+            other => Err(format!(
+                "Unknown Backend: {other}, expected koios, blockfrost, ogmios or node"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendKind::Koios => write!(f, "koios"),
+            BackendKind::Blockfrost => write!(f, "blockfrost"),
+            BackendKind::OgmiosKupo => write!(f, "ogmios"),
+            BackendKind::Node => write!(f, "node"),
+        }
+    }
+}
+
+/// A backend chosen at runtime.
+///
+/// Commands are generic over `ChainProvider`, so this lets the CLI and GUI pick the
+/// backend from a flag or the environment without any command knowing which it is.
+#[derive(Debug, Clone)]
+pub enum Backend {
+    Koios(Koios),
+    Blockfrost(Blockfrost),
+    OgmiosKupo(OgmiosKupo),
+    Node(NodeClient),
+}
+
+/// The error returned by whichever backend is in use.
+#[derive(Debug)]
+pub enum BackendError {
+    /// A Koios request failed.
+    Koios(KoiosError),
+    /// Blockfrost failed or refused a transaction.
+    Blockfrost(BlockfrostError),
+    /// Ogmios or Kupo failed.
+    Ogmios(OgmiosError),
+    /// The local node failed.
+    Node(NodeError),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Koios(err) => write!(f, "{err}"),
+            BackendError::Blockfrost(err) => write!(f, "{err}"),
+            BackendError::Ogmios(err) => write!(f, "{err}"),
+            BackendError::Node(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BackendError::Koios(err) => Some(err),
+            BackendError::Blockfrost(err) => Some(err),
+            BackendError::Ogmios(err) => Some(err),
+            BackendError::Node(err) => Some(err),
+        }
+    }
+}
+
+impl From<KoiosError> for BackendError {
+    fn from(err: KoiosError) -> Self {
+        BackendError::Koios(err)
+    }
+}
+
+impl From<BlockfrostError> for BackendError {
+    fn from(err: BlockfrostError) -> Self {
+        BackendError::Blockfrost(err)
+    }
+}
+
+impl From<OgmiosError> for BackendError {
+    fn from(err: OgmiosError) -> Self {
+        BackendError::Ogmios(err)
+    }
+}
+
+impl From<NodeError> for BackendError {
+    fn from(err: NodeError) -> Self {
+        BackendError::Node(err)
+    }
+}
+
+impl Backend {
+    /// Builds the backend named by `SEEDELF_BACKEND`, configured from the environment.
+    ///
+    /// # Arguments
+    ///
+    /// * `network_flag` - A boolean flag specifying the network:
+    ///     - `true` for Preprod/Testnet.
+    ///     - `false` for Mainnet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Backend)` - The configured backend, Koios when nothing is set.
+    /// * `Err(String)` - If the backend name or its settings are invalid.
+    pub fn from_env(network_flag: bool) -> Result<Self, String> {
+        let kind: BackendKind = match std::env::var(BACKEND_ENV) {
+            Ok(name) if !name.trim().is_empty() => name.parse()?,
+            _ => BackendKind::default(),
+        };
+        match kind {
+            BackendKind::Koios => Ok(Backend::Koios(Koios::from_env(network_flag)?)),
+            BackendKind::Blockfrost => Ok(Backend::Blockfrost(Blockfrost::from_env(network_flag)?)),
+            BackendKind::OgmiosKupo => Ok(Backend::OgmiosKupo(OgmiosKupo::from_env(network_flag)?)),
+            BackendKind::Node => Ok(Backend::Node(NodeClient::from_env(network_flag)?)),
+        }
+    }
+
+    /// Which backend this is.
+    pub fn kind(&self) -> BackendKind {
+        match self {
+            Backend::Koios(_) => BackendKind::Koios,
+            Backend::Blockfrost(_) => BackendKind::Blockfrost,
+            Backend::OgmiosKupo(_) => BackendKind::OgmiosKupo,
+            Backend::Node(_) => BackendKind::Node,
+        }
+    }
+}
+
+impl ChainProvider for Backend {
+    type Error = BackendError;
+
+    async fn tip(&self) -> Result<Vec<BlockchainTip>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::tip(koios).await?),
+            Backend::Blockfrost(blockfrost) => Ok(blockfrost.tip().await?),
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.tip().await?),
+            Backend::Node(node) => Ok(node.tip().await?),
+        }
+    }
+
+    async fn credential_utxos(
+        &self,
+        payment_credential: &str,
+    ) -> Result<Vec<UtxoResponse>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::credential_utxos(koios, payment_credential).await?),
+            Backend::Blockfrost(blockfrost) => {
+                Ok(blockfrost.credential_utxos(payment_credential).await?)
+            }
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.credential_utxos(payment_credential).await?),
+            Backend::Node(node) => Ok(node.credential_utxos(payment_credential).await?),
+        }
+    }
+
+    fn credential_utxo_pages<'a>(
+        &'a self,
+        payment_credential: &'a str,
+    ) -> impl Stream<Item = Result<Vec<UtxoResponse>, Self::Error>> + Send + 'a {
+        let pages: BoxStream<'a, Result<Vec<UtxoResponse>, BackendError>> = match self {
+            Backend::Koios(koios) => koios
+                .credential_utxo_pages(payment_credential)
+                .map_err(BackendError::from)
+                .boxed(),
+            Backend::Blockfrost(blockfrost) => {
+                ChainProvider::credential_utxo_pages(blockfrost, payment_credential)
+                    .map_err(BackendError::from)
+                    .boxed()
+            }
+            Backend::OgmiosKupo(ogmios) => {
+                ChainProvider::credential_utxo_pages(ogmios, payment_credential)
+                    .map_err(BackendError::from)
+                    .boxed()
+            }
+            Backend::Node(node) => ChainProvider::credential_utxo_pages(node, payment_credential)
+                .map_err(BackendError::from)
+                .boxed(),
+        };
+        pages
+    }
+
+    async fn address_utxos(&self, address: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::address_utxos(koios, address).await?),
+            Backend::Blockfrost(blockfrost) => Ok(blockfrost.address_utxos(address).await?),
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.address_utxos(address).await?),
+            Backend::Node(node) => Ok(node.address_utxos(address).await?),
+        }
+    }
+
+    async fn utxo_info(&self, utxo: &str) -> Result<Vec<UtxoResponse>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::utxo_info(koios, utxo).await?),
+            Backend::Blockfrost(blockfrost) => Ok(blockfrost.utxo_info(utxo).await?),
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.utxo_info(utxo).await?),
+            Backend::Node(node) => Ok(node.utxo_info(utxo).await?),
+        }
+    }
+
+    async fn datum_from_datum_hash(
+        &self,
+        datum_hash: String,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::datum_from_datum_hash(koios, datum_hash).await?),
+            Backend::Blockfrost(blockfrost) => {
+                Ok(blockfrost.datum_from_datum_hash(datum_hash).await?)
+            }
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.datum_from_datum_hash(datum_hash).await?),
+            Backend::Node(node) => Ok(node.datum_from_datum_hash(datum_hash).await?),
+        }
+    }
+
+    async fn datums_from_datum_hashes(
+        &self,
+        datum_hashes: Vec<String>,
+    ) -> Result<Vec<ResolvedDatum>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => {
+                Ok(Koios::datums_from_datum_hashes(koios, datum_hashes).await?)
+            }
+            Backend::Blockfrost(blockfrost) => {
+                Ok(blockfrost.datums_from_datum_hashes(datum_hashes).await?)
+            }
+            Backend::OgmiosKupo(ogmios) => {
+                Ok(ogmios.datums_from_datum_hashes(datum_hashes).await?)
+            }
+            Backend::Node(node) => Ok(node.datums_from_datum_hashes(datum_hashes).await?),
+        }
+    }
+
+    async fn evaluate_transaction(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::evaluate_transaction(koios, tx_cbor).await?),
+            Backend::Blockfrost(blockfrost) => Ok(blockfrost.evaluate_transaction(tx_cbor).await?),
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.evaluate_transaction(tx_cbor).await?),
+            Backend::Node(node) => Ok(node.evaluate_transaction(tx_cbor).await?),
+        }
+    }
+
+    async fn submit_tx(&self, tx_cbor: String) -> Result<Value, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::submit_tx(koios, tx_cbor).await?),
+            Backend::Blockfrost(blockfrost) => Ok(blockfrost.submit_tx(tx_cbor).await?),
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.submit_tx(tx_cbor).await?),
+            Backend::Node(node) => Ok(node.submit_tx(tx_cbor).await?),
+        }
+    }
+
+    async fn address_transactions(&self, address: String) -> Result<Vec<TxResponse>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::address_transactions(koios, address).await?),
+            Backend::Blockfrost(blockfrost) => Ok(blockfrost.address_transactions(address).await?),
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.address_transactions(address).await?),
+            Backend::Node(node) => Ok(node.address_transactions(address).await?),
+        }
+    }
+
+    async fn transaction_status(&self, tx_hash: String) -> Result<Vec<TxStatus>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::transaction_status(koios, tx_hash).await?),
+            Backend::Blockfrost(blockfrost) => Ok(blockfrost.transaction_status(tx_hash).await?),
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.transaction_status(tx_hash).await?),
+            Backend::Node(node) => Ok(node.transaction_status(tx_hash).await?),
+        }
+    }
+
+    async fn credential_transactions(
+        &self,
+        payment_credential: &str,
+        after_block_height: u64,
+    ) -> Result<Option<Vec<CredentialTx>>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(ChainProvider::credential_transactions(
+                koios,
+                payment_credential,
+                after_block_height,
+            )
+            .await?),
+            // the other backends have no cheap way to list what changed
+            _ => Ok(None),
+        }
+    }
+
+    async fn protocol_parameters(&self) -> Result<Option<ProtocolParameters>, Self::Error> {
+        match self {
+            Backend::Koios(koios) => Ok(Koios::protocol_parameters(koios).await?),
+            Backend::Blockfrost(blockfrost) => Ok(blockfrost.protocol_parameters().await?),
+            Backend::OgmiosKupo(ogmios) => Ok(ogmios.protocol_parameters().await?),
+            Backend::Node(node) => Ok(node.protocol_parameters().await?),
+        }
+    }
+}
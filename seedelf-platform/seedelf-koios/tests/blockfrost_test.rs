@@ -0,0 +1,114 @@
+use seedelf_koios::blockfrost::{
+    Blockfrost, BlockfrostError, BlockfrostUtxo, evaluation_to_ogmios, utxo_response,
+};
+use seedelf_koios::datum::cbor_to_json;
+use seedelf_koios::koios::{UtxoResponse, extract_bytes_with_logging};
+use seedelf_koios::provider::{BackendKind, ChainProvider};
+use serde_json::{Value, json};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[test]
+fn register_datum_from_cbor() {
+    // constr 0 [h'aa', h'bb']
+    let value: Value = cbor_to_json("d8799f41aa41bbff").unwrap();
+    assert_eq!(
+        value,
+        json!({ "constructor": 0, "fields": [{ "bytes": "aa" }, { "bytes": "bb" }] })
+    )
+}
+
+#[test]
+fn nested_datum_from_cbor() {
+    // constr 1 [-5, [1], {h'': 2}]
+    let value: Value = cbor_to_json("d87a9f249f01ffa14002ff").unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "constructor": 1,
+            "fields": [
+                { "int": -5 },
+                { "list": [{ "int": 1 }] },
+                { "map": [{ "k": { "bytes": "" }, "v": { "int": 2 } }] }
+            ]
+        })
+    )
+}
+
+#[test]
+fn blockfrost_utxo_maps_to_koios_shape() {
+    let raw: Value = json!({
+        "address": "addr_test1wzk4r3r7l8w3ugyyu5ww3nxnc3gq6vlsqdf2fx7fcuf2cfqflej3n",
+        "tx_hash": "f33b03d7230e333fb8f26a09b428ab1b3cb6074b1432e773aac353574f29e888",
+        "output_index": 2,
+        "amount": [
+            { "unit": "lovelace", "quantity": "1500000" },
+            { "unit": "b0cbd7cde289d6aa694214fcd95a39e7f3ef52fc94d1171664210677acab", "quantity": "1" }
+        ],
+        "block": "abcd",
+        "data_hash": null,
+        "inline_datum": "d8799f41aa41bbff",
+        "reference_script_hash": null
+    });
+    let utxo: BlockfrostUtxo = serde_json::from_value(raw).unwrap();
+    let mapped: UtxoResponse = utxo_response(utxo, "");
+
+    assert_eq!(mapped.tx_index, 2);
+    assert_eq!(mapped.value, "1500000");
+    let assets = mapped.asset_list.unwrap();
+    assert_eq!(
+        assets[0].policy_id,
+        "b0cbd7cde289d6aa694214fcd95a39e7f3ef52fc94d1171664210677"
+    );
+    assert_eq!(assets[0].asset_name, "acab");
+    let register = extract_bytes_with_logging(&mapped.inline_datum).unwrap();
+    assert_eq!(register.generator, "aa");
+    assert_eq!(register.public_value, "bb")
+}
+
+#[test]
+fn ogmios_v5_evaluation_is_rewritten() {
+    let v5: Value = json!({
+        "type": "jsonwsp/response",
+        "result": { "EvaluationResult": { "spend:0": { "memory": 1700, "steps": 476468 } } }
+    });
+    let v6: Value = evaluation_to_ogmios(v5);
+    assert_eq!(v6["result"][0]["validator"]["purpose"], "spend");
+    assert_eq!(v6["result"][0]["budget"]["cpu"], 476468);
+    assert_eq!(v6["result"][0]["budget"]["memory"], 1700);
+
+    let failure: Value = json!({ "result": { "EvaluationFailure": { "ScriptFailures": {} } } });
+    assert!(evaluation_to_ogmios(failure).get("error").is_some())
+}
+
+#[test]
+fn backend_names() {
+    assert_eq!(
+        "Blockfrost".parse::<BackendKind>(),
+        Ok(BackendKind::Blockfrost)
+    );
+    assert_eq!("koios".parse::<BackendKind>(), Ok(BackendKind::Koios));
+    assert!("nope".parse::<BackendKind>().is_err())
+}
+
+#[tokio::test]
+async fn rejected_submissions_are_errors() {
+    let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url: String = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buffer: [u8; 4096] = [0; 4096];
+        let _ = stream.read(&mut buffer).await.unwrap();
+        let body: &str = r#"{"status_code":400,"error":"Bad Request","message":"BadInputsUTxO"}"#;
+        let reply: String = format!(
+            "HTTP/1.1 400 Bad Request\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(reply.as_bytes()).await.unwrap();
+    });
+    let blockfrost: Blockfrost = Blockfrost::new(true, "preprodtest").with_base_url(url);
+    match blockfrost.submit_tx("84a0".to_string()).await {
+        Err(BlockfrostError::Rejected(body)) => assert!(body.contains("BadInputsUTxO")),
+        other => panic!("expected a rejection, got {other:?}"),
+    }
+}
@@ -0,0 +1,143 @@
+use futures_util::TryStreamExt;
+use seedelf_koios::koios::{Koios, KoiosError, UtxoResponse, backoff_delay};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Starts a stand-in Koios that answers each request with the next canned reply.
+///
+/// Returns the base URL and a count of the requests it has seen.
+async fn replay(replies: Vec<(u16, &'static str)>) -> (String, Arc<AtomicUsize>) {
+    let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url: String = format!("http://{}", listener.local_addr().unwrap());
+    let seen: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let counter: Arc<AtomicUsize> = seen.clone();
+    tokio::spawn(async move {
+        for (status, body) in replies {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request: Vec<u8> = Vec::new();
+            let mut buffer: [u8; 4096] = [0; 4096];
+            // the bodies are small, the headers and body arrive before the reply is needed
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let read: usize = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            counter.fetch_add(1, Ordering::SeqCst);
+            let reply: String = format!(
+                "HTTP/1.1 {status} Canned\r\nretry-after: 0\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(reply.as_bytes()).await.unwrap();
+        }
+    });
+    (url, seen)
+}
+
+#[tokio::test]
+async fn rate_limited_queries_are_retried() {
+    let (url, seen) = replay(vec![(429, ""), (503, "busy"), (200, "[]")]).await;
+    let koios: Koios = Koios::new(true).with_base_url(url);
+    let utxos: Vec<UtxoResponse> = koios.address_utxos("addr_test1").await.unwrap();
+    assert!(utxos.is_empty());
+    assert_eq!(seen.load(Ordering::SeqCst), 3);
+
+    let (url, _) = replay(vec![(429, ""), (429, "")]).await;
+    let koios: Koios = Koios::new(true).with_base_url(url).with_retries(1);
+    assert!(matches!(
+        koios.address_utxos("addr_test1").await,
+        Err(KoiosError::RateLimited { .. })
+    ));
+}
+
+#[tokio::test]
+async fn rejected_submissions_keep_the_ledger_error() {
+    let (url, seen) = replay(vec![(400, "BadInputsUTxO"), (202, "\"ab12\"")]).await;
+    let koios: Koios = Koios::new(true).with_base_url(url);
+    match koios.submit_tx("84a0".to_string()).await {
+        Err(KoiosError::NodeRejected(body)) => assert_eq!(body, "BadInputsUTxO"),
+        other => panic!("expected a rejection, got {other:?}"),
+    }
+    assert_eq!(seen.load(Ordering::SeqCst), 1);
+}
+
+/// Starts a stand-in Koios holding `rows` credential UTxOs, answering any page asked for.
+async fn paged(rows: usize) -> (String, Arc<AtomicUsize>) {
+    let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url: String = format!("http://{}", listener.local_addr().unwrap());
+    let seen: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let counter: Arc<AtomicUsize> = seen.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let counter: Arc<AtomicUsize> = counter.clone();
+            tokio::spawn(async move {
+                let mut buffer: [u8; 4096] = [0; 4096];
+                let read: usize = stream.read(&mut buffer).await.unwrap();
+                let request: String = String::from_utf8_lossy(&buffer[..read]).to_string();
+                let param = |name: &str| -> usize {
+                    let start: usize = request.find(&format!("{name}=")).unwrap() + name.len() + 1;
+                    request[start..]
+                        .split(['&', ' '])
+                        .next()
+                        .unwrap()
+                        .parse()
+                        .unwrap()
+                };
+                let (offset, limit) = (param("offset"), param("limit"));
+                counter.fetch_add(1, Ordering::SeqCst);
+                let page: Vec<UtxoResponse> = (offset..rows.min(offset + limit))
+                    .map(|tx_index| UtxoResponse {
+                        tx_index: tx_index as u64,
+                        ..Default::default()
+                    })
+                    .collect();
+                let body: String = serde_json::to_string(&page).unwrap();
+                let reply: String = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(reply.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    (url, seen)
+}
+
+#[tokio::test]
+async fn credential_pages_arrive_in_order_and_stop_after_a_short_page() {
+    let (url, seen) = paged(5).await;
+    let koios: Koios = Koios::new(true)
+        .with_base_url(url)
+        .with_page_size(2)
+        .with_concurrency(2);
+    let pages: Vec<Vec<UtxoResponse>> = koios
+        .credential_utxo_pages("cred")
+        .try_collect()
+        .await
+        .unwrap();
+    let sizes: Vec<usize> = pages.iter().map(Vec::len).collect();
+    assert_eq!(sizes, vec![2, 2, 1]);
+    // two windows of two pages, nothing is asked for past the window with the short page
+    assert_eq!(seen.load(Ordering::SeqCst), 4);
+    let utxos: Vec<UtxoResponse> = koios.credential_utxos("cred").await.unwrap();
+    assert!(
+        utxos
+            .iter()
+            .enumerate()
+            .all(|(i, u)| u.tx_index == i as u64)
+    );
+    assert_eq!(seen.load(Ordering::SeqCst), 8);
+}
+
+#[test]
+fn backoff_doubles_with_jitter() {
+    for attempt in 0..4 {
+        let base: Duration = Duration::from_millis(500 * (1 << attempt));
+        let delay: Duration = backoff_delay(attempt, None);
+        assert!(delay >= base && delay <= base + base / 2 + Duration::from_millis(1));
+    }
+    assert!(backoff_delay(20, None) <= Duration::from_secs(24));
+    assert!(backoff_delay(0, Some(Duration::from_secs(3))) >= Duration::from_secs(3));
+}
@@ -0,0 +1,196 @@
+#![cfg(unix)]
+
+use pallas_codec::minicbor::Encoder;
+use pallas_codec::minicbor::data::Tag;
+use seedelf_koios::koios::{BlockchainTip, UtxoResponse, extract_bytes_with_logging};
+use seedelf_koios::node::{ChainEvent, NodeClient};
+use seedelf_koios::provider::ChainProvider;
+use serde_json::Value;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
+use tokio::task::JoinHandle;
+
+const SCRIPT_HASH: &str = "94bca9c099e84ffd90d150316bb44c31a78702239076a0a80ea4a469";
+
+// recorded node replies
+const HANDSHAKE_ACCEPT: &str = "83011980108201f4";
+const ACQUIRED: &str = "8101";
+const CONWAY_ERA: &str = "820406";
+const TX_ACCEPTED: &str = "8101";
+
+/// Starts a stand-in node that answers each client message with the next recorded reply.
+///
+/// Messages the client sends once the replies run out, like `MsgDone`, are read and dropped.
+fn replay(name: &str, replies: Vec<(u16, Vec<u8>)>) -> (PathBuf, JoinHandle<()>) {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("seedelf-{name}-{}.socket", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener: UnixListener = UnixListener::bind(&path).unwrap();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut header: [u8; 8] = [0; 8];
+        for (protocol, reply) in replies {
+            stream.read_exact(&mut header).await.unwrap();
+            let mut request: Vec<u8> = vec![0; u16::from_be_bytes([header[6], header[7]]) as usize];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(u16::from_be_bytes([header[4], header[5]]), protocol);
+
+            let mut frame: Vec<u8> = vec![0, 0, 0, 0];
+            frame.extend((protocol | 0x8000).to_be_bytes());
+            frame.extend((reply.len() as u16).to_be_bytes());
+            frame.extend(reply);
+            stream.write_all(&frame).await.unwrap();
+        }
+        let mut rest: Vec<u8> = Vec::new();
+        let _ = stream.read_to_end(&mut rest).await;
+    });
+    (path, server)
+}
+
+fn recorded(cbor: &str) -> Vec<u8> {
+    hex::decode(cbor).unwrap()
+}
+
+/// A `GetUTxOByAddress` result holding one wallet output with a register datum.
+fn utxo_result() -> Vec<u8> {
+    let mut address: Vec<u8> = vec![0x10];
+    address.extend(hex::decode(SCRIPT_HASH).unwrap());
+    address.extend([7u8; 28]);
+
+    let mut e: Encoder<Vec<u8>> = Encoder::new(Vec::new());
+    e.array(2).unwrap().u8(4).unwrap();
+    e.array(1).unwrap().map(1).unwrap();
+    e.array(2)
+        .unwrap()
+        .bytes(&[0xab; 32])
+        .unwrap()
+        .u8(1)
+        .unwrap();
+    e.map(3).unwrap();
+    e.u8(0).unwrap().bytes(&address).unwrap();
+    e.u8(1).unwrap().u64(5_000_000).unwrap();
+    e.u8(2).unwrap().array(2).unwrap().u8(1).unwrap();
+    e.tag(Tag::new(24))
+        .unwrap()
+        .bytes(&recorded("d8799f41aa41bbff"))
+        .unwrap();
+    e.into_writer()
+}
+
+#[tokio::test]
+async fn node_tip_from_local_state_query() {
+    let (path, server) = replay(
+        "tip",
+        vec![
+            (0, recorded(HANDSHAKE_ACCEPT)),
+            (7, recorded(ACQUIRED)),
+            // [4, [slot, hash]]
+            (
+                7,
+                recorded(&format!("8204821a05f5e1005820{}", "cd".repeat(32))),
+            ),
+            // [4, [1, block_no]]
+            (7, recorded("820482011a002dc6c0")),
+        ],
+    );
+    let node: NodeClient = NodeClient::new(true, path.to_string_lossy());
+    let tips: Vec<BlockchainTip> = node.tip().await.unwrap();
+    server.await.unwrap();
+
+    assert_eq!(tips[0].abs_slot, 100_000_000);
+    assert_eq!(tips[0].block_no, 3_000_000);
+    assert_eq!(tips[0].hash, "cd".repeat(32));
+    assert_eq!(tips[0].block_time, 100_000_000 + 1_655_683_200)
+}
+
+#[tokio::test]
+async fn node_credential_utxos_resolve_registers() {
+    let (path, server) = replay(
+        "utxos",
+        vec![
+            (0, recorded(HANDSHAKE_ACCEPT)),
+            (7, recorded(ACQUIRED)),
+            (7, recorded(CONWAY_ERA)),
+            (7, utxo_result()),
+        ],
+    );
+    let node: NodeClient =
+        NodeClient::new(true, path.to_string_lossy()).with_stake_credential([7u8; 28]);
+    let utxos: Vec<UtxoResponse> = node.credential_utxos(SCRIPT_HASH).await.unwrap();
+    server.await.unwrap();
+
+    assert_eq!(utxos.len(), 1);
+    assert_eq!(utxos[0].tx_hash, "ab".repeat(32));
+    assert_eq!(utxos[0].tx_index, 1);
+    assert_eq!(utxos[0].value, "5000000");
+    assert_eq!(utxos[0].payment_cred, SCRIPT_HASH);
+    let register = extract_bytes_with_logging(&utxos[0].inline_datum).unwrap();
+    assert_eq!(register.generator, "aa");
+    assert_eq!(register.public_value, "bb")
+}
+
+#[tokio::test]
+async fn node_submits_over_local_tx_submission() {
+    let (path, server) = replay(
+        "submit",
+        vec![
+            (0, recorded(HANDSHAKE_ACCEPT)),
+            (7, recorded(ACQUIRED)),
+            (7, recorded(CONWAY_ERA)),
+            (6, recorded(TX_ACCEPTED)),
+        ],
+    );
+    let node: NodeClient = NodeClient::new(true, path.to_string_lossy());
+    // an empty conway transaction
+    let response: Value = node
+        .submit_tx("84a3008001800200a0f5f6".to_string())
+        .await
+        .unwrap();
+    server.await.unwrap();
+
+    assert_eq!(response.as_str().map(str::len), Some(64))
+}
+
+#[tokio::test]
+async fn node_follows_rollbacks_with_chain_sync() {
+    let tip: String = format!("82821a05f5e1005820{}1a002dc6c0", "cd".repeat(32));
+    let (path, server) = replay(
+        "follow",
+        vec![
+            (0, recorded(HANDSHAKE_ACCEPT)),
+            // intersect not found, carrying the tip
+            (5, recorded(&format!("8206{tip}"))),
+            // intersect found at the tip
+            (
+                5,
+                recorded(&format!("8305821a05f5e1005820{}{tip}", "cd".repeat(32))),
+            ),
+            // roll backward to a slot just before
+            (
+                5,
+                recorded(&format!("8303821a05f5e0ff5820{}{tip}", "ef".repeat(32))),
+            ),
+        ],
+    );
+    let node: NodeClient = NodeClient::new(true, path.to_string_lossy());
+    let mut follower = node.follow().await.unwrap();
+    let event: ChainEvent = follower.next_event().await.unwrap();
+    drop(follower);
+    server.await.unwrap();
+
+    assert_eq!(
+        event,
+        ChainEvent::RollBackward {
+            slot: 99_999_999,
+            hash: "ef".repeat(32)
+        }
+    )
+}
+
+#[tokio::test]
+async fn node_without_an_index_says_so() {
+    let node: NodeClient = NodeClient::new(true, "/nonexistent/node.socket");
+    let err = node.address_transactions(String::new()).await.unwrap_err();
+    assert!(err.to_string().contains("History"))
+}
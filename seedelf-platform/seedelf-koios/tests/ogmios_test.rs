@@ -0,0 +1,79 @@
+use seedelf_koios::koios::{UtxoResponse, extract_bytes_with_logging};
+use seedelf_koios::ogmios::{
+    KupoMatch, OgmiosError, OgmiosKupo, kupo_utxo_response, slot_to_epoch, slot_to_posix,
+    submission_to_tx_hash,
+};
+use seedelf_koios::provider::BackendKind;
+use serde_json::{Value, json};
+
+#[test]
+fn kupo_match_maps_to_koios_shape() {
+    let raw: Value = json!({
+        "transaction_index": 3,
+        "transaction_id": "f33b03d7230e333fb8f26a09b428ab1b3cb6074b1432e773aac353574f29e888",
+        "output_index": 1,
+        "address": "addr_test1wzk4r3r7l8w3ugyyu5ww3nxnc3gq6vlsqdf2fx7fcuf2cfqflej3n",
+        "value": {
+            "coins": 1500000,
+            "assets": {
+                "b0cbd7cde289d6aa694214fcd95a39e7f3ef52fc94d1171664210677.acab": 1,
+                "b0cbd7cde289d6aa694214fcd95a39e7f3ef52fc94d1171664210678": 7
+            }
+        },
+        "datum_hash": "923918e403bf43c34b4ef6b48eb2ee04babed17320d8d1b9ff9ad086e86f44ec",
+        "datum_type": "inline",
+        "script_hash": null,
+        "created_at": { "slot_no": 12345, "header_hash": "abcd" },
+        "spent_at": null
+    });
+    let utxo: KupoMatch = serde_json::from_value(raw).unwrap();
+    let mapped: UtxoResponse = kupo_utxo_response(utxo, Some("d8799f41aa41bbff"));
+
+    assert_eq!(mapped.tx_index, 1);
+    assert_eq!(mapped.value, "1500000");
+    assert!(!mapped.is_spent);
+    let assets = mapped.asset_list.unwrap();
+    assert_eq!(assets[0].asset_name, "acab");
+    assert_eq!(assets[1].asset_name, "");
+    assert_eq!(assets[1].quantity, "7");
+    let register = extract_bytes_with_logging(&mapped.inline_datum).unwrap();
+    assert_eq!(register.generator, "aa");
+    assert_eq!(register.public_value, "bb")
+}
+
+#[test]
+fn ogmios_submission_returns_the_tx_hash() {
+    let accepted: Value = json!({
+        "jsonrpc": "2.0",
+        "method": "submitTransaction",
+        "result": { "transaction": { "id": "ab12" } },
+        "id": 1
+    });
+    assert_eq!(submission_to_tx_hash(accepted).unwrap(), json!("ab12"));
+
+    let rejected: Value = json!({ "jsonrpc": "2.0", "error": { "code": 3005, "message": "nope" } });
+    match submission_to_tx_hash(rejected) {
+        Err(OgmiosError::Rejected(error)) => assert!(error.contains("nope")),
+        other => panic!("expected a rejection, got {other:?}"),
+    }
+}
+
+#[test]
+fn slots_to_time_and_epoch() {
+    // first shelley blocks
+    assert_eq!(slot_to_posix(4_492_800, false), 1_596_059_091);
+    assert_eq!(slot_to_posix(86_400, true), 1_655_769_600);
+    assert_eq!(slot_to_epoch(4_492_800 + 432_000 + 5, false), (209, 5));
+    assert_eq!(slot_to_epoch(86_400 + 10, true), (4, 10))
+}
+
+#[test]
+fn ogmios_urls() {
+    let backend: OgmiosKupo = OgmiosKupo::new(true)
+        .with_ogmios_url("https://ogmios.example.com/")
+        .with_kupo_url("http://kupo.example.com/");
+    assert_eq!(backend.ogmios_url(), "wss://ogmios.example.com");
+    assert_eq!(backend.kupo_url(), "http://kupo.example.com");
+    assert_eq!("ogmios".parse::<BackendKind>(), Ok(BackendKind::OgmiosKupo));
+    assert_eq!(BackendKind::OgmiosKupo.to_string(), "ogmios")
+}
@@ -0,0 +1,93 @@
+use pallas_codec::minicbor::Encoder;
+use pallas_codec::minicbor::data::Tag;
+use seedelf_koios::parameters::{PLUTUS_V3_COST_MODEL, ProtocolParameters, Ratio};
+use serde_json::{Value, json};
+
+#[test]
+fn reference_script_fee_is_tiered() {
+    let params: ProtocolParameters = ProtocolParameters::default();
+    assert_eq!(params.reference_script_fee(0), 0);
+    assert_eq!(params.reference_script_fee(25_600), 384_000);
+    // the second tier costs 18 per byte
+    assert_eq!(params.reference_script_fee(30_000), 384_000 + 4_400 * 18);
+    // the third costs 21.6 per byte
+    assert_eq!(params.reference_script_fee(51_300), 846_960);
+}
+
+#[test]
+fn fees_from_default_parameters() {
+    let params: ProtocolParameters = ProtocolParameters::default();
+    assert_eq!(params.min_fee(300), 44 * 300 + 155_381);
+    assert_eq!(params.script_fee(1_000_000, 500_000_000), 57_700 + 36_050);
+}
+
+#[test]
+fn cardano_cli_parameters_with_float_prices() {
+    let raw: Value = json!({
+        "txFeePerByte": 44,
+        "txFeeFixed": 155381,
+        "utxoCostPerByte": 4310,
+        "executionUnitPrices": { "priceMemory": 0.0577, "priceSteps": 0.0000721 },
+        "minFeeRefScriptCostPerByte": 15,
+        "maxTxSize": 16384,
+        "maxTxExecutionUnits": { "memory": 14000000, "steps": 10000000000u64 },
+        "collateralPercentage": 150,
+        "maxCollateralInputs": 3,
+        "costModels": { "PlutusV3": PLUTUS_V3_COST_MODEL.to_vec() }
+    });
+    let params: ProtocolParameters = ProtocolParameters::from_cardano_cli(&raw).unwrap();
+    assert_eq!(params, ProtocolParameters::default());
+}
+
+#[test]
+fn node_parameters_from_cbor() {
+    let mut e: Encoder<Vec<u8>> = Encoder::new(Vec::new());
+    e.array(31).unwrap();
+    for index in 0..31u64 {
+        match index {
+            0 => {
+                e.u64(45).unwrap();
+            }
+            15 => {
+                e.map(1).unwrap().u8(2).unwrap();
+                e.array(3)
+                    .unwrap()
+                    .i64(1)
+                    .unwrap()
+                    .i64(-2)
+                    .unwrap()
+                    .i64(3)
+                    .unwrap();
+            }
+            16 => {
+                e.array(2).unwrap();
+                e.tag(Tag::new(30)).unwrap().array(2).unwrap();
+                e.u64(577).unwrap().u64(10_000).unwrap();
+                e.tag(Tag::new(30)).unwrap().array(2).unwrap();
+                e.u64(721).unwrap().u64(10_000_000).unwrap();
+            }
+            17 => {
+                e.array(2)
+                    .unwrap()
+                    .u64(14_000_000)
+                    .unwrap()
+                    .u64(10_000_000_000)
+                    .unwrap();
+            }
+            30 => {
+                e.tag(Tag::new(30)).unwrap().array(2).unwrap();
+                e.u64(15).unwrap().u64(1).unwrap();
+            }
+            // every other parameter is a plain number
+            _ => {
+                e.u64(4_310).unwrap();
+            }
+        }
+    }
+    let params: ProtocolParameters = ProtocolParameters::from_cbor(&e.into_writer()).unwrap();
+    assert_eq!(params.min_fee_a, 45);
+    assert_eq!(params.coins_per_utxo_byte, 4_310);
+    assert_eq!(params.price_steps, Ratio::new(721, 10_000_000));
+    assert_eq!(params.min_fee_ref_script_cost_per_byte, Ratio::new(15, 1));
+    assert_eq!(params.plutus_v3_cost_model, vec![1, -2, 3]);
+}